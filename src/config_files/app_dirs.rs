@@ -0,0 +1,274 @@
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::config_files::xdg::{self, Behaviour, MacOSBehaviour};
+use crate::os::env::Env;
+
+/// Per-application directories, resolved from the [`xdg`] ones.
+///
+/// Name of the application directory depends on the platform:
+/// - `org\app` on Windows (with `config`, `data`, `cache` and `state` subdirectories, as all of
+///   them share the same base there),
+/// - `qualifier.org.app` on macOS, with [`MacOSBehaviour::UseLibrary`] (spaces become `-`),
+/// - `app` elsewhere (lowercase, without whitespace).
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::AppDirs;
+/// # fn foo() -> Option<()> {
+/// let dirs = AppDirs::new("com", "Example Corp", "Frobnicator")?;
+/// // `~/.config/frobnicator/config.toml` on Linux.
+/// let config = dirs.config_dir().join("config.toml");
+/// # None
+/// # }
+/// ```
+///
+/// Apps with multiple accounts or environments can use [profiles](AppDirs::with_profile).
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct AppDirs {
+    config: PathBuf,
+    data: PathBuf,
+    cache: PathBuf,
+    state: PathBuf,
+    runtime: Option<PathBuf>,
+    profile: Option<Profile>,
+}
+
+/// Directories of the chosen profile.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+struct Profile {
+    name: String,
+    config: PathBuf,
+    cache: PathBuf,
+    state: PathBuf,
+}
+
+impl AppDirs {
+    /// Resolve directories of the application `app` by the organization `org`, with reverse
+    /// domain `qualifier` (like `com`), using the current environment and
+    /// [`MacOSBehaviour::UseLibrary`].
+    ///
+    /// # Returns
+    ///
+    /// [`None`], if any of the base directories (except the runtime one) can't be resolved,
+    /// see [`xdg::config`].
+    pub fn new(qualifier: &str, org: &str, app: &str) -> Option<Self> {
+        Self::with_env(&Env::new(), MacOSBehaviour::UseLibrary, qualifier, org, app)
+    }
+
+    /// Same as the [`AppDirs::new`], but using `env` and `behaviour` (which can be set
+    /// [per category](xdg::Behaviour), each directory is named accordingly).
+    pub fn with_env(
+        env: &Env,
+        behaviour: impl Into<Behaviour>,
+        qualifier: &str,
+        org: &str,
+        app: &str,
+    ) -> Option<Self> {
+        let behaviour = behaviour.into();
+        let name = |behaviour: MacOSBehaviour| {
+            if cfg!(windows) {
+                Path::new(org).join(app)
+            } else if cfg!(target_os = "macos") && behaviour == MacOSBehaviour::UseLibrary {
+                let bundle = [qualifier, org, app]
+                    .iter()
+                    .filter(|part| !part.is_empty())
+                    .map(|part| part.trim().replace(' ', "-"))
+                    .collect::<Vec<_>>()
+                    .join(".");
+                PathBuf::from(bundle)
+            } else {
+                let name: String = app
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .flat_map(char::to_lowercase)
+                    .collect();
+                PathBuf::from(name)
+            }
+        };
+        let app_dir = |base: PathBuf, behaviour: MacOSBehaviour, kind: &str| {
+            let dir = base.join(name(behaviour));
+            if cfg!(windows) { dir.join(kind) } else { dir }
+        };
+        Some(Self {
+            config: app_dir(xdg::config(env, behaviour)?, behaviour.config, "config"),
+            data: app_dir(xdg::data(env, behaviour)?, behaviour.data, "data"),
+            cache: app_dir(xdg::cache(env, behaviour)?, behaviour.cache, "cache"),
+            state: app_dir(xdg::state(env, behaviour)?, behaviour.state, "state"),
+            runtime: xdg::runtime(env).map(|runtime| runtime.join(name(behaviour.config))),
+            profile: None,
+        })
+    }
+
+    /// Use the profile `name`: the config, cache and state directories are nested in a
+    /// `profiles/<name>` subdirectory (data and runtime ones are shared), replacing any profile
+    /// chosen before.
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::config_files::AppDirs;
+    /// # fn foo() -> Option<()> {
+    /// let dirs = AppDirs::new("com", "Example", "App")?.with_profile("work");
+    /// // `~/.config/app/profiles/work/config.toml` on Linux.
+    /// let config = dirs.config_dir().join("config.toml");
+    /// # None
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the `name` isn't a valid [profile name](AppDirs::with_profile_env), like `../other`.
+    pub fn with_profile(mut self, name: &str) -> Self {
+        assert!(is_profile_name(name), "invalid profile name `{name}`");
+        let nested = |base: &Path| base.join("profiles").join(name);
+        self.profile = Some(Profile {
+            name: name.to_owned(),
+            config: nested(&self.config),
+            cache: nested(&self.cache),
+            state: nested(&self.state),
+        });
+        self
+    }
+
+    /// Use the profile named by the `key` variable of the `env` (like `MYAPP_PROFILE`), if it's
+    /// set, see the [`AppDirs::with_profile`].
+    ///
+    /// Values, which aren't a single plain file name (like empty ones, `..`, or containing
+    /// separators) are ignored.
+    pub fn with_profile_env(self, env: &Env, key: &str) -> Self {
+        match env.get(key) {
+            Ok(name) if is_profile_name(name) => self.with_profile(name),
+            _ => self,
+        }
+    }
+
+    /// Return the name of the chosen profile, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_ref().map(|profile| profile.name.as_str())
+    }
+
+    /// List names of the existing profiles (directories in the `profiles` subdirectory of the
+    /// config directory), sorted.
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(Vec<String>)`](Vec) with the names (empty if there are no profiles), otherwise an
+    /// error, as reported by the OS.
+    pub fn list_profiles(&self) -> io::Result<Vec<String>> {
+        let entries = match std::fs::read_dir(self.config.join("profiles")) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut profiles = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if let Ok(name) = entry.file_name().into_string()
+                && entry.file_type()?.is_dir()
+                && is_profile_name(&name)
+            {
+                profiles.push(name);
+            }
+        }
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    /// Return the application config directory, under the [`xdg::config`] (and a
+    /// [profile](AppDirs::with_profile)).
+    pub fn config_dir(&self) -> &Path {
+        self.profile
+            .as_ref()
+            .map_or(&self.config, |profile| &profile.config)
+    }
+
+    /// Return the application data directory, under the [`xdg::data`].
+    pub fn data_dir(&self) -> &Path {
+        &self.data
+    }
+
+    /// Return the application cache directory, under the [`xdg::cache`] (and a
+    /// [profile](AppDirs::with_profile)).
+    pub fn cache_dir(&self) -> &Path {
+        self.profile
+            .as_ref()
+            .map_or(&self.cache, |profile| &profile.cache)
+    }
+
+    /// Return the application state directory, under the [`xdg::state`] (and a
+    /// [profile](AppDirs::with_profile)).
+    pub fn state_dir(&self) -> &Path {
+        self.profile
+            .as_ref()
+            .map_or(&self.state, |profile| &profile.state)
+    }
+
+    /// Return the application runtime directory, under the [`xdg::runtime`] (if it's set).
+    pub fn runtime_dir(&self) -> Option<&Path> {
+        self.runtime.as_deref()
+    }
+}
+
+/// Check, whether the `name` is a single plain file name.
+fn is_profile_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\'])
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+    use crate::fs::TreeSpec;
+    use claim::assert_ok;
+
+    #[test]
+    fn app_name_is_pushed() {
+        let env = Env::from_iter([
+            ("XDG_CONFIG_HOME", "/cfg"),
+            ("XDG_DATA_HOME", "/data"),
+            ("XDG_CACHE_HOME", "/cache"),
+            ("XDG_STATE_HOME", "/state"),
+        ]);
+        let dirs =
+            AppDirs::with_env(&env, MacOSBehaviour::UseLibrary, "com", "Org", "My App").unwrap();
+        assert_eq!(dirs.config_dir(), Path::new("/cfg/myapp"));
+        assert_eq!(dirs.data_dir(), Path::new("/data/myapp"));
+        assert_eq!(dirs.cache_dir(), Path::new("/cache/myapp"));
+        assert_eq!(dirs.state_dir(), Path::new("/state/myapp"));
+        assert_eq!(dirs.runtime_dir(), None);
+    }
+
+    #[test]
+    fn profiles_are_nested() {
+        let tmp = assert_ok!(
+            TreeSpec::new()
+                .file("cfg/app/profiles/work/config.toml", "")
+                .file("cfg/app/profiles/home/config.toml", "")
+                .file("cfg/app/profiles/file", "")
+                .create_temp()
+        );
+        let config = tmp.path().join("cfg");
+        let env = Env::from_iter([
+            ("XDG_CONFIG_HOME", config.to_str().unwrap()),
+            ("XDG_DATA_HOME", "/data"),
+            ("XDG_CACHE_HOME", "/cache"),
+            ("XDG_STATE_HOME", "/state"),
+            ("APP_PROFILE", "work"),
+            ("BAD_PROFILE", "../work"),
+        ]);
+        let dirs = AppDirs::with_env(&env, MacOSBehaviour::LinuxFallback, "", "", "app").unwrap();
+        assert_eq!(assert_ok!(dirs.list_profiles()), ["home", "work"]);
+        assert_eq!(dirs.clone().with_profile_env(&env, "BAD_PROFILE"), dirs);
+
+        let work = dirs
+            .with_profile("home")
+            .with_profile_env(&env, "APP_PROFILE");
+        assert_eq!(work.profile(), Some("work"));
+        assert_eq!(work.config_dir(), config.join("app/profiles/work"));
+        assert_eq!(work.cache_dir(), Path::new("/cache/app/profiles/work"));
+        assert_eq!(work.state_dir(), Path::new("/state/app/profiles/work"));
+        assert_eq!(work.data_dir(), Path::new("/data/app"));
+    }
+}
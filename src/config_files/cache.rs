@@ -0,0 +1,238 @@
+//! Eviction of cache directories, like the ones under the [`xdg::cache`](super::xdg::cache).
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::{AppDirs, cache::{self, PrunePolicy, Recency}};
+//! # use std::time::Duration;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+//! let policy = PrunePolicy::new()
+//!     .max_total_size(512 << 20)
+//!     .max_age(Duration::from_secs(30 * 24 * 60 * 60))
+//!     .recency(Recency::Accessed);
+//! let report = cache::prune(dirs.cache_dir(), policy)?;
+//! eprintln!("freed {} bytes", report.freed);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::fs::PathExt;
+
+/// Which timestamp of files tells how recently they were used.
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Recency {
+    /// Modification time.
+    #[default]
+    Modified,
+    /// Access time, for a least-recently-used eviction (filesystems mounted with the `noatime`
+    /// don't update it, though).
+    Accessed,
+}
+
+/// Policy of the [`prune`].
+///
+/// Default removes nothing.
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct PrunePolicy {
+    max_total_size: Option<u64>,
+    max_age: Option<Duration>,
+    recency: Recency,
+    dry_run: bool,
+}
+
+impl PrunePolicy {
+    /// Create default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove the least recently used files, until their total size is at most `max_total_size`
+    /// bytes.
+    pub fn max_total_size(mut self, max_total_size: u64) -> Self {
+        self.max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// Remove files not used for longer than `max_age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Use the `recency` timestamp for both of the limits.
+    pub fn recency(mut self, recency: Recency) -> Self {
+        self.recency = recency;
+        self
+    }
+
+    /// Don't remove anything, only report what would be removed.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Result of the [`prune`].
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Removed (or, in a [dry run](PrunePolicy::dry_run), to be removed) files, from the least
+    /// recently used.
+    pub removed: Vec<PathBuf>,
+    /// Total size of the removed files.
+    pub freed: u64,
+    /// Total size of the files left.
+    pub remaining: u64,
+}
+
+/// A file in the cache.
+struct Entry {
+    path: PathBuf,
+    len: u64,
+    used: SystemTime,
+}
+
+/// Prune files in the `dir` (recursively) according to the `policy`, removing directories
+/// emptied this way (but never the `dir` itself).
+///
+/// Symlinks are never followed: they are treated as (small) files, and only the links are
+/// removed, so nothing outside of the `dir` is affected.
+///
+/// # Returns
+///
+/// [`Ok(PruneReport)`](PruneReport) on a success, otherwise the first error encountered (files
+/// removed before it stay removed). Files (and directories) removed concurrently are skipped,
+/// and not reported.
+pub fn prune(dir: impl AsRef<Path>, policy: PrunePolicy) -> io::Result<PruneReport> {
+    let dir = dir.as_ref();
+    let mut entries = Vec::new();
+    collect(dir, policy.recency, &mut entries)?;
+    // The least recently used first, then by path, so the order is deterministic.
+    entries.sort_by(|a, b| a.used.cmp(&b.used).then_with(|| a.path.cmp(&b.path)));
+
+    let now = SystemTime::now();
+    let mut total: u64 = entries.iter().map(|entry| entry.len).sum();
+    let mut report = PruneReport::default();
+    for entry in entries {
+        let expired = policy.max_age.is_some_and(|max_age| {
+            now.duration_since(entry.used)
+                .is_ok_and(|age| age > max_age)
+        });
+        let oversized = policy
+            .max_total_size
+            .is_some_and(|max_total_size| total > max_total_size);
+        if !expired && !oversized {
+            continue;
+        }
+        if !policy.dry_run {
+            match entry.path.rm() {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    total -= entry.len;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            }
+            match entry.path.remove_empty_parents(dir) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        total -= entry.len;
+        report.freed += entry.len;
+        report.removed.push(entry.path);
+    }
+    report.remaining = total;
+    Ok(report)
+}
+
+fn collect(dir: &Path, recency: Recency, entries: &mut Vec<Entry>) -> io::Result<()> {
+    /// Skip entries removed, while they are collected.
+    fn present<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let Some(entry) = present(entry)? else {
+            continue;
+        };
+        // Doesn't follow symlinks.
+        let Some(metadata) = present(entry.metadata())? else {
+            continue;
+        };
+        if metadata.is_dir() {
+            present(collect(&entry.path(), recency, entries))?;
+            continue;
+        }
+        let used = match recency {
+            Recency::Modified => metadata.modified()?,
+            Recency::Accessed => metadata.accessed()?,
+        };
+        entries.push(Entry {
+            path: entry.path(),
+            len: metadata.len(),
+            used,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::TreeSpec;
+    use claim::assert_ok;
+
+    #[test]
+    fn oldest_files_are_pruned_first() {
+        let tmp = assert_ok!(
+            TreeSpec::new()
+                .file("a/old", "1234")
+                .file("new", "1234")
+                .file("b/c/older", "1234")
+                .create_temp()
+        );
+        let root = tmp.path();
+        let now = SystemTime::now();
+        let hour = Duration::from_secs(60 * 60);
+        for (path, age) in [("a/old", 2), ("new", 0), ("b/c/older", 3)] {
+            assert_ok!(root.join(path).set_modified(now - hour * age));
+        }
+
+        let policy = PrunePolicy::new().max_total_size(4).dry_run(true);
+        let report = assert_ok!(prune(root, policy));
+        assert_eq!(report.removed, [root.join("b/c/older"), root.join("a/old")]);
+        assert_eq!((report.freed, report.remaining), (8, 4));
+        assert!(root.join("b/c/older").exists());
+
+        let policy = PrunePolicy::new().max_age(hour * 5 / 2);
+        let report = assert_ok!(prune(root, policy));
+        assert_eq!(report.removed, [root.join("b/c/older")]);
+        assert!(!root.join("b").exists());
+        assert!(root.join("a/old").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_not_followed() {
+        let outside = assert_ok!(TreeSpec::new().file("data/file", "1234").create_temp());
+        let cache = assert_ok!(TreeSpec::new().create_temp());
+        let link = cache.path().join("link");
+        assert_ok!(std::os::unix::fs::symlink(
+            outside.path().join("data"),
+            &link
+        ));
+        let report = assert_ok!(prune(cache.path(), PrunePolicy::new().max_total_size(0)));
+        assert_eq!(report.removed, [link]);
+        assert!(outside.path().join("data/file").exists());
+    }
+}
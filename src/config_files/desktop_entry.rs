@@ -0,0 +1,505 @@
+//! Reading and writing of the freedesktop [desktop entries] (`.desktop` files), which register
+//! applications in menus, and autostart them.
+//!
+//! A [`DesktopEntry`] is an [`Ini`] with the `[Desktop Entry]` group, so comments and other
+//! groups (like actions) are kept, when editing. Its string values are escaped, lists are
+//! separated by `;`, keys can be [localized](DesktopEntry::get_localized), and the `Exec` key is
+//! [split](split_exec) into arguments by the spec rules:
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::desktop_entry::{self, DesktopEntry, InstallDir};
+//! # use rustvil::os::env::Env;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut entry = DesktopEntry::new("Frobnicator");
+//! entry.set_exec(&["/opt/frob/bin/frob", "--gui", "%F"]);
+//! entry.set_list("Categories", &["Utility", "Development"]);
+//! entry.set_localized("Name", "de", "Frobnikator");
+//! let id = "com.example.Frobnicator";
+//! let path = desktop_entry::install(&entry, id, InstallDir::Applications, &Env::new())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [desktop entries]: https://specifications.freedesktop.org/desktop-entry-spec/latest/
+
+use std::{
+    fmt::{self, Display},
+    io,
+    path::PathBuf,
+};
+
+use thiserror::Error;
+
+use crate::config_files::{
+    Backup,
+    ini::{Ini, IniError},
+    save_atomic,
+    xdg::{self, MacOSBehaviour},
+};
+use crate::os::env::Env;
+
+/// Name of the main group.
+const GROUP: &str = "Desktop Entry";
+
+/// Errors of the desktop entries.
+#[non_exhaustive]
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum DesktopEntryError {
+    /// File isn't a valid INI.
+    #[error(transparent)]
+    Ini(#[from] IniError),
+
+    /// File has no `[Desktop Entry]` group.
+    #[error("missing the `[Desktop Entry]` group")]
+    MissingGroup,
+
+    /// The `Exec` key isn't quoted properly.
+    #[error("invalid `Exec`: {0}")]
+    Exec(String),
+}
+
+/// Parsed desktop entry, see the [module](self) docs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DesktopEntry {
+    ini: Ini,
+}
+
+/// Where the [`install`] puts a desktop entry.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum InstallDir {
+    /// `applications` in the [`xdg::data`], for application menus.
+    Applications,
+    /// `autostart` in the [`xdg::config`], for starting on a login.
+    Autostart,
+}
+
+impl DesktopEntry {
+    /// Create an entry of an application (`Type=Application`) with the `name`.
+    pub fn new(name: &str) -> Self {
+        let mut entry = Self { ini: Ini::new() };
+        entry.set("Type", "Application");
+        entry.set("Name", name);
+        entry
+    }
+
+    /// Parse the `contents` of a `.desktop` file.
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(DesktopEntry)`](DesktopEntry) on a success, otherwise a [`DesktopEntryError`].
+    pub fn parse(contents: &str) -> Result<Self, DesktopEntryError> {
+        let ini = Ini::parse(contents)?;
+        if ini.section(GROUP).is_none() {
+            return Err(DesktopEntryError::MissingGroup);
+        }
+        Ok(Self { ini })
+    }
+
+    /// Return the underlying [`Ini`], e.g. to read the `[Desktop Action ...]` groups.
+    pub fn ini(&self) -> &Ini {
+        &self.ini
+    }
+
+    /// Return the underlying [`Ini`] mutably (values written there aren't escaped).
+    pub fn ini_mut(&mut self) -> &mut Ini {
+        &mut self.ini
+    }
+
+    /// Return the unescaped string value of the `key`.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.ini.get(GROUP, key).map(unescape)
+    }
+
+    /// Return the value of the `key` for the `locale` (like `de_DE.UTF-8@euro`), falling back
+    /// as the spec requires: `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`, `lang@MODIFIER`, `lang`,
+    /// and the unlocalized `key`.
+    pub fn get_localized(&self, key: &str, locale: &str) -> Option<String> {
+        let locale = match locale.split_once('.') {
+            // Encoding is ignored, but the modifier is kept.
+            Some((base, rest)) => match rest.split_once('@') {
+                Some((_, modifier)) => format!("{base}@{modifier}"),
+                None => base.to_owned(),
+            },
+            None => locale.to_owned(),
+        };
+        let (base, modifier) = match locale.split_once('@') {
+            Some((base, modifier)) => (base, Some(modifier)),
+            None => (locale.as_str(), None),
+        };
+        let (lang, country) = match base.split_once('_') {
+            Some((lang, country)) => (lang, Some(country)),
+            None => (base, None),
+        };
+        let mut candidates = Vec::new();
+        if let (Some(country), Some(modifier)) = (country, modifier) {
+            candidates.push(format!("{lang}_{country}@{modifier}"));
+        }
+        if let Some(country) = country {
+            candidates.push(format!("{lang}_{country}"));
+        }
+        if let Some(modifier) = modifier {
+            candidates.push(format!("{lang}@{modifier}"));
+        }
+        if !lang.is_empty() {
+            candidates.push(lang.to_owned());
+        }
+        candidates
+            .iter()
+            .find_map(|locale| self.get(&format!("{key}[{locale}]")))
+            .or_else(|| self.get(key))
+    }
+
+    /// Return the list value of the `key` (separated by `;`).
+    pub fn get_list(&self, key: &str) -> Option<Vec<String>> {
+        let value = self.ini.get(GROUP, key)?;
+        let mut items = Vec::new();
+        let mut item = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(';') => item.push(';'),
+                    Some(escaped) => {
+                        item.push('\\');
+                        item.push(escaped);
+                    }
+                    None => item.push('\\'),
+                },
+                ';' => items.push(unescape(&std::mem::take(&mut item))),
+                c => item.push(c),
+            }
+        }
+        if !item.is_empty() {
+            items.push(unescape(&item));
+        }
+        Some(items)
+    }
+
+    /// Return the boolean value of the `key` (`true` or `false`).
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.ini.get(GROUP, key)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Return the arguments of the `Exec` key, see the [`split_exec`].
+    pub fn exec(&self) -> Option<Result<Vec<String>, DesktopEntryError>> {
+        self.get("Exec").map(|exec| split_exec(&exec))
+    }
+
+    /// Set the `key` to the string `value` (escaping it).
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.ini.set(GROUP, key, escape(value));
+    }
+
+    /// Set the `key` for the `locale` (like `de` or `pt_BR`) to the `value`.
+    pub fn set_localized(&mut self, key: &str, locale: &str, value: &str) {
+        self.set(&format!("{key}[{locale}]"), value);
+    }
+
+    /// Set the `key` to the list of `items`.
+    pub fn set_list(&mut self, key: &str, items: &[&str]) {
+        let value: String = items
+            .iter()
+            .map(|item| format!("{};", escape(item).replace(';', "\\;")))
+            .collect();
+        self.ini.set(GROUP, key, value);
+    }
+
+    /// Set the `key` to the boolean `value`.
+    pub fn set_bool(&mut self, key: &str, value: bool) {
+        self.ini.set(GROUP, key, value.to_string());
+    }
+
+    /// Set the `Exec` key to the `args`, quoting them as needed. Arguments, which are a field
+    /// code (like `%F`), are kept as they are, in others `%` is escaped as `%%`.
+    pub fn set_exec(&mut self, args: &[&str]) {
+        let exec: Vec<String> = args
+            .iter()
+            .map(|arg| {
+                if is_field_code(arg) {
+                    (*arg).to_owned()
+                } else {
+                    quote(&arg.replace('%', "%%"))
+                }
+            })
+            .collect();
+        self.set("Exec", &exec.join(" "));
+    }
+
+    /// Remove the `key`, returning its raw value, if any.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.ini.remove(GROUP, key)
+    }
+}
+
+impl Display for DesktopEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.ini.fmt(f)
+    }
+}
+
+/// Split the (unescaped) value of the `Exec` key into arguments: they are separated by spaces,
+/// and may be enclosed in double quotes, inside of which the `"`, `` ` ``, `$` and `\` are
+/// escaped by a backslash.
+///
+/// Field codes (like `%f`, or `%%`) are kept as they are, so they can be expanded in the
+/// arguments afterwards.
+///
+/// # Returns
+///
+/// [`Ok(Vec<String>)`](Vec) with the arguments, otherwise a [`DesktopEntryError::Exec`].
+pub fn split_exec(exec: &str) -> Result<Vec<String>, DesktopEntryError> {
+    let mut args = Vec::new();
+    let mut chars = exec.chars().peekable();
+    loop {
+        while chars.next_if_eq(&' ').is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Ok(args);
+        };
+        let mut arg = String::new();
+        if first == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(escaped @ ('"' | '`' | '$' | '\\')) => arg.push(escaped),
+                        _ => {
+                            return Err(DesktopEntryError::Exec(
+                                "invalid escape in a quoted argument".to_owned(),
+                            ));
+                        }
+                    },
+                    Some(c) => arg.push(c),
+                    None => {
+                        return Err(DesktopEntryError::Exec(
+                            "unterminated quoted argument".to_owned(),
+                        ));
+                    }
+                }
+            }
+            if chars.peek().is_some_and(|&c| c != ' ') {
+                return Err(DesktopEntryError::Exec(
+                    "expected a space after a quoted argument".to_owned(),
+                ));
+            }
+        } else {
+            while let Some(c) = chars.next_if(|&c| c != ' ') {
+                arg.push(c);
+            }
+        }
+        args.push(arg);
+    }
+}
+
+/// Install the `entry` as `<id>.desktop` (where the `id` is like `com.example.App`) into the
+/// `dir`, resolved from the `env` (atomically, creating the directory).
+///
+/// # Returns
+///
+/// [`Ok(PathBuf)`](PathBuf) with the path of the installed file, an error of kind
+/// [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput), if the `id` is empty, `.` or `..`,
+/// or contains a path separator, otherwise an error, as reported by the OS (of kind
+/// [`ErrorKind::NotFound`](io::ErrorKind::NotFound), if the base directory can't be resolved).
+pub fn install(entry: &DesktopEntry, id: &str, dir: InstallDir, env: &Env) -> io::Result<PathBuf> {
+    // Otherwise the file could be written outside of the `dir`.
+    if matches!(id, "" | "." | "..") || id.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid desktop entry id `{id}`"),
+        ));
+    }
+    let behaviour = MacOSBehaviour::LinuxFallback;
+    let base = match dir {
+        InstallDir::Applications => {
+            xdg::try_data(env, behaviour).map(|data| data.join("applications"))
+        }
+        InstallDir::Autostart => {
+            xdg::try_config(env, behaviour).map(|config| config.join("autostart"))
+        }
+    }
+    .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))?;
+    let path = base.join(format!("{id}.desktop"));
+    save_atomic(&path, entry.to_string(), Backup::No)?;
+    Ok(path)
+}
+
+/// Escape a string value.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for (index, c) in value.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            // Otherwise it would be trimmed.
+            ' ' if index == 0 => escaped.push_str("\\s"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Unescape a string value (unknown escapes are kept).
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Check, whether the `arg` is a field code of the `Exec` (including the deprecated ones).
+fn is_field_code(arg: &str) -> bool {
+    matches!(
+        arg,
+        "%f" | "%F" | "%u" | "%U" | "%i" | "%c" | "%k" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m"
+    )
+}
+
+/// Quote an argument of the `Exec`, if it contains reserved characters.
+fn quote(arg: &str) -> String {
+    const RESERVED: &[char] = &[
+        ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(',
+        ')', '`',
+    ];
+    if !arg.is_empty() && !arg.contains(RESERVED) {
+        return arg.to_owned();
+    }
+    let mut quoted = String::from('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+    use std::ffi::OsString;
+
+    const ENTRY: &str = r#"# Installed by the frobnicator.
+[Desktop Entry]
+Type=Application
+Name=Frobnicator
+Name[de]=Frobnikator
+Name[sr@latin]=Frobnikator (latinica)
+Comment=Frobnicates\sthings\nquickly
+Exec="/opt/My Apps/frob" --title "a \\"b\\"" %F
+Categories=Utility;Semi\;colon;
+Terminal=false
+
+[Desktop Action new]
+Name=New Window
+"#;
+
+    #[test]
+    fn entry_is_parsed() {
+        let entry = assert_ok!(DesktopEntry::parse(ENTRY));
+        assert_eq!(entry.get("Type").as_deref(), Some("Application"));
+        assert_eq!(
+            entry.get("Comment").as_deref(),
+            Some("Frobnicates things\nquickly")
+        );
+        assert_eq!(
+            entry.get_localized("Name", "de_AT.UTF-8").as_deref(),
+            Some("Frobnikator")
+        );
+        assert_eq!(
+            entry.get_localized("Name", "sr_RS.UTF-8@latin").as_deref(),
+            Some("Frobnikator (latinica)")
+        );
+        assert_eq!(
+            entry.get_localized("Name", "fr_FR").as_deref(),
+            Some("Frobnicator")
+        );
+        assert_eq!(
+            entry.get_list("Categories"),
+            Some(vec!["Utility".to_owned(), "Semi;colon".to_owned()])
+        );
+        assert_eq!(entry.get_bool("Terminal"), Some(false));
+        assert_eq!(
+            assert_ok!(entry.exec().unwrap()),
+            ["/opt/My Apps/frob", "--title", "a \"b\"", "%F"]
+        );
+        assert_eq!(
+            entry.ini().get("Desktop Action new", "Name"),
+            Some("New Window")
+        );
+        assert_eq!(entry.to_string(), ENTRY);
+        assert_eq!(
+            assert_err!(DesktopEntry::parse("Name=x\n")),
+            DesktopEntryError::MissingGroup
+        );
+    }
+
+    #[test]
+    fn written_values_are_read_back() {
+        let mut entry = DesktopEntry::new(" Spaced");
+        let args = ["/bin/app", "--name", "it's $HOME", "back\\slash", "%u"];
+        entry.set_exec(&args);
+        entry.set_list("Keywords", &["a;b", "c"]);
+        let entry = assert_ok!(DesktopEntry::parse(&entry.to_string()));
+        assert_eq!(entry.get("Name").as_deref(), Some(" Spaced"));
+        assert_eq!(assert_ok!(entry.exec().unwrap()), args);
+        assert_eq!(
+            entry.get_list("Keywords"),
+            Some(vec!["a;b".to_owned(), "c".to_owned()])
+        );
+        let mut entry = DesktopEntry::new("Percent");
+        entry.set_exec(&["app", "--progress=50%", "%f"]);
+        assert_eq!(entry.get("Exec").as_deref(), Some("app --progress=50%% %f"));
+        assert_err!(split_exec("\"unterminated"));
+        assert_err!(split_exec("\"a\"b"));
+    }
+
+    #[test]
+    fn entry_is_installed() {
+        let tmp = tempfile::tempdir().expect("needed for tests");
+        let env: Env = [(OsString::from("XDG_DATA_HOME"), OsString::from(tmp.path()))].into();
+        let entry = DesktopEntry::new("App");
+        let path = assert_ok!(install(
+            &entry,
+            "com.example.App",
+            InstallDir::Applications,
+            &env
+        ));
+        assert_eq!(
+            path,
+            tmp.path().join("applications/com.example.App.desktop")
+        );
+        assert_eq!(
+            assert_ok!(crate::fs::PathExt::read_to_string(path.as_path())),
+            entry.to_string()
+        );
+        for id in ["", "..", "../x", "a/b", "a\\b"] {
+            let err = assert_err!(install(&entry, id, InstallDir::Applications, &env));
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
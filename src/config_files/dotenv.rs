@@ -0,0 +1,461 @@
+//! Loader of the `.env` files.
+//!
+//! Files consist of `KEY=value` lines (optionally prefixed with `export`), with `#` comments:
+//!
+//! ```text
+//! # Comment.
+//! export DATABASE_HOST=localhost  # Inline comments need a space before the `#`.
+//! DATABASE_URL="postgres://${DATABASE_HOST}:${DATABASE_PORT:-5432}/app"
+//! GREETING='Hello,
+//! World!'
+//! ```
+//!
+//! - unquoted values are trimmed, and can escape characters with a `\`,
+//! - `'single-quoted'` values are literal,
+//! - `"double-quoted"` values support the `\n`, `\r`, `\t`, `\"`, `\\` and `\$` escapes,
+//! - both quoted values can span multiple lines,
+//! - `$VAR`, `${VAR}` and `${VAR:-default}` are interpolated in unquoted and double-quoted
+//!   values, from the variables defined above and the environment (undefined ones become empty).
+//!
+//! Loading never modifies the process environment, unless the [`load_into_process`] is used.
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::dotenv;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let env = dotenv::load(".env")?;
+//! let url = env.get("DATABASE_URL")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::Chars,
+};
+
+use thiserror::Error;
+
+use crate::fs::PathExt;
+use crate::os::env::Env;
+
+/// Errors encountered when loading a `.env` file.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DotenvError {
+    /// File couldn't be read.
+    #[error("cannot read `{}`: {source}", .path.display())]
+    Io {
+        /// Path of the file.
+        path: PathBuf,
+        /// Underlying error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// File has an invalid syntax.
+    #[error("{}:{line}: {message}", .path.display())]
+    Parse {
+        /// Path of the file.
+        path: PathBuf,
+        /// Line of the error (1-based).
+        line: usize,
+        /// Description of the error.
+        message: String,
+    },
+}
+
+/// Syntax error of `.env` contents, see the [`Env::from_dotenv_str`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct ParseError {
+    /// Line of the error (1-based).
+    pub line: usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+/// Load variables of the `.env` file at the `path`, interpolating variables missing in the file
+/// from the process environment.
+///
+/// # Returns
+///
+/// [`Ok(Env)`](Env) with only the variables of the file on a success, otherwise a
+/// [`DotenvError`].
+pub fn load(path: impl AsRef<Path>) -> Result<Env, DotenvError> {
+    let path = path.as_ref();
+    let variables = parse_file(path, &Env::new(), Precedence::File)?;
+    Ok(variables
+        .into_iter()
+        .map(|(key, value)| (OsString::from(key), OsString::from(value)))
+        .collect())
+}
+
+/// Load variables of the `.env` file at the `path` into the `env`.
+///
+/// Variables already in the `env` take precedence, both for the interpolation, and over the
+/// ones in the file (which only fills the missing ones), the same as in most `.env` loaders.
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise a [`DotenvError`] (and the `env` is left untouched).
+pub fn load_into(path: impl AsRef<Path>, env: &mut Env) -> Result<(), DotenvError> {
+    let variables = parse_file(path.as_ref(), env, Precedence::Env)?;
+    for (key, value) in variables {
+        if !env.has(&key) {
+            env.set(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Same as the [`load_into`], but sets the variables in the process environment (skipping the
+/// ones already set).
+///
+/// # Safety
+///
+/// Same as for the [`std::env::set_var`]: no other thread may access the environment at the
+/// same time (which, in practice, should only be done early in the `main`).
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise a [`DotenvError`] (and the environment is left
+/// untouched).
+pub unsafe fn load_into_process(path: impl AsRef<Path>) -> Result<(), DotenvError> {
+    let env = Env::new();
+    let variables = parse_file(path.as_ref(), &env, Precedence::Env)?;
+    for (key, value) in variables {
+        if !env.has(&key) {
+            // SAFETY: Upheld by the caller.
+            unsafe { std::env::set_var(key, value) };
+        }
+    }
+    Ok(())
+}
+
+/// Which variables are used first by the interpolation.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Precedence {
+    /// Ones defined earlier in the file.
+    File,
+    /// Ones in the [`Env`].
+    Env,
+}
+
+fn parse_file(
+    path: &Path,
+    env: &Env,
+    precedence: Precedence,
+) -> Result<Vec<(String, String)>, DotenvError> {
+    let contents = path.read_to_string().map_err(|source| DotenvError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse(&contents, env, precedence).map_err(|err| DotenvError::Parse {
+        path: path.to_path_buf(),
+        line: err.line,
+        message: err.message,
+    })
+}
+
+/// Parse the `contents` of a `.env` file into variables (in order, possibly repeated),
+/// interpolating them from the `env` too.
+pub(crate) fn parse(
+    contents: &str,
+    env: &Env,
+    precedence: Precedence,
+) -> Result<Vec<(String, String)>, ParseError> {
+    let mut parser = Parser {
+        chars: contents.chars().peekable(),
+        line: 1,
+        env,
+        precedence,
+        defined: HashMap::new(),
+    };
+    let mut variables = Vec::new();
+    while let Some((key, value)) = parser.variable()? {
+        parser.defined.insert(key.clone(), value.clone());
+        variables.push((key, value));
+    }
+    Ok(variables)
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    env: &'a Env,
+    precedence: Precedence,
+    defined: HashMap<String, String>,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn next_if(&mut self, predicate: impl FnOnce(&char) -> bool) -> Option<char> {
+        let c = self.chars.next_if(predicate)?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_blanks(&mut self) {
+        while self.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+    }
+
+    /// Skip the rest of the line, which may only contain a comment.
+    fn end_of_line(&mut self) -> Result<(), ParseError> {
+        self.skip_blanks();
+        match self.next() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.next_if(|&c| c == '\n').is_some() => Ok(()),
+            Some('#') => {
+                while self.next().is_some_and(|c| c != '\n') {}
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("unexpected `{c}` after the value"))),
+        }
+    }
+
+    /// Parse a variable name (interpolated ones can't contain dots, like in shells).
+    fn key(&mut self, dots: bool) -> String {
+        let mut key = String::new();
+        while let Some(c) =
+            self.next_if(|&c| c.is_ascii_alphanumeric() || c == '_' || (dots && c == '.'))
+        {
+            key.push(c);
+        }
+        key
+    }
+
+    /// Parse the next `KEY=value`, skipping empty lines and comments.
+    fn variable(&mut self) -> Result<Option<(String, String)>, ParseError> {
+        loop {
+            while self.next_if(|c| c.is_whitespace()).is_some() {}
+            match self.chars.peek() {
+                None => return Ok(None),
+                Some('#') => self.end_of_line()?,
+                Some(_) => break,
+            }
+        }
+        let mut key = self.key(true);
+        if key == "export" && self.next_if(|&c| c == ' ' || c == '\t').is_some() {
+            self.skip_blanks();
+            // Unless it's a variable named `export`.
+            if self.chars.peek() != Some(&'=') {
+                key = self.key(true);
+            }
+        }
+        if key.is_empty() || key.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error("expected a variable name"));
+        }
+        self.skip_blanks();
+        if self.next_if(|&c| c == '=').is_none() {
+            return Err(self.error(format!("expected `=` after `{key}`")));
+        }
+        self.skip_blanks();
+        let value = match self.chars.peek() {
+            Some('\'') => self.single_quoted()?,
+            Some('"') => self.double_quoted()?,
+            _ => self.unquoted()?,
+        };
+        self.end_of_line()?;
+        Ok(Some((key, value)))
+    }
+
+    fn single_quoted(&mut self) -> Result<String, ParseError> {
+        let line = self.line;
+        self.next();
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(value),
+                Some(c) => value.push(c),
+                None => return Err(unterminated(line)),
+            }
+        }
+    }
+
+    fn double_quoted(&mut self) -> Result<String, ParseError> {
+        let line = self.line;
+        self.next();
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => match self.next() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => return Err(unterminated(line)),
+                },
+                Some('$') => self.interpolate(&mut value)?,
+                Some(c) => value.push(c),
+                None => return Err(unterminated(line)),
+            }
+        }
+    }
+
+    fn unquoted(&mut self) -> Result<String, ParseError> {
+        let mut value = String::new();
+        while let Some(c) = self.next_if(|&c| c != '\n') {
+            match c {
+                '#' if value.ends_with([' ', '\t']) || value.is_empty() => {
+                    while self.next_if(|&c| c != '\n').is_some() {}
+                }
+                '\\' => value.extend(self.next_if(|&c| c != '\n')),
+                '$' => self.interpolate(&mut value)?,
+                c => value.push(c),
+            }
+        }
+        Ok(value.trim_end().to_owned())
+    }
+
+    /// Append the value of the variable after a `$`, or a literal `$` if there is no name.
+    fn interpolate(&mut self, value: &mut String) -> Result<(), ParseError> {
+        if self.next_if(|&c| c == '{').is_none() {
+            let key = self.key(false);
+            if key.is_empty() {
+                value.push('$');
+            } else {
+                value.push_str(&self.lookup(&key).unwrap_or_default());
+            }
+            return Ok(());
+        }
+        let line = self.line;
+        let key = self.key(false);
+        let default = if self.next_if(|&c| c == ':').is_some() {
+            if self.next_if(|&c| c == '-').is_none() {
+                return Err(self.error("expected `:-` in `${...}`"));
+            }
+            let mut default = String::new();
+            while let Some(c) = self.next_if(|&c| c != '}') {
+                default.push(c);
+            }
+            Some(default)
+        } else {
+            None
+        };
+        if self.next_if(|&c| c == '}').is_none() {
+            return Err(ParseError {
+                line,
+                message: "unterminated `${`".to_owned(),
+            });
+        }
+        let found = self.lookup(&key).filter(|found| !found.is_empty());
+        value.push_str(&found.or(default).unwrap_or_default());
+        Ok(())
+    }
+
+    fn lookup(&self, key: &str) -> Option<String> {
+        let defined = || self.defined.get(key).cloned();
+        let env = || self.env.get(key).ok().map(str::to_owned);
+        match self.precedence {
+            Precedence::File => defined().or_else(env),
+            Precedence::Env => env().or_else(defined),
+        }
+    }
+}
+
+fn unterminated(line: usize) -> ParseError {
+    ParseError {
+        line,
+        message: "unterminated quoted value".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+    use tempfile::tempdir;
+
+    fn parse_str(contents: &str) -> Result<Vec<(String, String)>, ParseError> {
+        let env = Env::from([(OsString::from("HOME"), OsString::from("/home/user"))]);
+        parse(contents, &env, Precedence::File)
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn syntax_is_parsed() {
+        let contents = r#"
+# Comment
+export HOST = localhost  # Inline comment.
+URL="http://${HOST}:${PORT:-8080}/\$path\n"
+LITERAL='$HOST \n # not a comment'
+MULTILINE="a
+b"
+CACHE=$HOME/.cache#not-a-comment
+EMPTY=
+ESCAPED=a\ b\#c
+"#;
+        assert_eq!(
+            assert_ok!(parse_str(contents)),
+            pairs(&[
+                ("HOST", "localhost"),
+                ("URL", "http://localhost:8080/$path\n"),
+                ("LITERAL", "$HOST \\n # not a comment"),
+                ("MULTILINE", "a\nb"),
+                ("CACHE", "/home/user/.cache#not-a-comment"),
+                ("EMPTY", ""),
+                ("ESCAPED", "a b#c"),
+            ])
+        );
+    }
+
+    #[test]
+    fn syntax_errors_have_lines() {
+        let err = assert_err!(parse_str("A=1\nB\n"));
+        assert_eq!(err.line, 2);
+        let err = assert_err!(parse_str("A=1\n\nB=\"unterminated\n\n"));
+        assert_eq!(err.line, 3);
+        let err = assert_err!(parse_str("A='x' y\n"));
+        assert_eq!(err.line, 1);
+        assert_err!(parse_str("1A=x\n"));
+    }
+
+    #[test]
+    fn env_takes_precedence_when_loading_into() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join(".env");
+        assert_ok!(path.write("USER=file\nGREETING=\"hi $USER\"\nNEW=1\n"));
+        let mut env = Env::from([(OsString::from("USER"), OsString::from("env"))]);
+        assert_ok!(load_into(&path, &mut env));
+        assert_eq!(env.get("USER"), Ok("env"));
+        assert_eq!(env.get("GREETING"), Ok("hi env"));
+        assert_eq!(env.get("NEW"), Ok("1"));
+
+        let loaded = assert_ok!(load(&path));
+        assert_eq!(loaded.get("GREETING"), Ok("hi file"));
+        let err = assert_err!(load(tmp.path().join("missing")));
+        assert!(matches!(err, DotenvError::Io { .. }));
+    }
+}
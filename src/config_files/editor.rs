@@ -0,0 +1,111 @@
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::os::env::Env;
+
+/// Resolve the user's editor: the `$VISUAL`, or the `$EDITOR` (ignoring empty ones), or a
+/// platform fallback: `notepad` on Windows, otherwise `nano` (if it's in the `$PATH`) or `vi`.
+///
+/// The value may contain arguments, like `code --wait`, separated by whitespace (unless the
+/// whole value is an existing file, like `C:\Program Files\Editor\editor.exe`); quoting isn't
+/// supported.
+///
+/// # Returns
+///
+/// [`Command`] running the editor, to which the file to edit should be appended as the last
+/// argument.
+pub fn editor(env: &Env) -> Command {
+    let configured = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .find_map(|key| env.get_non_empty(key));
+    if let Some(value) = configured {
+        return command(value);
+    }
+    let fallback = if cfg!(windows) {
+        "notepad"
+    } else if in_path(env, "nano") {
+        "nano"
+    } else {
+        "vi"
+    };
+    Command::new(fallback)
+}
+
+/// Open the `path` in the [`editor`] (resolved from the process environment), and wait for it
+/// to exit.
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{AppDirs, open_in_editor};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+/// open_in_editor(dirs.config_dir().join("config.toml"))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) if the editor exited successfully, otherwise an error, as reported by the OS,
+/// or of kind [`ErrorKind::Other`](io::ErrorKind::Other) describing the exit status.
+pub fn open_in_editor(path: impl AsRef<Path>) -> io::Result<()> {
+    let mut editor = editor(&Env::new());
+    let status = editor.arg(path.as_ref()).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "editor `{}` exited with {status}",
+            editor.get_program().display()
+        )))
+    }
+}
+
+/// Split the `value` of the `$VISUAL` or `$EDITOR` into a program and its arguments.
+fn command(value: &OsStr) -> Command {
+    if Path::new(value).is_file() {
+        return Command::new(value);
+    }
+    let Some(value) = value.to_str() else {
+        return Command::new(value);
+    };
+    let mut words = value.split_whitespace().map(OsString::from);
+    let mut command = Command::new(words.next().unwrap_or_default());
+    command.args(words);
+    command
+}
+
+fn in_path(env: &Env, program: &str) -> bool {
+    env.get_os("PATH").is_some_and(|path| {
+        std::env::split_paths(path)
+            .map(|dir: PathBuf| dir.join(program))
+            .any(|candidate| candidate.is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visual_is_preferred_and_split() {
+        let visual = editor(&Env::from_iter([
+            ("VISUAL", "code  --wait"),
+            ("EDITOR", "vim"),
+        ]));
+        assert_eq!(visual.get_program(), "code");
+        assert_eq!(visual.get_args().collect::<Vec<_>>(), ["--wait"]);
+
+        let empty_visual = editor(&Env::from_iter([("VISUAL", ""), ("EDITOR", "vim")]));
+        assert_eq!(empty_visual.get_program(), "vim");
+
+        let fallback = if cfg!(windows) { "notepad" } else { "vi" };
+        assert_eq!(
+            editor(&Env::from_iter([("PATH", "")])).get_program(),
+            fallback
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use crate::os::env::Env;
+pub use crate::os::env::ExpandError;
+
+/// Substitute variables from the `env` in the `value`, like `"${XDG_DATA_HOME}/app"`.
+///
+/// - `$VAR` and `${VAR}` are replaced with the value of `VAR`,
+/// - `${VAR:-default}` uses the `default` (which gets expanded too), if `VAR` is unset or
+///   empty,
+/// - `$$` is a literal `$`, and so is a `$` not followed by a name.
+///
+/// ```rust,no_run
+/// # use rustvil::config_files;
+/// # use rustvil::os::env::Env;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let path = config_files::expand("${XDG_DATA_HOME:-$HOME/.local/share}/app", &Env::new())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(String)`](String) with the expanded value on a success, otherwise an [`ExpandError`]
+/// (e.g. for undefined variables without a default).
+///
+/// See the [`Env::expand`] for more options (like the Windows-style `%VAR%`).
+pub fn expand(value: &str, env: &Env) -> Result<String, ExpandError> {
+    crate::os::env::expand_posix(value, env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+    use std::ffi::OsString;
+
+    fn env() -> Env {
+        [
+            (OsString::from("HOME"), OsString::from("/home/user")),
+            (OsString::from("EMPTY"), OsString::new()),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn variables_are_substituted() {
+        let env = env();
+        let cases = [
+            ("$HOME/.config", "/home/user/.config"),
+            ("${HOME}_x", "/home/user_x"),
+            (
+                "${XDG_DATA_HOME:-$HOME/.local/share}/app",
+                "/home/user/.local/share/app",
+            ),
+            ("${EMPTY:-${MISSING:-nested}}", "nested"),
+            ("${EMPTY}", ""),
+            ("cost: $$5, $ 1, ${}", "cost: $5, $ 1, ${}"),
+            ("ż$HOME", "ż/home/user"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(assert_ok!(expand(value, &env)), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn undefined_variables_are_errors() {
+        let env = env();
+        assert_eq!(
+            expand("$HOME/$MISSING", &env),
+            Err(ExpandError::Undefined("MISSING".to_owned()))
+        );
+        assert_eq!(
+            expand("${A:-${MISSING}}", &env),
+            Err(ExpandError::Undefined("MISSING".to_owned()))
+        );
+        assert_eq!(expand("a ${HOME", &env), Err(ExpandError::Unterminated(2)));
+        assert_eq!(
+            expand("${A:-x ${B}", &env),
+            Err(ExpandError::Unterminated(0))
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Minimal INI parser and writer.
+//!
+//! Files consist of `[section]` headers and `key = value` entries, with `;` and `#` comments on
+//! their own lines. Entries before the first header belong to the global section, named `""`.
+//! Keys may repeat (like in the systemd units), and values are taken verbatim (trimmed), without
+//! quotes or escapes.
+//!
+//! Order of sections and entries is kept, and so are comments, blank lines and formatting of the
+//! unmodified lines, so that [`Ini`] can be edited and written back with the [`Display`]:
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::ini::Ini;
+//! # use rustvil::fs::PathExt;
+//! # use std::path::Path;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let path = Path::new("app.desktop");
+//! let mut ini = Ini::parse(&path.read_to_string()?)?;
+//! if ini.get("Desktop Entry", "Terminal") != Some("false") {
+//!     ini.set("Desktop Entry", "Terminal", "false");
+//!     path.write(ini.to_string())?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Display};
+
+use thiserror::Error;
+
+/// Syntax error of an INI file.
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+#[error("line {line}: {message}")]
+pub struct IniError {
+    /// Line of the error (1-based).
+    pub line: usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+/// Parsed INI file, see the [module](self) docs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ini {
+    // The first one is the global section (without a header).
+    sections: Vec<Section>,
+}
+
+/// A named section of an [`Ini`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Section {
+    name: String,
+    // Original header line, if unmodified.
+    header: Option<String>,
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Line {
+    Entry {
+        key: String,
+        value: String,
+        // Original line, if unmodified.
+        raw: Option<String>,
+    },
+    // Comments and blank lines, verbatim.
+    Other(String),
+}
+
+impl Section {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            header: None,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Return the name of the section (`""` for the global one).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the value of the last `key` entry of the section.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|(entry, _)| *entry == key)
+            .map(|(_, value)| value)
+            .last()
+    }
+
+    /// Return values of all the `key` entries of the section, in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.entries()
+            .filter(move |(entry, _)| *entry == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Return all the entries of the section, as `(key, value)` pairs, in order.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            Line::Other(_) => None,
+        })
+    }
+}
+
+impl Ini {
+    /// Create an empty [`Ini`].
+    pub fn new() -> Self {
+        Self {
+            sections: vec![Section::new("")],
+        }
+    }
+
+    /// Parse the `contents` of an INI file.
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(Ini)`](Ini) on a success, otherwise an [`IniError`] for lines which are neither
+    /// headers, entries nor comments.
+    pub fn parse(contents: &str) -> Result<Self, IniError> {
+        let mut ini = Self::new();
+        for (index, raw) in contents.lines().enumerate() {
+            let line = raw.trim();
+            let error = |message: &str| IniError {
+                line: index + 1,
+                message: message.to_owned(),
+            };
+            let section = ini
+                .sections
+                .last_mut()
+                .expect("global section always exists");
+            if line.is_empty() || line.starts_with([';', '#']) {
+                section.lines.push(Line::Other(raw.to_owned()));
+            } else if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("expected `]` at the end of the section header"))?;
+                ini.sections.push(Section {
+                    header: Some(raw.to_owned()),
+                    ..Section::new(name.trim())
+                });
+            } else {
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| error("expected `key = value`"))?;
+                let key = key.trim_end();
+                if key.is_empty() {
+                    return Err(error("expected a key before `=`"));
+                }
+                section.lines.push(Line::Entry {
+                    key: key.to_owned(),
+                    value: value.trim_start().to_owned(),
+                    raw: Some(raw.to_owned()),
+                });
+            }
+        }
+        Ok(ini)
+    }
+
+    /// Return all the sections (starting with the global one), in order.
+    ///
+    /// Repeated headers are separate sections.
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter()
+    }
+
+    /// Return the first section named `name`.
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Return the value of the last `key` entry in the sections named `section`.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .rev()
+            .filter(|candidate| candidate.name == section)
+            .find_map(|candidate| candidate.get(key))
+    }
+
+    /// Set the `key` in the `section` to the `value`.
+    ///
+    /// The last existing `key` entry is modified, if any. Otherwise, the entry is added at the end
+    /// of the last section named `section` (which is created, if needed).
+    pub fn set(&mut self, section: &str, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        let existing = self
+            .sections
+            .iter_mut()
+            .filter(|candidate| candidate.name == section)
+            .flat_map(|candidate| candidate.lines.iter_mut())
+            .filter_map(|line| match line {
+                Line::Entry {
+                    key: entry,
+                    value,
+                    raw,
+                } if entry == key => Some((value, raw)),
+                _ => None,
+            })
+            .last();
+        if let Some((old, raw)) = existing {
+            *old = value;
+            *raw = None;
+            return;
+        }
+        let entry = Line::Entry {
+            key: key.to_owned(),
+            value,
+            raw: None,
+        };
+        match self
+            .sections
+            .iter_mut()
+            .rfind(|candidate| candidate.name == section)
+        {
+            Some(existing) => {
+                // Before the trailing blank lines and comments, which usually separate sections.
+                let at = existing
+                    .lines
+                    .iter()
+                    .rposition(|line| matches!(line, Line::Entry { .. }))
+                    .map_or(0, |last| last + 1);
+                existing.lines.insert(at, entry);
+            }
+            None => {
+                let mut new = Section::new(section);
+                new.lines.push(entry);
+                self.sections.push(new);
+            }
+        }
+    }
+
+    /// Remove all the `key` entries in the sections named `section`.
+    ///
+    /// # Returns
+    ///
+    /// Value of the last removed entry, if any.
+    pub fn remove(&mut self, section: &str, key: &str) -> Option<String> {
+        let mut removed = None;
+        for candidate in self.sections.iter_mut().filter(|s| s.name == section) {
+            candidate.lines.retain_mut(|line| match line {
+                Line::Entry {
+                    key: entry, value, ..
+                } if entry == key => {
+                    removed = Some(std::mem::take(value));
+                    false
+                }
+                _ => true,
+            });
+        }
+        removed
+    }
+}
+
+impl Default for Ini {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Ini {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, section) in self.sections.iter().enumerate() {
+            if index > 0 {
+                match &section.header {
+                    Some(header) => writeln!(f, "{header}")?,
+                    None => {
+                        let previous = &self.sections[index - 1];
+                        // By a blank line, unless it's the first one in the file.
+                        let separated = match previous.lines.last() {
+                            Some(Line::Other(line)) => line.trim().is_empty(),
+                            Some(Line::Entry { .. }) => false,
+                            None => index == 1,
+                        };
+                        if !separated {
+                            writeln!(f)?;
+                        }
+                        writeln!(f, "[{}]", section.name)?;
+                    }
+                }
+            }
+            for line in &section.lines {
+                match line {
+                    Line::Entry { raw: Some(raw), .. } | Line::Other(raw) => writeln!(f, "{raw}")?,
+                    Line::Entry { key, value, .. } => writeln!(f, "{key} = {value}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+
+    const UNIT: &str = "\
+; Global comment
+name=unit
+
+[Service]
+# Restart policy.
+ExecStart = /bin/true
+ExecStart = /bin/false
+\tRestart=always
+
+[Install]
+WantedBy=multi-user.target
+";
+
+    #[test]
+    fn entries_are_parsed_in_order() {
+        let ini = assert_ok!(Ini::parse(UNIT));
+        assert_eq!(ini.get("", "name"), Some("unit"));
+        assert_eq!(ini.get("Service", "ExecStart"), Some("/bin/false"));
+        assert_eq!(ini.get("Service", "Restart"), Some("always"));
+        assert_eq!(ini.get("Install", "name"), None);
+        let service = ini.section("Service").unwrap();
+        assert_eq!(
+            service.get_all("ExecStart").collect::<Vec<_>>(),
+            ["/bin/true", "/bin/false"]
+        );
+        let names: Vec<_> = ini.sections().map(Section::name).collect();
+        assert_eq!(names, ["", "Service", "Install"]);
+        assert_eq!(ini.to_string(), UNIT);
+    }
+
+    #[test]
+    fn edits_keep_the_rest_of_the_file() {
+        let mut ini = assert_ok!(Ini::parse(UNIT));
+        ini.set("Service", "Restart", "no");
+        ini.set("Service", "User", "nobody");
+        ini.set("Timer", "OnCalendar", "daily");
+        assert_eq!(
+            ini.remove("Install", "WantedBy").as_deref(),
+            Some("multi-user.target")
+        );
+        assert_eq!(ini.remove("Install", "WantedBy"), None);
+        assert_eq!(
+            ini.to_string(),
+            "\
+; Global comment
+name=unit
+
+[Service]
+# Restart policy.
+ExecStart = /bin/true
+ExecStart = /bin/false
+Restart = no
+User = nobody
+
+[Install]
+
+[Timer]
+OnCalendar = daily
+"
+        );
+        let mut new = Ini::new();
+        new.set("", "root", "1");
+        new.set("a", "key", "value");
+        assert_eq!(new.to_string(), "root = 1\n\n[a]\nkey = value\n");
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected() {
+        let err = assert_err!(Ini::parse("[a]\nkey\n"));
+        assert_eq!(err.line, 2);
+        assert_eq!(assert_err!(Ini::parse("[a\n")).line, 1);
+        assert_eq!(assert_err!(Ini::parse(" = value\n")).line, 1);
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::config_files::{ConfigError, MergeStrategy, load, merge, schema::Value};
+
+/// A config loaded from multiple files (like a system one, and a user one), each
+/// [merged](merge) on top of the previous ones.
+///
+/// With the [host overrides](LayeredConfig::host_overrides), every `config.toml` is followed by
+/// an optional `config.<hostname>.toml`, for dotfiles shared by multiple machines:
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{AppDirs, LayeredConfig};
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     theme: String,
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+/// let config: Config = LayeredConfig::new()
+///     .file("/etc/app/config.toml")
+///     .file(dirs.config_dir().join("config.toml"))
+///     .host_overrides(true)
+///     .load()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LayeredConfig {
+    files: Vec<PathBuf>,
+    host_overrides: bool,
+    strategy: MergeStrategy,
+}
+
+impl LayeredConfig {
+    /// Create a config without any layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the file at the `path` as the next layer (it's skipped, if it doesn't exist).
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(path.into());
+        self
+    }
+
+    /// Follow each file, like `config.toml`, with the `config.<hostname>.toml` (the short
+    /// [hostname](crate::os::hostname), up to the first `.`).
+    pub fn host_overrides(mut self, host_overrides: bool) -> Self {
+        self.host_overrides = host_overrides;
+        self
+    }
+
+    /// Merge the layers according to the `strategy`.
+    pub fn strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Return paths of all the layers, in order (which don't have to exist).
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let host = if self.host_overrides {
+            crate::os::hostname().ok().and_then(|hostname| {
+                let hostname = hostname.to_str()?.split('.').next()?.to_owned();
+                (!hostname.is_empty()).then_some(hostname)
+            })
+        } else {
+            None
+        };
+        self.files
+            .iter()
+            .flat_map(|path| {
+                let host_path = host.as_deref().map(|host| host_path(path, host));
+                std::iter::once(path.clone()).chain(host_path)
+            })
+            .collect()
+    }
+
+    /// Load and merge all the existing layers.
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(Value)`](Value) with the merged value (an empty table, if no layer exists),
+    /// otherwise the first [`ConfigError`].
+    pub fn load_value(&self) -> Result<Value, ConfigError> {
+        let mut merged = Value::Table(Default::default());
+        for path in self.paths() {
+            // Even with an unknown format.
+            if !path.exists() {
+                continue;
+            }
+            let layer: Value = match load(&path) {
+                Ok(layer) => layer,
+                Err(ConfigError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            merge(&mut merged, layer, self.strategy);
+        }
+        Ok(merged)
+    }
+
+    /// Load and merge all the existing layers into a typed `T`.
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(T)`](Ok) on a success, otherwise the first [`ConfigError`], or the
+    /// [`ConfigError::Merged`], if the merged value doesn't match the `T`.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        self.load_value()?
+            .deserialize_into()
+            .map_err(|err| ConfigError::Merged {
+                paths: self.paths(),
+                message: err.to_string(),
+            })
+    }
+}
+
+/// Insert the `host` before the extension of the `path`.
+fn host_path(path: &Path, host: &str) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(host);
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_files::save;
+    use claim::{assert_err, assert_ok};
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    fn extension() -> &'static str {
+        if cfg!(feature = "json") {
+            "json"
+        } else if cfg!(feature = "toml") {
+            "toml"
+        } else {
+            "yaml"
+        }
+    }
+
+    #[test]
+    fn layers_are_merged_in_order() {
+        let tmp = tempdir().expect("needed for tests");
+        let (system, user) = (
+            tmp.path().join(format!("system.{}", extension())),
+            tmp.path().join(format!("user.{}", extension())),
+        );
+        let table = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), Value::from(*value)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert_ok!(save(
+            &system,
+            &table(&[("theme", "dark"), ("font", "mono")])
+        ));
+        assert_ok!(save(&user, &table(&[("theme", "light")])));
+        let host = assert_ok!(crate::os::hostname());
+        let host = host.to_string_lossy();
+        let host = host.split('.').next().unwrap();
+        assert_ok!(save(host_path(&user, host), &table(&[("font", "serif")])));
+
+        let layered = LayeredConfig::new()
+            .file(&system)
+            .file(tmp.path().join("missing.toml"))
+            .file(&user);
+        let merged: BTreeMap<String, String> = assert_ok!(layered.load());
+        assert_eq!(merged["theme"], "light");
+        assert_eq!(merged["font"], "mono");
+        let merged: BTreeMap<String, String> =
+            assert_ok!(layered.clone().host_overrides(true).load());
+        assert_eq!(merged["font"], "serif");
+
+        let err = assert_err!(layered.load::<Vec<String>>());
+        assert!(matches!(err, ConfigError::Merged { .. }), "{err}");
+    }
+
+    #[test]
+    fn host_path_is_inserted_before_extension() {
+        assert_eq!(
+            host_path(Path::new("app/config.toml"), "laptop"),
+            Path::new("app/config.laptop.toml")
+        );
+        assert_eq!(
+            host_path(Path::new(".apprc"), "laptop"),
+            Path::new(".apprc.laptop")
+        );
+    }
+}
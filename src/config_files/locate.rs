@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+
+use crate::config_files::{
+    home,
+    xdg::{self, MacOSBehaviour},
+};
+use crate::os::env::Env;
+
+/// Result of the [`find_config`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ConfigLookup {
+    /// First existing candidate, if any.
+    pub found: Option<PathBuf>,
+    /// All the searched paths, in order (useful for "no config found" errors).
+    pub candidates: Vec<PathBuf>,
+}
+
+/// Search for a config file of the `app`, trying each of the `names` (like `config.toml` or
+/// `config.yaml`) in the `<dir>/<app>` of each config directory: the user one ([`xdg::config`],
+/// with [`MacOSBehaviour::LinuxFallback`]) first, then the [`xdg::config_dirs`].
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::find_config;
+/// # use rustvil::os::env::Env;
+/// let lookup = find_config("frobnicator", &["config.toml", "config.yaml"], &Env::new());
+/// if lookup.found.is_none() {
+///     eprintln!("no config found, searched:");
+///     for candidate in &lookup.candidates {
+///         eprintln!("  {}", candidate.display());
+///     }
+/// }
+/// ```
+pub fn find_config(app: &str, names: &[&str], env: &Env) -> ConfigLookup {
+    let candidates: Vec<PathBuf> = xdg::config_search_paths(env, MacOSBehaviour::LinuxFallback)
+        .flat_map(|dir| {
+            let dir = dir.join(app);
+            names.iter().map(move |name| dir.join(name))
+        })
+        .collect();
+    let found = candidates.iter().find(|path| path.is_file()).cloned();
+    ConfigLookup { found, candidates }
+}
+
+/// Result of the [`find_project_config`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ProjectConfig {
+    /// Found config file.
+    pub path: PathBuf,
+    /// Root of the project: the nearest directory (from the config) containing `.git`, or the
+    /// directory of the config, if there is none.
+    pub root: PathBuf,
+}
+
+/// Search for a project-local config file, trying each of the `names` (like `.apprc` or
+/// `app.toml`) in the `start_dir` and its parents, like git or cargo do.
+///
+/// The search stops at the root of a git repository (a directory containing `.git`), and never
+/// reaches the [`home`] directory (configs there aren't project-local), nor crosses a filesystem
+/// boundary (on `unix`).
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::find_project_config;
+/// # fn main() -> std::io::Result<()> {
+/// if let Some(project) = find_project_config(std::env::current_dir()?, &[".apprc", "app.toml"]) {
+///     eprintln!("using {} in {}", project.path.display(), project.root.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Some(ProjectConfig)`](ProjectConfig) with the nearest config, or [`None`] if there is none
+/// (or the `start_dir` can't be made absolute).
+pub fn find_project_config(start_dir: impl AsRef<Path>, names: &[&str]) -> Option<ProjectConfig> {
+    let start = std::path::absolute(start_dir).ok()?;
+    let home = home();
+    #[cfg(unix)]
+    let device = {
+        use std::os::unix::fs::MetadataExt;
+        let device = |dir: &Path| dir.metadata().ok().map(|metadata| metadata.dev());
+        let start_device = device(&start);
+        move |dir: &Path| device(dir) == start_device
+    };
+    let mut found = None;
+    for dir in start.ancestors() {
+        if home.as_deref() == Some(dir) {
+            break;
+        }
+        #[cfg(unix)]
+        if !device(dir) {
+            break;
+        }
+        if found.is_none() {
+            found = names
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file());
+        }
+        if dir.join(".git").exists() {
+            return found.map(|path| ProjectConfig {
+                path,
+                root: dir.to_path_buf(),
+            });
+        }
+    }
+    found.map(|path| {
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        ProjectConfig { path, root }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::TreeSpec;
+    use claim::{assert_ok, assert_some};
+    use std::ffi::OsString;
+
+    #[test]
+    fn user_config_is_preferred() {
+        let tmp = assert_ok!(
+            TreeSpec::new()
+                .file("user/app/config.yaml", "")
+                .file("system/app/config.toml", "")
+                .create_temp()
+        );
+        let (user, system) = (tmp.path().join("user"), tmp.path().join("system"));
+        let env: Env = [
+            (OsString::from("XDG_CONFIG_HOME"), user.clone().into()),
+            (OsString::from("XDG_CONFIG_DIRS"), system.clone().into()),
+        ]
+        .into();
+        let lookup = find_config("app", &["config.toml", "config.yaml"], &env);
+        assert_eq!(lookup.found, Some(user.join("app/config.yaml")));
+        assert_eq!(
+            lookup.candidates,
+            [
+                user.join("app/config.toml"),
+                user.join("app/config.yaml"),
+                system.join("app/config.toml"),
+                system.join("app/config.yaml"),
+            ]
+        );
+        assert_eq!(find_config("other", &["config.toml"], &env).found, None);
+    }
+
+    #[test]
+    fn project_config_is_found_in_parents() {
+        let tmp = assert_ok!(
+            TreeSpec::new()
+                .file(".apprc", "")
+                .file("repo/.git/HEAD", "")
+                .file("repo/sub/app.toml", "")
+                .file("repo/sub/deep/file", "")
+                .file("plain/a/file", "")
+                .create_temp()
+        );
+        let root = tmp.path();
+        let names = [".apprc", "app.toml"];
+        let project = assert_some!(find_project_config(root.join("repo/sub/deep"), &names));
+        assert_eq!(project.path, root.join("repo/sub/app.toml"));
+        assert_eq!(project.root, root.join("repo"));
+        // Stops at the repository root.
+        assert_eq!(find_project_config(root.join("repo"), &[".apprc"]), None);
+        let project = assert_some!(find_project_config(root.join("plain/a"), &names));
+        assert_eq!(project.path, root.join(".apprc"));
+        assert_eq!(project.root, root);
+    }
+}
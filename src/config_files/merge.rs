@@ -0,0 +1,197 @@
+use crate::config_files::schema::Value;
+
+/// How the [`merge`] treats arrays.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// Overlay arrays replace the base ones.
+    #[default]
+    Replace,
+    /// Overlay arrays are appended to the base ones.
+    Append,
+}
+
+/// Strategy of the [`merge`].
+///
+/// Default replaces arrays, and keeps nulls.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct MergeStrategy {
+    arrays: ArrayMerge,
+    null_deletes: bool,
+}
+
+impl MergeStrategy {
+    /// Create default strategy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge arrays according to the `arrays`.
+    pub fn arrays(mut self, arrays: ArrayMerge) -> Self {
+        self.arrays = arrays;
+        self
+    }
+
+    /// Remove keys, which are null in the overlay, from the base (like the JSON merge patch
+    /// does). TOML has no nulls, so it doesn't apply to it.
+    pub fn null_deletes(mut self, null_deletes: bool) -> Self {
+        self.null_deletes = null_deletes;
+        self
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Values supported by the [`merge`]: the [`schema::Value`](Value), and with the features,
+/// [`toml::Value`] and [`serde_json::Value`].
+pub trait Mergeable: sealed::Sealed + Sized {
+    #[doc(hidden)]
+    fn merge_with(&mut self, overlay: Self, strategy: MergeStrategy);
+}
+
+macro_rules! mergeable {
+    ($value:ty, $table:path, $array:path, $is_null:expr) => {
+        impl sealed::Sealed for $value {}
+
+        impl Mergeable for $value {
+            fn merge_with(&mut self, overlay: Self, strategy: MergeStrategy) {
+                match (self, overlay) {
+                    ($table(base), $table(overlay)) => {
+                        for (key, value) in overlay {
+                            if strategy.null_deletes && $is_null(&value) {
+                                base.remove(&key);
+                            } else if let Some(existing) = base.get_mut(&key) {
+                                existing.merge_with(value, strategy);
+                            } else {
+                                // Merged into an empty table, so nested nulls are removed too.
+                                let value = match value {
+                                    $table(table) => {
+                                        let mut empty = $table(Default::default());
+                                        empty.merge_with($table(table), strategy);
+                                        empty
+                                    }
+                                    value => value,
+                                };
+                                base.insert(key, value);
+                            }
+                        }
+                    }
+                    ($array(base), $array(overlay)) if strategy.arrays == ArrayMerge::Append => {
+                        base.extend(overlay);
+                    }
+                    (base, overlay) => *base = overlay,
+                }
+            }
+        }
+    };
+}
+
+mergeable!(Value, Value::Table, Value::Array, |value: &Value| {
+    matches!(value, Value::Null)
+});
+#[cfg(feature = "toml")]
+mergeable!(
+    toml::Value,
+    toml::Value::Table,
+    toml::Value::Array,
+    |_: &toml::Value| false
+);
+#[cfg(feature = "json")]
+mergeable!(
+    serde_json::Value,
+    serde_json::Value::Object,
+    serde_json::Value::Array,
+    serde_json::Value::is_null
+);
+
+/// Deep-merge the `overlay` into the `base`: tables are merged recursively, and other values
+/// (including arrays, unless the `strategy` says otherwise) are replaced.
+///
+/// Useful for layered configs, or `--set key=value` overrides:
+///
+/// ```rust
+/// # use rustvil::config_files::{ArrayMerge, MergeStrategy, merge};
+/// # #[cfg(not(feature = "toml"))]
+/// # fn main() {}
+/// # #[cfg(feature = "toml")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut config: toml::Value = toml::from_str("ui = { theme = 'dark', font = 'mono' }")?;
+/// let overrides: toml::Value = toml::from_str("ui = { theme = 'light' }")?;
+/// merge(&mut config, overrides, MergeStrategy::new().arrays(ArrayMerge::Append));
+/// assert_eq!(config["ui"]["theme"].as_str(), Some("light"));
+/// assert_eq!(config["ui"]["font"].as_str(), Some("mono"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn merge<V: Mergeable>(base: &mut V, overlay: V, strategy: MergeStrategy) {
+    base.merge_with(overlay, strategy);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn table(entries: &[(&str, Value)]) -> Value {
+        Value::Table(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn tables_are_merged_recursively() {
+        let mut base = table(&[
+            ("name", Value::from("app")),
+            ("tags", Value::from(vec![Value::from("a")])),
+            ("ui", table(&[("theme", Value::from("dark"))])),
+        ]);
+        let overlay = table(&[
+            ("name", Value::Null),
+            ("tags", Value::from(vec![Value::from("b")])),
+            ("ui", table(&[("font", Value::from("mono"))])),
+            ("new", table(&[("gone", Value::Null)])),
+        ]);
+        let mut replaced = base.clone();
+        merge(&mut replaced, overlay.clone(), MergeStrategy::new());
+        assert_eq!(replaced.get("name"), Some(&Value::Null));
+        assert_eq!(
+            replaced.get("tags"),
+            Some(&Value::from(vec![Value::from("b")]))
+        );
+
+        let strategy = MergeStrategy::new()
+            .arrays(ArrayMerge::Append)
+            .null_deletes(true);
+        merge(&mut base, overlay, strategy);
+        assert_eq!(
+            base,
+            table(&[
+                (
+                    "tags",
+                    Value::from(vec![Value::from("a"), Value::from("b")])
+                ),
+                (
+                    "ui",
+                    table(&[
+                        ("theme", Value::from("dark")),
+                        ("font", Value::from("mono"))
+                    ])
+                ),
+                ("new", table(&[])),
+            ])
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_nulls_delete_keys() {
+        let mut base = serde_json::json!({"a": 1, "b": {"c": 2, "d": 3}});
+        let overlay = serde_json::json!({"b": {"c": null}, "e": [1]});
+        merge(&mut base, overlay, MergeStrategy::new().null_deletes(true));
+        assert_eq!(base, serde_json::json!({"a": 1, "b": {"d": 3}, "e": [1]}));
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::fs::{MkdirOptions, PathExt, TempBuilder};
+
+/// What the [`migrate`] does with the legacy config.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum MigrateMode {
+    /// Copy it, leaving the legacy one untouched (e.g. for older versions of the app).
+    Copy,
+    /// Move it.
+    Move,
+    /// Move it, and leave a symlink to the new one in its place (for tools reading the legacy
+    /// one directly).
+    Symlink,
+    /// Move it, and leave a `<name>.migrated` file next to it, containing the new path.
+    Marker,
+}
+
+/// Migrate a config file from the first existing of the `legacy_paths` (like `~/.apprc`) to
+/// the `new_path` (like `~/.config/app/config`), unless the `new_path` already exists.
+///
+/// New file is created atomically, with permissions of the legacy one (and parent directories
+/// are created, as needed). Files are renamed, when possible, and otherwise (across filesystems,
+/// or for symlinks, which are resolved) copied and removed.
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{self, AppDirs, MigrateMode};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let home = config_files::home().ok_or("no home directory")?;
+/// let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+/// let new_path = dirs.config_dir().join("config.toml");
+/// let legacy = config_files::migrate([home.join(".apprc")], &new_path, MigrateMode::Marker)?;
+/// if let Some(legacy) = legacy {
+///     eprintln!("moved {} to {}", legacy.display(), new_path.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(Some(PathBuf))`](PathBuf) with the migrated legacy path, [`Ok(None)`](Ok) if there was
+/// nothing to migrate, otherwise an error, as reported by the OS.
+pub fn migrate(
+    legacy_paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    new_path: impl AsRef<Path>,
+    mode: MigrateMode,
+) -> io::Result<Option<PathBuf>> {
+    let new_path = new_path.as_ref();
+    if new_path.symlink_metadata().is_ok() {
+        return Ok(None);
+    }
+    let Some(legacy) = legacy_paths
+        .into_iter()
+        .map(|path| path.as_ref().to_path_buf())
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+    if let Some(parent) = new_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        parent.mkdir(MkdirOptions::WithParents)?;
+    }
+    if mode == MigrateMode::Copy {
+        copy_atomically(&legacy, new_path)?;
+        return Ok(Some(legacy));
+    }
+    // Moving a symlink itself could break it, if it's relative.
+    let renamed = !legacy.is_symlink()
+        && match legacy.rename_to(new_path) {
+            Ok(()) => true,
+            Err(err) if err.kind() == io::ErrorKind::CrossesDevices => false,
+            Err(err) => return Err(err),
+        };
+    if !renamed {
+        copy_atomically(&legacy, new_path)?;
+        legacy.rm()?;
+    }
+    match mode {
+        MigrateMode::Symlink => {
+            let target = std::path::absolute(new_path)?;
+            crate::fs::symlink(&target, &legacy)?;
+        }
+        MigrateMode::Marker => {
+            let marker = legacy.with_added_extension("migrated");
+            marker.write(format!("{}\n", new_path.display()))?;
+        }
+        MigrateMode::Copy | MigrateMode::Move => {}
+    }
+    Ok(Some(legacy))
+}
+
+/// Copy the `from` into a new file at the `to`, with permissions of the `from`.
+fn copy_atomically(from: &Path, to: &Path) -> io::Result<()> {
+    let contents = from.read()?;
+    let parent = match to.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = TempBuilder::new().parent(parent).tempfile()?;
+    file.write_all(&contents)?;
+    file.set_permissions(from.metadata()?.permissions())?;
+    file.sync_all()?;
+    file.persist(to)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+    use tempfile::tempdir;
+
+    #[test]
+    fn first_legacy_config_is_migrated() {
+        let tmp = tempdir().expect("needed for tests");
+        let (rc, old) = (tmp.path().join(".apprc"), tmp.path().join(".app.conf"));
+        let new = tmp.path().join("config/app/config");
+        assert_ok!(old.write("old"));
+        let migrated = assert_ok!(migrate([&rc, &old], &new, MigrateMode::Marker));
+        assert_eq!(migrated, Some(old.clone()));
+        assert_eq!(assert_ok!(new.read_to_string()), "old");
+        assert!(!old.exists());
+        let marker = assert_ok!(tmp.path().join(".app.conf.migrated").read_to_string());
+        assert_eq!(marker, format!("{}\n", new.display()));
+
+        // Already migrated.
+        assert_ok!(rc.write("rc"));
+        assert_eq!(assert_ok!(migrate([&rc], &new, MigrateMode::Move)), None);
+        assert_eq!(assert_ok!(new.read_to_string()), "old");
+    }
+
+    #[test]
+    fn copies_and_symlinks_keep_legacy_path_working() {
+        let tmp = tempdir().expect("needed for tests");
+        let rc = tmp.path().join(".apprc");
+        let (copied, linked) = (tmp.path().join("a/config"), tmp.path().join("b/config"));
+        assert_ok!(rc.write("rc"));
+        assert_eq!(
+            assert_ok!(migrate([&rc], &copied, MigrateMode::Copy)),
+            Some(rc.clone())
+        );
+        assert_eq!(assert_ok!(copied.read_to_string()), "rc");
+        assert!(!rc.is_symlink());
+        #[cfg(unix)]
+        {
+            assert_ok!(migrate([&rc], &linked, MigrateMode::Symlink));
+            assert!(rc.is_symlink());
+            assert_ok!(linked.write("new"));
+            assert_eq!(assert_ok!(rc.read_to_string()), "new");
+        }
+        assert_eq!(
+            assert_ok!(migrate(
+                [tmp.path().join("missing")],
+                &linked,
+                MigrateMode::Move
+            )),
+            None
+        );
+    }
+}
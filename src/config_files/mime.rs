@@ -0,0 +1,233 @@
+//! Lookup, and setting of the default applications for MIME types, as stored in the
+//! [`mimeapps.list`] files by the freedesktop (Linux, BSD) desktops:
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::{desktop_entry::DesktopEntry, mime};
+//! # use rustvil::os::env::Env;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let env = Env::new();
+//! if let Some(app) = mime::default_app_for("text/html", &env) {
+//!     let entry = DesktopEntry::parse(&std::fs::read_to_string(&app.path)?)?;
+//!     println!("{} opens the HTML files", entry.get("Name").unwrap_or(app.id));
+//! }
+//! mime::set_default_app("x-scheme-handler/myapp", "com.example.App.desktop", &env)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`mimeapps.list`]: https://specifications.freedesktop.org/mime-apps-spec/latest/
+
+use std::{io, path::PathBuf};
+
+use crate::config_files::{
+    Backup,
+    ini::Ini,
+    save_atomic,
+    xdg::{self, MacOSBehaviour},
+};
+use crate::fs::PathExt;
+use crate::os::env::Env;
+
+const DEFAULT: &str = "Default Applications";
+const ADDED: &str = "Added Associations";
+const REMOVED: &str = "Removed Associations";
+
+/// Result of the [`default_app_for`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DefaultApp {
+    /// Desktop file ID, like `firefox.desktop`.
+    pub id: String,
+    /// Path of the installed desktop file, see the
+    /// [`DesktopEntry`](crate::config_files::desktop_entry::DesktopEntry).
+    pub path: PathBuf,
+}
+
+/// Get paths of the `mimeapps.list` files, in the order of precedence: in each of the
+/// [config](xdg::config_search_paths), and then the `applications` subdirectory of each of the
+/// [data](xdg::data_search_paths) directories, the `<desktop>-mimeapps.list` for each of the
+/// `$XDG_CURRENT_DESKTOP` (lowercase), then the `mimeapps.list`.
+///
+/// Files don't have to exist.
+pub fn mimeapps_paths(env: &Env) -> Vec<PathBuf> {
+    let desktops: Vec<String> = env
+        .get_os("XDG_CURRENT_DESKTOP")
+        .and_then(|desktops| desktops.to_str())
+        .map(|desktops| {
+            desktops
+                .split(':')
+                .filter(|desktop| !desktop.is_empty())
+                .map(str::to_lowercase)
+                .collect()
+        })
+        .unwrap_or_default();
+    let behaviour = MacOSBehaviour::LinuxFallback;
+    let data = xdg::data_search_paths(env, behaviour).map(|dir| dir.join("applications"));
+    xdg::config_search_paths(env, behaviour)
+        .chain(data)
+        .flat_map(|dir| {
+            desktops
+                .iter()
+                .map(|desktop| dir.join(format!("{desktop}-mimeapps.list")))
+                .chain([dir.join("mimeapps.list")])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Find the default application for the `mime_type` (like `text/html`, or
+/// `x-scheme-handler/https`): the first installed one from the `Default Applications` of the
+/// [`mimeapps_paths`], otherwise from their `Added Associations` (unless it's in the `Removed
+/// Associations` of the same, or a more important file).
+///
+/// Applications only associated in the desktop files (through the `mimeinfo.cache`) aren't
+/// considered.
+///
+/// # Returns
+///
+/// [`Some(DefaultApp)`](DefaultApp) with the application, or [`None`] if there is none.
+pub fn default_app_for(mime_type: &str, env: &Env) -> Option<DefaultApp> {
+    let lists: Vec<Ini> = mimeapps_paths(env)
+        .iter()
+        // Missing and invalid files are ignored.
+        .filter_map(|path| Ini::parse(&path.read_to_string().ok()?).ok())
+        .collect();
+    let installed = |id: &str| {
+        desktop_file(env, id).map(|path| DefaultApp {
+            id: id.to_owned(),
+            path,
+        })
+    };
+    let default = lists
+        .iter()
+        .flat_map(|list| ids(list, DEFAULT, mime_type))
+        .find_map(|id| installed(&id));
+    if default.is_some() {
+        return default;
+    }
+    let mut removed = Vec::new();
+    for list in &lists {
+        removed.extend(ids(list, REMOVED, mime_type));
+        let added = ids(list, ADDED, mime_type)
+            .into_iter()
+            .filter(|id| !removed.contains(id))
+            .find_map(|id| installed(&id));
+        if added.is_some() {
+            return added;
+        }
+    }
+    None
+}
+
+/// Set the default application for the `mime_type` to the desktop file `id` (like
+/// `com.example.App.desktop`), in the user's `mimeapps.list` (in the [`xdg::config`]).
+///
+/// The file is written atomically, keeping other entries and comments.
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise an error, as reported by the OS (of kind
+/// [`ErrorKind::InvalidData`](io::ErrorKind::InvalidData), if the existing file isn't valid).
+pub fn set_default_app(mime_type: &str, id: &str, env: &Env) -> io::Result<()> {
+    let config = xdg::try_config(env, MacOSBehaviour::LinuxFallback)
+        .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))?;
+    let path = config.join("mimeapps.list");
+    let mut list = match path.read_to_string() {
+        Ok(contents) => {
+            Ini::parse(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ini::new(),
+        Err(err) => return Err(err),
+    };
+    list.set(DEFAULT, mime_type, format!("{id};"));
+    save_atomic(&path, list.to_string(), Backup::No)
+}
+
+/// Return the desktop file IDs of the `mime_type` in the `section`.
+fn ids(list: &Ini, section: &str, mime_type: &str) -> Vec<String> {
+    list.get(section, mime_type)
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Find the installed desktop file with the `id`, in the `applications` subdirectory of the data
+/// directories (where the `-` in the ID may stand for a subdirectory, like `kde-app.desktop` for
+/// the `kde/app.desktop`).
+fn desktop_file(env: &Env, id: &str) -> Option<PathBuf> {
+    let dashes = id.matches('-').count();
+    xdg::data_search_paths(env, MacOSBehaviour::LinuxFallback)
+        .map(|dir| dir.join("applications"))
+        .find_map(|dir| {
+            (0..=dashes)
+                .map(|nested| dir.join(id.replacen('-', "/", nested)))
+                .find(|candidate| candidate.is_file())
+        })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config_files::desktop_entry::{self, DesktopEntry, InstallDir};
+    use crate::fs::MkdirOptions;
+    use claim::{assert_none, assert_ok, assert_some};
+    use std::ffi::OsString;
+    use tempfile::tempdir;
+
+    #[test]
+    fn default_app_follows_precedence() {
+        let tmp = tempdir().expect("needed for tests");
+        let dir = |name: &str| tmp.path().join(name);
+        let env: Env = [
+            ("XDG_CONFIG_HOME", dir("config")),
+            ("XDG_CONFIG_DIRS", dir("etc")),
+            ("XDG_DATA_HOME", dir("data")),
+            ("XDG_DATA_DIRS", dir("share")),
+            ("XDG_CURRENT_DESKTOP", PathBuf::from("X-Generic:GNOME")),
+        ]
+        .into_iter()
+        .map(|(key, value)| (OsString::from(key), value.into_os_string()))
+        .collect();
+        for id in ["viewer", "editor", "gnome-viewer"] {
+            let entry = DesktopEntry::new(id);
+            assert_ok!(desktop_entry::install(
+                &entry,
+                id,
+                InstallDir::Applications,
+                &env
+            ));
+        }
+        assert_ok!(dir("etc").mkdir(MkdirOptions::WithParents));
+        assert_ok!(dir("etc/mimeapps.list").write(
+            "[Default Applications]\ntext/plain=missing.desktop;viewer.desktop;\n\
+             [Added Associations]\nimage/png=viewer.desktop;editor.desktop\n"
+        ));
+        assert_ok!(dir("config").mkdir(MkdirOptions::WithParents));
+        assert_ok!(
+            dir("config/gnome-mimeapps.list")
+                .write("[Removed Associations]\nimage/png=viewer.desktop\n")
+        );
+
+        let plain = assert_some!(default_app_for("text/plain", &env));
+        assert_eq!(plain.id, "viewer.desktop");
+        assert_eq!(plain.path, dir("data/applications/viewer.desktop"));
+        assert_eq!(
+            assert_some!(default_app_for("image/png", &env)).id,
+            "editor.desktop"
+        );
+        assert_none!(default_app_for("audio/ogg", &env));
+
+        assert_ok!(set_default_app("text/plain", "gnome-viewer.desktop", &env));
+        assert_eq!(
+            assert_some!(default_app_for("text/plain", &env)).id,
+            "gnome-viewer.desktop"
+        );
+        assert_eq!(
+            assert_ok!(dir("config/mimeapps.list").read_to_string()),
+            "[Default Applications]\ntext/plain = gnome-viewer.desktop;\n"
+        );
+    }
+}
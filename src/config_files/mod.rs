@@ -1,6 +1,21 @@
 //! Configuration file utilities and standard directory paths.
 //!
-//! Provides helpers for locating configuration files, including XDG Base Directory support.
+//! Provides helpers for locating configuration files, including XDG Base Directory support, and
+//! per-application directories with the [`AppDirs`]. [`find_config`] looks for a config file
+//! in all of the standard places, and [`load`]/[`save`] (de)serialize it (with the __toml__,
+//! __json__ or __yaml__ feature). [`save_atomic`] writes a file, so it survives crashes intact,
+//! and [`install_default`] creates a default config on the first run.
+//! [`dotenv`] loads the `.env` files into an [`Env`](crate::os::env::Env), and [`ini`] edits the
+//! INI files. [`expand`] substitutes variables in config values, like `${XDG_DATA_HOME}/app`.
+//! [`migrate`] moves configs from legacy locations, like `~/.apprc`, and [`schema`] validates them.
+//! [`cache::prune`] evicts old files from cache directories. [`state::Store`] persists a versioned
+//! state. [`find_project_config`] looks for a project-local config, like `.apprc`, in parent
+//! directories, and [`open_in_editor`] lets users edit a config in their [`editor`]. [`shell`]
+//! finds rc files of the user's shell, e.g. to add the app to the `$PATH`. [`merge`] deep-merges
+//! config values, like overrides into defaults, and [`LayeredConfig`] loads a config from multiple
+//! files. [`desktop_entry`] registers GUI launchers, and [`mime`] finds default apps for file
+//! types. [`snapshot`] exports the settings of an app, and [`restore`] imports them (with the
+//! __tar__ or __zip__ feature).
 //!
 //! ```rust,no_run
 //! # use rustvil::config_files::xdg::{self, MacOSBehaviour};
@@ -15,8 +30,68 @@
 
 use std::{env::home_dir, path::PathBuf};
 
+mod app_dirs;
+pub mod cache;
+pub mod desktop_entry;
+pub mod dotenv;
+mod editor;
+mod expand;
+pub mod ini;
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+mod layered;
+mod locate;
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+mod merge;
+mod migrate;
+pub mod mime;
+mod save;
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "toml", feature = "json", feature = "yaml")))
+)]
+pub mod schema;
+pub mod shell;
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod snapshot;
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "toml", feature = "json", feature = "yaml")))
+)]
+pub mod state;
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+mod typed;
 pub mod xdg;
 
+pub use app_dirs::AppDirs;
+pub use editor::{editor, open_in_editor};
+pub use expand::{ExpandError, expand};
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "toml", feature = "json", feature = "yaml")))
+)]
+pub use layered::LayeredConfig;
+pub use locate::{ConfigLookup, ProjectConfig, find_config, find_project_config};
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "toml", feature = "json", feature = "yaml")))
+)]
+pub use merge::{ArrayMerge, MergeStrategy, Mergeable, merge};
+pub use migrate::{MigrateMode, migrate};
+pub use save::{Backup, install_default, save_atomic};
+#[cfg(any(feature = "tar", feature = "zip"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tar", feature = "zip"))))]
+pub use snapshot::{RestoreMode, restore, snapshot};
+#[cfg(any(feature = "toml", feature = "json", feature = "yaml"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "toml", feature = "json", feature = "yaml")))
+)]
+pub use typed::{ConfigError, ConfigFormat, load, save, save_value_atomic};
+
 /// Wrapper around [`std::env::home_dir`].
 pub fn home() -> Option<PathBuf> {
     home_dir()
@@ -0,0 +1,178 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::fs::{MkdirOptions, PathExt, anonymous_file};
+
+/// Whether the [`save_atomic`] should keep the previous version of the file as `<name>.bak`.
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Backup {
+    /// Only the new version is kept.
+    No,
+    /// The previous version is copied to `<name>.bak`, replacing the previous backup.
+    Yes,
+}
+
+/// Save the `contents` into the `path` (creating its parent directories), so that it contains
+/// either the old, or the new contents, even after a crash.
+///
+/// Contents are written into a temporary file next to the `path`, which gets permissions (and
+/// the owner, where allowed) of the current file, is flushed to the disk, and then renamed over
+/// the `path`. If the `path` is a symlink, the file it points to is replaced, and the symlink is
+/// kept. With the [`Backup::Yes`], the current file (if any) is copied to `<name>.bak` first,
+/// replacing the previous backup.
+///
+/// Typed configs are saved this way by the [`save`](crate::config_files::save) and the
+/// [`save_value_atomic`](crate::config_files::save_value_atomic) (with the __toml__, __json__
+/// or __yaml__ feature).
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{self, Backup};
+/// # fn main() -> std::io::Result<()> {
+/// config_files::save_atomic("app/config.ini", "[ui]\ntheme = dark\n", Backup::Yes)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise an error, as reported by the OS. On an error, the
+/// `path` is left untouched (but the backup may already be updated).
+pub fn save_atomic(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+    backup: Backup,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        parent.mkdir(MkdirOptions::WithParents)?;
+    }
+    if backup == Backup::Yes {
+        match path.copy_to(path.with_added_extension("bak")) {
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+    crate::fs::replace_atomically(path, contents.as_ref())
+}
+
+/// Install the default `contents` of a config at the `path` (creating its parent directories),
+/// unless it already exists, e.g. on the first run of an app.
+///
+/// File appears atomically, fully written, and it's never overwritten, even when racing with
+/// another process. It's private (mode `0o600` on `unix`), as configs often hold secrets.
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{self, AppDirs};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+/// let path = dirs.config_dir().join("config.toml");
+/// if config_files::install_default(&path, "# See `app --help`.\ntheme = 'dark'\n")? {
+///     eprintln!("created {}", path.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(bool)`](bool) telling, whether the file was created, otherwise an error, as reported by
+/// the OS.
+pub fn install_default(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<bool> {
+    let path = path.as_ref();
+    if path.symlink_metadata().is_ok() {
+        return Ok(false);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    parent.mkdir(MkdirOptions::WithParents)?;
+    let mut file = anonymous_file(parent)?;
+    file.write_all(contents.as_ref())?;
+    file.sync_all()?;
+    match file.materialize(path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => return Ok(false),
+        Err(err) => return Err(err),
+    }
+    #[cfg(unix)]
+    std::fs::File::open(parent)?.sync_all()?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+    use tempfile::tempdir;
+
+    #[test]
+    fn previous_version_is_backed_up() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("app").join("config.ini");
+        let backup = tmp.path().join("app").join("config.ini.bak");
+        assert_ok!(save_atomic(&path, "v1", Backup::Yes));
+        assert!(!backup.exists());
+        assert_ok!(save_atomic(&path, "v2", Backup::Yes));
+        assert_eq!(assert_ok!(path.read_to_string()), "v2");
+        assert_eq!(assert_ok!(backup.read_to_string()), "v1");
+        assert_ok!(save_atomic(&path, "v3", Backup::No));
+        assert_eq!(assert_ok!(path.read_to_string()), "v3");
+        assert_eq!(assert_ok!(backup.read_to_string()), "v1");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_are_preserved() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempdir().expect("needed for tests");
+        let (path, backup) = (tmp.path().join(".env"), tmp.path().join(".env.bak"));
+        assert_ok!(path.write("TOKEN=1"));
+        assert_ok!(std::fs::set_permissions(
+            &path,
+            PermissionsExt::from_mode(0o600)
+        ));
+        assert_ok!(save_atomic(&path, "TOKEN=2", Backup::Yes));
+        for path in [&path, &backup] {
+            let mode = assert_ok!(path.metadata()).permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_is_kept() {
+        let tmp = tempdir().expect("needed for tests");
+        let (target, link) = (tmp.path().join("config.ini"), tmp.path().join("link.ini"));
+        assert_ok!(target.write("v1"));
+        assert_ok!(std::os::unix::fs::symlink(&target, &link));
+        assert_ok!(save_atomic(&link, "v2", Backup::Yes));
+        assert!(assert_ok!(link.symlink_metadata()).is_symlink());
+        assert_eq!(assert_ok!(target.read_to_string()), "v2");
+        assert_eq!(
+            assert_ok!(tmp.path().join("link.ini.bak").read_to_string()),
+            "v1"
+        );
+    }
+
+    #[test]
+    fn default_is_installed_once() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("app").join("config.toml");
+        assert!(assert_ok!(install_default(&path, "default")));
+        assert_eq!(assert_ok!(path.read_to_string()), "default");
+        assert_ok!(path.write("edited"));
+        assert!(!assert_ok!(install_default(&path, "default")));
+        assert_eq!(assert_ok!(path.read_to_string()), "edited");
+        assert_eq!(
+            assert_ok!(std::fs::read_dir(path.parent().unwrap())).count(),
+            1
+        );
+    }
+}
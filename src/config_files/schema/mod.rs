@@ -0,0 +1,372 @@
+//! Lightweight validation of config values.
+//!
+//! A [`Schema`] declares the expected keys of a table, with their [kinds](Kind), defaults, and
+//! deprecated aliases. [`Schema::validate`] reports every [`Issue`] at once (so users can fix
+//! them in one go), and fills in the defaults:
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::{self, schema::{Kind, Schema, Value}};
+//! #[derive(serde::Deserialize)]
+//! struct Config {
+//!     theme: String,
+//!     retries: u32,
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let schema = Schema::new()
+//!     .required("theme", Kind::String)
+//!     .with_default("retries", 3)
+//!     .alias("retry_count", "retries");
+//! let raw: Value = config_files::load("config.toml")?;
+//! let validated = schema.validate(&raw);
+//! for issue in &validated.issues {
+//!     eprintln!("config.toml: {issue}");
+//! }
+//! if validated.is_ok() {
+//!     let config: Config = validated.value.deserialize_into()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, fmt};
+
+mod value;
+
+pub use value::{Kind, Value};
+
+/// Expected keys of a table, see the [module](self) docs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    keys: Vec<Key>,
+    aliases: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Key {
+    name: String,
+    kind: Kind,
+    required: bool,
+    default: Option<Value>,
+    table: Option<Schema>,
+}
+
+impl Key {
+    fn new(name: &str, kind: Kind) -> Self {
+        Self {
+            name: name.to_owned(),
+            kind,
+            required: false,
+            default: None,
+            table: None,
+        }
+    }
+}
+
+/// Result of the [`Schema::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validated {
+    /// Validated value, with defaults filled in, and deprecated keys renamed.
+    pub value: Value,
+    /// All the problems found, in order.
+    pub issues: Vec<Issue>,
+}
+
+impl Validated {
+    /// Check, whether there are no [errors](Issue::is_error) (warnings are fine).
+    pub fn is_ok(&self) -> bool {
+        !self.issues.iter().any(Issue::is_error)
+    }
+}
+
+/// A problem found by the [`Schema::validate`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Issue {
+    /// Dotted path of the key, like `ui.theme` (empty for the whole value).
+    pub key: String,
+    /// What's wrong.
+    pub kind: IssueKind,
+}
+
+/// Kind of an [`Issue`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum IssueKind {
+    /// Key isn't declared in the schema (a warning, it's kept in the value).
+    Unknown,
+    /// Key is a deprecated alias (a warning, it's renamed in the value).
+    Deprecated {
+        /// Dotted path of the new key.
+        replacement: String,
+    },
+    /// Value has an unexpected kind (an error).
+    WrongType {
+        /// Declared kind.
+        expected: Kind,
+        /// Actual kind.
+        found: Kind,
+    },
+    /// Required key is missing (an error).
+    Missing,
+}
+
+impl Issue {
+    fn new(key: String, kind: IssueKind) -> Self {
+        Self { key, kind }
+    }
+
+    /// Check, whether the issue makes the value invalid (otherwise it's a warning).
+    pub fn is_error(&self) -> bool {
+        matches!(self.kind, IssueKind::WrongType { .. } | IssueKind::Missing)
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = &self.key;
+        match &self.kind {
+            IssueKind::Unknown => write!(f, "unknown key `{key}`"),
+            IssueKind::Deprecated { replacement } => {
+                write!(f, "key `{key}` is deprecated, use `{replacement}` instead")
+            }
+            IssueKind::WrongType { expected, found } if key.is_empty() => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            IssueKind::WrongType { expected, found } => {
+                write!(
+                    f,
+                    "key `{key}` has a wrong type: expected {expected}, found {found}"
+                )
+            }
+            IssueKind::Missing => write!(f, "missing required key `{key}`"),
+        }
+    }
+}
+
+impl Schema {
+    /// Create a [`Schema`] without any keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare an optional key `name` of the `kind`.
+    pub fn key(mut self, name: &str, kind: Kind) -> Self {
+        self.keys.push(Key::new(name, kind));
+        self
+    }
+
+    /// Declare a required key `name` of the `kind`.
+    pub fn required(mut self, name: &str, kind: Kind) -> Self {
+        self.keys.push(Key {
+            required: true,
+            ..Key::new(name, kind)
+        });
+        self
+    }
+
+    /// Declare an optional key `name` with the `default` value (and of its kind).
+    pub fn with_default(mut self, name: &str, default: impl Into<Value>) -> Self {
+        let default = default.into();
+        self.keys.push(Key {
+            default: Some(default.clone()),
+            ..Key::new(name, default.kind())
+        });
+        self
+    }
+
+    /// Declare an optional key `name`, which is a table validated by the `schema` (and filled
+    /// with its defaults, even if missing).
+    pub fn table(mut self, name: &str, schema: Schema) -> Self {
+        self.keys.push(Key {
+            table: Some(schema),
+            ..Key::new(name, Kind::Table)
+        });
+        self
+    }
+
+    /// Declare a `deprecated` name of the key `name`.
+    pub fn alias(mut self, deprecated: &str, name: &str) -> Self {
+        self.aliases.push((deprecated.to_owned(), name.to_owned()));
+        self
+    }
+
+    /// Validate the `value`, which should be a table, see the [module](self) docs.
+    pub fn validate(&self, value: &Value) -> Validated {
+        let mut issues = Vec::new();
+        let value = self.validate_at("", value, &mut issues);
+        Validated { value, issues }
+    }
+
+    fn validate_at(&self, prefix: &str, value: &Value, issues: &mut Vec<Issue>) -> Value {
+        let path = |key: &str| {
+            if prefix.is_empty() {
+                key.to_owned()
+            } else {
+                format!("{prefix}.{key}")
+            }
+        };
+        let Value::Table(table) = value else {
+            issues.push(Issue::new(
+                prefix.to_owned(),
+                IssueKind::WrongType {
+                    expected: Kind::Table,
+                    found: value.kind(),
+                },
+            ));
+            return value.clone();
+        };
+        let mut table = table.clone();
+        for (deprecated, name) in &self.aliases {
+            if let Some(aliased) = table.remove(deprecated) {
+                issues.push(Issue::new(
+                    path(deprecated),
+                    IssueKind::Deprecated {
+                        replacement: path(name),
+                    },
+                ));
+                table.entry(name.clone()).or_insert(aliased);
+            }
+        }
+        for key in table.keys() {
+            if !self.keys.iter().any(|declared| declared.name == *key) {
+                issues.push(Issue::new(path(key), IssueKind::Unknown));
+            }
+        }
+        for key in &self.keys {
+            match table.get_mut(&key.name) {
+                Some(found) if !key.kind.matches(found) => issues.push(Issue::new(
+                    path(&key.name),
+                    IssueKind::WrongType {
+                        expected: key.kind,
+                        found: found.kind(),
+                    },
+                )),
+                Some(found) => {
+                    if let Some(schema) = &key.table {
+                        *found = schema.validate_at(&path(&key.name), found, issues);
+                    }
+                }
+                None if key.required => {
+                    issues.push(Issue::new(path(&key.name), IssueKind::Missing));
+                }
+                None => {
+                    if let Some(default) = &key.default {
+                        table.insert(key.name.clone(), default.clone());
+                    } else if let Some(schema) = &key.table {
+                        let empty = Value::Table(BTreeMap::new());
+                        let filled = schema.validate_at(&path(&key.name), &empty, issues);
+                        table.insert(key.name.clone(), filled);
+                    }
+                }
+            }
+        }
+        Value::Table(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+
+    fn table(entries: &[(&str, Value)]) -> Value {
+        Value::Table(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn issues_are_reported_and_defaults_filled() {
+        let schema = Schema::new()
+            .required("name", Kind::String)
+            .with_default("retries", 3)
+            .key("ratio", Kind::Float)
+            .alias("retry_count", "retries")
+            .table("ui", Schema::new().with_default("theme", "dark"));
+        let value = table(&[
+            ("name", Value::from(1)),
+            ("ratio", Value::from(2)),
+            ("retry_count", Value::from(5)),
+            ("colour", Value::from(true)),
+        ]);
+        let validated = schema.validate(&value);
+        let messages: Vec<String> = validated.issues.iter().map(Issue::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "key `retry_count` is deprecated, use `retries` instead",
+                "unknown key `colour`",
+                "key `name` has a wrong type: expected string, found integer",
+            ]
+        );
+        assert!(!validated.is_ok());
+        assert_eq!(
+            validated.value,
+            table(&[
+                ("name", Value::from(1)),
+                ("ratio", Value::from(2)),
+                ("retries", Value::from(5)),
+                ("colour", Value::from(true)),
+                ("ui", table(&[("theme", Value::from("dark"))])),
+            ])
+        );
+
+        let validated = schema.validate(&table(&[("ui", Value::from(vec![]))]));
+        assert_eq!(
+            validated.issues,
+            [
+                Issue::new("name".to_owned(), IssueKind::Missing),
+                Issue::new(
+                    "ui".to_owned(),
+                    IssueKind::WrongType {
+                        expected: Kind::Table,
+                        found: Kind::Array
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn values_are_converted_to_types() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        enum Mode {
+            Fast,
+            Limited { rate: u32 },
+        }
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Config {
+            name: String,
+            tags: Vec<String>,
+            nick: Option<String>,
+            modes: Vec<Mode>,
+        }
+
+        let value = table(&[
+            ("name", Value::from("app")),
+            ("tags", Value::from(vec![Value::from("a")])),
+            ("nick", Value::Null),
+            (
+                "modes",
+                Value::from(vec![
+                    Value::from("Fast"),
+                    table(&[("Limited", table(&[("rate", Value::from(5))]))]),
+                ]),
+            ),
+        ]);
+        let config: Config = assert_ok!(value.deserialize_into());
+        assert_eq!(
+            config,
+            Config {
+                name: "app".to_owned(),
+                tags: vec!["a".to_owned()],
+                nick: None,
+                modes: vec![Mode::Fast, Mode::Limited { rate: 5 }],
+            }
+        );
+    }
+}
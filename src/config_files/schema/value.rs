@@ -0,0 +1,334 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{
+        self, DeserializeOwned, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+        value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+    },
+    forward_to_deserialize_any,
+};
+
+/// A format-independent config value, which can be [loaded](crate::config_files::load) from any
+/// of the supported formats, [validated](super::Schema::validate), and then
+/// [converted](Value::deserialize_into) to a typed config.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Null (`null` in the JSON, `~` in the YAML).
+    Null,
+    /// Boolean.
+    Bool(bool),
+    /// Integer (unsigned ones above the [`i64::MAX`] are [floats](Value::Float)).
+    Integer(i64),
+    /// Floating point number.
+    Float(f64),
+    /// String.
+    String(String),
+    /// Array.
+    Array(Vec<Value>),
+    /// Table (an object in the JSON, a mapping in the YAML), with keys sorted.
+    Table(BTreeMap<String, Value>),
+}
+
+/// Type of a [`Value`], as expected by a [`Schema`](super::Schema).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Kind {
+    /// Anything.
+    Any,
+    /// [`Value::Null`].
+    Null,
+    /// [`Value::Bool`].
+    Bool,
+    /// [`Value::Integer`].
+    Integer,
+    /// [`Value::Float`] (or an [`Value::Integer`], when expected).
+    Float,
+    /// [`Value::String`].
+    String,
+    /// [`Value::Array`].
+    Array,
+    /// [`Value::Table`].
+    Table,
+}
+
+impl Kind {
+    /// Check, whether the `value` is of this kind.
+    pub fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Float => matches!(value, Value::Float(_) | Value::Integer(_)),
+            kind => kind == value.kind(),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Any => "any value",
+            Self::Null => "null",
+            Self::Bool => "boolean",
+            Self::Integer => "integer",
+            Self::Float => "number",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Table => "table",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Value {
+    /// Return the [`Kind`] of the value (never the [`Kind::Any`]).
+    pub fn kind(&self) -> Kind {
+        match self {
+            Self::Null => Kind::Null,
+            Self::Bool(_) => Kind::Bool,
+            Self::Integer(_) => Kind::Integer,
+            Self::Float(_) => Kind::Float,
+            Self::String(_) => Kind::String,
+            Self::Array(_) => Kind::Array,
+            Self::Table(_) => Kind::Table,
+        }
+    }
+
+    /// Return the value of the `key`, if `self` is a [table](Value::Table).
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Table(table) => table.get(key),
+            _ => None,
+        }
+    }
+
+    /// Return the string, if `self` is a [string](Value::String).
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Convert the value into a typed `T`.
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(T)`](Ok) on a success, otherwise an error describing the mismatch.
+    pub fn deserialize_into<T: DeserializeOwned>(self) -> Result<T, de::value::Error> {
+        T::deserialize(self)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Self::Array(value)
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Value {
+    fn from(value: BTreeMap<String, Value>) -> Self {
+        Self::Table(value)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(value) => serializer.serialize_bool(*value),
+            Self::Integer(value) => serializer.serialize_i64(*value),
+            Self::Float(value) => serializer.serialize_f64(*value),
+            Self::String(value) => serializer.serialize_str(value),
+            Self::Array(value) => value.serialize(serializer),
+            Self::Table(value) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any config value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Integer(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(i64::try_from(value).map_or(Value::Float(value as f64), Value::Integer))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::Float(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_owned()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut array = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            array.push(element);
+        }
+        Ok(Value::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut table = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            table.insert(key, value);
+        }
+        Ok(Value::Table(table))
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::Null => visitor.visit_unit(),
+            Self::Bool(value) => visitor.visit_bool(value),
+            Self::Integer(value) => visitor.visit_i64(value),
+            Self::Float(value) => visitor.visit_f64(value),
+            Self::String(value) => visitor.visit_string(value),
+            Self::Array(value) => {
+                let mut seq = SeqDeserializer::new(value.into_iter());
+                let visited = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(visited)
+            }
+            Self::Table(value) => {
+                let mut map = MapDeserializer::new(value.into_iter());
+                let visited = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(visited)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            // Unit variants are strings, and others are tables with a single entry.
+            Self::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Self::Table(table) => visitor.visit_enum(MapAccessDeserializer::new(
+                MapDeserializer::new(table.into_iter()),
+            )),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl Value {
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Self::Null => de::Unexpected::Unit,
+            Self::Bool(value) => de::Unexpected::Bool(*value),
+            Self::Integer(value) => de::Unexpected::Signed(*value),
+            Self::Float(value) => de::Unexpected::Float(*value),
+            Self::String(value) => de::Unexpected::Str(value),
+            Self::Array(_) => de::Unexpected::Seq,
+            Self::Table(_) => de::Unexpected::Map,
+        }
+    }
+}
+
+impl IntoDeserializer<'_, de::value::Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
@@ -0,0 +1,335 @@
+//! Detection of the user's shell, and editing of its rc files, e.g. for installers adding the
+//! app to the `$PATH`:
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::{shell::{self, Shell}, xdg};
+//! # use rustvil::os::env::Env;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let env = Env::new();
+//! let bin = xdg::bin(&env).ok_or("no home directory")?;
+//! if let Some(detected) = shell::detect(&env)
+//!     && let Some(rc_file) = detected.rc_files.first()
+//! {
+//!     let line = match detected.shell {
+//!         Shell::Fish => format!("fish_add_path {}", bin.display()),
+//!         _ => format!("export PATH=\"{}:$PATH\"", bin.display()),
+//!     };
+//!     shell::append_managed_block(rc_file, "myapp", &line)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::config_files::{
+    Backup, home, save_atomic,
+    xdg::{self, MacOSBehaviour},
+};
+use crate::fs::PathExt;
+use crate::os::env::Env;
+
+/// A known shell.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Shell {
+    /// Bash.
+    Bash,
+    /// Zsh.
+    Zsh,
+    /// Fish.
+    Fish,
+    /// A POSIX shell, like `sh`, `dash` or `ksh`.
+    Sh,
+    /// PowerShell (both the `pwsh` and the Windows PowerShell).
+    PowerShell,
+    /// Windows Command Prompt.
+    Cmd,
+}
+
+/// Result of the [`detect`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DetectedShell {
+    /// Detected shell.
+    pub shell: Shell,
+    /// Its rc files, see the [`Shell::rc_files`].
+    pub rc_files: Vec<PathBuf>,
+}
+
+impl Shell {
+    /// Identify the shell by its `program`, like `/bin/zsh`, `-bash` (as login shells are
+    /// named), or `pwsh.exe`.
+    pub fn from_program(program: impl AsRef<Path>) -> Option<Self> {
+        let name = program.as_ref().file_name()?.to_str()?.to_lowercase();
+        let name = name.trim_start_matches('-');
+        let name = name.strip_suffix(".exe").unwrap_or(name);
+        match name {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            "sh" | "dash" | "ash" | "ksh" | "mksh" => Some(Self::Sh),
+            "pwsh" | "powershell" => Some(Self::PowerShell),
+            "cmd" => Some(Self::Cmd),
+            _ => None,
+        }
+    }
+
+    /// Return rc (or profile) files of the shell, the preferred one for additions first:
+    /// - `~/.bashrc` and `~/.bash_profile` for the [`Shell::Bash`],
+    /// - `.zshenv` and `.zshrc` in the `$ZDOTDIR` (or home) for the [`Shell::Zsh`],
+    /// - `config.fish` in the `fish` subdirectory of the [`xdg::config`] for the [`Shell::Fish`],
+    /// - `~/.profile` for the [`Shell::Sh`],
+    /// - `Microsoft.PowerShell_profile.ps1` in the `PowerShell` (and `WindowsPowerShell`)
+    ///   subdirectory of the documents on Windows, or of the [`xdg::config`] elsewhere, for the
+    ///   [`Shell::PowerShell`],
+    /// - nothing for the [`Shell::Cmd`] (it uses the registry).
+    ///
+    /// Files don't have to exist.
+    pub fn rc_files(self, env: &Env) -> Vec<PathBuf> {
+        let home_file = |name: &str| home().map(|home| home.join(name));
+        let config_file = |dir: &str, name: &str| {
+            xdg::config(env, MacOSBehaviour::LinuxFallback)
+                .map(|config| config.join(dir).join(name))
+        };
+        let files = match self {
+            Self::Bash => vec![home_file(".bashrc"), home_file(".bash_profile")],
+            Self::Zsh => {
+                let dir = env
+                    .get_os("ZDOTDIR")
+                    .filter(|dir| !dir.is_empty())
+                    .map(PathBuf::from)
+                    .or_else(home);
+                vec![
+                    dir.as_ref().map(|dir| dir.join(".zshenv")),
+                    dir.as_ref().map(|dir| dir.join(".zshrc")),
+                ]
+            }
+            Self::Fish => vec![config_file("fish", "config.fish")],
+            Self::Sh => vec![home_file(".profile")],
+            Self::PowerShell if cfg!(windows) => {
+                let documents = xdg::user_dirs(env).documents;
+                ["PowerShell", "WindowsPowerShell"]
+                    .into_iter()
+                    .map(|dir| {
+                        let profile = Path::new(dir).join("Microsoft.PowerShell_profile.ps1");
+                        documents.as_ref().map(|documents| documents.join(profile))
+                    })
+                    .collect()
+            }
+            Self::PowerShell => vec![config_file(
+                "powershell",
+                "Microsoft.PowerShell_profile.ps1",
+            )],
+            Self::Cmd => Vec::new(),
+        };
+        files.into_iter().flatten().collect()
+    }
+}
+
+/// Detect the user's shell: from the `$SHELL`, or the parent process (on Linux), or on Windows,
+/// from the environment (PowerShell, if the `$PSModulePath` has at least 3 entries, as it does
+/// in PowerShell sessions, otherwise the Command Prompt).
+///
+/// # Returns
+///
+/// [`Some(DetectedShell)`](DetectedShell) with the shell, and its rc files, or [`None`] if it
+/// isn't known.
+pub fn detect(env: &Env) -> Option<DetectedShell> {
+    let shell = env
+        .get_os("SHELL")
+        .and_then(Shell::from_program)
+        .or_else(parent_shell)
+        .or_else(|| {
+            if !cfg!(windows) {
+                return None;
+            }
+            let modules = env
+                .get_os("PSModulePath")
+                .map_or(0, |path| std::env::split_paths(path).count());
+            Some(if modules >= 3 {
+                Shell::PowerShell
+            } else {
+                Shell::Cmd
+            })
+        })?;
+    Some(DetectedShell {
+        shell,
+        rc_files: shell.rc_files(env),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn parent_shell() -> Option<Shell> {
+    let parent = std::os::unix::process::parent_id();
+    let name = Path::new("/proc")
+        .join(parent.to_string())
+        .join("comm")
+        .read_to_string()
+        .ok()?;
+    Shell::from_program(name.trim_end())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parent_shell() -> Option<Shell> {
+    None
+}
+
+/// Add the `contents` to the rc file at the `path`, as a block delimited by comments with the
+/// `name`, like `# >>> name >>>` and `# <<< name <<<`, replacing that block, if it's already
+/// there (so it's idempotent).
+///
+/// File is written atomically (through symlinks, which are common for dotfiles), and created,
+/// if it doesn't exist.
+///
+/// # Returns
+///
+/// [`Ok(bool)`](bool) telling, whether the file was changed, otherwise an error, as reported by
+/// the OS.
+pub fn append_managed_block(
+    path: impl AsRef<Path>,
+    name: &str,
+    contents: &str,
+) -> io::Result<bool> {
+    let path = path.as_ref();
+    let path = match path.canonicalize() {
+        Ok(resolved) => resolved,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
+        Err(err) => return Err(err),
+    };
+    let current = match path.read_to_string() {
+        Ok(current) => current,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let (start, end) = (format!("# >>> {name} >>>"), format!("# <<< {name} <<<"));
+    // Files edited on Windows keep their line endings.
+    let newline = if current.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let contents: Vec<&str> = contents.trim_end_matches(['\r', '\n']).lines().collect();
+    let mut block = [start.as_str(), &contents.join(newline), end.as_str(), ""].join(newline);
+    // Lines with their endings, so the untouched ones are copied exactly.
+    let lines: Vec<&str> = current.split_inclusive('\n').collect();
+    let is = |line: &str, marker: &str| line.trim_end_matches(['\r', '\n']) == marker;
+    let start_at = lines.iter().position(|line| is(line, &start));
+    let end_at = start_at.and_then(|start_at| {
+        let after = lines[start_at + 1..].iter().position(|line| is(line, &end));
+        after.map(|after| start_at + 1 + after)
+    });
+    let updated = match (start_at, end_at) {
+        (Some(start_at), Some(end_at)) => {
+            // Just like the end marker, the block ends the file without a newline.
+            if !lines[end_at].ends_with('\n') {
+                block.truncate(block.len() - newline.len());
+            }
+            let (before, after) = (&lines[..start_at], &lines[end_at + 1..]);
+            [before, &[block.as_str()], after].concat().concat()
+        }
+        _ if current.is_empty() => block,
+        _ => {
+            let separator = if current.ends_with('\n') {
+                newline.to_owned()
+            } else {
+                newline.repeat(2)
+            };
+            format!("{current}{separator}{block}")
+        }
+    };
+    if updated == current {
+        return Ok(false);
+    }
+    save_atomic(&path, updated, Backup::No)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_ok, assert_some};
+    use std::ffi::OsString;
+    use tempfile::tempdir;
+
+    #[test]
+    fn shells_are_identified() {
+        assert_eq!(Shell::from_program("/usr/bin/zsh"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_program("-bash"), Some(Shell::Bash));
+        assert_eq!(Shell::from_program("pwsh.exe"), Some(Shell::PowerShell));
+        assert_eq!(Shell::from_program("/bin/dash"), Some(Shell::Sh));
+        assert_eq!(Shell::from_program("/usr/bin/python3"), None);
+
+        let env: Env = [
+            (OsString::from("SHELL"), OsString::from("/usr/bin/zsh")),
+            (OsString::from("ZDOTDIR"), OsString::from("/home/user/.zsh")),
+        ]
+        .into();
+        let detected = assert_some!(detect(&env));
+        assert_eq!(detected.shell, Shell::Zsh);
+        assert_eq!(
+            detected.rc_files,
+            [
+                PathBuf::from("/home/user/.zsh/.zshenv"),
+                PathBuf::from("/home/user/.zsh/.zshrc"),
+            ]
+        );
+    }
+
+    #[test]
+    fn managed_block_is_replaced() {
+        let tmp = tempdir().expect("needed for tests");
+        let rc = tmp.path().join(".bashrc");
+        assert_ok!(rc.write("alias ll='ls -l'"));
+        assert!(assert_ok!(append_managed_block(&rc, "app", "export A=1")));
+        assert!(!assert_ok!(append_managed_block(
+            &rc,
+            "app",
+            "export A=1\n"
+        )));
+        let edited = assert_ok!(rc.read_to_string()) + "alias la='ls -a'\n";
+        assert_ok!(rc.write(edited));
+        assert!(assert_ok!(append_managed_block(&rc, "app", "export A=2")));
+        assert_eq!(
+            assert_ok!(rc.read_to_string()),
+            "alias ll='ls -l'\n\n# >>> app >>>\nexport A=2\n# <<< app <<<\nalias la='ls -a'\n"
+        );
+    }
+
+    #[test]
+    fn managed_block_after_stray_end_marker_is_replaced() {
+        let tmp = tempdir().expect("needed for tests");
+        let rc = tmp.path().join(".bashrc");
+        let contents =
+            "# <<< app <<<\n# >>> app >>>\nexport A=1\n# <<< app <<<\nalias la='ls -a'\n";
+        assert_ok!(rc.write(contents));
+        assert!(assert_ok!(append_managed_block(&rc, "app", "export A=2")));
+        assert_eq!(
+            assert_ok!(rc.read_to_string()),
+            "# <<< app <<<\n# >>> app >>>\nexport A=2\n# <<< app <<<\nalias la='ls -a'\n"
+        );
+    }
+
+    #[test]
+    fn managed_block_keeps_line_endings() {
+        let tmp = tempdir().expect("needed for tests");
+        let rc = tmp.path().join("profile.ps1");
+        assert_ok!(rc.write("$a = 1\r\n# >>> app >>>\r\nold\r\n# <<< app <<<"));
+        assert!(assert_ok!(append_managed_block(&rc, "app", "new\nlines\n")));
+        assert_eq!(
+            assert_ok!(rc.read_to_string()),
+            "$a = 1\r\n# >>> app >>>\r\nnew\r\nlines\r\n# <<< app <<<"
+        );
+        assert!(!assert_ok!(append_managed_block(&rc, "app", "new\nlines")));
+
+        assert_ok!(rc.write("$a = 1\r\n"));
+        assert!(assert_ok!(append_managed_block(&rc, "app", "new")));
+        assert_eq!(
+            assert_ok!(rc.read_to_string()),
+            "$a = 1\r\n\r\n# >>> app >>>\r\nnew\r\n# <<< app <<<\r\n"
+        );
+    }
+}
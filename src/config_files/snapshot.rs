@@ -0,0 +1,351 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::config_files::{AppDirs, ini::Ini};
+use crate::fs::{
+    MkdirOptions, PathExt, TempDir,
+    archive::{self, Format, UnpackOptions},
+};
+
+/// Section of the manifest.
+const MANIFEST: &str = "snapshot";
+const VERSION: &str = "1";
+
+/// How the [`restore`] treats the current contents of the directories.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Directories are replaced with the snapshot ones.
+    #[default]
+    Replace,
+    /// Files from the snapshot replace the current ones, and other current files are kept.
+    Merge,
+}
+
+/// Archive the config and state directories of an app (the existing ones), with a manifest, into
+/// a new archive at the `dest`, whose format is guessed from the extension (see the
+/// [`Format::from_path`]).
+///
+/// Gives apps an "export my settings" feature, restored by the [`restore`]:
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{self, AppDirs, RestoreMode};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+/// config_files::snapshot(&dirs, "settings.tar.gz")?;
+/// // Later, or on another machine.
+/// config_files::restore("settings.tar.gz", &dirs, RestoreMode::Replace)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise an error, as reported by the OS (of kind
+/// [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput), if the format isn't known).
+pub fn snapshot(dirs: &AppDirs, dest: impl AsRef<Path>) -> io::Result<()> {
+    let dest = dest.as_ref();
+    let format = Format::from_path(dest).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` has no known archive extension", dest.display()),
+        )
+    })?;
+    let mut manifest = Ini::new();
+    manifest.set(MANIFEST, "version", VERSION);
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    manifest.set(MANIFEST, "created", created.as_secs().to_string());
+    if let Some(profile) = dirs.profile() {
+        manifest.set(MANIFEST, "profile", profile);
+    }
+    let tmp = TempDir::new()?;
+    let manifest_path = tmp.path().join("manifest.ini");
+    let mut parts = vec![("manifest.ini", manifest_path.as_path())];
+    for (name, dir) in parts_of(dirs) {
+        if dir.is_dir() {
+            manifest.set(MANIFEST, name, "true");
+            parts.push((name, dir));
+        }
+    }
+    manifest_path.write(manifest.to_string())?;
+    archive::pack_parts(&parts, dest, format)
+}
+
+/// Restore the directories of an app from the `snapshot` made by the [`snapshot`], according to
+/// the `mode`.
+///
+/// The snapshot is unpacked next to the directories, which are then all moved aside, and the
+/// restored ones take their places, so they're never left partially restored: on a failure,
+/// all of them are moved back. Only once everything is restored, the previous directories
+/// become the `<name>.bak` ones (replacing older backups). Directories missing in the snapshot
+/// are left untouched.
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise an error, as reported by the OS (of kind
+/// [`ErrorKind::InvalidData`](io::ErrorKind::InvalidData), if the `snapshot` isn't valid).
+pub fn restore(snapshot: impl AsRef<Path>, dirs: &AppDirs, mode: RestoreMode) -> io::Result<()> {
+    let snapshot = snapshot.as_ref();
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("`{}` isn't a snapshot", snapshot.display()),
+        )
+    };
+    let parts = parts_of(dirs);
+    let parent_of = |dir: &Path| -> io::Result<PathBuf> {
+        let parent = dir.parent().ok_or_else(invalid)?;
+        parent.mkdir(MkdirOptions::WithParents)?;
+        Ok(parent.to_path_buf())
+    };
+    // Within the same filesystem as (at least) the first directory, so it can be moved.
+    let unpacked = TempDir::new_in(parent_of(parts[0].1)?)?;
+    archive::unpack(snapshot, unpacked.path(), UnpackOptions::new())?;
+    let manifest = unpacked
+        .path()
+        .join("manifest.ini")
+        .read_to_string()
+        .ok()
+        .and_then(|manifest| Ini::parse(&manifest).ok())
+        .ok_or_else(invalid)?;
+    if manifest.get(MANIFEST, "version") != Some(VERSION) {
+        return Err(invalid());
+    }
+
+    let mut holders = Vec::new();
+    let mut swaps = Vec::new();
+    for (name, dir) in parts {
+        if manifest.get(MANIFEST, name) != Some("true") {
+            continue;
+        }
+        let holder = TempDir::new_in(parent_of(dir)?)?;
+        let restored = holder.path().join("restored");
+        move_dir(&unpacked.path().join(name), &restored)?;
+        if mode == RestoreMode::Merge && dir.is_dir() {
+            keep_missing(dir, &restored)?;
+        }
+        swaps.push(Swap {
+            target: dir,
+            restored,
+            previous: holder.path().join("previous"),
+            moved: false,
+            swapped: false,
+        });
+        holders.push(holder);
+    }
+    if let Err(err) = swap_all(&mut swaps) {
+        if rollback(&swaps).is_err() {
+            // Nothing may be removed then, the previous directories could be in there.
+            for holder in holders {
+                holder.keep();
+            }
+        }
+        return Err(err);
+    }
+    for swap in swaps.iter().filter(|swap| swap.moved) {
+        let backup = swap.target.with_added_extension("bak");
+        match backup.rmtree() {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        swap.previous.rename_to(&backup)?;
+    }
+    Ok(())
+}
+
+/// Return parts of a snapshot, without duplicates (like the config and state directories
+/// being the same one, which is the default on macOS).
+fn parts_of(dirs: &AppDirs) -> Vec<(&'static str, &Path)> {
+    let mut parts = Vec::new();
+    let mut seen = Vec::new();
+    for (name, dir) in [("config", dirs.config_dir()), ("state", dirs.state_dir())] {
+        let canonical = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        if !seen.contains(&canonical) {
+            seen.push(canonical);
+            parts.push((name, dir));
+        }
+    }
+    parts
+}
+
+/// Directory being restored by the [`restore`].
+struct Swap<'a> {
+    target: &'a Path,
+    /// Restored contents, next to the `target`.
+    restored: PathBuf,
+    /// Where the current `target` is kept, until everything is restored.
+    previous: PathBuf,
+    /// Whether the `target` existed, and was moved to the `previous`.
+    moved: bool,
+    /// Whether the `restored` was moved to the `target`.
+    swapped: bool,
+}
+
+/// Move all the targets aside, and then the restored directories into their places.
+fn swap_all(swaps: &mut [Swap]) -> io::Result<()> {
+    for swap in swaps.iter_mut() {
+        match swap.target.rename_to(&swap.previous) {
+            Ok(()) => swap.moved = true,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+    for swap in swaps.iter_mut() {
+        swap.restored.rename_to(swap.target)?;
+        swap.swapped = true;
+    }
+    Ok(())
+}
+
+/// Undo the [`swap_all`], as far as possible.
+///
+/// # Returns
+/// [`Ok(())`](Ok), if all the targets were put back, otherwise the last error.
+fn rollback(swaps: &[Swap]) -> io::Result<()> {
+    let mut result = Ok(());
+    for swap in swaps.iter().rev() {
+        if swap.swapped
+            && let Err(err) = swap.target.rename_to(&swap.restored)
+        {
+            result = Err(err);
+            continue;
+        }
+        if swap.moved
+            && let Err(err) = swap.previous.rename_to(swap.target)
+        {
+            result = Err(err);
+        }
+    }
+    result
+}
+
+/// Move the `from` directory to the `to`, even across filesystems.
+fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    match from.rename_to(to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            to.mkdir(MkdirOptions::WithoutParents)?;
+            keep_missing(from, to)
+        }
+        result => result,
+    }
+}
+
+/// Copy entries of the `from`, which are missing in the `to`, into it.
+fn keep_missing(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let (source, target) = (entry.path(), to.join(entry.file_name()));
+        let file_type = entry.file_type()?;
+        match target.symlink_metadata() {
+            Ok(metadata) if metadata.is_dir() && file_type.is_dir() => {
+                keep_missing(&source, &target)?;
+            }
+            // The restored one wins.
+            Ok(_) => {}
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            Err(_) if file_type.is_dir() => {
+                target.mkdir(MkdirOptions::WithoutParents)?;
+                keep_missing(&source, &target)?;
+            }
+            Err(_) if file_type.is_symlink() => {
+                crate::fs::symlink(&std::fs::read_link(&source)?, &target)?;
+            }
+            Err(_) => source.reflink_or_copy_to(&target)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_files::xdg::MacOSBehaviour;
+    use crate::fs::TreeSpec;
+    use crate::os::env::Env;
+    use claim::{assert_err, assert_ok, assert_some};
+    use tempfile::tempdir;
+
+    fn extension() -> &'static str {
+        if cfg!(feature = "tar") {
+            "tar.gz"
+        } else {
+            "zip"
+        }
+    }
+
+    #[test]
+    fn snapshot_is_restored() {
+        let tmp = tempdir().expect("needed for tests");
+        let mut env = Env::empty();
+        env.extend([
+            ("XDG_CONFIG_HOME", tmp.path().join("config")),
+            ("XDG_STATE_HOME", tmp.path().join("state")),
+        ]);
+        let dirs = AppDirs::with_env(&env, MacOSBehaviour::LinuxFallback, "", "", "app").unwrap();
+        let config = TreeSpec::new()
+            .file("config.toml", "theme = 'dark'")
+            .file("themes/dark.toml", "");
+        assert_ok!(config.create_in(dirs.config_dir()));
+        let state = TreeSpec::new().file("history", "ls");
+        assert_ok!(state.create_in(dirs.state_dir()));
+        let archive = tmp.path().join(format!("settings.{}", extension()));
+        assert_ok!(snapshot(&dirs, &archive));
+
+        assert_ok!(
+            dirs.config_dir()
+                .join("config.toml")
+                .write("theme = 'light'")
+        );
+        assert_ok!(dirs.config_dir().join("local.toml").write(""));
+        assert_ok!(restore(&archive, &dirs, RestoreMode::Merge));
+        config
+            .clone()
+            .file("local.toml", "")
+            .assert_matches(dirs.config_dir());
+        let backup = dirs.config_dir().with_added_extension("bak");
+        assert_eq!(
+            assert_ok!(backup.join("config.toml").read_to_string()),
+            "theme = 'light'"
+        );
+
+        assert_ok!(restore(&archive, &dirs, RestoreMode::Replace));
+        config.assert_matches(dirs.config_dir());
+        state.assert_matches(dirs.state_dir());
+
+        let other = tmp.path().join(format!("other.{}", extension()));
+        assert_ok!(archive::pack(
+            dirs.state_dir(),
+            &other,
+            assert_some!(Format::from_path(&other))
+        ));
+        let err = assert_err!(restore(&other, &dirs, RestoreMode::Replace));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn shared_dir_is_restored_once() {
+        let tmp = tempdir().expect("needed for tests");
+        let mut env = Env::empty();
+        env.extend([
+            ("XDG_CONFIG_HOME", tmp.path().join("data")),
+            ("XDG_STATE_HOME", tmp.path().join("data")),
+        ]);
+        let dirs = AppDirs::with_env(&env, MacOSBehaviour::LinuxFallback, "", "", "app").unwrap();
+        assert_eq!(dirs.config_dir(), dirs.state_dir());
+        let saved = TreeSpec::new().file("config.toml", "saved");
+        assert_ok!(saved.create_in(dirs.config_dir()));
+        let archive = tmp.path().join(format!("settings.{}", extension()));
+        assert_ok!(snapshot(&dirs, &archive));
+
+        let original = TreeSpec::new().file("config.toml", "original");
+        assert_ok!(dirs.config_dir().rmtree());
+        assert_ok!(original.create_in(dirs.config_dir()));
+        assert_ok!(restore(&archive, &dirs, RestoreMode::Replace));
+        saved.assert_matches(dirs.config_dir());
+        original.assert_matches(dirs.config_dir().with_added_extension("bak"));
+    }
+}
@@ -0,0 +1,308 @@
+//! Versioned persistence of a small application state.
+//!
+//! A [`Store`] saves the state together with a format version, like
+//! `{"version": 2, "state": {...}}`, and upgrades older files with registered functions, when
+//! loading them. Files are written [atomically](crate::config_files::save_atomic), in a format
+//! detected by the extension, see the
+//! [`ConfigFormat::from_path`](crate::config_files::ConfigFormat::from_path).
+//!
+//! ```rust,no_run
+//! # use rustvil::config_files::{AppDirs, schema::Value, state::Store};
+//! #[derive(Default, serde::Serialize, serde::Deserialize)]
+//! struct State {
+//!     recent_files: Vec<String>,
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+//! // Version 1 had `recent` instead of the `recent_files`.
+//! let path = dirs.state_dir().join("state.json");
+//! let store = Store::<State>::new(path, 2).upgrade(1, |mut state| {
+//!     if let Value::Table(table) = &mut state
+//!         && let Some(recent) = table.remove("recent")
+//!     {
+//!         table.insert("recent_files".to_owned(), recent);
+//!     }
+//!     Ok(state)
+//! });
+//! let mut state = store.load_or_default()?;
+//! state.recent_files.push("notes.txt".to_owned());
+//! store.save(&state)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, Serializer, de::DeserializeOwned, ser::SerializeStruct};
+use thiserror::Error;
+
+use crate::config_files::{Backup, ConfigError, load, save_value_atomic, schema::Value};
+
+/// Errors encountered when loading or saving a [`Store`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum StateError {
+    /// File couldn't be read, parsed or written.
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    /// File doesn't have the expected `version` and `state` keys, or the state doesn't match the
+    /// type (after upgrades).
+    #[error("invalid state in `{}`: {message}", .path.display())]
+    Invalid {
+        /// Path of the file.
+        path: PathBuf,
+        /// Description of the error.
+        message: String,
+    },
+
+    /// File was written by a newer version of the application.
+    #[error("state in `{}` has version {version}, newer than the supported {supported}", .path.display())]
+    Newer {
+        /// Path of the file.
+        path: PathBuf,
+        /// Version of the file.
+        version: u32,
+        /// Current version of the [`Store`].
+        supported: u32,
+    },
+
+    /// No upgrade is registered from the `from` version (or it failed).
+    #[error("cannot upgrade state in `{}` from version {from}: {message}", .path.display())]
+    Upgrade {
+        /// Path of the file.
+        path: PathBuf,
+        /// Version, which couldn't be upgraded.
+        from: u32,
+        /// Description of the error.
+        message: String,
+    },
+}
+
+/// Function upgrading a state from some version to the next one.
+type Upgrade = Box<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// Store of a state of type `T` at a path, see the [module](self) docs.
+pub struct Store<T> {
+    path: PathBuf,
+    version: u32,
+    upgrades: BTreeMap<u32, Upgrade>,
+    _state: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for Store<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Store")
+            .field("path", &self.path)
+            .field("version", &self.version)
+            .field("upgrades", &self.upgrades.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Store<T> {
+    /// Create a store at the `path` (like `state_dir/state.json`), where the current format of
+    /// the state has the `version`.
+    pub fn new(path: impl Into<PathBuf>, version: u32) -> Self {
+        Self {
+            path: path.into(),
+            version,
+            upgrades: BTreeMap::new(),
+            _state: PhantomData,
+        }
+    }
+
+    /// Register the `upgrade` of a state from the version `from` to the `from + 1`.
+    ///
+    /// It receives the raw state, and returns the upgraded one, or an error message.
+    pub fn upgrade(
+        mut self,
+        from: u32,
+        upgrade: impl Fn(Value) -> Result<Value, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.upgrades.insert(from, Box::new(upgrade));
+        self
+    }
+
+    /// Return the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the current version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Load the state, upgrading it from an older version, if needed (the file is rewritten on
+    /// the next [save](Store::save)).
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(Some(T))`](Some) on a success, [`Ok(None)`](Ok) if the file doesn't exist, otherwise
+    /// a [`StateError`].
+    pub fn load(&self) -> Result<Option<T>, StateError> {
+        let raw: Value = match load(&self.path) {
+            Ok(raw) => raw,
+            Err(ConfigError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let invalid = |message: &str| StateError::Invalid {
+            path: self.path.clone(),
+            message: message.to_owned(),
+        };
+        let Value::Table(mut table) = raw else {
+            return Err(invalid("expected a table"));
+        };
+        let version = match table.get("version") {
+            Some(&Value::Integer(version)) => {
+                u32::try_from(version).map_err(|_| invalid("`version` is out of range"))?
+            }
+            _ => return Err(invalid("expected an integer `version`")),
+        };
+        let mut state = table
+            .remove("state")
+            .ok_or_else(|| invalid("missing `state`"))?;
+        if version > self.version {
+            return Err(StateError::Newer {
+                path: self.path.clone(),
+                version,
+                supported: self.version,
+            });
+        }
+        for from in version..self.version {
+            let upgrade_error = |message: String| StateError::Upgrade {
+                path: self.path.clone(),
+                from,
+                message,
+            };
+            let upgrade = self
+                .upgrades
+                .get(&from)
+                .ok_or_else(|| upgrade_error("no upgrade registered".to_owned()))?;
+            state = upgrade(state).map_err(upgrade_error)?;
+        }
+        state
+            .deserialize_into()
+            .map(Some)
+            .map_err(|err| invalid(&err.to_string()))
+    }
+
+    /// Same as the [`Store::load`], but returns the [`Default`], if the file doesn't exist.
+    pub fn load_or_default(&self) -> Result<T, StateError>
+    where
+        T: Default,
+    {
+        Ok(self.load()?.unwrap_or_default())
+    }
+
+    /// Save the `state` in the current version (creating parent directories).
+    ///
+    /// # Returns
+    ///
+    /// [`Ok(())`](Ok) on a success, otherwise a [`StateError`].
+    pub fn save(&self, state: &T) -> Result<(), StateError> {
+        let versioned = Versioned {
+            version: self.version,
+            state,
+        };
+        Ok(save_value_atomic(&self.path, &versioned, Backup::No)?)
+    }
+}
+
+/// Format of the file.
+struct Versioned<'a, T> {
+    version: u32,
+    state: &'a T,
+}
+
+impl<T: Serialize> Serialize for Versioned<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut versioned = serializer.serialize_struct("Versioned", 2)?;
+        versioned.serialize_field("version", &self.version)?;
+        versioned.serialize_field("state", self.state)?;
+        versioned.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_files::save;
+    use claim::{assert_err, assert_ok};
+    use serde::Deserialize;
+    use tempfile::tempdir;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct State {
+        count: u32,
+        name: String,
+    }
+
+    fn extension() -> &'static str {
+        if cfg!(feature = "json") {
+            "json"
+        } else if cfg!(feature = "toml") {
+            "toml"
+        } else {
+            "yaml"
+        }
+    }
+
+    #[test]
+    fn old_versions_are_upgraded() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join(format!("state.{}", extension()));
+        let store = Store::<State>::new(&path, 3)
+            .upgrade(1, |mut state| {
+                if let Value::Table(table) = &mut state {
+                    let count = table.remove("counter").unwrap_or(Value::Integer(0));
+                    table.insert("count".to_owned(), count);
+                }
+                Ok(state)
+            })
+            .upgrade(2, |mut state| {
+                if let Value::Table(table) = &mut state {
+                    table.insert("name".to_owned(), Value::from("upgraded"));
+                }
+                Ok(state)
+            });
+        assert_eq!(assert_ok!(store.load()), None);
+        assert_eq!(assert_ok!(store.load_or_default()), State::default());
+
+        let old = BTreeMap::from([
+            ("version", Value::from(1)),
+            (
+                "state",
+                Value::Table(BTreeMap::from([("counter".to_owned(), Value::from(7))])),
+            ),
+        ]);
+        assert_ok!(save(&path, &old));
+        let upgraded = State {
+            count: 7,
+            name: "upgraded".to_owned(),
+        };
+        assert_eq!(assert_ok!(store.load()), Some(upgraded));
+
+        let state = State {
+            count: 1,
+            name: "new".to_owned(),
+        };
+        assert_ok!(store.save(&state));
+        assert_eq!(assert_ok!(store.load()), Some(state));
+
+        let err = assert_err!(Store::<State>::new(&path, 2).load());
+        assert!(matches!(err, StateError::Newer { version: 3, .. }), "{err}");
+        assert_ok!(save(&path, &old));
+        let err = assert_err!(Store::<State>::new(&path, 2).load());
+        assert!(matches!(err, StateError::Upgrade { from: 1, .. }), "{err}");
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use thiserror::Error;
+
+use crate::config_files::{Backup, save_atomic};
+use crate::fs::PathExt;
+
+/// Format of a config file, supported by the [`load`] and the [`save`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// TOML, for the `.toml` extension.
+    #[cfg(feature = "toml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+    Toml,
+    /// JSON, for the `.json` extension.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    Json,
+    /// YAML, for the `.yaml` and `.yml` extensions.
+    #[cfg(feature = "yaml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Detect format from the extension of the `path` (case-insensitive).
+    ///
+    /// # Returns
+    ///
+    /// [`None`] for unknown extensions, and formats with disabled features.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            #[cfg(feature = "toml")]
+            "toml" => Some(Self::Toml),
+            #[cfg(feature = "json")]
+            "json" => Some(Self::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Deserialize `contents` of the file at `path` (used only for errors).
+    fn parse<T: DeserializeOwned>(self, path: &Path, contents: &str) -> Result<T, ConfigError> {
+        let parse_error = |position: Option<(usize, usize)>, message: String| ConfigError::Parse {
+            path: path.to_path_buf(),
+            format: self,
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            message,
+        };
+        match self {
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::from_str(contents).map_err(|err| {
+                let position = err.span().map(|span| line_and_column(contents, span.start));
+                parse_error(position, err.message().to_owned())
+            }),
+            #[cfg(feature = "json")]
+            Self::Json => serde_json::from_str(contents).map_err(|err| {
+                let position = (err.line() > 0).then(|| (err.line(), err.column()));
+                parse_error(position, strip_position(err.to_string()))
+            }),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml_ng::from_str(contents).map_err(|err| {
+                let position = err
+                    .location()
+                    .map(|location| (location.line(), location.column()));
+                parse_error(position, strip_position(err.to_string()))
+            }),
+        }
+    }
+
+    /// Serialize `value` for the file at `path` (used only for errors).
+    fn serialize<T: Serialize + ?Sized>(
+        self,
+        path: &Path,
+        value: &T,
+    ) -> Result<String, ConfigError> {
+        let serialize_error = |message: String| ConfigError::Serialize {
+            path: path.to_path_buf(),
+            format: self,
+            message,
+        };
+        match self {
+            #[cfg(feature = "toml")]
+            Self::Toml => {
+                toml::to_string_pretty(value).map_err(|err| serialize_error(err.to_string()))
+            }
+            #[cfg(feature = "json")]
+            Self::Json => serde_json::to_string_pretty(value)
+                .map(|mut json| {
+                    json.push('\n');
+                    json
+                })
+                .map_err(|err| serialize_error(err.to_string())),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => {
+                serde_yaml_ng::to_string(value).map_err(|err| serialize_error(err.to_string()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            #[cfg(feature = "toml")]
+            Self::Toml => "TOML",
+            #[cfg(feature = "json")]
+            Self::Json => "JSON",
+            #[cfg(feature = "yaml")]
+            Self::Yaml => "YAML",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Errors encountered when loading or saving a config file.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// File couldn't be read or written.
+    #[error("cannot access `{}`: {source}", .path.display())]
+    Io {
+        /// Path of the file.
+        path: PathBuf,
+        /// Underlying error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// Format couldn't be detected from the extension (or its feature is disabled).
+    #[error("unknown config format of `{}`", .path.display())]
+    UnknownFormat {
+        /// Path of the file.
+        path: PathBuf,
+    },
+
+    /// File isn't valid, or doesn't match the expected type.
+    #[error("{}{}: invalid {format}: {message}", .path.display(), position(*.line, *.column))]
+    Parse {
+        /// Path of the file.
+        path: PathBuf,
+        /// Format of the file.
+        format: ConfigFormat,
+        /// Line of the error (1-based), if known.
+        line: Option<usize>,
+        /// Column of the error (1-based), if known.
+        column: Option<usize>,
+        /// Description of the error, without the position.
+        message: String,
+    },
+
+    /// Value can't be represented in the format (like a top-level array in the TOML).
+    #[error("cannot save `{}` as {format}: {message}", .path.display())]
+    Serialize {
+        /// Path of the file.
+        path: PathBuf,
+        /// Format of the file.
+        format: ConfigFormat,
+        /// Description of the error.
+        message: String,
+    },
+
+    /// Merged config of the [`LayeredConfig`](crate::config_files::LayeredConfig) doesn't match
+    /// the expected type.
+    #[error("invalid config merged from {}: {message}", paths_list(.paths))]
+    Merged {
+        /// Paths of all the layers, in order.
+        paths: Vec<PathBuf>,
+        /// Description of the error.
+        message: String,
+    },
+}
+
+/// Format the `paths` as a comma-separated list.
+fn paths_list(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("`{}`", path.display()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format `:line:column` suffix of a path.
+fn position(line: Option<usize>, column: Option<usize>) -> String {
+    match (line, column) {
+        (Some(line), Some(column)) => format!(":{line}:{column}"),
+        (Some(line), None) => format!(":{line}"),
+        _ => String::new(),
+    }
+}
+
+/// Translate byte `offset` into the 1-based line and column (in characters).
+#[cfg_attr(not(feature = "toml"), allow(dead_code))]
+fn line_and_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..contents.floor_char_boundary(offset)];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Strip the position suffix, which serde formats append to messages.
+#[cfg_attr(not(any(feature = "json", feature = "yaml")), allow(dead_code))]
+fn strip_position(mut message: String) -> String {
+    if let Some(at) = message.rfind(" at line ") {
+        message.truncate(at);
+    }
+    message
+}
+
+fn detect_format(path: &Path) -> Result<ConfigFormat, ConfigError> {
+    ConfigFormat::from_path(path).ok_or_else(|| ConfigError::UnknownFormat {
+        path: path.to_path_buf(),
+    })
+}
+
+/// Load a config of type `T` from the `path`, in a format detected by the
+/// [extension](ConfigFormat::from_path).
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{self, AppDirs};
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     theme: String,
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dirs = AppDirs::new("com", "Example", "App").ok_or("no home directory")?;
+/// let config: Config = config_files::load(dirs.config_dir().join("config.toml"))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(T)`](Ok) on a success, otherwise a [`ConfigError`] (including the path, and the position
+/// for [parse errors](ConfigError::Parse)).
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, ConfigError> {
+    let path = path.as_ref();
+    let format = detect_format(path)?;
+    let contents = path.read_to_string().map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    format.parse(path, &contents)
+}
+
+/// Save the `value` into the `path` (creating its parent directories), in a format detected
+/// by the [extension](ConfigFormat::from_path).
+///
+/// File is replaced [atomically](save_atomic), so it's never left half-written.
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise a [`ConfigError`].
+pub fn save<T: Serialize + ?Sized>(path: impl AsRef<Path>, value: &T) -> Result<(), ConfigError> {
+    save_value_atomic(path, value, Backup::No)
+}
+
+/// Like the [`save`], but optionally keeps the previous version of the file as `<name>.bak`
+/// (see the [`save_atomic`]).
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::{self, Backup};
+/// # use std::collections::BTreeMap;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = BTreeMap::from([("theme", "dark")]);
+/// config_files::save_value_atomic("app/config.toml", &config, Backup::Yes)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Returns
+///
+/// [`Ok(())`](Ok) on a success, otherwise a [`ConfigError`].
+pub fn save_value_atomic<T: Serialize + ?Sized>(
+    path: impl AsRef<Path>,
+    value: &T,
+    backup: Backup,
+) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let contents = detect_format(path)?.serialize(path, value)?;
+    save_atomic(path, contents, backup).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+    use serde::Deserialize;
+    use tempfile::tempdir;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        retries: u32,
+    }
+
+    fn extensions() -> Vec<&'static str> {
+        vec![
+            #[cfg(feature = "toml")]
+            "toml",
+            #[cfg(feature = "json")]
+            "json",
+            #[cfg(feature = "yaml")]
+            "yml",
+        ]
+    }
+
+    #[test]
+    fn save_and_load_roundtrip() {
+        let tmp = tempdir().expect("needed for tests");
+        let config = Config {
+            name: "app".to_owned(),
+            retries: 3,
+        };
+        for extension in extensions() {
+            let path = tmp
+                .path()
+                .join("nested")
+                .join(format!("config.{extension}"));
+            assert_ok!(save(&path, &config));
+            assert_eq!(assert_ok!(load::<Config>(&path)), config);
+        }
+        let err = assert_err!(load::<Config>(tmp.path().join("config.ini")));
+        assert!(matches!(err, ConfigError::UnknownFormat { .. }));
+        let err = assert_err!(load::<Config>(
+            tmp.path().join(format!("missing.{}", extensions()[0]))
+        ));
+        assert!(matches!(err, ConfigError::Io { .. }));
+    }
+
+    #[test]
+    fn parse_errors_have_positions() {
+        let tmp = tempdir().expect("needed for tests");
+        let invalid = [
+            ("toml", "name = \"app\"\nretries = \"many\"\n"),
+            (
+                "json",
+                "{\n  \"name\": \"app\",\n  \"retries\": \"many\"\n}\n",
+            ),
+            ("yml", "name: app\nretries: many\n"),
+        ];
+        for (extension, contents) in invalid {
+            if !extensions().contains(&extension) {
+                continue;
+            }
+            let path = tmp.path().join(format!("config.{extension}"));
+            assert_ok!(path.write(contents));
+            let err = assert_err!(load::<Config>(&path));
+            let ConfigError::Parse { line, .. } = &err else {
+                panic!("unexpected error: {err}");
+            };
+            assert!(matches!(line, Some(2 | 3)), "{err}");
+            let message = err.to_string();
+            assert!(
+                message.starts_with(&format!("{}:", path.display())),
+                "{message}"
+            );
+            assert!(!message.contains(" at line "), "{message}");
+        }
+    }
+
+    #[test]
+    fn positions_are_computed_from_offsets() {
+        assert_eq!(line_and_column("a\nbc\nd", 0), (1, 1));
+        assert_eq!(line_and_column("a\nbc\nd", 3), (2, 2));
+        assert_eq!(line_and_column("ż\nż", 5), (2, 2));
+        // Offsets inside of a character are rounded down.
+        assert_eq!(line_and_column("ż\nż", 4), (2, 1));
+    }
+}
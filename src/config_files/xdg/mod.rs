@@ -0,0 +1,707 @@
+//! XDG Base Directory Specification support.
+//!
+//! Implements cross-platform path resolution following the XDG Base Directory spec,
+//! with platform-specific fallbacks for Windows and macOS. [`user_dirs`] resolves the
+//! well-known user directories, like Desktop or Downloads, and the
+//! [`config_search_paths`] (and its analogues) list all the directories to search, in order. [`bin`]
+//! is where user executables are installed.
+//!
+//! It was based on following spec: <https://specifications.freedesktop.org/basedir-spec/latest/#variables>, but it assumes Linux only.
+//! More granular description can be found here: <https://github.com/adrg/xdg/blob/master/README.md#xdg-base-directory>.
+
+use crate::fs::{MkdirOptions, PathExt};
+use crate::os::env::Env;
+use std::{io, path::PathBuf};
+use thiserror::Error;
+
+use crate::config_files::home;
+
+mod user_dirs;
+
+pub use user_dirs::{UserDirs, user_dirs};
+
+/// How macOS XDG should be treated.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum MacOSBehaviour {
+    /// Use fallbacks in `~/Library/...`.
+    UseLibrary,
+    /// Use fallbacks as Linux ones, like `~/.config/...`.
+    LinuxFallback,
+}
+
+/// [`MacOSBehaviour`] chosen separately for each category of directories, like `~/.config` for
+/// configs, but `~/Library/Caches` for caches:
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::xdg::{self, Behaviour, MacOSBehaviour};
+/// # use rustvil::os::env::Env;
+/// let behaviour = Behaviour {
+///     cache: MacOSBehaviour::UseLibrary,
+///     ..Behaviour::all(MacOSBehaviour::LinuxFallback)
+/// };
+/// let cache = xdg::cache(&Env::new(), behaviour);
+/// ```
+///
+/// Functions taking it accept a plain [`MacOSBehaviour`] as well, which applies to all of them.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Behaviour {
+    /// Used by the [`config`].
+    pub config: MacOSBehaviour,
+    /// Used by the [`cache`].
+    pub cache: MacOSBehaviour,
+    /// Used by the [`data`].
+    pub data: MacOSBehaviour,
+    /// Used by the [`state`].
+    pub state: MacOSBehaviour,
+    /// Whether relative `$XDG_*_HOME` values are used.
+    pub relative: RelativePaths,
+}
+
+/// How relative values of the `$XDG_*` variables are treated.
+///
+/// The spec requires ignoring them (so the fallback is used instead), but some callers want
+/// the raw value anyway, e.g. to report it.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum RelativePaths {
+    /// Ignore them, as the spec requires.
+    Ignore,
+    /// Use them as they are.
+    Keep,
+}
+
+impl Behaviour {
+    /// Use the same `behaviour` for all the categories, and [ignore](RelativePaths::Ignore)
+    /// relative paths.
+    pub const fn all(behaviour: MacOSBehaviour) -> Self {
+        Self {
+            config: behaviour,
+            cache: behaviour,
+            data: behaviour,
+            state: behaviour,
+            relative: RelativePaths::Ignore,
+        }
+    }
+}
+
+impl From<MacOSBehaviour> for Behaviour {
+    fn from(behaviour: MacOSBehaviour) -> Self {
+        Self::all(behaviour)
+    }
+}
+
+/// Errors explaining, why a base directory couldn't be resolved, see the [`try_config`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum XdgError {
+    /// Home directory (needed by the fallback) couldn't be determined, like when the `$HOME`
+    /// isn't set.
+    #[error("cannot determine the home directory")]
+    NoHome,
+
+    /// Variable is set, but its value is ignored (as it's empty, or relative), and the fallback
+    /// failed too.
+    #[error("`${key}` must be an absolute path, but it's `{}`", .value.display())]
+    Invalid {
+        /// Name of the variable.
+        key: String,
+        /// Ignored value.
+        value: PathBuf,
+    },
+
+    /// Variable, which has no fallback (like the `$XDG_RUNTIME_DIR`), isn't set.
+    #[error("`${key}` is not set")]
+    NotSet {
+        /// Name of the variable.
+        key: String,
+    },
+
+    /// Platform API for the well-known folders failed (Windows only).
+    #[error("cannot get the known folder: {source}")]
+    Platform {
+        /// Underlying error.
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Home directory of the `env`: its `$HOME` (`%USERPROFILE%` on Windows), if it's absolute, or
+/// the [`home`] of the process.
+pub(crate) fn home_of(env: &Env) -> Option<PathBuf> {
+    let key = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env.get_non_empty(key)
+        .map(PathBuf::from)
+        .filter(|home| home.is_absolute())
+        .or_else(home)
+}
+
+/// Fallback of a base directory: `~\AppData\Local` on Windows (or the `%LOCALAPPDATA%`, or its
+/// Known Folder, with the `windows_suffix`), `~/Library/<macos>` on macOS with the
+/// [`MacOSBehaviour::UseLibrary`], and `~/<linux>` elsewhere.
+fn fallback(
+    env: &Env,
+    behaviour: MacOSBehaviour,
+    macos: &str,
+    linux: &str,
+    windows_suffix: Option<&str>,
+) -> Result<PathBuf, XdgError> {
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::UI::Shell::FOLDERID_LocalAppData;
+        let _ = (behaviour, macos, linux);
+        if let Some(home) = home_of(env) {
+            return Ok(home.join("AppData").join("Local"));
+        }
+        let local = match env.get_non_empty("LOCALAPPDATA") {
+            Some(local) => PathBuf::from(local),
+            None => known_folder(&FOLDERID_LocalAppData)
+                .map_err(|source| XdgError::Platform { source })?,
+        };
+        Ok(match windows_suffix {
+            Some(suffix) => local.join(suffix),
+            None => local,
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = windows_suffix;
+        let home = home_of(env).ok_or(XdgError::NoHome)?;
+        if cfg!(target_os = "macos") && behaviour == MacOSBehaviour::UseLibrary {
+            Ok(home.join("Library").join(macos))
+        } else {
+            Ok(home.join(linux))
+        }
+    }
+}
+
+/// Get the Known Folder with the `id` (like the `FOLDERID_LocalAppData`).
+#[cfg(windows)]
+fn known_folder(id: &windows_sys::core::GUID) -> io::Result<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+    use windows_sys::Win32::{
+        System::Com::CoTaskMemFree,
+        UI::Shell::{KF_FLAG_DEFAULT, SHGetKnownFolderPath},
+    };
+
+    let mut path = std::ptr::null_mut();
+    // SAFETY: The GUID and the out-pointer are valid for the duration of the call, and a `NULL`
+    // token means the current user.
+    let result = unsafe {
+        SHGetKnownFolderPath(id, KF_FLAG_DEFAULT as u32, std::ptr::null_mut(), &mut path)
+    };
+    let folder = if result == 0 {
+        // SAFETY: On a success, `path` is a NUL-terminated wide string.
+        let wide = unsafe {
+            let len = (0..).take_while(|&i| *path.add(i) != 0).count();
+            std::slice::from_raw_parts(path, len)
+        };
+        Ok(PathBuf::from(std::ffi::OsString::from_wide(wide)))
+    } else {
+        Err(io::Error::from_raw_os_error(result))
+    };
+    // SAFETY: The buffer is allocated by the call (or `NULL`), and must be freed even on a failure.
+    unsafe { CoTaskMemFree(path.cast()) };
+    folder
+}
+
+/// Resolve a base directory from the `key`, or the `fallback`, see the [`try_config`].
+fn resolve(
+    env: &Env,
+    key: &str,
+    relative: RelativePaths,
+    fallback: impl FnOnce() -> Result<PathBuf, XdgError>,
+) -> Result<PathBuf, XdgError> {
+    if let Some(dir) = var(env, key, relative) {
+        return Ok(dir);
+    }
+    fallback().map_err(|err| match env.get_non_empty(key) {
+        // More actionable, than the failed fallback.
+        Some(value) if !matches!(err, XdgError::Platform { .. }) => XdgError::Invalid {
+            key: key.to_owned(),
+            value: PathBuf::from(value),
+        },
+        _ => err,
+    })
+}
+
+/// Get proper path for `$XDG_CONFIG_HOME`.
+///
+/// # Returns
+///
+/// Most of time it should be [`Some`] variant.
+/// [`None`] is returned if and only if:
+/// 1. `env` has no (absolute) key `"HOME"` (`"USERPROFILE"` on Windows), [`home`] returns
+///    `None`, and `env` has no key `"LOCALAPPDATA"` (Windows only),
+/// 2. `env` has no key `"XDG_CONFIG_HOME"` (or its value is [ignored](RelativePaths)).
+///
+/// Use the [`try_config`] to tell users, why.
+pub fn config(env: &Env, behaviour: impl Into<Behaviour>) -> Option<PathBuf> {
+    try_config(env, behaviour).ok()
+}
+
+/// Same as the [`config`], but explains a failure.
+///
+/// # Returns
+///
+/// [`Ok(PathBuf)`](PathBuf) with the directory, otherwise an [`XdgError`]: the
+/// [`XdgError::Invalid`], if the variable is set to an ignored value, the [`XdgError::NoHome`]
+/// if it isn't set, or the [`XdgError::Platform`], if the Known Folders API failed.
+pub fn try_config(env: &Env, behaviour: impl Into<Behaviour>) -> Result<PathBuf, XdgError> {
+    let behaviour = behaviour.into();
+    resolve(env, "XDG_CONFIG_HOME", behaviour.relative, || {
+        fallback(
+            env,
+            behaviour.config,
+            "Application Support",
+            ".config",
+            None,
+        )
+    })
+}
+
+/// Get proper path for `$XDG_DATA_HOME`.
+///
+/// # Returns
+///
+/// Most of time it should be [`Some`] variant.
+/// [`None`] is returned if and only if:
+/// 1. `env` has no (absolute) key `"HOME"` (`"USERPROFILE"` on Windows), [`home`] returns
+///    `None`, and `env` has no key `"LOCALAPPDATA"` (Windows only),
+/// 2. `env` has no key `"XDG_DATA_HOME"` (or its value is [ignored](RelativePaths)).
+///
+/// Use the [`try_data`] to tell users, why.
+pub fn data(env: &Env, behaviour: impl Into<Behaviour>) -> Option<PathBuf> {
+    try_data(env, behaviour).ok()
+}
+
+/// Same as the [`data`], but explains a failure, see the [`try_config`].
+pub fn try_data(env: &Env, behaviour: impl Into<Behaviour>) -> Result<PathBuf, XdgError> {
+    let behaviour = behaviour.into();
+    resolve(env, "XDG_DATA_HOME", behaviour.relative, || {
+        fallback(
+            env,
+            behaviour.data,
+            "Application Support",
+            ".local/share",
+            None,
+        )
+    })
+}
+
+/// Get proper path for `$XDG_CACHE_HOME`.
+///
+/// # Returns
+///
+/// Most of time it should be [`Some`] variant.
+/// [`None`] is returned if and only if:
+/// 1. `env` has no (absolute) key `"HOME"` (`"USERPROFILE"` on Windows), [`home`] returns
+///    `None`, and `env` has no key `"LOCALAPPDATA"` (Windows only),
+/// 2. `env` has no key `"XDG_CACHE_HOME"` (or its value is [ignored](RelativePaths)).
+///
+/// Use the [`try_cache`] to tell users, why.
+pub fn cache(env: &Env, behaviour: impl Into<Behaviour>) -> Option<PathBuf> {
+    try_cache(env, behaviour).ok()
+}
+
+/// Same as the [`cache`], but explains a failure, see the [`try_config`].
+pub fn try_cache(env: &Env, behaviour: impl Into<Behaviour>) -> Result<PathBuf, XdgError> {
+    let behaviour = behaviour.into();
+    resolve(env, "XDG_CACHE_HOME", behaviour.relative, || {
+        fallback(env, behaviour.cache, "Caches", ".cache", Some("caches"))
+    })
+}
+
+/// Get proper path for `$XDG_STATE_HOME`.
+///
+/// # Returns
+///
+/// Most of time it should be [`Some`] variant.
+/// [`None`] is returned if and only if:
+/// 1. `env` has no (absolute) key `"HOME"` (`"USERPROFILE"` on Windows), [`home`] returns
+///    `None`, and `env` has no key `"LOCALAPPDATA"` (Windows only)
+/// 2. `env` has no key `"XDG_STATE_HOME"` (or its value is [ignored](RelativePaths)).
+///
+/// Use the [`try_state`] to tell users, why.
+pub fn state(env: &Env, behaviour: impl Into<Behaviour>) -> Option<PathBuf> {
+    try_state(env, behaviour).ok()
+}
+
+/// Same as the [`state`], but explains a failure, see the [`try_config`].
+pub fn try_state(env: &Env, behaviour: impl Into<Behaviour>) -> Result<PathBuf, XdgError> {
+    let behaviour = behaviour.into();
+    resolve(env, "XDG_STATE_HOME", behaviour.relative, || {
+        fallback(
+            env,
+            behaviour.state,
+            "Application Support",
+            ".local/state",
+            None,
+        )
+    })
+}
+
+/// Get proper path for `$XDG_RUNTIME_DIR`.
+///
+/// # Returns
+///
+/// [`None`] if `env` has no key `"XDG_RUNTIME_DIR"` (or it's relative): the spec defines no
+/// fallback, and there is no equivalent on Windows or macOS.
+pub fn runtime(env: &Env) -> Option<PathBuf> {
+    try_runtime(env).ok()
+}
+
+/// Same as the [`runtime`], but explains a failure.
+///
+/// # Returns
+///
+/// [`Ok(PathBuf)`](PathBuf) with the directory, otherwise an [`XdgError::Invalid`], if the
+/// variable is set to an ignored value, or the [`XdgError::NotSet`].
+pub fn try_runtime(env: &Env) -> Result<PathBuf, XdgError> {
+    let key = "XDG_RUNTIME_DIR";
+    resolve(env, key, RelativePaths::Ignore, || {
+        Err(XdgError::NotSet {
+            key: key.to_owned(),
+        })
+    })
+}
+
+/// Get proper path for `$XDG_BIN_HOME`, where user executables are installed.
+///
+/// The variable isn't in the spec yet, but it names the `~/.local/bin`, which the spec mentions
+/// (and is used as the fallback, also on macOS). Windows uses the `~\AppData\Local\Programs\bin`.
+///
+/// # Returns
+///
+/// [`None`] if and only if `env` has no (absolute) key `"HOME"` (`"USERPROFILE"` on Windows),
+/// [`home`] returns `None`, and `env` has no key `"XDG_BIN_HOME"` (or it's relative).
+pub fn bin(env: &Env) -> Option<PathBuf> {
+    var(env, "XDG_BIN_HOME", RelativePaths::Ignore).or_else(|| {
+        home_of(env).map(|mut home| {
+            if cfg!(windows) {
+                home.push("AppData");
+                home.push("Local");
+                home.push("Programs");
+            } else {
+                home.push(".local");
+            }
+            home.push("bin");
+            home
+        })
+    })
+}
+
+/// Check, whether the [`bin`] directory is in the `$PATH`, so installed executables can be run
+/// by name (otherwise users should be told to add it).
+pub fn is_on_path(env: &Env) -> bool {
+    let (Some(bin), Some(path)) = (bin(env), env.get_os("PATH")) else {
+        return false;
+    };
+    let canonical = bin.canonicalize().ok();
+    std::env::split_paths(path)
+        .any(|dir| dir == bin || canonical.is_some() && dir.canonicalize().ok() == canonical)
+}
+
+/// Read a directory from the `key`, ignoring empty, and unless `relative` is
+/// [`RelativePaths::Keep`], relative values.
+fn var(env: &Env, key: &str, relative: RelativePaths) -> Option<PathBuf> {
+    env.get_non_empty(key)
+        .map(PathBuf::from)
+        .filter(|dir| relative == RelativePaths::Keep || dir.is_absolute())
+}
+
+/// Create the resolved `dir` (with parents), if it doesn't exist yet.
+///
+/// Newly created `private` directories get mode `0o700` on `unix`.
+fn ensure(dir: Result<PathBuf, XdgError>, private: bool) -> io::Result<PathBuf> {
+    let dir = dir.map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))?;
+    let existed = dir.is_dir();
+    dir.mkdir(MkdirOptions::WithParents)?;
+    #[cfg(unix)]
+    if private && !existed {
+        use std::os::unix::fs::PermissionsExt;
+        dir.set_permissions(std::fs::Permissions::from_mode(0o700))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (private, existed);
+    Ok(dir)
+}
+
+/// Same as the [`config`], but creates the directory if needed.
+///
+/// # Returns
+///
+/// [`Ok(PathBuf)`](PathBuf) with the existing directory, or an error of kind
+/// [`ErrorKind::NotFound`](io::ErrorKind::NotFound) wrapping the [`XdgError`], if it can't be
+/// resolved, or as reported by the [`PathExt::mkdir`].
+pub fn config_ensure(env: &Env, behaviour: impl Into<Behaviour>) -> io::Result<PathBuf> {
+    ensure(try_config(env, behaviour), false)
+}
+
+/// Same as the [`data`], but creates the directory if needed, see the [`config_ensure`].
+pub fn data_ensure(env: &Env, behaviour: impl Into<Behaviour>) -> io::Result<PathBuf> {
+    ensure(try_data(env, behaviour), false)
+}
+
+/// Same as the [`cache`], but creates the directory if needed, see the [`config_ensure`].
+pub fn cache_ensure(env: &Env, behaviour: impl Into<Behaviour>) -> io::Result<PathBuf> {
+    ensure(try_cache(env, behaviour), false)
+}
+
+/// Same as the [`state`], but creates the directory (with mode `0o700` on `unix`) if needed,
+/// see the [`config_ensure`].
+pub fn state_ensure(env: &Env, behaviour: impl Into<Behaviour>) -> io::Result<PathBuf> {
+    ensure(try_state(env, behaviour), true)
+}
+
+/// Same as the [`runtime`], but creates the directory (with mode `0o700` on `unix`, as the spec
+/// requires) if needed, see the [`config_ensure`].
+pub fn runtime_ensure(env: &Env) -> io::Result<PathBuf> {
+    ensure(try_runtime(env), true)
+}
+
+/// Parse a list of directories from the `key` (separated like the `$PATH`, skipping relative
+/// ones, as the spec requires), or use `default`, if none is left.
+fn dirs_list(env: &Env, key: &str, default: impl FnOnce() -> Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut dirs = env.get_paths(key);
+    dirs.retain(|dir| dir.is_absolute());
+    if dirs.is_empty() { default() } else { dirs }
+}
+
+/// System-wide directory on Windows: `%ProgramData%`.
+fn program_data(env: &Env) -> PathBuf {
+    env.get_non_empty("ProgramData")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"))
+}
+
+/// Get system config directories from `$XDG_CONFIG_DIRS`, in the order of preference.
+///
+/// # Returns
+///
+/// Absolute entries of the variable (separated with `:`, or `;` on Windows), or if there are none,
+/// a platform default: `/etc/xdg`, `/Library/Application Support` (macOS), or `%ProgramData%`
+/// (Windows).
+pub fn config_dirs(env: &Env) -> Vec<PathBuf> {
+    dirs_list(env, "XDG_CONFIG_DIRS", || {
+        if cfg!(windows) {
+            vec![program_data(env)]
+        } else if cfg!(target_os = "macos") {
+            vec![PathBuf::from("/Library/Application Support")]
+        } else {
+            vec![PathBuf::from("/etc/xdg")]
+        }
+    })
+}
+
+/// Get system data directories from `$XDG_DATA_DIRS`, in the order of preference.
+///
+/// # Returns
+///
+/// Absolute entries of the variable (separated with `:`, or `;` on Windows), or if there are none,
+/// a platform default: `/usr/local/share` and `/usr/share`, `/Library/Application Support`
+/// (macOS), or `%ProgramData%` (Windows).
+pub fn data_dirs(env: &Env) -> Vec<PathBuf> {
+    dirs_list(env, "XDG_DATA_DIRS", || {
+        if cfg!(windows) {
+            vec![program_data(env)]
+        } else if cfg!(target_os = "macos") {
+            vec![PathBuf::from("/Library/Application Support")]
+        } else {
+            vec![
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/usr/share"),
+            ]
+        }
+    })
+}
+
+/// Chain the `user` directory with the `system` ones, skipping duplicates of it.
+fn search_paths(user: Option<PathBuf>, system: Vec<PathBuf>) -> impl Iterator<Item = PathBuf> {
+    let system: Vec<PathBuf> = system
+        .into_iter()
+        .filter(|dir| user.as_ref() != Some(dir))
+        .collect();
+    user.into_iter().chain(system)
+}
+
+/// Get all the config directories, in the order of precedence: the user one ([`config`]), then
+/// the system ones ([`config_dirs`]).
+///
+/// Useful for custom lookups, like merging a config from all of them (applying the last one
+/// first):
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::xdg::{self, MacOSBehaviour};
+/// # use rustvil::os::env::Env;
+/// let env = Env::new();
+/// let mut candidates: Vec<_> = xdg::config_search_paths(&env, MacOSBehaviour::LinuxFallback)
+///     .map(|dir| dir.join("app/config.toml"))
+///     .filter(|path| path.is_file())
+///     .collect();
+/// candidates.reverse();
+/// ```
+pub fn config_search_paths(
+    env: &Env,
+    behaviour: impl Into<Behaviour>,
+) -> impl Iterator<Item = PathBuf> {
+    search_paths(config(env, behaviour), config_dirs(env))
+}
+
+/// Get all the data directories, in the order of precedence: the user one ([`data`]), then the
+/// system ones ([`data_dirs`]).
+pub fn data_search_paths(
+    env: &Env,
+    behaviour: impl Into<Behaviour>,
+) -> impl Iterator<Item = PathBuf> {
+    search_paths(data(env, behaviour), data_dirs(env))
+}
+
+/// Get all the state directories, in the order of precedence: only the user one ([`state`]), as
+/// the spec defines no system ones.
+pub fn state_search_paths(
+    env: &Env,
+    behaviour: impl Into<Behaviour>,
+) -> impl Iterator<Item = PathBuf> {
+    search_paths(state(env, behaviour), Vec::new())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn system_dirs_defaults() {
+        let empty = Env::from_iter([("XDG_DATA_DIRS", "")]);
+        assert_eq!(config_dirs(&empty), [PathBuf::from("/etc/xdg")]);
+        assert_eq!(
+            data_dirs(&empty),
+            [
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/usr/share")
+            ]
+        );
+    }
+
+    #[test]
+    fn ensured_dirs_are_created() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempfile::tempdir().expect("needed for tests");
+        let (state, cache) = (tmp.path().join("state"), tmp.path().join("a/cache"));
+        let env = Env::from_iter([
+            ("XDG_STATE_HOME", state.to_str().unwrap()),
+            ("XDG_CACHE_HOME", cache.to_str().unwrap()),
+        ]);
+        let created = assert_ok!(state_ensure(&env, MacOSBehaviour::LinuxFallback));
+        assert_eq!(created, state);
+        let mode = assert_ok!(state.metadata()).permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert_eq!(
+            assert_ok!(cache_ensure(&env, MacOSBehaviour::LinuxFallback)),
+            cache
+        );
+        assert!(cache.is_dir());
+        let err = assert_err!(runtime_ensure(&env));
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn relative_values_are_ignored() {
+        let env = Env::from_iter([
+            ("HOME", "/home/user"),
+            ("XDG_CONFIG_HOME", "relative/config"),
+            ("XDG_CACHE_HOME", ""),
+            ("XDG_RUNTIME_DIR", "run"),
+            ("XDG_DATA_DIRS", "share:/opt/share"),
+        ]);
+        let behaviour = MacOSBehaviour::LinuxFallback;
+        assert_ne!(
+            config(&env, behaviour),
+            Some(PathBuf::from("relative/config"))
+        );
+        assert_ne!(cache(&env, behaviour), Some(PathBuf::new()));
+        // Fallbacks use the `$HOME` of the `env`.
+        #[cfg(unix)]
+        {
+            assert_eq!(
+                config(&env, behaviour),
+                Some(PathBuf::from("/home/user/.config"))
+            );
+            assert_eq!(
+                cache(&env, behaviour),
+                Some(PathBuf::from("/home/user/.cache"))
+            );
+        }
+        let raw = Behaviour {
+            relative: RelativePaths::Keep,
+            ..Behaviour::all(behaviour)
+        };
+        assert_eq!(config(&env, raw), Some(PathBuf::from("relative/config")));
+        assert_eq!(runtime(&env), None);
+        assert_eq!(data_dirs(&env), [PathBuf::from("/opt/share")]);
+    }
+
+    #[test]
+    fn system_dirs_are_split() {
+        let env = Env::from_iter([
+            ("XDG_CONFIG_DIRS", "/a::/b"),
+            ("XDG_DATA_DIRS", "/opt/share"),
+        ]);
+        assert_eq!(
+            config_dirs(&env),
+            [PathBuf::from("/a"), PathBuf::from("/b")]
+        );
+        assert_eq!(data_dirs(&env), [PathBuf::from("/opt/share")]);
+    }
+
+    #[test]
+    fn errors_tell_why() {
+        let err = assert_err!(try_runtime(&Env::empty()));
+        assert!(matches!(&err, XdgError::NotSet { key } if key == "XDG_RUNTIME_DIR"));
+        let err = assert_err!(try_runtime(&Env::from_iter([("XDG_RUNTIME_DIR", "run")])));
+        assert_eq!(
+            err.to_string(),
+            "`$XDG_RUNTIME_DIR` must be an absolute path, but it's `run`"
+        );
+        let err = assert_err!(runtime_ensure(&Env::empty()));
+        assert_eq!(err.to_string(), "`$XDG_RUNTIME_DIR` is not set");
+    }
+
+    #[test]
+    fn bin_dir_is_looked_up_in_path() {
+        let on_path = Env::from_iter([
+            ("XDG_BIN_HOME", "/home/user/bin"),
+            ("PATH", "/usr/bin:/home/user/bin/"),
+        ]);
+        assert_eq!(bin(&on_path), Some(PathBuf::from("/home/user/bin")));
+        assert!(is_on_path(&on_path));
+        let missing = Env::from_iter([("XDG_BIN_HOME", "/home/user/bin"), ("PATH", "/usr/bin")]);
+        assert!(!is_on_path(&missing));
+        #[cfg(unix)]
+        assert_eq!(
+            bin(&Env::from_iter([("HOME", "/home/user")])),
+            Some(PathBuf::from("/home/user/.local/bin"))
+        );
+    }
+
+    #[test]
+    fn search_paths_start_with_user_dir() {
+        let env = Env::from_iter([
+            ("XDG_CONFIG_HOME", "/home/user/.config"),
+            ("XDG_CONFIG_DIRS", "/etc/xdg:/home/user/.config:/opt/xdg"),
+            ("XDG_STATE_HOME", "/home/user/.state"),
+        ]);
+        let behaviour = MacOSBehaviour::LinuxFallback;
+        let paths: Vec<PathBuf> = config_search_paths(&env, behaviour).collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/home/user/.config"),
+                PathBuf::from("/etc/xdg"),
+                PathBuf::from("/opt/xdg"),
+            ]
+        );
+        let paths: Vec<PathBuf> = state_search_paths(&env, behaviour).collect();
+        assert_eq!(paths, [PathBuf::from("/home/user/.state")]);
+    }
+}
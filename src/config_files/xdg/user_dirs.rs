@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::config_files::xdg::{self, MacOSBehaviour};
+use crate::fs::PathExt;
+use crate::os::env::Env;
+
+/// Well-known user directories, like the Desktop or Downloads, see the [`user_dirs`].
+///
+/// Each one is [`None`] if it isn't configured (and has no platform default), or is disabled (set
+/// to just the `"$HOME/"`).
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct UserDirs {
+    /// `$XDG_DESKTOP_DIR`.
+    pub desktop: Option<PathBuf>,
+    /// `$XDG_DOCUMENTS_DIR`.
+    pub documents: Option<PathBuf>,
+    /// `$XDG_DOWNLOAD_DIR`.
+    pub download: Option<PathBuf>,
+    /// `$XDG_MUSIC_DIR`.
+    pub music: Option<PathBuf>,
+    /// `$XDG_PICTURES_DIR`.
+    pub pictures: Option<PathBuf>,
+    /// `$XDG_VIDEOS_DIR` (`~/Movies` on macOS).
+    pub videos: Option<PathBuf>,
+    /// `$XDG_TEMPLATES_DIR` (without a default on macOS).
+    pub templates: Option<PathBuf>,
+    /// `$XDG_PUBLICSHARE_DIR`.
+    pub public_share: Option<PathBuf>,
+}
+
+/// Kind of the user directory.
+#[derive(Clone, Copy)]
+enum Kind {
+    Desktop,
+    Documents,
+    Download,
+    Music,
+    Pictures,
+    Videos,
+    Templates,
+    PublicShare,
+}
+
+impl Kind {
+    /// Key in the `user-dirs.dirs`.
+    fn key(self) -> &'static str {
+        match self {
+            Self::Desktop => "XDG_DESKTOP_DIR",
+            Self::Documents => "XDG_DOCUMENTS_DIR",
+            Self::Download => "XDG_DOWNLOAD_DIR",
+            Self::Music => "XDG_MUSIC_DIR",
+            Self::Pictures => "XDG_PICTURES_DIR",
+            Self::Videos => "XDG_VIDEOS_DIR",
+            Self::Templates => "XDG_TEMPLATES_DIR",
+            Self::PublicShare => "XDG_PUBLICSHARE_DIR",
+        }
+    }
+
+    /// Name of the directory in the home on macOS.
+    #[cfg_attr(windows, allow(dead_code))]
+    fn macos_name(self) -> Option<&'static str> {
+        match self {
+            Self::Desktop => Some("Desktop"),
+            Self::Documents => Some("Documents"),
+            Self::Download => Some("Downloads"),
+            Self::Music => Some("Music"),
+            Self::Pictures => Some("Pictures"),
+            Self::Videos => Some("Movies"),
+            Self::Templates => None,
+            Self::PublicShare => Some("Public"),
+        }
+    }
+
+    #[cfg(windows)]
+    fn known_folder(self) -> windows_sys::core::GUID {
+        use windows_sys::Win32::UI::Shell::{
+            FOLDERID_Desktop, FOLDERID_Documents, FOLDERID_Downloads, FOLDERID_Music,
+            FOLDERID_Pictures, FOLDERID_Public, FOLDERID_Templates, FOLDERID_Videos,
+        };
+        match self {
+            Self::Desktop => FOLDERID_Desktop,
+            Self::Documents => FOLDERID_Documents,
+            Self::Download => FOLDERID_Downloads,
+            Self::Music => FOLDERID_Music,
+            Self::Pictures => FOLDERID_Pictures,
+            Self::Videos => FOLDERID_Videos,
+            Self::Templates => FOLDERID_Templates,
+            Self::PublicShare => FOLDERID_Public,
+        }
+    }
+}
+
+/// Get the well-known user directories.
+///
+/// They are read from the `user-dirs.dirs` in the [`xdg::config`] directory (as written by the
+/// `xdg-user-dirs-update`), with the `$HOME` expanded from the `env`. Directories missing there
+/// fall back to the Known Folders on Windows (which ignores the file), and to the `~/Desktop`,
+/// `~/Downloads`, etc. on macOS.
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::xdg;
+/// # use rustvil::os::env::Env;
+/// let downloads = xdg::user_dirs(&Env::new()).download;
+/// ```
+pub fn user_dirs(env: &Env) -> UserDirs {
+    let home = xdg::home_of(env);
+    let mut configured = if cfg!(windows) {
+        HashMap::new()
+    } else {
+        xdg::config(env, MacOSBehaviour::LinuxFallback)
+            .and_then(|dir| dir.join("user-dirs.dirs").read_to_string().ok())
+            .map(|contents| parse(&contents, home.as_deref()))
+            .unwrap_or_default()
+    };
+    let mut dir = |kind: Kind| {
+        configured
+            .remove(kind.key())
+            .unwrap_or_else(|| fallback(kind, home.as_deref()))
+    };
+    UserDirs {
+        desktop: dir(Kind::Desktop),
+        documents: dir(Kind::Documents),
+        download: dir(Kind::Download),
+        music: dir(Kind::Music),
+        pictures: dir(Kind::Pictures),
+        videos: dir(Kind::Videos),
+        templates: dir(Kind::Templates),
+        public_share: dir(Kind::PublicShare),
+    }
+}
+
+/// Parse `KEY="value"` lines of the `user-dirs.dirs`, where values are either absolute, or
+/// relative to the `"$HOME/"`. Directory set to just the `"$HOME/"` is disabled ([`None`]).
+fn parse(contents: &str, home: Option<&Path>) -> HashMap<String, Option<PathBuf>> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+            let value = unescape(value);
+            let path = match value.strip_prefix("$HOME") {
+                Some(relative) if relative.trim_start_matches('/').is_empty() => None,
+                Some(relative) if relative.starts_with('/') => {
+                    Some(home?.join(relative.trim_start_matches('/')))
+                }
+                _ if value.starts_with('/') => Some(PathBuf::from(value)),
+                _ => return None,
+            };
+            Some((key.trim().to_owned(), path))
+        })
+        .collect()
+}
+
+/// Remove the shell backslash escapes.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(windows)]
+fn fallback(kind: Kind, _home: Option<&Path>) -> Option<PathBuf> {
+    super::known_folder(&kind.known_folder()).ok()
+}
+
+#[cfg(not(windows))]
+fn fallback(kind: Kind, home: Option<&Path>) -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        Some(home?.join(kind.macos_name()?))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_dirs_file_is_parsed() {
+        let contents = r#"
+# This file is written by xdg-user-dirs-update
+XDG_DESKTOP_DIR="$HOME/Desktop"
+XDG_DOWNLOAD_DIR="$HOME/My \"Downloads\""
+XDG_PUBLICSHARE_DIR="/srv/public"
+XDG_TEMPLATES_DIR="$HOME/"
+XDG_MUSIC_DIR="relative/music"
+XDG_VIDEOS_DIR=$HOME/Videos
+"#;
+        let dirs = parse(contents, Some(Path::new("/home/user")));
+        assert_eq!(
+            dirs,
+            HashMap::from([
+                (
+                    "XDG_DESKTOP_DIR".to_owned(),
+                    Some("/home/user/Desktop".into())
+                ),
+                (
+                    "XDG_DOWNLOAD_DIR".to_owned(),
+                    Some("/home/user/My \"Downloads\"".into())
+                ),
+                ("XDG_PUBLICSHARE_DIR".to_owned(), Some("/srv/public".into())),
+                // Disabled.
+                ("XDG_TEMPLATES_DIR".to_owned(), None),
+            ])
+        );
+        assert_eq!(parse(contents, None).len(), 2);
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn user_dirs_are_read_from_config() {
+        use crate::fs::TreeSpec;
+        use claim::assert_ok;
+        use std::ffi::OsString;
+
+        let tmp = assert_ok!(
+            TreeSpec::new()
+                .file("user-dirs.dirs", "XDG_DOWNLOAD_DIR=\"$HOME/dl\"\n")
+                .create_temp()
+        );
+        let env: Env = [
+            (OsString::from("XDG_CONFIG_HOME"), tmp.path().into()),
+            (OsString::from("HOME"), OsString::from("/home/user")),
+        ]
+        .into();
+        let dirs = user_dirs(&env);
+        assert_eq!(dirs.download, Some(PathBuf::from("/home/user/dl")));
+        assert_eq!(dirs.desktop, None);
+    }
+}
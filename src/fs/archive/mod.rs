@@ -0,0 +1,634 @@
+//! Packing and unpacking of archives.
+//!
+//! Tarballs (plain and gzipped) require the __tar__ feature, and zip archives require the
+//! __zip__ feature.
+//!
+//! ```rust,no_run
+//! # use rustvil::fs::archive::{self, Format, UnpackOptions};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! archive::pack("cache", "cache.tar.gz", Format::TarGz)?;
+//! archive::unpack("cache.tar.gz", "restored", UnpackOptions::new())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Unpacking is safe for untrusted archives: entries can't be written outside of the destination
+//! (neither directly, nor through symlinks, which must point inside of it), and setuid, setgid and
+//! sticky bits are dropped.
+
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::fs::{MkdirOptions, PathExt, SniffedType, TempFile};
+
+#[cfg(feature = "tar")]
+mod tarball;
+#[cfg(feature = "zip")]
+mod zipfile;
+
+/// Format of an archive.
+#[non_exhaustive]
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Uncompressed tarball.
+    #[cfg(feature = "tar")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tar")))]
+    Tar,
+    /// Gzipped tarball.
+    #[cfg(feature = "tar")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tar")))]
+    TarGz,
+    /// Zip archive, with deflated entries.
+    #[cfg(feature = "zip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+    Zip,
+}
+
+impl Format {
+    /// Guess format from an extension of the `path` (like `.tar.gz`, `.tgz` or `.zip`).
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let name = path.as_ref().file_name()?.to_str()?.to_ascii_lowercase();
+        #[cfg(feature = "tar")]
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            return Some(Self::TarGz);
+        } else if name.ends_with(".tar") {
+            return Some(Self::Tar);
+        }
+        #[cfg(feature = "zip")]
+        if name.ends_with(".zip") {
+            return Some(Self::Zip);
+        }
+        None
+    }
+
+    /// Detect format of the archive at `path` from its contents.
+    fn sniff(path: &Path) -> io::Result<Self> {
+        match path.sniff_type()? {
+            #[cfg(feature = "tar")]
+            SniffedType::Tar => Ok(Self::Tar),
+            #[cfg(feature = "tar")]
+            SniffedType::Gzip => Ok(Self::TarGz),
+            #[cfg(feature = "zip")]
+            SniffedType::Zip => Ok(Self::Zip),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "`{}` isn't an archive of a supported format",
+                    path.display()
+                ),
+            )),
+        }
+    }
+}
+
+/// Options for controlling the [`unpack`].
+///
+/// Default detects the format from the contents, keeps the permissions, and fails on existing
+/// files.
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct UnpackOptions {
+    format: Option<Format>,
+    strip_components: usize,
+    overwrite: bool,
+    preserve_permissions: bool,
+}
+
+impl Default for UnpackOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            strip_components: 0,
+            overwrite: false,
+            preserve_permissions: true,
+        }
+    }
+}
+
+impl UnpackOptions {
+    /// Create default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Don't detect the format, assume the `format`.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Drop `n` leading components from entry paths (like `tar --strip-components`), skipping
+    /// entries, which have no more.
+    pub fn strip_components(mut self, n: usize) -> Self {
+        self.strip_components = n;
+        self
+    }
+
+    /// Replace existing files and symlinks, instead of failing.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Apply permissions stored in the archive (otherwise defaults for a new entry are used).
+    pub fn preserve_permissions(mut self, preserve_permissions: bool) -> Self {
+        self.preserve_permissions = preserve_permissions;
+        self
+    }
+}
+
+/// Pack the `dir` contents into a new archive at the `dest`.
+///
+/// Entries are stored with paths relative to the `dir`, in a sorted order. Symlinks are stored
+/// as symlinks, and permissions are kept. Archive is written atomically: it appears complete, or
+/// not at all.
+///
+/// # Returns
+/// [`Ok(())`](Ok) on a success, otherwise the first error encountered.
+pub fn pack(dir: impl AsRef<Path>, dest: impl AsRef<Path>, format: Format) -> io::Result<()> {
+    let mut entries = Vec::new();
+    walk(dir.as_ref(), "", &mut entries)?;
+    write(&entries, dest.as_ref(), format)
+}
+
+/// Pack the `parts` (files, or directories with their contents) under their names into a new
+/// archive at the `dest`, like the [`pack`].
+pub(crate) fn pack_parts(parts: &[(&str, &Path)], dest: &Path, format: Format) -> io::Result<()> {
+    let mut entries = Vec::new();
+    for (name, path) in parts {
+        entries.push(PackEntry {
+            path: path.to_path_buf(),
+            name: (*name).to_owned(),
+        });
+        if path.symlink_metadata()?.is_dir() {
+            walk(path, &format!("{name}/"), &mut entries)?;
+        }
+    }
+    write(&entries, dest, format)
+}
+
+/// Write the `entries` into a new archive at the `dest`, atomically.
+fn write(entries: &[PackEntry], dest: &Path, format: Format) -> io::Result<()> {
+    let parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = TempFile::new_in(parent)?;
+    match format {
+        #[cfg(feature = "tar")]
+        Format::Tar => tarball::pack(entries, &mut file, false)?,
+        #[cfg(feature = "tar")]
+        Format::TarGz => tarball::pack(entries, &mut file, true)?,
+        #[cfg(feature = "zip")]
+        Format::Zip => zipfile::pack(entries, &mut file)?,
+    }
+    file.sync_all()?;
+    file.persist(dest)?;
+    Ok(())
+}
+
+/// Unpack the `archive` into the `dest` directory (created if needed).
+///
+/// Symlinks must be relative and point inside of the `dest`, and no entry is written through
+/// a symlink. Entries other than directories, files, symlinks and hard links (like devices) are
+/// skipped.
+///
+/// # Returns
+/// [`Ok(())`](Ok) on a success, otherwise the first error encountered (entries unpacked before
+/// it are left in place): of kind [`ErrorKind::InvalidData`](io::ErrorKind::InvalidData)
+/// for malformed or malicious archives, or [`ErrorKind::AlreadyExists`](io::ErrorKind::AlreadyExists)
+/// for existing files, unless [overwriting](UnpackOptions::overwrite).
+pub fn unpack(
+    archive: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    options: UnpackOptions,
+) -> io::Result<()> {
+    let archive = archive.as_ref();
+    let format = match options.format {
+        Some(format) => format,
+        None => Format::sniff(archive)?,
+    };
+    let mut unpacker = Unpacker::new(dest.as_ref(), options)?;
+    let file = File::open(archive)?;
+    match format {
+        #[cfg(feature = "tar")]
+        Format::Tar => tarball::unpack(file, false, &mut unpacker)?,
+        #[cfg(feature = "tar")]
+        Format::TarGz => tarball::unpack(file, true, &mut unpacker)?,
+        #[cfg(feature = "zip")]
+        Format::Zip => zipfile::unpack(file, &mut unpacker)?,
+    }
+    unpacker.finish()
+}
+
+/// Entry of the packed directory.
+struct PackEntry {
+    path: PathBuf,
+    /// Path relative to the packed directory, with `/` separators.
+    name: String,
+}
+
+/// Collect entries under the `dir` (excluding itself), named with the `prefix`, sorted by name,
+/// parents first.
+fn walk(dir: &Path, prefix: &str, out: &mut Vec<PackEntry>) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("`{}` isn't valid UTF-8", entry.path().display()),
+            ));
+        };
+        let name = format!("{prefix}{file_name}");
+        let metadata = entry.path().symlink_metadata()?;
+        out.push(PackEntry {
+            path: entry.path(),
+            name: name.clone(),
+        });
+        if metadata.is_dir() {
+            walk(&entry.path(), &format!("{name}/"), out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes entries of an archive, being unpacked, into the destination.
+struct Unpacker {
+    dest: PathBuf,
+    options: UnpackOptions,
+    /// Permissions of directories are applied last, so read-only ones can be filled.
+    dir_modes: Vec<(PathBuf, u32)>,
+}
+
+impl Unpacker {
+    fn new(dest: &Path, options: UnpackOptions) -> io::Result<Self> {
+        dest.mkdir(MkdirOptions::WithParents)?;
+        Ok(Self {
+            dest: dest.to_path_buf(),
+            options,
+            dir_modes: Vec::new(),
+        })
+    }
+
+    /// Map an entry name into the destination path, `None` if it should be skipped.
+    fn target(&self, name: &Path) -> io::Result<Option<PathBuf>> {
+        let stripped: PathBuf = name
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .skip(self.options.strip_components)
+            .collect();
+        if stripped.as_os_str().is_empty() {
+            return Ok(None);
+        }
+        let target = self.dest.join_secure(&stripped).map_err(invalid_data)?;
+        // Escaping through an extracted symlink.
+        let relative = target
+            .strip_prefix(&self.dest)
+            .expect("joined onto the `dest`");
+        let mut ancestor = self.dest.clone();
+        for component in relative.parent().into_iter().flat_map(Path::components) {
+            ancestor.push(component);
+            match ancestor.symlink_metadata() {
+                Ok(metadata) if metadata.is_symlink() => {
+                    return Err(invalid_data(format!(
+                        "`{}` would be written through a symlink",
+                        name.display()
+                    )));
+                }
+                _ => {}
+            }
+        }
+        Ok(Some(target))
+    }
+
+    /// Prepare `target` for a new non-directory entry.
+    fn make_room(&self, target: &Path) -> io::Result<()> {
+        if let Some(parent) = target.parent() {
+            parent.mkdir(MkdirOptions::WithParents)?;
+        }
+        match target.symlink_metadata() {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+            Ok(_) if !self.options.overwrite => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("`{}` already exists", target.display()),
+            )),
+            Ok(metadata) if metadata.is_dir() => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("`{}` is a directory", target.display()),
+            )),
+            Ok(_) => target.rm(),
+        }
+    }
+
+    fn dir(&mut self, name: &Path, mode: Option<u32>) -> io::Result<()> {
+        let Some(target) = self.target(name)? else {
+            return Ok(());
+        };
+        if is_symlink(&target) {
+            return Err(invalid_data(format!(
+                "directory `{}` would replace a symlink",
+                name.display()
+            )));
+        }
+        target.mkdir(MkdirOptions::WithParents)?;
+        if let Some(mode) = mode.filter(|_| self.options.preserve_permissions) {
+            self.dir_modes.push((target, mode));
+        }
+        Ok(())
+    }
+
+    fn file(&mut self, name: &Path, mode: Option<u32>, contents: &mut dyn Read) -> io::Result<()> {
+        let Some(target) = self.target(name)? else {
+            return Ok(());
+        };
+        self.make_room(&target)?;
+        let mut file = File::create_new(&target)?;
+        io::copy(contents, &mut file)?;
+        if let Some(mode) = mode.filter(|_| self.options.preserve_permissions) {
+            set_mode(&file, mode)?;
+        }
+        Ok(())
+    }
+
+    fn symlink(&mut self, name: &Path, link_target: &Path) -> io::Result<()> {
+        let Some(target) = self.target(name)? else {
+            return Ok(());
+        };
+        let relative = target.strip_prefix(&self.dest).unwrap_or(&target);
+        let parent = relative.parent().unwrap_or(Path::new(""));
+        if self.resolve(parent, link_target)?.is_none() {
+            return Err(invalid_data(format!(
+                "symlink `{}` points outside of the destination",
+                name.display()
+            )));
+        }
+        self.make_room(&target)?;
+        crate::fs::path_ext::symlink(link_target, &target)
+    }
+
+    #[cfg_attr(not(feature = "tar"), allow(dead_code))]
+    fn hard_link(&mut self, name: &Path, link_target: &Path) -> io::Result<()> {
+        let (Some(target), Some(original)) = (self.target(name)?, self.target(link_target)?) else {
+            return Ok(());
+        };
+        if is_symlink(&original) {
+            return Err(invalid_data(format!(
+                "hard link `{}` points to a symlink",
+                name.display()
+            )));
+        }
+        self.make_room(&target)?;
+        original.hard_link_to(&target)
+    }
+
+    /// Resolve the `path` from the `base` directory (both relative to the `dest`), following the
+    /// symlinks extracted so far.
+    ///
+    /// A `..` is allowed only out of the `base` (and its ancestors), or of existing directories,
+    /// because other entries (like a file, or a missing one) could become symlinks later.
+    ///
+    /// # Returns
+    /// [`Ok(Some(PathBuf))`](PathBuf) relative to the `dest`, [`Ok(None)`](None) if it leaves the
+    /// `dest` (or there are too many symlinks), otherwise an error, as reported by the OS.
+    fn resolve(&self, base: &Path, path: &Path) -> io::Result<Option<PathBuf>> {
+        /// Like the `MAXSYMLINKS` of Linux.
+        const MAX_LINKS: usize = 40;
+        if path.has_root() {
+            return Ok(None);
+        }
+        let mut pending: Vec<OsString> = path
+            .components()
+            .rev()
+            .map(|component| component.as_os_str().to_os_string())
+            .collect();
+        // Has no symlinks, nor `..`, see the `target`.
+        let mut resolved = base.to_path_buf();
+        let mut links = 0;
+        while let Some(component) = pending.pop() {
+            if component == "." {
+                continue;
+            }
+            if component == ".." {
+                let is_dir = base.starts_with(&resolved)
+                    || self
+                        .dest
+                        .join(&resolved)
+                        .symlink_metadata()
+                        .is_ok_and(|metadata| metadata.is_dir());
+                if !is_dir || !resolved.pop() {
+                    return Ok(None);
+                }
+                continue;
+            }
+            resolved.push(&component);
+            let current = self.dest.join(&resolved);
+            if !is_symlink(&current) {
+                continue;
+            }
+            links += 1;
+            let link = std::fs::read_link(&current)?;
+            if links > MAX_LINKS || link.has_root() {
+                return Ok(None);
+            }
+            resolved.pop();
+            pending.extend(
+                link.components()
+                    .rev()
+                    .map(|component| component.as_os_str().to_os_string()),
+            );
+        }
+        Ok(Some(resolved))
+    }
+
+    fn finish(self) -> io::Result<()> {
+        for (dir, mode) in self.dir_modes.iter().rev() {
+            set_dir_mode(dir, *mode)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    path.symlink_metadata()
+        .is_ok_and(|metadata| metadata.is_symlink())
+}
+
+fn invalid_data(err: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+#[cfg(unix)]
+fn set_mode(file: &File, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    file.set_permissions(std::fs::Permissions::from_mode(mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn set_mode(file: &File, mode: u32) -> io::Result<()> {
+    let mut permissions = file.metadata()?.permissions();
+    permissions.set_readonly(mode & 0o200 == 0);
+    file.set_permissions(permissions)
+}
+
+/// Apply the `mode` to the directory at `path`, without following a symlink.
+fn set_dir_mode(path: &Path, mode: u32) -> io::Result<()> {
+    if is_symlink(path) {
+        return Err(invalid_data(format!(
+            "`{}` was replaced with a symlink",
+            path.display()
+        )));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        let dir = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW | libc::O_DIRECTORY)
+            .open(path)?;
+        set_mode(&dir, mode)
+    }
+    #[cfg(not(unix))]
+    {
+        let mut permissions = path.symlink_metadata()?.permissions();
+        permissions.set_readonly(mode & 0o200 == 0);
+        path.set_permissions(permissions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::TreeSpec;
+    use claim::{assert_err, assert_ok};
+    use tempfile::tempdir;
+
+    fn formats() -> Vec<Format> {
+        vec![
+            #[cfg(feature = "tar")]
+            Format::Tar,
+            #[cfg(feature = "tar")]
+            Format::TarGz,
+            #[cfg(feature = "zip")]
+            Format::Zip,
+        ]
+    }
+
+    #[test]
+    fn pack_and_unpack_roundtrip() {
+        let spec = TreeSpec::new()
+            .dir("empty")
+            .file("a.txt", "a")
+            .file("sub/b.bin", b"\0\x01\x02");
+        #[cfg(unix)]
+        let spec = spec.symlink("sub/link", "../a.txt");
+        let src = assert_ok!(spec.create_temp());
+        let tmp = tempdir().expect("needed for tests");
+        for format in formats() {
+            let archive = tmp.path().join(format!("{format:?}"));
+            assert_ok!(pack(src.path(), &archive, format));
+            let dest = tmp.path().join(format!("{format:?}-unpacked"));
+            assert_ok!(unpack(&archive, &dest, UnpackOptions::new()));
+            spec.assert_matches(&dest);
+            // Existing files are kept, unless asked otherwise.
+            let err = assert_err!(unpack(&archive, &dest, UnpackOptions::new()));
+            assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+            assert_ok!(unpack(
+                &archive,
+                &dest,
+                UnpackOptions::new().overwrite(true)
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn permissions_are_preserved() {
+        use std::os::unix::fs::PermissionsExt;
+        let src = assert_ok!(TreeSpec::new().file("run.sh", "").create_temp());
+        let script = src.path().join("run.sh");
+        assert_ok!(script.set_permissions(std::fs::Permissions::from_mode(0o750)));
+        let tmp = tempdir().expect("needed for tests");
+        for format in formats() {
+            let archive = tmp.path().join(format!("{format:?}"));
+            assert_ok!(pack(src.path(), &archive, format));
+            let dest = tmp.path().join(format!("{format:?}-unpacked"));
+            assert_ok!(unpack(&archive, &dest, UnpackOptions::new().format(format)));
+            let mode = assert_ok!(dest.join("run.sh").metadata())
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o750);
+        }
+    }
+
+    #[test]
+    fn strip_components_and_traversal() {
+        let tmp = tempdir().expect("needed for tests");
+        let mut unpacker = assert_ok!(Unpacker::new(
+            &tmp.path().join("dest"),
+            UnpackOptions::new().strip_components(1)
+        ));
+        assert_ok!(unpacker.file(Path::new("top"), None, &mut &b""[..]));
+        assert_ok!(unpacker.file(Path::new("top/a"), None, &mut &b"a"[..]));
+        assert_eq!(assert_ok!(tmp.path().join("dest/a").read_to_string()), "a");
+        assert!(!tmp.path().join("dest/top").exists());
+        for name in ["top/../../escape", "top/a/../../../escape"] {
+            let err = assert_err!(unpacker.file(Path::new(name), None, &mut &b""[..]));
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        let err = assert_err!(unpacker.symlink(Path::new("top/link"), Path::new("../..")));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinks_are_not_followed() {
+        let tmp = tempdir().expect("needed for tests");
+        let mut unpacker = assert_ok!(Unpacker::new(
+            &tmp.path().join("dest"),
+            UnpackOptions::new()
+        ));
+        assert_ok!(unpacker.dir(Path::new("sub"), None));
+        assert_ok!(unpacker.symlink(Path::new("link"), Path::new("sub")));
+        let err = assert_err!(unpacker.file(Path::new("link/x"), None, &mut &b""[..]));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(!tmp.path().join("dest/sub/x").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlink_chains_are_resolved() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempdir().expect("needed for tests");
+        let mode = |path: &Path| assert_ok!(path.metadata()).permissions().mode() & 0o777;
+        let before = mode(tmp.path());
+        let mut unpacker = assert_ok!(Unpacker::new(
+            &tmp.path().join("dest"),
+            UnpackOptions::new()
+        ));
+        assert_ok!(unpacker.symlink(Path::new("a"), Path::new(".")));
+        // Lexically inside, but `a/a` is the `dest`, so `a/a/..` is its parent.
+        let err = assert_err!(unpacker.symlink(Path::new("x"), Path::new("a/a/..")));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(!tmp.path().join("dest/x").exists());
+        // `y` could become a symlink later.
+        let err = assert_err!(unpacker.symlink(Path::new("z"), Path::new("y/..")));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_ok!(unpacker.dir(Path::new("x"), Some(0o777)));
+        assert_ok!(unpacker.symlink(Path::new("b"), Path::new("a/x")));
+
+        // Existing symlinks aren't turned into directories, nor hard linked.
+        let err = assert_err!(unpacker.dir(Path::new("b"), Some(0o777)));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = assert_err!(unpacker.hard_link(Path::new("h"), Path::new("b")));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_ok!(unpacker.finish());
+        assert_eq!(mode(tmp.path()), before);
+        assert_eq!(mode(&tmp.path().join("dest/x")), 0o777);
+    }
+}
@@ -0,0 +1,84 @@
+use std::{fs::File, io};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use tar::{Archive, Builder, EntryType};
+
+use super::{PackEntry, Unpacker};
+
+pub(super) fn pack(entries: &[PackEntry], out: &mut File, gzip: bool) -> io::Result<()> {
+    if gzip {
+        let encoder = append_all(entries, GzEncoder::new(out, Compression::default()))?;
+        encoder.finish()?;
+    } else {
+        append_all(entries, out)?;
+    }
+    Ok(())
+}
+
+fn append_all<W: io::Write>(entries: &[PackEntry], out: W) -> io::Result<W> {
+    let mut builder = Builder::new(out);
+    builder.follow_symlinks(false);
+    for entry in entries {
+        builder.append_path_with_name(&entry.path, &entry.name)?;
+    }
+    builder.into_inner()
+}
+
+pub(super) fn unpack(file: File, gzip: bool, unpacker: &mut Unpacker) -> io::Result<()> {
+    if gzip {
+        unpack_from(GzDecoder::new(io::BufReader::new(file)), unpacker)
+    } else {
+        unpack_from(io::BufReader::new(file), unpacker)
+    }
+}
+
+fn unpack_from(reader: impl io::Read, unpacker: &mut Unpacker) -> io::Result<()> {
+    let mut archive = Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.into_owned();
+        let mode = entry.header().mode().ok();
+        let link_name = entry.link_name()?.map(|link| link.into_owned());
+        match (entry.header().entry_type(), link_name) {
+            (EntryType::Directory, _) => unpacker.dir(&name, mode)?,
+            (EntryType::Regular | EntryType::Continuous, _) => {
+                unpacker.file(&name, mode, &mut entry)?;
+            }
+            (EntryType::Symlink, Some(target)) => unpacker.symlink(&name, &target)?,
+            (EntryType::Link, Some(target)) => unpacker.hard_link(&name, &target)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::archive::{UnpackOptions, unpack};
+    use claim::{assert_err, assert_ok};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn traversing_entries_are_rejected() {
+        let tmp = tempdir().expect("needed for tests");
+        let archive = tmp.path().join("evil.tar");
+        let mut builder = Builder::new(assert_ok!(File::create(&archive)));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_entry_type(EntryType::Regular);
+        // `set_path` refuses `..`, so write the name directly.
+        header.as_gnu_mut().unwrap().name[..12].copy_from_slice(b"../escape.sh");
+        header.set_cksum();
+        assert_ok!(builder.append(&header, &b"evil"[..]));
+        assert_ok!(builder.finish());
+        drop(builder);
+
+        let dest = tmp.path().join("dest");
+        let err = assert_err!(unpack(&archive, &dest, UnpackOptions::new()));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(!tmp.path().join("escape.sh").exists());
+        assert!(Path::new(&dest).is_dir());
+    }
+}
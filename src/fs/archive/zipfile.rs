@@ -0,0 +1,75 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use super::{PackEntry, Unpacker};
+
+pub(super) fn pack(entries: &[PackEntry], out: &mut File) -> io::Result<()> {
+    let mut writer = ZipWriter::new(out);
+    for entry in entries {
+        let metadata = entry.path.symlink_metadata()?;
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(mode_of(&metadata));
+        if metadata.is_dir() {
+            writer.add_directory(entry.name.as_str(), options)?;
+        } else if metadata.is_symlink() {
+            let target = std::fs::read_link(&entry.path)?;
+            let Some(target) = target.to_str() else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("target of `{}` isn't valid UTF-8", entry.path.display()),
+                ));
+            };
+            // Zip uses `/` separators, also on Windows.
+            writer.add_symlink(entry.name.as_str(), target.replace('\\', "/"), options)?;
+        } else {
+            let mut file = File::open(&entry.path)?;
+            let large = metadata.len() >= u64::from(u32::MAX);
+            writer.start_file(entry.name.as_str(), options.large_file(large))?;
+            io::copy(&mut file, &mut writer)?;
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn mode_of(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn mode_of(metadata: &std::fs::Metadata) -> u32 {
+    let mode = if metadata.is_dir() { 0o755 } else { 0o644 };
+    if metadata.permissions().readonly() {
+        mode & !0o222
+    } else {
+        mode
+    }
+}
+
+pub(super) fn unpack(file: File, unpacker: &mut Unpacker) -> io::Result<()> {
+    let mut archive = ZipArchive::new(io::BufReader::new(file))?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let name = file.name()?.into_owned();
+        let name = Path::new(&name);
+        let mode = file.unix_mode().map(|mode| mode & 0o7777);
+        if file.is_symlink() {
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            unpacker.symlink(name, Path::new(&target))?;
+        } else if file.is_dir() {
+            unpacker.dir(name, mode)?;
+        } else {
+            unpacker.file(name, mode, &mut file)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use std::{fs::File, io};
+
+/// Copy up to `len` bytes from the `src` into the `dst`, starting at (and advancing) their
+/// current offsets, without passing data through userspace, when possible.
+///
+/// Linux uses the `copy_file_range` (falling back to the `sendfile`, e.g. across filesystems on
+/// old kernels), and macOS uses the `fcopyfile`, when the whole `src` is copied into an empty
+/// `dst`. Otherwise, and on other platforms, data is copied with plain reads and writes.
+///
+/// # Returns
+/// [`Ok(u64)`](u64) with the number of copied bytes (less than `len`, if the `src` ended),
+/// otherwise an error, as reported by the OS.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rustvil::fs::copy_range;
+/// # use std::fs::File;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (src, dst) = (File::open("disk.img")?, File::create("disk.img.bak")?);
+/// let copied = copy_range(&src, &dst, u64::MAX)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn copy_range(src: &File, dst: &File, len: u64) -> io::Result<u64> {
+    let mut copied = 0;
+    if let Some(fast) = copy_range_fast(src, dst, len)? {
+        if fast == len {
+            return Ok(fast);
+        }
+        copied = fast;
+    }
+    // Anything left after the fast path (which stops at the end of `src`) is copied, in case
+    // it was wrong about reaching the end (like with the `/proc` files).
+    copied += io::copy(&mut io::Read::take(src, len - copied), &mut &*dst)?;
+    Ok(copied)
+}
+
+/// Copy with the kernel, returning `None` if nothing could be copied this way.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn copy_range_fast(src: &File, dst: &File, len: u64) -> io::Result<Option<u64>> {
+    use std::os::fd::AsRawFd;
+
+    /// Syscall tried for the next chunk.
+    #[derive(PartialEq)]
+    enum Method {
+        CopyFileRange,
+        Sendfile,
+    }
+
+    let (src, dst) = (src.as_raw_fd(), dst.as_raw_fd());
+    let mut method = Method::CopyFileRange;
+    let mut copied = 0;
+    while copied < len {
+        // Both syscalls are limited to a bit less than 2GiB per call anyway.
+        let chunk = (len - copied).min(1 << 30) as usize;
+        // SAFETY: Both descriptors are valid for the duration of the call, and `NULL`
+        // offsets mean the file offsets are used (and updated).
+        let result = unsafe {
+            match method {
+                Method::CopyFileRange => libc::copy_file_range(
+                    src,
+                    std::ptr::null_mut(),
+                    dst,
+                    std::ptr::null_mut(),
+                    chunk,
+                    0,
+                ),
+                Method::Sendfile => libc::sendfile(dst, src, std::ptr::null_mut(), chunk),
+            }
+        };
+        match result {
+            0 => break,
+            copied_now if copied_now > 0 => copied += copied_now as u64,
+            _ => {
+                let err = io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(libc::EINTR) => {}
+                    // Only before the first byte, so no data was written through other means.
+                    Some(
+                        libc::EXDEV | libc::ENOSYS | libc::EINVAL | libc::EOPNOTSUPP | libc::EPERM,
+                    ) if copied == 0 && method == Method::CopyFileRange => {
+                        method = Method::Sendfile;
+                    }
+                    Some(libc::ENOSYS | libc::EINVAL) if copied == 0 => return Ok(None),
+                    _ => return Err(err),
+                }
+            }
+        }
+    }
+    Ok(Some(copied))
+}
+
+#[cfg(target_vendor = "apple")]
+fn copy_range_fast(src: &File, dst: &File, len: u64) -> io::Result<Option<u64>> {
+    use std::{
+        io::{Seek, SeekFrom},
+        os::fd::AsRawFd,
+    };
+    let src_len = src.metadata()?.len();
+    let whole_file = (&*src).stream_position()? == 0
+        && (&*dst).stream_position()? == 0
+        && dst.metadata()?.len() == 0
+        && len >= src_len;
+    if !whole_file {
+        return Ok(None);
+    }
+    // SAFETY: Both descriptors are valid for the duration of the call, and a state is optional.
+    let result = unsafe {
+        libc::fcopyfile(
+            src.as_raw_fd(),
+            dst.as_raw_fd(),
+            std::ptr::null_mut(),
+            libc::COPYFILE_DATA,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Offsets are left as they were.
+    (&*src).seek(SeekFrom::Start(src_len))?;
+    (&*dst).seek(SeekFrom::Start(src_len))?;
+    Ok(Some(src_len))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple")))]
+fn copy_range_fast(_src: &File, _dst: &File, _len: u64) -> io::Result<Option<u64>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use std::io::{Seek, SeekFrom};
+    use tempfile::tempdir;
+
+    #[test]
+    fn ranges_are_copied_from_offsets() {
+        let tmp = tempdir().expect("needed for tests");
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        let contents: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+        assert_ok!(a.write(&contents));
+        let mut src = assert_ok!(File::open(&a));
+        let dst = assert_ok!(File::create(&b));
+        assert_ok!(src.seek(SeekFrom::Start(10)));
+        assert_eq!(assert_ok!(copy_range(&src, &dst, 1000)), 1000);
+        assert_eq!(assert_ok!(copy_range(&src, &dst, u64::MAX)), 100_000 - 1010);
+        assert_eq!(assert_ok!(copy_range(&src, &dst, 10)), 0);
+        assert_eq!(assert_ok!(b.read()), contents[10..]);
+    }
+}
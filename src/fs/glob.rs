@@ -0,0 +1,194 @@
+//! Minimal glob patterns, for the [`PathExt::remove_matching`](crate::fs::PathExt::remove_matching).
+
+use std::io;
+
+/// Compiled glob pattern.
+///
+/// Supports `?` (any character), `*` (any run of characters), `[abc]`/`[a-z]`/`[!abc]`
+/// (character class), `\` (escape), and `**` as a whole component (any number of components).
+/// Separator (`/`) is never matched by a wildcard.
+#[derive(Debug, Clone)]
+pub(crate) struct Glob {
+    components: Vec<Component>,
+    /// Patterns without a separator match only a file name, at any depth.
+    name_only: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Component {
+    AnyComponents,
+    Tokens(Vec<Token>),
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyRun,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> io::Result<Self> {
+        let pattern = pattern.trim_start_matches("./");
+        let components = pattern
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(|component| match component {
+                "**" => Ok(Component::AnyComponents),
+                _ => tokenize(component).map(Component::Tokens),
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        if components.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "empty glob pattern",
+            ));
+        }
+        Ok(Self {
+            name_only: !pattern.contains('/'),
+            components,
+        })
+    }
+
+    /// Check if `path` (relative, with `/` separated components) matches.
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        if self.name_only {
+            return components
+                .last()
+                .is_some_and(|name| match_components(&self.components, &[name]));
+        }
+        match_components(&self.components, &components)
+    }
+}
+
+fn invalid(pattern: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("invalid glob pattern `{pattern}`"),
+    )
+}
+
+fn tokenize(component: &str) -> io::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = component.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '?' => Token::AnyChar,
+            '*' => Token::AnyRun,
+            '\\' => Token::Char(chars.next().ok_or_else(|| invalid(component))?),
+            '[' => {
+                let mut negated = false;
+                let mut ranges = Vec::new();
+                let mut first = true;
+                loop {
+                    let c = chars.next().ok_or_else(|| invalid(component))?;
+                    match c {
+                        '!' | '^' if first && !negated => {
+                            negated = true;
+                            continue;
+                        }
+                        // `]` right after the opening is a literal.
+                        ']' if !first => break,
+                        _ => {}
+                    }
+                    first = false;
+                    let mut lookahead = chars.clone();
+                    if lookahead.next() == Some('-')
+                        && let Some(end) = lookahead.next().filter(|&end| end != ']')
+                    {
+                        chars = lookahead;
+                        ranges.push((c, end));
+                    } else {
+                        ranges.push((c, c));
+                    }
+                }
+                Token::Class { negated, ranges }
+            }
+            c => Token::Char(c),
+        });
+    }
+    Ok(tokens)
+}
+
+fn match_components(pattern: &[Component], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((Component::AnyComponents, rest)) => {
+            (0..=path.len()).any(|skip| match_components(rest, &path[skip..]))
+        }
+        Some((Component::Tokens(tokens), rest)) => {
+            path.split_first().is_some_and(|(name, path)| {
+                let name: Vec<char> = name.chars().collect();
+                match_tokens(tokens, &name) && match_components(rest, path)
+            })
+        }
+    }
+}
+
+fn match_tokens(tokens: &[Token], name: &[char]) -> bool {
+    match tokens.split_first() {
+        None => name.is_empty(),
+        Some((Token::AnyRun, rest)) => {
+            (0..=name.len()).any(|skip| match_tokens(rest, &name[skip..]))
+        }
+        Some((token, rest)) => name.split_first().is_some_and(|(&c, name)| {
+            let matched = match token {
+                Token::Char(expected) => c == *expected,
+                Token::AnyChar => true,
+                Token::Class { negated, ranges } => {
+                    ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)) != *negated
+                }
+                Token::AnyRun => unreachable!("handled above"),
+            };
+            matched && match_tokens(rest, name)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        assert_ok!(Glob::new(pattern)).matches(path)
+    }
+
+    #[test]
+    fn wildcards_and_classes() {
+        assert!(matches("*.tmp", "a.tmp"));
+        assert!(matches("*.tmp", "deep/dir/a.tmp"));
+        assert!(!matches("*.tmp", "a.tmp.bak"));
+        assert!(matches("file?.log", "file1.log"));
+        assert!(!matches("file?.log", "file10.log"));
+        assert!(matches("[a-c]x", "bx"));
+        assert!(!matches("[!a-c]x", "bx"));
+        assert!(matches("[]]", "]"));
+        assert!(matches(r"\*", "*"));
+        assert!(!matches(r"\*", "a"));
+    }
+
+    #[test]
+    fn separators_and_recursive_wildcard() {
+        assert!(matches("cache/*", "cache/x"));
+        assert!(!matches("cache/*", "cache/x/y"));
+        assert!(!matches("cache/*", "other/cache/x"));
+        assert!(matches("**/target", "target"));
+        assert!(matches("**/target", "a/b/target"));
+        assert!(matches("a/**/*.o", "a/x/y/z.o"));
+        assert!(matches("a/**/*.o", "a/z.o"));
+        assert!(!matches("a/*", "a"));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert_err!(Glob::new(""));
+        assert_err!(Glob::new("[abc"));
+        assert_err!(Glob::new("abc\\"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Windows extended-length (`\\?\`) paths handling.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(windows)]
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Whether the [`PathExt`](crate::fs::PathExt) handles long paths on Windows, see the
+/// [`set_long_paths`].
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum LongPaths {
+    /// Apply and strip the `\\?\` prefix where needed (default).
+    Enabled,
+    /// Pass paths to the OS, and return them, as they are.
+    Disabled,
+}
+
+/// Configure (process-wide) handling of the paths longer than `MAX_PATH` on Windows.
+///
+/// When [enabled](LongPaths::Enabled), the [`PathExt`](crate::fs::PathExt) operations calling
+/// the Win32 API directly convert long paths into the extended-length `\\?\` form (operations
+/// based on the [`std::fs`] do it on their own), and returned paths (like the ones from the
+/// [`PathExt::resolve`](crate::fs::PathExt::resolve)) are stripped from the prefix, when they
+/// can be represented without it.
+///
+/// This function has no effect on other platforms.
+pub fn set_long_paths(long_paths: LongPaths) {
+    ENABLED.store(long_paths == LongPaths::Enabled, Ordering::Relaxed);
+}
+
+/// Maximum length (in UTF-16 units) of a path usable without the prefix.
+///
+/// It's `MAX_PATH`, less the room for a `8.3` file name, which directories must have.
+#[cfg(windows)]
+const MAX_LEGACY_LEN: usize = 260 - 12;
+
+/// Convert `path` into the extended-length form, if it's too long for the legacy Win32 API.
+#[cfg(windows)]
+pub(crate) fn to_extended(path: &Path) -> Cow<'_, Path> {
+    use std::os::windows::ffi::OsStrExt;
+    if !ENABLED.load(Ordering::Relaxed) || path.as_os_str().encode_wide().count() < MAX_LEGACY_LEN {
+        return Cow::Borrowed(path);
+    }
+    let raw = path.as_os_str().to_string_lossy();
+    if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") {
+        return Cow::Borrowed(path);
+    }
+    // Extended paths are passed verbatim, so they must be normalized (absolute, no `.`/`..`,
+    // only backslashes) beforehand.
+    let Ok(absolute) = std::path::absolute(path) else {
+        return Cow::Borrowed(path);
+    };
+    let absolute = absolute.into_os_string();
+    let mut extended = std::ffi::OsString::new();
+    match absolute.to_str().and_then(|s| s.strip_prefix(r"\\")) {
+        Some(unc) => {
+            extended.push(r"\\?\UNC\");
+            extended.push(unc);
+        }
+        None => {
+            extended.push(r"\\?\");
+            extended.push(absolute);
+        }
+    }
+    Cow::Owned(PathBuf::from(extended))
+}
+
+/// Strip the extended-length prefix from the `path`, if it stays valid without it.
+#[cfg(windows)]
+#[cfg_attr(not(feature = "full-resolve"), allow(dead_code))]
+pub(crate) fn strip_extended(path: PathBuf) -> PathBuf {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return path;
+    }
+    let Some(raw) = path.to_str() else {
+        return path;
+    };
+    let stripped = if let Some(unc) = raw.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{unc}")
+    } else if let Some(local) = raw.strip_prefix(r"\\?\") {
+        // Only drive paths, not volume GUIDs and such.
+        let bytes = local.as_bytes();
+        if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || &bytes[1..3] != br":\" {
+            return path;
+        }
+        local.to_owned()
+    } else {
+        return path;
+    };
+    // Legacy API would reinterpret such components.
+    let reinterpreted = stripped.split('\\').any(|component| {
+        let trailing = component.ends_with(['.', ' ']) && !matches!(component, "." | "..");
+        trailing || crate::fs::sanitize::is_reserved(component)
+    });
+    if reinterpreted || stripped.encode_utf16().count() >= MAX_LEGACY_LEN {
+        return path;
+    }
+    PathBuf::from(stripped)
+}
+
+#[cfg(windows)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_paths_are_extended() {
+        let short = Path::new(r"C:\short\path");
+        assert_eq!(to_extended(short), short);
+        let long = format!(r"C:\{}\..\file.txt", "a".repeat(300));
+        let extended = to_extended(Path::new(&long));
+        assert_eq!(extended, Path::new(r"\\?\C:\file.txt"));
+        let unc = format!(r"\\server\share\{}", "b".repeat(300));
+        let extended = to_extended(Path::new(&unc));
+        assert!(
+            extended
+                .to_str()
+                .unwrap()
+                .starts_with(r"\\?\UNC\server\share\")
+        );
+    }
+
+    #[test]
+    fn prefix_is_stripped_when_possible() {
+        assert_eq!(
+            strip_extended(PathBuf::from(r"\\?\C:\Users\x")),
+            Path::new(r"C:\Users\x")
+        );
+        assert_eq!(
+            strip_extended(PathBuf::from(r"\\?\UNC\server\share\x")),
+            Path::new(r"\\server\share\x")
+        );
+        // Not representable without the prefix.
+        for path in [
+            r"\\?\C:\trailing.",
+            r"\\?\C:\dir\NUL",
+            r"\\?\Volume{1234}\x",
+        ] {
+            assert_eq!(strip_extended(PathBuf::from(path)), Path::new(path));
+        }
+        let long = format!(r"\\?\C:\{}", "a".repeat(300));
+        assert_eq!(strip_extended(PathBuf::from(&long)), Path::new(&long));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    fs::File,
+    io,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+/// Owned mapping of `len` bytes at `ptr`, unmapped on a drop.
+#[derive(Debug)]
+struct RawMap {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: `RawMap` is an owned region of memory, not tied to any thread.
+unsafe impl Send for RawMap {}
+// SAFETY: Shared access to `RawMap` only allows reads.
+unsafe impl Sync for RawMap {}
+
+impl RawMap {
+    fn new(file: &File, writable: bool) -> io::Result<Self> {
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::other("file is too big to be mapped"))?;
+        if len == 0 {
+            // Mapping zero bytes is an error on every platform.
+            return Ok(Self {
+                ptr: NonNull::dangling(),
+                len,
+            });
+        }
+        Self::map(file, len, writable)
+    }
+
+    #[cfg(unix)]
+    fn map(file: &File, len: usize, writable: bool) -> io::Result<Self> {
+        use std::os::fd::AsRawFd;
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        // SAFETY: `file` is a valid descriptor, the kernel picks the address.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                prot,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: NonNull::new(ptr.cast()).expect("successful mmap is never NULL"),
+            len,
+        })
+    }
+
+    #[cfg(windows)]
+    fn map(file: &File, len: usize, writable: bool) -> io::Result<Self> {
+        use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle};
+        use windows_sys::Win32::System::Memory::{
+            CreateFileMappingW, FILE_MAP_READ, FILE_MAP_WRITE, MapViewOfFile, PAGE_READONLY,
+            PAGE_READWRITE,
+        };
+        let (protect, access) = if writable {
+            (PAGE_READWRITE, FILE_MAP_READ | FILE_MAP_WRITE)
+        } else {
+            (PAGE_READONLY, FILE_MAP_READ)
+        };
+        // SAFETY: `file` is a valid handle, other pointers are allowed to be NULL.
+        let mapping = unsafe {
+            CreateFileMappingW(
+                file.as_raw_handle(),
+                std::ptr::null(),
+                protect,
+                0,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if mapping.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `mapping` is a freshly created, valid handle, which we exclusively own. The
+        // view keeps the mapping alive, so it can be closed right after.
+        let mapping = unsafe { OwnedHandle::from_raw_handle(mapping) };
+        // SAFETY: `mapping` is a valid file mapping handle.
+        let view = unsafe { MapViewOfFile(mapping.as_raw_handle(), access, 0, 0, len) };
+        let Some(ptr) = NonNull::new(view.Value.cast()) else {
+            return Err(io::Error::last_os_error());
+        };
+        Ok(Self { ptr, len })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn map(_file: &File, _len: usize, _writable: bool) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "memory mapping is not supported on this platform",
+        ))
+    }
+
+    #[cfg(unix)]
+    fn flush(&self) -> io::Result<()> {
+        // SAFETY: `ptr` and `len` describe a live mapping.
+        if unsafe { libc::msync(self.ptr.as_ptr().cast(), self.len, libc::MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn flush(&self) -> io::Result<()> {
+        use windows_sys::Win32::{Foundation::FALSE, System::Memory::FlushViewOfFile};
+        // SAFETY: `ptr` and `len` describe a live mapping.
+        if unsafe { FlushViewOfFile(self.ptr.as_ptr().cast(), self.len) } == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: `ptr` is valid for reads of `len` bytes (or dangling, when `len == 0`).
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: `ptr` is valid for reads and writes of `len` bytes (or dangling, when
+        // `len == 0`), writability is guaranteed by the `MmapMut`.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for RawMap {
+    fn drop(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        #[cfg(unix)]
+        // SAFETY: `ptr` and `len` describe a live mapping, which is never used again.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+        #[cfg(windows)]
+        // SAFETY: `ptr` is a live view, which is never used again.
+        unsafe {
+            use windows_sys::Win32::System::Memory::{MEMORY_MAPPED_VIEW_ADDRESS, UnmapViewOfFile};
+            UnmapViewOfFile(MEMORY_MAPPED_VIEW_ADDRESS {
+                Value: self.ptr.as_ptr().cast(),
+            });
+        }
+    }
+}
+
+/// Read-only memory-mapped view of a whole file. Dereferences into `[u8]`.
+///
+/// Usually created by the [`PathExt::mmap`](crate::fs::PathExt::mmap).
+#[derive(Debug)]
+pub struct Mmap {
+    map: RawMap,
+}
+
+impl Mmap {
+    /// Map `file`, which must be opened for reading.
+    ///
+    /// # Safety
+    /// See [`PathExt::mmap`](crate::fs::PathExt::mmap).
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        RawMap::new(file, false).map(|map| Self { map })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.map.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Writable, shared memory-mapped view of a whole file. Dereferences into `[u8]`.
+///
+/// Writes are visible to other mappings of the file, and are eventually written back to the
+/// file, [`flush`](MmapMut::flush) forces it.
+///
+/// Usually created by the [`PathExt::mmap_mut`](crate::fs::PathExt::mmap_mut).
+#[derive(Debug)]
+pub struct MmapMut {
+    map: RawMap,
+}
+
+impl MmapMut {
+    /// Map `file`, which must be opened for reading and writing.
+    ///
+    /// # Safety
+    /// See [`PathExt::mmap_mut`](crate::fs::PathExt::mmap_mut).
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        RawMap::new(file, true).map(|map| Self { map })
+    }
+
+    /// Synchronously write modified pages back to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        if self.map.len == 0 {
+            return Ok(());
+        }
+        self.map.flush()
+    }
+}
+
+impl Deref for MmapMut {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.map.as_slice()
+    }
+}
+
+impl DerefMut for MmapMut {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.map.as_mut_slice()
+    }
+}
+
+impl AsRef<[u8]> for MmapMut {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for MmapMut {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use tempfile::tempdir;
+
+    #[test]
+    fn mmap_reads_contents() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.write("test"));
+        // SAFETY: Nobody else modifies the file.
+        let map = assert_ok!(unsafe { path.mmap() });
+        assert_eq!(&*map, b"test");
+    }
+
+    #[test]
+    fn mmap_empty_file() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.touch());
+        // SAFETY: Nobody else modifies the file.
+        let map = assert_ok!(unsafe { path.mmap() });
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn mmap_mut_writes_back() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.write("test"));
+        {
+            // SAFETY: Nobody else accesses the file.
+            let mut map = assert_ok!(unsafe { path.mmap_mut() });
+            map[0] = b'b';
+            assert_ok!(map.flush());
+        }
+        assert_eq!(assert_ok!(path.read_to_string()), "best");
+    }
+}
@@ -26,6 +26,81 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! [`PathBufExt`] complements it with fluent edits of the [`PathBuf`](std::path::PathBuf), like
+//! [adding a suffix to the file name](PathBufExt::push_file_name_suffix).
+//!
+//! ## Archives
+//!
+//! [`archive`] packs and unpacks tarballs and zip archives (with the __tar__ or __zip__
+//! feature).
+//!
+//! ## Pid files
+//!
+//! [`PidFile`] combines the above primitives into a daemon-friendly, self-cleaning pid file.
+//!
+//! ## Log rotation
+//!
+//! [`Rotator`] rotates log files of long-running daemons by a size or an age (optionally
+//! compressing them, with the __gzip__ feature).
+//!
+//! ## Test fixtures
+//!
+//! [`TreeSpec`] declaratively describes a directory tree, which can be created, and later
+//! compared against.
+//!
+//! ## Temporary files
+//!
+//! [`TempDir`] and [`TempFile`] are RAII temporary entries, removed on a drop (unless
+//! [kept](TempFile::keep)), and configurable through the [`TempBuilder`]. [`anonymous_file`]
+//! creates a file without any name, which can be [given one](AnonymousFile::materialize) once
+//! it's complete. [`UmaskGuard`] scopes a (restrictive) process umask, e.g. for writing
+//! secrets.
 
+#[cfg(any(feature = "tar", feature = "zip"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tar", feature = "zip"))))]
+pub mod archive;
+mod copy;
+mod glob;
+mod long_path;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod path_buf_ext;
 mod path_ext;
+mod pid_file;
+mod range_lock;
+mod rotate;
+mod sanitize;
+mod sniff;
+mod stats;
+mod temp;
+mod tree_spec;
+#[cfg(unix)]
+mod umask;
+#[cfg(feature = "watch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "watch")))]
+pub mod watch;
+#[cfg(all(
+    feature = "xattr",
+    any(target_os = "linux", target_os = "android", target_vendor = "apple")
+))]
+mod xattr;
+pub use copy::copy_range;
+pub use long_path::{LongPaths, set_long_paths};
+#[cfg(feature = "mmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+pub use mmap::*;
+pub use path_buf_ext::PathBufExt;
 pub use path_ext::*;
+pub(crate) use path_ext::{replace_atomically, symlink};
+pub use pid_file::*;
+pub use range_lock::{LockType, RangeLockGuard};
+pub use rotate::Rotator;
+pub use sanitize::{Platform, sanitize_filename};
+pub use sniff::SniffedType;
+pub use stats::FsStats;
+pub use temp::*;
+pub use tree_spec::TreeSpec;
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+pub use umask::UmaskGuard;
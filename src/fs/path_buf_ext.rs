@@ -0,0 +1,104 @@
+use std::{
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+mod sealed {
+    use std::path::PathBuf;
+
+    pub trait Sealed {}
+    impl Sealed for PathBuf {}
+}
+
+/// Extension trait for the [`PathBuf`] with fluent in-place edits.
+///
+/// Consuming methods (like the `joined`) are meant for building paths in a single expression,
+/// the `&mut` ones (`push_*`, `pop_*`) for editing existing buffers.
+///
+/// ```rust
+/// # use rustvil::fs::PathBufExt;
+/// # use std::path::{Path, PathBuf};
+/// let logs = PathBuf::from("/var/log").joined(["app", "2026", "10"]);
+/// assert_eq!(logs, Path::new("/var/log/app/2026/10"));
+///
+/// let mut path = PathBuf::from("/srv/www/site/index.html");
+/// path.pop_n(2).push_file_name_suffix("-old");
+/// assert_eq!(path, Path::new("/srv/www-old"));
+/// ```
+pub trait PathBufExt: sealed::Sealed {
+    /// Insert `suffix` at the end of the file stem, before the extension: `photo.jpg` with `-old`
+    /// becomes `photo-old.jpg`.
+    ///
+    /// Paths without a file name (like `/` or `..`) are left unchanged.
+    fn push_file_name_suffix(&mut self, suffix: impl AsRef<OsStr>) -> &mut Self;
+
+    /// [`PathBuf::pop`] `n` times, stopping early at the root (or at an empty path).
+    fn pop_n(&mut self, n: usize) -> &mut Self;
+
+    /// [`PathBuf::push`] all the `components` in order, and return the result.
+    fn joined<I>(self, components: I) -> PathBuf
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>;
+}
+
+impl PathBufExt for PathBuf {
+    fn push_file_name_suffix(&mut self, suffix: impl AsRef<OsStr>) -> &mut Self {
+        let (Some(stem), extension) = (self.file_stem(), self.extension()) else {
+            return self;
+        };
+        let mut name = OsString::from(stem);
+        name.push(suffix);
+        if let Some(extension) = extension {
+            name.push(".");
+            name.push(extension);
+        }
+        self.set_file_name(name);
+        self
+    }
+
+    fn pop_n(&mut self, n: usize) -> &mut Self {
+        for _ in 0..n {
+            if !self.pop() {
+                break;
+            }
+        }
+        self
+    }
+
+    fn joined<I>(mut self, components: I) -> PathBuf
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        self.extend(components);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_suffix_goes_before_extension() {
+        let mut path = PathBuf::from("dir/photo.jpg");
+        path.push_file_name_suffix("-old");
+        assert_eq!(path, Path::new("dir/photo-old.jpg"));
+        let mut path = PathBuf::from(".bashrc");
+        path.push_file_name_suffix("~");
+        assert_eq!(path, Path::new(".bashrc~"));
+        let mut path = PathBuf::from("..");
+        path.push_file_name_suffix("x");
+        assert_eq!(path, Path::new(".."));
+    }
+
+    #[test]
+    fn pop_n_and_joined() {
+        let mut path = PathBuf::from("/a/b/c");
+        assert_eq!(path.pop_n(2), Path::new("/a"));
+        assert_eq!(path.pop_n(5), Path::new("/"));
+        let path = PathBuf::from("/srv").joined(["www", "site"]);
+        assert_eq!(path, Path::new("/srv/www/site"));
+    }
+}
@@ -1,14 +1,15 @@
-#[cfg(any(feature = "full-resolve", feature = "expand-user"))]
-use std::path::PathBuf;
+use crate::fs::{FsStats, LockType, RangeLockGuard, SniffedType};
 
 use std::{
+    ffi::{OsStr, OsString},
     fs::{
         File, OpenOptions, Permissions, copy, create_dir, create_dir_all, hard_link, read,
         read_to_string, remove_dir, remove_dir_all, remove_file, rename, set_permissions, write,
     },
     io::{self},
     ops::{Deref, DerefMut},
-    path::Path,
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 /// A RAII guard, which calls [`(*self).unlock()`](std::fs::File::unlock) on a drop.
@@ -46,6 +47,98 @@ pub enum MkdirOptions {
     WithParents,
 }
 
+/// Mode accepted by the [`PathExt::chmod`]: either numeric (like `0o644`), or symbolic (like
+/// `"u+x,go-w"`).
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum ChmodMode<'a> {
+    /// Absolute mode, including the set-ID and the sticky bits.
+    Numeric(u32),
+    /// Symbolic mode, as understood by the `chmod(1)`.
+    Symbolic(&'a str),
+}
+
+#[cfg(unix)]
+impl From<u32> for ChmodMode<'_> {
+    fn from(value: u32) -> Self {
+        Self::Numeric(value)
+    }
+}
+
+#[cfg(unix)]
+impl<'a> From<&'a str> for ChmodMode<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::Symbolic(value)
+    }
+}
+
+/// Apply symbolic `spec` (or an octal string) to the `mode`.
+///
+/// Returns `None` if `spec` is malformed.
+#[cfg(unix)]
+fn apply_symbolic_mode(spec: &str, mut mode: u32, is_dir: bool) -> Option<u32> {
+    if !spec.is_empty() && spec.bytes().all(|b| matches!(b, b'0'..=b'7')) {
+        return u32::from_str_radix(spec, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777);
+    }
+    for clause in spec.split(',') {
+        let mut chars = clause.chars().peekable();
+        // Bits affected by the clause (permissions and special bits of the selected classes).
+        let mut who = 0;
+        while let Some(&c) = chars.peek() {
+            who |= match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                'a' => 0o7777,
+                _ => break,
+            };
+            chars.next();
+        }
+        // Note: Unlike the `chmod(1)`, this ignores the umask.
+        if who == 0 {
+            who = 0o7777;
+        }
+        let mut any_op = false;
+        while let Some(op) = chars.next() {
+            if !matches!(op, '+' | '-' | '=') {
+                return None;
+            }
+            any_op = true;
+            let mut bits = 0;
+            while let Some(&c) = chars.peek() {
+                bits |= match c {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                    'X' => 0,
+                    's' => 0o6000,
+                    't' => 0o1000,
+                    // Copy permissions of the given class.
+                    'u' => ((mode >> 6) & 0o7) * 0o111,
+                    'g' => ((mode >> 3) & 0o7) * 0o111,
+                    'o' => (mode & 0o7) * 0o111,
+                    _ => break,
+                };
+                chars.next();
+            }
+            let bits = bits & who;
+            mode = match op {
+                '+' => mode | bits,
+                '-' => mode & !bits,
+                _ => (mode & !who) | bits,
+            };
+        }
+        if !any_op {
+            return None;
+        }
+    }
+    Some(mode)
+}
+
 mod sealed {
     use std::path::Path;
 
@@ -60,6 +153,141 @@ pub enum ShouldBlock {
     Yes,
 }
 
+/// Whether the [`PathExt::set_times`] should follow symlinks (changing their target).
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum FollowSymlinks {
+    No,
+    Yes,
+}
+
+/// Whether the [`PathExt::is_hidden_with`] should treat dotfiles as hidden on Windows.
+///
+/// On other platforms, dotfiles are always hidden.
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum HiddenDotfiles {
+    No,
+    Yes,
+}
+
+/// Set of metadata kinds copied by the [`PathExt::copy_metadata_from`].
+///
+/// Kinds can be combined with `|`, e.g. `MetadataKinds::PERMISSIONS | MetadataKinds::TIMESTAMPS`
+/// (which is also the [`Default`]).
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct MetadataKinds(u8);
+
+impl MetadataKinds {
+    /// Nothing.
+    pub const NONE: Self = Self(0);
+    /// Permissions (mode bits on `unix`, the read-only attribute on Windows).
+    pub const PERMISSIONS: Self = Self(1);
+    /// Access and modification times.
+    pub const TIMESTAMPS: Self = Self(1 << 1);
+    /// Owner and group. Usually requires root privileges, unless the owner stays the same.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    pub const OWNERSHIP: Self = Self(1 << 2);
+    /// Extended attributes.
+    #[cfg(all(unix, feature = "xattr"))]
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "xattr"))))]
+    pub const XATTRS: Self = Self(1 << 3);
+
+    /// Check if all kinds from the `other` are in `self`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for MetadataKinds {
+    fn default() -> Self {
+        Self::PERMISSIONS | Self::TIMESTAMPS
+    }
+}
+
+impl std::ops::BitOr for MetadataKinds {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for MetadataKinds {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Options for controlling the [`PathExt::remove_matching`].
+///
+/// Default removes every match, at any depth.
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct RemoveOptions {
+    dry_run: bool,
+    max_depth: Option<usize>,
+}
+
+impl RemoveOptions {
+    /// Create default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Don't remove anything, only report what would be removed.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Look at most `max_depth` levels deep (direct children are at depth 1).
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+}
+
+/// Options for controlling the [`PathExt::modify`] and the [`PathExt::modify_bytes`].
+///
+/// Default takes the lock, and doesn't make a backup.
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
+pub struct ModifyOptions {
+    backup_suffix: Option<OsString>,
+    lock: bool,
+}
+
+impl Default for ModifyOptions {
+    fn default() -> Self {
+        Self {
+            backup_suffix: None,
+            lock: true,
+        }
+    }
+}
+
+impl ModifyOptions {
+    /// Create default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Before replacing the file, copy it to a sibling with the `suffix` appended to the name
+    /// (like `~` or `.bak`), overwriting any previous backup.
+    pub fn backup(mut self, suffix: impl AsRef<OsStr>) -> Self {
+        self.backup_suffix = Some(suffix.as_ref().to_os_string());
+        self
+    }
+
+    /// Hold an exclusive lock of a sibling `<name>.lock` file for the whole read-modify-write (so
+    /// concurrent modifications aren't lost).
+    ///
+    /// The lock file is persistent: it's created on the first use, and never removed (removing
+    /// it would let another process lock a new file, while the old one is still locked).
+    pub fn lock(mut self, lock: bool) -> Self {
+        self.lock = lock;
+        self
+    }
+}
+
 /// Extension trait for the [`Path`] with additional filesystem operations.
 ///
 /// Most of it are [`std::fs`] wrappers, changing from a functional to an OOP style, but there are some
@@ -105,6 +333,96 @@ pub trait PathExt: sealed::Sealed {
     /// ```
     fn touch(&self) -> io::Result<File>;
 
+    /// Create a new file and its parent directories, failing if the file already exists.
+    ///
+    /// Unlike the [`PathExt::touch`], the check and the creation are a single atomic step
+    /// ([`OpenOptions::create_new`]), so exactly one of racing callers succeeds.
+    ///
+    /// # Returns
+    /// [`Ok(File)`](std::fs::File) opened for reading and writing, if created successfully, an
+    /// error of kind [`ErrorKind::AlreadyExists`](io::ErrorKind::AlreadyExists) if the file
+    /// exists, otherwise an error, as reported by the [`PathExt::mkdir`] or the
+    /// [`OpenOptions::open`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// match Path::new("jobs/42.claimed").create_new() {
+    ///     Ok(_) => { /* We own the job. */ }
+    ///     Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => { /* Someone else does. */ }
+    ///     Err(err) => return Err(err.into()),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn create_new(&self) -> io::Result<File>;
+
+    /// Atomically create the first free file out of `self`, `name (1).ext`, `name (2).ext`, ...
+    ///
+    /// This is the same as the [`next_available_with("{stem} ({n}){ext}")`](PathExt::next_available_with).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // `report.pdf`, or `report (1).pdf` if the former exists, and so on.
+    /// let (path, file) = Path::new("Downloads/report.pdf").next_available()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn next_available(&self) -> io::Result<(PathBuf, File)>;
+
+    /// Atomically create the first free file out of `self`, and names generated by the `pattern`.
+    ///
+    /// `pattern` is a file name, in which `{stem}` is replaced by the file stem of `self`, `{ext}`
+    /// by its extension (with a leading dot, or empty, and both parts of the `.tar.*`, like the
+    /// `.tar.gz`), and `{n}` by the counter, starting with 1.
+    /// Parent directories are created if needed, like in the [`PathExt::touch`].
+    ///
+    /// Files are created with [`OpenOptions::create_new`], so there is no race between checking
+    /// the name and claiming it.
+    ///
+    /// # Returns
+    /// [`Ok((PathBuf, File))`](File) with the claimed path and the opened (for writing) file, an
+    /// error of kind [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput) if `self` has no
+    /// file name, or `pattern` has no `{n}`, otherwise an error, as reported by the
+    /// [`OpenOptions::open`].
+    fn next_available_with(&self, pattern: impl AsRef<str>) -> io::Result<(PathBuf, File)>;
+
+    /// Create a named pipe (FIFO) at `self` with `mode` (subject to the umask), and its parent
+    /// directories.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) if created successfully, otherwise an error, as reported by the
+    /// [`PathExt::mkdir`] or the `mkfifo` (e.g. of kind
+    /// [`ErrorKind::AlreadyExists`](io::ErrorKind::AlreadyExists)).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let fifo = Path::new("/tmp/app/control");
+    /// fifo.mkfifo(0o600)?;
+    /// assert!(fifo.is_fifo());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    fn mkfifo(&self, mode: u32) -> io::Result<()>;
+
+    /// Returns `true` if `self` exists, and is a named pipe (following symlinks).
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    fn is_fifo(&self) -> bool;
+
     /// Create directories at given [`Path`].
     ///
     /// # Returns
@@ -181,95 +499,775 @@ pub trait PathExt: sealed::Sealed {
     /// ```
     fn lock_shared(&self, should_block: ShouldBlock) -> io::Result<FileLockGuard>;
 
+    /// Lock `len` bytes of the `self` starting at the `offset`, creating a file if needed.
+    ///
+    /// Length `0` locks everything from the `offset` up to the end of the file (and beyond).
+    ///
+    /// On Linux it uses the open file description locks (`F_OFD_SETLK`), which are owned by the
+    /// opened file, so guards conflict also within a process. On other `unix` platforms classic
+    /// `fcntl` locks are used, which are owned by the process, so they conflict only with other
+    /// processes, and all of them are released once any descriptor of the file is closed (see
+    /// the [`RangeLockGuard`]). On Windows it uses the `LockFileEx`, and locks are mandatory.
+    ///
+    /// # Returns
+    /// [`Ok(RangeLockGuard)`](RangeLockGuard) on a success. If a conflicting lock is held, and
+    /// `should_block` is [`ShouldBlock::No`], fails with [`ErrorKind::WouldBlock`](io::ErrorKind::WouldBlock).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::*;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let journal = Path::new("journal.log");
+    /// // Reserve a slot for our record, others may write past it concurrently.
+    /// let slot = journal.lock_range(4096, 512, LockType::Exclusive, ShouldBlock::Yes)?;
+    /// // Write the record...
+    /// drop(slot);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn lock_range(
+        &self,
+        offset: u64,
+        len: u64,
+        lock_type: LockType,
+        should_block: ShouldBlock,
+    ) -> io::Result<RangeLockGuard>;
+
     /// Resolve `self` fully, as best as possible.
     ///
     /// Unlike [`std::fs::canonicalize`], this function __doesn't__ fail, if `self` points to a
-    /// non-existing file.
+    /// non-existing file. On Windows, the `\\?\` prefix is stripped, when possible (see the
+    /// [`set_long_paths`](crate::fs::set_long_paths)).
     ///
     /// This function requires the __full-resolve__ feature.
     #[cfg(feature = "full-resolve")]
     #[cfg_attr(docsrs, doc(cfg(feature = "full-resolve")))]
     fn resolve(&self) -> io::Result<PathBuf>;
 
-    /// Canonicalize `self` fully: expand `~` into the `$HOME`.
+    /// Expand the leading `~` into the [`home`](crate::config_files::home), and the leading
+    /// `~user` into home directory of the `user` (`unix` only).
     ///
-    /// Also, because of the current implementation, this function will fail, if `self` is not a
-    /// `UTF-8` path.
+    /// Only the first component is inspected, nothing else is resolved (no symlinks, no `..`). If
+    /// home directory (or user) is unknown, `self` is returned unchanged, just like shells do.
     ///
-    /// This function requires the __expand-user__ feature.
-    #[cfg(feature = "expand-user")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "expand-user")))]
-    fn expand_user(&self) -> io::Result<PathBuf>;
-
-    /// Canonicalize `self` fully: expand `~` into a `home`.
+    /// # Returns
+    /// [`Ok(PathBuf)`](PathBuf) with the expanded path, otherwise an error, as reported by the
+    /// user database lookup.
     ///
-    /// Also, because of the current implementation, this function will fail, if `self` is not a
-    /// `UTF-8` path.
+    /// # Examples
     ///
-    /// This function requires the __expand-user__ feature.
-    #[cfg(feature = "expand-user")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "expand-user")))]
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config = Path::new("~/.config/app.toml").expand_user()?;
+    /// let shared = Path::new("~www-data/public").expand_user()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn expand_user(&self) -> io::Result<PathBuf>;
+
+    /// Same as the [`PathExt::expand_user`], but expands `~` into the `home`.
     fn expand_user_with(&self, home: impl AsRef<str>) -> io::Result<PathBuf>;
 
-    /// Canonicalize `self` fully: expand `~` into a `home()`.
+    /// Same as the [`PathExt::expand_user`], but expands `~` into the `home()`.
     ///
-    /// Also, because of the current implementation, this function will fail, if `self` is not a
-    /// `UTF-8` path.
-    ///
-    /// This function requires the __expand-user__ feature.
-    #[cfg(feature = "expand-user")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "expand-user")))]
+    /// `home` is called only if `self` starts with `~`.
     fn expand_user_with_fn<F, H>(&self, home: F) -> io::Result<PathBuf>
     where
         H: AsRef<str>,
         F: FnOnce() -> H;
 
-    /// Returns `true` if path exists on a disk and points to an executable file.
+    /// Start watching `self` for changes, see [`Watcher`](crate::fs::watch::Watcher) for details.
     ///
-    /// Current implementation only considers `unix` and `windows` cfg's, any other always returns
-    /// `false`.
-    fn is_executable(&self) -> bool;
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// for event in Path::new("config.toml").watch()? {
+    ///     println!("{:?}", event?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// This function requires the __watch__ feature.
+    #[cfg(feature = "watch")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "watch")))]
+    fn watch(&self) -> io::Result<crate::fs::watch::Watcher>;
 
-    /// A wrapper around [`std::fs::copy`].
-    fn copy_to(&self, to: impl AsRef<Path>) -> io::Result<u64>;
+    /// Map `self` into memory, read-only.
+    ///
+    /// # Safety
+    /// Mapped file must not be modified (or truncated) while the [`Mmap`](crate::fs::Mmap) is
+    /// alive, neither by this process nor any other one. Otherwise contents of the returned slice
+    /// can change under its feet (which is an undefined behaviour), or accessing it can crash the
+    /// process (e.g. with `SIGBUS`, after a truncation).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // SAFETY: Nobody modifies `big.bin`.
+    /// let map = unsafe { Path::new("big.bin").mmap()? };
+    /// println!("first byte: {:?}", map.first());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// This function requires the __mmap__ feature.
+    #[cfg(feature = "mmap")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+    unsafe fn mmap(&self) -> io::Result<crate::fs::Mmap>;
 
-    /// A wrapper around [`std::fs::hard_link`].
-    fn hard_link_to(&self, to: impl AsRef<Path>) -> io::Result<()>;
+    /// Map `self` into memory, for reading and writing.
+    ///
+    /// # Safety
+    /// Same as for [`PathExt::mmap`], but additionally the file must not be mapped (or accessed)
+    /// anywhere else while the [`MmapMut`](crate::fs::MmapMut) is alive, as it hands out a
+    /// `&mut [u8]`, which must be unique.
+    ///
+    /// This function requires the __mmap__ feature.
+    #[cfg(feature = "mmap")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+    unsafe fn mmap_mut(&self) -> io::Result<crate::fs::MmapMut>;
 
-    /// A wrapper around [`std::fs::read`].
-    fn read(&self) -> io::Result<Vec<u8>>;
+    /// Get value of the extended attribute `name` of `self`.
+    ///
+    /// # Returns
+    /// [`Ok(None)`](None) if there is no such attribute, otherwise its value, or an error as
+    /// reported by the `getxattr`.
+    ///
+    /// Currently only Linux and macOS are supported, other platforms always fail with
+    /// [`ErrorKind::Unsupported`](io::ErrorKind::Unsupported).
+    ///
+    /// This function requires the __xattr__ feature.
+    #[cfg(feature = "xattr")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xattr")))]
+    fn xattr_get(&self, name: impl AsRef<OsStr>) -> io::Result<Option<Vec<u8>>>;
 
-    /// A wrapper around [`std::fs::read_to_string`].
-    fn read_to_string(&self) -> io::Result<String>;
+    /// Set the extended attribute `name` of `self` to `value`, creating it if needed.
+    ///
+    /// See [`PathExt::xattr_get`] for supported platforms.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let path = Path::new("download.tar.gz");
+    /// path.xattr_set("user.xdg.origin.url", "https://example.com/download.tar.gz")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// This function requires the __xattr__ feature.
+    #[cfg(feature = "xattr")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xattr")))]
+    fn xattr_set(&self, name: impl AsRef<OsStr>, value: impl AsRef<[u8]>) -> io::Result<()>;
 
-    /// A wrapper around [`std::fs::rename`].
-    fn rename_to(&self, to: impl AsRef<Path>) -> io::Result<()>;
+    /// List names of the extended attributes of `self`.
+    ///
+    /// See [`PathExt::xattr_get`] for supported platforms.
+    ///
+    /// This function requires the __xattr__ feature.
+    #[cfg(feature = "xattr")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xattr")))]
+    fn xattr_list(&self) -> io::Result<Vec<OsString>>;
 
-    /// A wrapper around [`std::fs::remove_file`].
-    fn rm(&self) -> io::Result<()>;
+    /// Remove the extended attribute `name` of `self`.
+    ///
+    /// See [`PathExt::xattr_get`] for supported platforms.
+    ///
+    /// This function requires the __xattr__ feature.
+    #[cfg(feature = "xattr")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "xattr")))]
+    fn xattr_remove(&self, name: impl AsRef<OsStr>) -> io::Result<()>;
 
-    /// A wrapper around [`std::fs::remove_dir`].
-    fn rmdir(&self) -> io::Result<()>;
+    /// Change mode of `self`, following symlinks.
+    ///
+    /// Symbolic modes follow the grammar of the `chmod(1)`: comma separated clauses of
+    /// `[ugoa]*([-+=][rwxXst]*|[-+=][ugo])+`, applied relative to the current mode. Unlike the
+    /// `chmod(1)`, clauses without a class (like `+x`) are __not__ masked by the umask. Octal
+    /// strings (like `"755"`) are also accepted.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) if changed successfully, an error of kind
+    /// [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput) for malformed modes, otherwise an
+    /// error, as reported by the [`PathExt::set_permissions`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let path = Path::new("script.sh");
+    /// path.chmod(0o644)?;
+    /// path.chmod("u+x,go-w")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    fn chmod<'a>(&self, mode: impl Into<ChmodMode<'a>>) -> io::Result<()>;
 
-    /// A wrapper around [`std::fs::remove_dir_all`].
-    fn rmtree(&self) -> io::Result<()>;
+    /// Change owner and/or group of `self`, following symlinks.
+    ///
+    /// [`None`] leaves the respective ID unchanged. This is a wrapper around
+    /// [`std::os::unix::fs::chown`].
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    fn chown(&self, uid: Option<u32>, gid: Option<u32>) -> io::Result<()>;
 
-    /// A wrapper around [`std::fs::set_permissions`].
-    fn set_permissions(&self, permissions: Permissions) -> io::Result<()>;
+    /// Same as the [`PathExt::chown`], but changes the symlink itself, instead of its target.
+    ///
+    /// This is a wrapper around [`std::os::unix::fs::lchown`].
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    fn lchown(&self, uid: Option<u32>, gid: Option<u32>) -> io::Result<()>;
 
-    /// A wrapper around [`std::fs::write`].
-    fn write(&self, contents: impl AsRef<[u8]>) -> io::Result<()>;
-}
+    /// Change owner and group of `self` by their names, following symlinks.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) if changed successfully, an error of kind
+    /// [`ErrorKind::NotFound`](io::ErrorKind::NotFound), if there is no such user or group,
+    /// otherwise an error, as reported by the [`PathExt::chown`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Path::new("/var/www/index.html").chown_user("www-data", "www-data")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    fn chown_user(&self, user: impl AsRef<str>, group: impl AsRef<str>) -> io::Result<()>;
 
-impl PathExt for Path {
-    // FIXME: Take `OpenOptions` as a parameter?
-    fn touch(&self) -> io::Result<File> {
-        if let Some(parent) = self.parent() {
-            parent.mkdir(MkdirOptions::WithParents)?;
-        }
-        let mut opts = OpenOptions::new();
-        opts.read(true).write(true).create(true).truncate(false);
-        #[cfg(unix)]
+    /// Change group of `self` by its name, following symlinks.
+    ///
+    /// See [`PathExt::chown_user`] for errors.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    fn chgrp(&self, group: impl AsRef<str>) -> io::Result<()>;
+
+    /// Compute a relative path, which leads from `base` to `self`.
+    ///
+    /// Computation is purely lexical: symlinks are __not__ resolved (use [`std::fs::canonicalize`]
+    /// first if this matters), and `.` components are ignored.
+    ///
+    /// # Returns
+    /// [`Ok(PathBuf)`](PathBuf) with the relative path (`.` if both paths are the same), or an
+    /// error of kind [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput), when there is no
+    /// such path: one path is absolute and the other isn't, paths are on different drives
+    /// (Windows), or `base` contains a `..` past the common prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let relative = Path::new("/usr/lib/libc.so").relative_to("/usr/bin")?;
+    /// assert_eq!(relative, Path::new("../lib/libc.so"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn relative_to(&self, base: impl AsRef<Path>) -> io::Result<PathBuf>;
+
+    /// Join an untrusted, relative `path` onto `self`, so the result can't escape it.
+    ///
+    /// Computation is purely lexical: `..` components are allowed, as long as they stay inside
+    /// `self`, and symlinks are __not__ resolved, so callers must not follow symlinks they don't
+    /// control (like ones extracted from an archive).
+    ///
+    /// # Returns
+    /// [`Ok(PathBuf)`](PathBuf) with the joined path, or an error of kind
+    /// [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput), when `path` is absolute, has a
+    /// prefix (Windows), or goes above `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let base = Path::new("/srv/uploads");
+    /// assert_eq!(base.join_secure("a/../b.txt")?, Path::new("/srv/uploads/b.txt"));
+    /// assert!(base.join_secure("../../etc/passwd").is_err());
+    /// assert!(base.join_secure("/etc/passwd").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn join_secure(&self, path: impl AsRef<Path>) -> io::Result<PathBuf>;
+
+    /// Returns `true` if `self` is hidden, according to the platform convention.
+    ///
+    /// On Windows it checks the `FILE_ATTRIBUTE_HIDDEN` attribute (so the path has to exist), on
+    /// other platforms the file name has to start with a dot (and the path doesn't need to
+    /// exist).
+    ///
+    /// This is the same as the [`is_hidden_with(HiddenDotfiles::No)`](PathExt::is_hidden_with).
+    fn is_hidden(&self) -> bool;
+
+    /// Same as the [`PathExt::is_hidden`], but on Windows it can additionally treat dotfiles as
+    /// hidden.
+    fn is_hidden_with(&self, dotfiles: HiddenDotfiles) -> bool;
+
+    /// Returns `true` if path exists on a disk and points to an executable file.
+    ///
+    /// Current implementation only considers `unix` and `windows` cfg's, any other always returns
+    /// `false`.
+    fn is_executable(&self) -> bool;
+
+    /// A wrapper around [`std::fs::copy`].
+    fn copy_to(&self, to: impl AsRef<Path>) -> io::Result<u64>;
+
+    /// Copy contents and permissions of `self` into the `to` (truncating it, if it exists) with
+    /// the [`copy_range`](crate::fs::copy_range), avoiding userspace buffers.
+    ///
+    /// # Returns
+    /// [`Ok(u64)`](u64) with the number of copied bytes, otherwise an error, as reported by the
+    /// [`File::open`], the [`File::create`], or the [`copy_range`](crate::fs::copy_range), or of
+    /// kind [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput), if the `to` is the same
+    /// file (like a hard link, or a symlink to `self`), which would be truncated otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Path::new("vm/disk.qcow2").copy_to_fast("backup/disk.qcow2")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn copy_to_fast(&self, to: impl AsRef<Path>) -> io::Result<u64>;
+
+    /// A wrapper around [`std::fs::hard_link`].
+    fn hard_link_to(&self, to: impl AsRef<Path>) -> io::Result<()>;
+
+    /// Returns `true` if `self` and `other` are the same file (following symlinks): e.g. hard
+    /// links, or different paths to the same entry.
+    ///
+    /// Compares device and inode numbers on `unix`, and volume serial numbers and file indices on
+    /// Windows.
+    ///
+    /// # Returns
+    /// [`Ok(bool)`](bool) on a success, otherwise an error, as reported by the metadata queries
+    /// (e.g. if either path doesn't exist). On other platforms this function always fails with
+    /// [`ErrorKind::Unsupported`](io::ErrorKind::Unsupported).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (src, dst) = (Path::new("a.txt"), Path::new("./b.txt"));
+    /// if !src.same_file_as(dst)? {
+    ///     src.copy_to(dst)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn same_file_as(&self, other: impl AsRef<Path>) -> io::Result<bool>;
+
+    /// Return number of hard links pointing to the `self` (following symlinks).
+    ///
+    /// See [`PathExt::same_file_as`] for supported platforms.
+    fn hard_link_count(&self) -> io::Result<u64>;
+
+    /// Make a copy-on-write clone of `self` at `to`, which must not exist.
+    ///
+    /// Uses the `FICLONE` on Linux (btrfs, XFS, ...) and the `clonefile` on macOS (APFS). Clone
+    /// shares data blocks with `self`, so it's instant and doesn't use any extra space.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) if cloned successfully, otherwise an error, as reported by the OS (e.g. if
+    /// filesystem doesn't support reflinks, or `to` is on a different filesystem). On other
+    /// platforms this function always fails with [`ErrorKind::Unsupported`](io::ErrorKind::Unsupported).
+    fn reflink_to(&self, to: impl AsRef<Path>) -> io::Result<()>;
+
+    /// Try [`PathExt::reflink_to`], falling back to copying the contents and permissions, if the
+    /// platform or filesystem doesn't support reflinks (or `to` is on another filesystem).
+    ///
+    /// Like the [`PathExt::reflink_to`], `to` must not exist.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) if cloned or copied successfully, otherwise an error, as reported by the OS
+    /// (of kind [`ErrorKind::AlreadyExists`](io::ErrorKind::AlreadyExists), if `to` exists).
+    fn reflink_or_copy_to(&self, to: impl AsRef<Path>) -> io::Result<()>;
+
+    /// Recreate the directory tree `self` at `to` (which must not exist), hard linking every file
+    /// instead of copying it, like the `cp -al`.
+    ///
+    /// Files on a different filesystem than `to` are copied instead. Symlinks are recreated
+    /// (not followed), and directories get the permissions of their originals. Linked files share
+    /// contents __and__ metadata with the originals, so modifying them in place modifies both.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) on a success, otherwise the first error encountered (leaving the partial
+    /// tree in place).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Snapshot, which takes space only for the directories.
+    /// Path::new("backups/latest").link_tree("backups/2026-10-14")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn link_tree(&self, to: impl AsRef<Path>) -> io::Result<()>;
+
+    /// Copy chosen kinds of metadata from `src` onto `self` (following symlinks on both).
+    ///
+    /// Ownership is applied first (as changing it can clear the set-ID bits), and permissions
+    /// last (so a read-only `src` doesn't prevent applying the rest).
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) if everything was copied, otherwise the first error, as reported by the OS
+    /// (e.g. if ownership can't be changed without privileges). Metadata applied before the
+    /// failure is left in place.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::{MetadataKinds, PathExt};
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (src, dst) = (Path::new("a.txt"), Path::new("b.txt"));
+    /// dst.write(src.read()?)?;
+    /// dst.copy_metadata_from(src, MetadataKinds::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn copy_metadata_from(&self, src: impl AsRef<Path>, kinds: MetadataKinds) -> io::Result<()>;
+
+    /// Find the offset of the first byte, at which contents of the `self` and the `other` differ.
+    ///
+    /// If one file is a prefix of the other, the offset is the length of the shorter one. Files
+    /// are read in chunks, so they can be arbitrarily large.
+    ///
+    /// # Returns
+    /// [`Ok(None)`](None) if files are identical, [`Ok(Some(offset))`](Some) if they differ,
+    /// otherwise an error, as reported by the [`File::open`] or [`std::io::Read::read`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// if let Some(offset) = Path::new("original.iso").first_difference("copy.iso")? {
+    ///     eprintln!("copy is corrupted at byte {offset}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn first_difference(&self, other: impl AsRef<Path>) -> io::Result<Option<u64>>;
+
+    /// Detect type of the file `self` by its first few KiB (magic bytes), see [`SniffedType`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::{PathExt, SniffedType};
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// if Path::new("download").sniff_type()? == SniffedType::Gzip {
+    ///     // Decompress...
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn sniff_type(&self) -> io::Result<SniffedType>;
+
+    /// Return statistics (sizes, and type) of the filesystem containing `self`.
+    ///
+    /// Uses the `statvfs` on `unix`, and the `GetDiskFreeSpaceExW` on Windows.
+    ///
+    /// # Returns
+    /// [`Ok(FsStats)`](FsStats) on a success, otherwise an error, as reported by the OS (e.g. if
+    /// `self` doesn't exist).
+    fn fs_stats(&self) -> io::Result<FsStats>;
+
+    /// Check if at least `bytes` are [available](FsStats::available) on the filesystem, where
+    /// `self` would be written.
+    ///
+    /// `self` doesn't have to exist, its closest existing ancestor is queried instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let target = Path::new("/opt/app/bundle.tar");
+    /// if !target.has_space_for(512 * 1024 * 1024)? {
+    ///     return Err("not enough free space".into());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn has_space_for(&self, bytes: u64) -> io::Result<bool>;
+
+    /// A wrapper around [`std::fs::read`].
+    fn read(&self) -> io::Result<Vec<u8>>;
+
+    /// A wrapper around [`std::fs::read_to_string`].
+    fn read_to_string(&self) -> io::Result<String>;
+
+    /// Read at most `limit` bytes from the start of the file `self`.
+    ///
+    /// # Returns
+    /// [`Ok((Vec<u8>, bool))`](Vec) on a success, where the flag indicates, that the file was
+    /// longer than `limit` (and the contents are truncated). Otherwise an error, as reported by
+    /// the [`File::open`] or [`std::io::Read::read_to_end`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (avatar, truncated) = Path::new("upload.png").read_at_most(1024 * 1024)?;
+    /// if truncated {
+    ///     return Err("avatar is bigger than 1 MiB".into());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn read_at_most(&self, limit: usize) -> io::Result<(Vec<u8>, bool)>;
+
+    /// Like the [`PathExt::read_at_most`], but reads a UTF-8 string.
+    ///
+    /// A character cut in half by the `limit` is dropped from the truncated string.
+    ///
+    /// # Returns
+    /// Same as the [`PathExt::read_at_most`], additionally fails with
+    /// [`ErrorKind::InvalidData`](io::ErrorKind::InvalidData), if read contents are not UTF-8.
+    fn read_to_string_at_most(&self, limit: usize) -> io::Result<(String, bool)>;
+
+    /// A wrapper around [`std::fs::rename`].
+    fn rename_to(&self, to: impl AsRef<Path>) -> io::Result<()>;
+
+    /// A wrapper around [`std::fs::remove_file`].
+    fn rm(&self) -> io::Result<()>;
+
+    /// Overwrite contents of the regular file `self` with random data `passes` times, and remove
+    /// it.
+    ///
+    /// # Limitations
+    /// This only overwrites the blocks, which the file occupies __now__. Old copies of the data
+    /// can survive in many places, out of reach of this function:
+    /// - copy-on-write filesystems (btrfs, ZFS, APFS) and log-structured ones write new data
+    ///   elsewhere, and snapshots keep the old blocks,
+    /// - SSDs and flash storage remap writes (wear levelling) and keep overprovisioned blocks,
+    /// - journals (e.g. `data=journal` ext4), backups, swap, and caches.
+    ///
+    /// Treat it as a best-effort measure for briefly stored secrets, full-disk encryption is the
+    /// only reliable protection.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) if shredded successfully, an error of kind
+    /// [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput) if `self` is not a regular file
+    /// (symlinks are not followed), otherwise an error, as reported by the I/O operations.
+    fn shred(&self, passes: usize) -> io::Result<()>;
+
+    /// A wrapper around [`std::fs::remove_dir`].
+    fn rmdir(&self) -> io::Result<()>;
+
+    /// A wrapper around [`std::fs::remove_dir_all`].
+    fn rmtree(&self) -> io::Result<()>;
+
+    /// A forceful version of the [`PathExt::rmtree`], for trees which resist the removal.
+    ///
+    /// It first tries [`PathExt::rmtree`], and if that fails, it removes the tree entry by entry
+    /// (never following symlinks):
+    /// - on Windows, read-only attributes are cleared, and removals failing because of sharing
+    ///   violations (e.g. an antivirus scanning the file) are retried with a backoff,
+    /// - on `unix`, directories without the write or the search permission are `chmod`-ed first
+    ///   (which is enough for entries owned by the current user).
+    fn rmtree_force(&self) -> io::Result<()>;
+
+    /// Returns `true` if `self` is a directory without any entries.
+    ///
+    /// Only the first entry is read, so this is cheap even for huge directories.
+    fn is_empty_dir(&self) -> bool;
+
+    /// Remove all entries under the directory `self`, which match the `glob` pattern.
+    ///
+    /// Pattern is matched against paths relative to the `self`, using `/` as the separator;
+    /// patterns without a separator match just the file name, at any depth. Supported syntax:
+    /// `?`, `*`, `[a-z]`, `[!a-z]`, `\` (escape), and `**` (any number of components). Matching
+    /// directories are removed with all their contents, symlinks are never followed.
+    ///
+    /// # Returns
+    /// [`Ok(Vec<PathBuf>)`](Vec) with the removed (or, in a [dry run](RemoveOptions::dry_run),
+    /// to be removed) entries in a sorted order, otherwise the first error encountered (entries
+    /// removed before it stay removed). Fails with [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput)
+    /// on a malformed pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::{PathExt, RemoveOptions};
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let cache = Path::new("/var/cache/app");
+    /// let doomed = cache.remove_matching("**/*.partial", RemoveOptions::new().dry_run(true))?;
+    /// println!("Would remove {doomed:?}");
+    /// cache.remove_matching("**/*.partial", RemoveOptions::new())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn remove_matching(
+        &self,
+        glob: impl AsRef<str>,
+        opts: RemoveOptions,
+    ) -> io::Result<Vec<PathBuf>>;
+
+    /// Remove empty ancestors of `self`, going up until a non-empty one, or the `root`.
+    ///
+    /// Only ancestors strictly inside of the `root` are considered, so `root` itself is never
+    /// removed. `self` is not removed either, this is meant to be called after removing it.
+    ///
+    /// # Returns
+    /// [`Ok(usize)`](usize) with the number of removed directories, otherwise an error, as
+    /// reported by the [`PathExt::rmdir`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let path = Path::new("cache/ab/cd/entry");
+    /// path.rm()?;
+    /// // Removes `cache/ab/cd` and `cache/ab`, if they are empty now.
+    /// path.remove_empty_parents("cache")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn remove_empty_parents(&self, root: impl AsRef<Path>) -> io::Result<usize>;
+
+    /// A wrapper around [`std::fs::set_permissions`].
+    fn set_permissions(&self, permissions: Permissions) -> io::Result<()>;
+
+    /// Set access and modification times of the `self`, leaving the `None` ones unchanged.
+    ///
+    /// With [`FollowSymlinks::No`], times of a symlink itself are changed, instead of its target.
+    /// Unlike the [`File::set_times`], this doesn't require opening the file (on `unix`).
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) on a success, otherwise an error, as reported by the `utimensat` on `unix`,
+    /// or by the [`File::set_times`] elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::{FollowSymlinks, PathExt};
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (src, dst) = (Path::new("src/link"), Path::new("dst/link"));
+    /// let metadata = src.symlink_metadata()?;
+    /// dst.set_times(None, Some(metadata.modified()?), FollowSymlinks::No)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn set_times(
+        &self,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+        follow: FollowSymlinks,
+    ) -> io::Result<()>;
+
+    /// Set modification time of the `self` (following symlinks), see the [`PathExt::set_times`].
+    fn set_modified(&self, modified: SystemTime) -> io::Result<()>;
+
+    /// A wrapper around [`std::fs::write`].
+    fn write(&self, contents: impl AsRef<[u8]>) -> io::Result<()>;
+
+    /// Like the [`PathExt::write`], but the file gets exactly the `mode` permissions, before any
+    /// of the `contents` are written.
+    ///
+    /// On `unix` a new file is created with the `mode`, and an existing one is `chmod`ed right
+    /// after opening (`umask` doesn't apply either way). On Windows only the owner's write bit
+    /// matters: without it, the file is made read-only once the `contents` are written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::PathExt;
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Path::new("id_ed25519").write_with_mode("secret key", 0o600)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn write_with_mode(&self, contents: impl AsRef<[u8]>, mode: u32) -> io::Result<()>;
+
+    /// Edit the file `self` in place: read it, run `f` on the contents, and atomically replace
+    /// the file with the result (a temporary sibling is renamed over it, keeping permissions, and
+    /// the owner, where allowed). If `self` is a symlink, the file it points to is replaced, and
+    /// the symlink is kept.
+    ///
+    /// If contents don't change, the file isn't written at all. When [locking](ModifyOptions::lock),
+    /// a persistent `<name>.lock` sibling is created (and never removed), and modifications
+    /// through it are serialized, also across processes.
+    ///
+    /// # Returns
+    /// [`Ok(R)`](Ok) with the result of the `f`, otherwise an error, as reported by the
+    /// [`PathExt::read`], [`PathExt::lock`], or the filesystem while replacing the file. Invalid
+    /// UTF-8 is reported with the [`ErrorKind::InvalidData`](io::ErrorKind::InvalidData) (then use
+    /// the [`PathExt::modify_bytes`]).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::fs::{ModifyOptions, PathExt};
+    /// # use std::path::Path;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Path::new("/etc/hosts").modify(ModifyOptions::new().backup("~"), |hosts| {
+    ///     hosts.push_str("127.0.0.1 dev.local\n");
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn modify<R>(&self, options: ModifyOptions, f: impl FnOnce(&mut String) -> R) -> io::Result<R>;
+
+    /// Same as the [`PathExt::modify`], but for arbitrary bytes.
+    fn modify_bytes<R>(
+        &self,
+        options: ModifyOptions,
+        f: impl FnOnce(&mut Vec<u8>) -> R,
+    ) -> io::Result<R>;
+}
+
+impl PathExt for Path {
+    // FIXME: Take `OpenOptions` as a parameter?
+    fn touch(&self) -> io::Result<File> {
+        if let Some(parent) = self.parent() {
+            parent.mkdir(MkdirOptions::WithParents)?;
+        }
+        let mut opts = OpenOptions::new();
+        opts.read(true).write(true).create(true).truncate(false);
+        #[cfg(unix)]
         {
             use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
             if let Ok(metadata) = self.metadata() {
@@ -279,7 +1277,115 @@ impl PathExt for Path {
                 opts.mode(permissions & MASK);
             }
         }
-        opts.open(self)
+        opts.open(self)
+    }
+
+    fn create_new(&self) -> io::Result<File> {
+        if let Some(parent) = self.parent() {
+            parent.mkdir(MkdirOptions::WithParents)?;
+        }
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(self)
+    }
+
+    fn next_available(&self) -> io::Result<(PathBuf, File)> {
+        self.next_available_with("{stem} ({n}){ext}")
+    }
+
+    fn next_available_with(&self, pattern: impl AsRef<str>) -> io::Result<(PathBuf, File)> {
+        /// Give up after that many taken names.
+        const MAX_ATTEMPTS: usize = 1 << 16;
+        let pattern = pattern.as_ref();
+        if !pattern.contains("{n}") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "pattern has to contain `{n}`",
+            ));
+        }
+        let mut stem = self
+            .file_stem()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+        let mut ext = self.extension().map(|ext| {
+            let mut dotted = std::ffi::OsString::from(".");
+            dotted.push(ext);
+            dotted
+        });
+        // Compressed tarballs (like the `.tar.gz`) keep both parts of the extension together.
+        if let (Some(ext), Some(inner)) = (&mut ext, Path::new(stem).extension())
+            && inner.eq_ignore_ascii_case("tar")
+        {
+            let mut dotted = std::ffi::OsString::from(".");
+            dotted.push(inner);
+            dotted.push(&*ext);
+            *ext = dotted;
+            stem = Path::new(stem).file_stem().unwrap_or(stem);
+        }
+        if let Some(parent) = self.parent() {
+            parent.mkdir(MkdirOptions::WithParents)?;
+        }
+
+        let create = |path: &Path| OpenOptions::new().write(true).create_new(true).open(path);
+        match create(self) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            result => return result.map(|file| (self.to_path_buf(), file)),
+        }
+        let render = |n: usize| {
+            let mut name = std::ffi::OsString::new();
+            let mut rest = pattern;
+            while !rest.is_empty() {
+                if let Some(tail) = rest.strip_prefix("{stem}") {
+                    name.push(stem);
+                    rest = tail;
+                } else if let Some(tail) = rest.strip_prefix("{ext}") {
+                    name.push(ext.as_deref().unwrap_or_default());
+                    rest = tail;
+                } else if let Some(tail) = rest.strip_prefix("{n}") {
+                    name.push(n.to_string());
+                    rest = tail;
+                } else {
+                    let len = rest.chars().next().map_or(1, char::len_utf8);
+                    name.push(&rest[..len]);
+                    rest = &rest[len..];
+                }
+            }
+            name
+        };
+        for n in 1..=MAX_ATTEMPTS {
+            let path = self.with_file_name(render(n));
+            match create(&path) {
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                result => return result.map(|file| (path, file)),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "too many files with the same name already exist",
+        ))
+    }
+
+    #[cfg(unix)]
+    fn mkfifo(&self, mode: u32) -> io::Result<()> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        if let Some(parent) = self.parent() {
+            parent.mkdir(MkdirOptions::WithParents)?;
+        }
+        let path = CString::new(self.as_os_str().as_bytes())?;
+        // SAFETY: `path` is NUL terminated.
+        if unsafe { libc::mkfifo(path.as_ptr(), mode as libc::mode_t) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn is_fifo(&self) -> bool {
+        use std::os::unix::fs::FileTypeExt;
+        self.metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_fifo())
     }
 
     fn mkdir(&self, opts: MkdirOptions) -> io::Result<()> {
@@ -319,6 +1425,127 @@ impl PathExt for Path {
         result.map(|_| FileLockGuard { file })
     }
 
+    fn lock_range(
+        &self,
+        offset: u64,
+        len: u64,
+        lock_type: LockType,
+        should_block: ShouldBlock,
+    ) -> io::Result<RangeLockGuard> {
+        crate::fs::range_lock::lock_range(self.touch()?, offset, len, lock_type, should_block)
+    }
+
+    fn join_secure(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        use std::path::Component;
+        let path = path.as_ref();
+        let mut joined = self.to_path_buf();
+        let mut depth = 0usize;
+        for component in path.components() {
+            match component {
+                Component::Normal(name) => {
+                    joined.push(name);
+                    depth += 1;
+                }
+                Component::CurDir => {}
+                Component::ParentDir if depth > 0 => {
+                    joined.pop();
+                    depth -= 1;
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("`{}` escapes `{}`", path.display(), self.display()),
+                    ));
+                }
+            }
+        }
+        Ok(joined)
+    }
+
+    fn relative_to(&self, base: impl AsRef<Path>) -> io::Result<PathBuf> {
+        use std::path::Component;
+        let base = base.as_ref();
+        let invalid = |reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "no relative path from `{}` to `{}`: {reason}",
+                    base.display(),
+                    self.display()
+                ),
+            )
+        };
+        if self.is_absolute() != base.is_absolute() {
+            return Err(invalid("only one of paths is absolute"));
+        }
+        let same_component = |a: &Component, b: &Component| match (a, b) {
+            // Drive letters are case insensitive.
+            (Component::Prefix(a), Component::Prefix(b)) => a
+                .as_os_str()
+                .as_encoded_bytes()
+                .eq_ignore_ascii_case(b.as_os_str().as_encoded_bytes()),
+            _ => a == b,
+        };
+
+        let mut target = self
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .peekable();
+        let mut base = base
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .peekable();
+        while let (Some(a), Some(b)) = (target.peek(), base.peek()) {
+            if !same_component(a, b) {
+                break;
+            }
+            target.next();
+            base.next();
+        }
+
+        let mut relative = PathBuf::new();
+        for component in base {
+            match component {
+                Component::Normal(_) => relative.push(".."),
+                Component::ParentDir => return Err(invalid("`..` in base is ambiguous")),
+                _ => return Err(invalid("paths have different roots")),
+            }
+        }
+        for component in target {
+            if matches!(component, Component::Prefix(_) | Component::RootDir) {
+                return Err(invalid("paths have different roots"));
+            }
+            relative.push(component);
+        }
+        if relative.as_os_str().is_empty() {
+            relative.push(".");
+        }
+        Ok(relative)
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.is_hidden_with(HiddenDotfiles::No)
+    }
+
+    #[cfg(windows)]
+    fn is_hidden_with(&self, dotfiles: HiddenDotfiles) -> bool {
+        use std::os::windows::fs::MetadataExt;
+        use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_HIDDEN;
+        let is_dotfile = || {
+            self.file_name()
+                .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+        };
+        self.symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+            || (matches!(dotfiles, HiddenDotfiles::Yes) && is_dotfile())
+    }
+
+    #[cfg(not(windows))]
+    fn is_hidden_with(&self, _dotfiles: HiddenDotfiles) -> bool {
+        self.file_name()
+            .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+    }
+
     #[cfg(unix)]
     fn is_executable(&self) -> bool {
         use std::os::unix::prelude::*;
@@ -344,80 +1571,991 @@ impl PathExt for Path {
         false
     }
 
-    fn copy_to(&self, to: impl AsRef<Path>) -> io::Result<u64> {
-        copy(self, to)
+    #[cfg(unix)]
+    fn chmod<'a>(&self, mode: impl Into<ChmodMode<'a>>) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = match mode.into() {
+            ChmodMode::Numeric(mode) => mode,
+            ChmodMode::Symbolic(spec) => {
+                let metadata = self.metadata()?;
+                let current = metadata.permissions().mode() & 0o7777;
+                apply_symbolic_mode(spec, current, metadata.is_dir()).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid mode `{spec}`"),
+                    )
+                })?
+            }
+        };
+        self.set_permissions(Permissions::from_mode(mode))
+    }
+
+    #[cfg(unix)]
+    fn chown(&self, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+        std::os::unix::fs::chown(self, uid, gid)
+    }
+
+    #[cfg(unix)]
+    fn lchown(&self, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+        std::os::unix::fs::lchown(self, uid, gid)
+    }
+
+    #[cfg(unix)]
+    fn chown_user(&self, user: impl AsRef<str>, group: impl AsRef<str>) -> io::Result<()> {
+        let user = user.as_ref();
+        let uid = crate::os::passwd::user_by_name(user.as_ref())?
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("no such user `{user}`"))
+            })?
+            .uid;
+        let gid = group_id(group.as_ref())?;
+        self.chown(Some(uid), Some(gid))
+    }
+
+    #[cfg(unix)]
+    fn chgrp(&self, group: impl AsRef<str>) -> io::Result<()> {
+        self.chown(None, Some(group_id(group.as_ref())?))
+    }
+
+    fn same_file_as(&self, other: impl AsRef<Path>) -> io::Result<bool> {
+        let (this, _) = file_identity(self)?;
+        let (other, _) = file_identity(other.as_ref())?;
+        Ok(this == other)
+    }
+
+    fn hard_link_count(&self) -> io::Result<u64> {
+        file_identity(self).map(|(_, links)| links)
+    }
+
+    fn copy_to(&self, to: impl AsRef<Path>) -> io::Result<u64> {
+        copy(self, to)
+    }
+
+    fn copy_to_fast(&self, to: impl AsRef<Path>) -> io::Result<u64> {
+        let to = to.as_ref();
+        let src = File::open(self)?;
+        let permissions = src.metadata()?.permissions();
+        // Like the `cp`, which refuses to truncate the source.
+        if let (Ok((this, _)), Ok((other, _))) = (file_identity(self), file_identity(to))
+            && this == other
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "`{}` and `{}` are the same file",
+                    self.display(),
+                    to.display()
+                ),
+            ));
+        }
+        let dst = File::create(to)?;
+        let copied = crate::fs::copy_range(&src, &dst, u64::MAX)?;
+        dst.set_permissions(permissions)?;
+        Ok(copied)
+    }
+
+    fn hard_link_to(&self, to: impl AsRef<Path>) -> io::Result<()> {
+        hard_link(self, to)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn reflink_to(&self, to: impl AsRef<Path>) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        let to = to.as_ref();
+        let src = File::open(self)?;
+        let dst = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(src.metadata()?.permissions().mode())
+            .open(to)?;
+        // SAFETY: Both descriptors are valid for the duration of the call.
+        if unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) } != 0 {
+            let err = io::Error::last_os_error();
+            drop(dst);
+            let _ = to.rm();
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    #[cfg(target_vendor = "apple")]
+    fn reflink_to(&self, to: impl AsRef<Path>) -> io::Result<()> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+        let src = CString::new(self.as_os_str().as_bytes())?;
+        let dst = CString::new(to.as_ref().as_os_str().as_bytes())?;
+        // SAFETY: Both paths are NUL terminated.
+        if unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple")))]
+    fn reflink_to(&self, _to: impl AsRef<Path>) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reflinks are not supported on this platform",
+        ))
+    }
+
+    fn reflink_or_copy_to(&self, to: impl AsRef<Path>) -> io::Result<()> {
+        let to = to.as_ref();
+        match self.reflink_to(to) {
+            Err(err) if is_reflink_unsupported(&err) => {}
+            result => return result,
+        }
+        let mut src = File::open(self)?;
+        let mut dst = OpenOptions::new().write(true).create_new(true).open(to)?;
+        let copied = io::copy(&mut src, &mut dst)
+            .and_then(|_| dst.set_permissions(src.metadata()?.permissions()));
+        if copied.is_err() {
+            drop(dst);
+            let _ = to.rm();
+        }
+        copied
+    }
+
+    fn link_tree(&self, to: impl AsRef<Path>) -> io::Result<()> {
+        link_tree_impl(self, to.as_ref())
+    }
+
+    fn copy_metadata_from(&self, src: impl AsRef<Path>, kinds: MetadataKinds) -> io::Result<()> {
+        let src = src.as_ref();
+        let metadata = src.metadata()?;
+        #[cfg(unix)]
+        if kinds.contains(MetadataKinds::OWNERSHIP) {
+            use std::os::unix::fs::MetadataExt;
+            self.chown(Some(metadata.uid()), Some(metadata.gid()))?;
+        }
+        #[cfg(all(unix, feature = "xattr"))]
+        if kinds.contains(MetadataKinds::XATTRS) {
+            for name in src.xattr_list()? {
+                // Attribute could be removed in the meantime.
+                if let Some(value) = src.xattr_get(&name)? {
+                    self.xattr_set(&name, value)?;
+                }
+            }
+        }
+        if kinds.contains(MetadataKinds::TIMESTAMPS) {
+            self.set_times(
+                Some(metadata.accessed()?),
+                Some(metadata.modified()?),
+                FollowSymlinks::Yes,
+            )?;
+        }
+        if kinds.contains(MetadataKinds::PERMISSIONS) {
+            self.set_permissions(metadata.permissions())?;
+        }
+        Ok(())
+    }
+
+    fn first_difference(&self, other: impl AsRef<Path>) -> io::Result<Option<u64>> {
+        const CHUNK: usize = 64 * 1024;
+        let (mut this, mut other) = (File::open(self)?, File::open(other)?);
+        let (mut this_buf, mut other_buf) = (vec![0u8; CHUNK], vec![0u8; CHUNK]);
+        let mut offset = 0u64;
+        loop {
+            let this_len = read_full(&mut this, &mut this_buf)?;
+            let other_len = read_full(&mut other, &mut other_buf)?;
+            let common = this_len.min(other_len);
+            if let Some(i) = (0..common).find(|&i| this_buf[i] != other_buf[i]) {
+                return Ok(Some(offset + i as u64));
+            }
+            if this_len != other_len {
+                return Ok(Some(offset + common as u64));
+            }
+            if this_len < CHUNK {
+                return Ok(None);
+            }
+            offset += CHUNK as u64;
+        }
+    }
+
+    fn sniff_type(&self) -> io::Result<SniffedType> {
+        use std::io::Read;
+        let mut prefix = Vec::with_capacity(crate::fs::sniff::SNIFF_LEN);
+        File::open(self)?
+            .take(crate::fs::sniff::SNIFF_LEN as u64)
+            .read_to_end(&mut prefix)?;
+        Ok(SniffedType::from_bytes(&prefix))
+    }
+
+    fn fs_stats(&self) -> io::Result<FsStats> {
+        crate::fs::stats::stats(self)
+    }
+
+    fn has_space_for(&self, bytes: u64) -> io::Result<bool> {
+        let mut existing = self;
+        while !existing.exists() {
+            match existing.parent() {
+                // Relative paths end with an empty parent.
+                Some(parent) if parent.as_os_str().is_empty() => existing = Path::new("."),
+                Some(parent) => existing = parent,
+                None => break,
+            }
+        }
+        Ok(existing.fs_stats()?.available >= bytes)
+    }
+
+    fn read(&self) -> io::Result<Vec<u8>> {
+        read(self)
+    }
+
+    fn read_to_string(&self) -> io::Result<String> {
+        read_to_string(self)
+    }
+
+    fn read_at_most(&self, limit: usize) -> io::Result<(Vec<u8>, bool)> {
+        use std::io::Read;
+        let file = File::open(self)?;
+        // Size is only a hint, file can grow, or be a special file reporting `0`.
+        let hint = file.metadata().map_or(0, |metadata| metadata.len());
+        let mut contents = Vec::with_capacity(usize::try_from(hint).unwrap_or(limit).min(limit));
+        // One more byte tells, if there is anything after the limit.
+        file.take((limit as u64).saturating_add(1))
+            .read_to_end(&mut contents)?;
+        let truncated = contents.len() > limit;
+        contents.truncate(limit);
+        Ok((contents, truncated))
+    }
+
+    fn read_to_string_at_most(&self, limit: usize) -> io::Result<(String, bool)> {
+        let (mut contents, truncated) = self.read_at_most(limit)?;
+        if truncated
+            && let Err(err) = std::str::from_utf8(&contents)
+            && err.error_len().is_none()
+        {
+            contents.truncate(err.valid_up_to());
+        }
+        let contents = String::from_utf8(contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok((contents, truncated))
+    }
+
+    fn rename_to(&self, to: impl AsRef<Path>) -> io::Result<()> {
+        rename(self, to)
+    }
+
+    fn rm(&self) -> io::Result<()> {
+        remove_file(self)
+    }
+
+    fn shred(&self, passes: usize) -> io::Result<()> {
+        use std::io::{Seek, Write};
+        if !self.symlink_metadata()?.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only regular files can be shredded",
+            ));
+        }
+        let mut file = OpenOptions::new().write(true).open(self)?;
+        let len = file.metadata()?.len();
+        let mut buf = vec![0; 64 * 1024];
+        for _ in 0..passes {
+            file.rewind()?;
+            let mut left = len;
+            while left > 0 {
+                let chunk = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+                crate::fs::temp::fill_random(&mut buf[..chunk]);
+                file.write_all(&buf[..chunk])?;
+                left -= chunk as u64;
+            }
+            // Every pass has to reach the disk, otherwise only the last one would.
+            file.sync_all()?;
+        }
+        file.set_len(0)?;
+        file.sync_all()?;
+        drop(file);
+        self.rm()
+    }
+
+    fn rmdir(&self) -> io::Result<()> {
+        remove_dir(self)
+    }
+
+    fn rmtree(&self) -> io::Result<()> {
+        remove_dir_all(self)
+    }
+
+    fn rmtree_force(&self) -> io::Result<()> {
+        if self.rmtree().is_ok() {
+            return Ok(());
+        }
+        rmtree_force_impl(self)
+    }
+
+    fn is_empty_dir(&self) -> bool {
+        std::fs::read_dir(self).is_ok_and(|mut entries| entries.next().is_none())
+    }
+
+    fn remove_matching(
+        &self,
+        glob: impl AsRef<str>,
+        opts: RemoveOptions,
+    ) -> io::Result<Vec<PathBuf>> {
+        let glob = crate::fs::glob::Glob::new(glob.as_ref())?;
+        let mut matched = Vec::new();
+        remove_matching_impl(self, "", 1, &glob, opts, &mut matched)?;
+        Ok(matched)
+    }
+
+    fn remove_empty_parents(&self, root: impl AsRef<Path>) -> io::Result<usize> {
+        let root = root.as_ref();
+        let mut removed = 0;
+        for ancestor in self.ancestors().skip(1) {
+            if ancestor == root || !ancestor.starts_with(root) || !ancestor.is_empty_dir() {
+                break;
+            }
+            match ancestor.rmdir() {
+                Ok(()) => removed += 1,
+                // Somebody raced us.
+                Err(err) if err.kind() == io::ErrorKind::DirectoryNotEmpty => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(removed)
+    }
+
+    fn set_permissions(&self, permissions: Permissions) -> io::Result<()> {
+        set_permissions(self, permissions)
+    }
+
+    fn set_times(
+        &self,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+        follow: FollowSymlinks,
+    ) -> io::Result<()> {
+        set_times_impl(self, accessed, modified, follow)
+    }
+
+    fn set_modified(&self, modified: SystemTime) -> io::Result<()> {
+        self.set_times(None, Some(modified), FollowSymlinks::Yes)
+    }
+
+    fn write(&self, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        write(self, contents)
+    }
+
+    #[cfg(unix)]
+    fn write_with_mode(&self, contents: impl AsRef<[u8]>, mode: u32) -> io::Result<()> {
+        use std::io::Write;
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(mode)
+            .open(self)?;
+        // Applies to the existing files, and undoes the `umask`.
+        file.set_permissions(Permissions::from_mode(mode))?;
+        file.write_all(contents.as_ref())
+    }
+
+    #[cfg(not(unix))]
+    fn write_with_mode(&self, contents: impl AsRef<[u8]>, mode: u32) -> io::Result<()> {
+        self.write(contents)?;
+        if mode & 0o200 == 0 {
+            let mut permissions = self.metadata()?.permissions();
+            permissions.set_readonly(true);
+            self.set_permissions(permissions)?;
+        }
+        Ok(())
+    }
+
+    fn modify<R>(&self, options: ModifyOptions, f: impl FnOnce(&mut String) -> R) -> io::Result<R> {
+        modify_impl(self, options, |contents| {
+            let mut contents = String::from_utf8(contents)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            let result = f(&mut contents);
+            Ok((contents.into_bytes(), result))
+        })
+    }
+
+    fn modify_bytes<R>(
+        &self,
+        options: ModifyOptions,
+        f: impl FnOnce(&mut Vec<u8>) -> R,
+    ) -> io::Result<R> {
+        modify_impl(self, options, |mut contents| {
+            let result = f(&mut contents);
+            Ok((contents, result))
+        })
+    }
+
+    #[cfg(feature = "mmap")]
+    unsafe fn mmap(&self) -> io::Result<crate::fs::Mmap> {
+        let file = File::open(self)?;
+        // SAFETY: Upheld by the caller.
+        unsafe { crate::fs::Mmap::map(&file) }
+    }
+
+    #[cfg(feature = "mmap")]
+    unsafe fn mmap_mut(&self) -> io::Result<crate::fs::MmapMut> {
+        let file = OpenOptions::new().read(true).write(true).open(self)?;
+        // SAFETY: Upheld by the caller.
+        unsafe { crate::fs::MmapMut::map(&file) }
+    }
+
+    #[cfg(all(
+        feature = "xattr",
+        any(target_os = "linux", target_os = "android", target_vendor = "apple")
+    ))]
+    fn xattr_get(&self, name: impl AsRef<OsStr>) -> io::Result<Option<Vec<u8>>> {
+        crate::fs::xattr::get(self, name.as_ref())
+    }
+
+    #[cfg(all(
+        feature = "xattr",
+        any(target_os = "linux", target_os = "android", target_vendor = "apple")
+    ))]
+    fn xattr_set(&self, name: impl AsRef<OsStr>, value: impl AsRef<[u8]>) -> io::Result<()> {
+        crate::fs::xattr::set(self, name.as_ref(), value.as_ref())
+    }
+
+    #[cfg(all(
+        feature = "xattr",
+        any(target_os = "linux", target_os = "android", target_vendor = "apple")
+    ))]
+    fn xattr_list(&self) -> io::Result<Vec<OsString>> {
+        crate::fs::xattr::list(self)
+    }
+
+    #[cfg(all(
+        feature = "xattr",
+        any(target_os = "linux", target_os = "android", target_vendor = "apple")
+    ))]
+    fn xattr_remove(&self, name: impl AsRef<OsStr>) -> io::Result<()> {
+        crate::fs::xattr::remove(self, name.as_ref())
+    }
+
+    // TODO: Implement (e.g. with the `extattr_*` family on BSDs, and alternate data streams on
+    // Windows).
+    #[cfg(all(
+        feature = "xattr",
+        not(any(target_os = "linux", target_os = "android", target_vendor = "apple"))
+    ))]
+    fn xattr_get(&self, _name: impl AsRef<OsStr>) -> io::Result<Option<Vec<u8>>> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    #[cfg(all(
+        feature = "xattr",
+        not(any(target_os = "linux", target_os = "android", target_vendor = "apple"))
+    ))]
+    fn xattr_set(&self, _name: impl AsRef<OsStr>, _value: impl AsRef<[u8]>) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    #[cfg(all(
+        feature = "xattr",
+        not(any(target_os = "linux", target_os = "android", target_vendor = "apple"))
+    ))]
+    fn xattr_list(&self) -> io::Result<Vec<OsString>> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    #[cfg(all(
+        feature = "xattr",
+        not(any(target_os = "linux", target_os = "android", target_vendor = "apple"))
+    ))]
+    fn xattr_remove(&self, _name: impl AsRef<OsStr>) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    #[cfg(feature = "watch")]
+    fn watch(&self) -> io::Result<crate::fs::watch::Watcher> {
+        crate::fs::watch::Watcher::new(self)
+    }
+
+    #[cfg(feature = "full-resolve")]
+    fn resolve(&self) -> io::Result<PathBuf> {
+        use soft_canonicalize::soft_canonicalize;
+        let resolved = soft_canonicalize(self)?;
+        #[cfg(windows)]
+        let resolved = crate::fs::long_path::strip_extended(resolved);
+        Ok(resolved)
+    }
+
+    fn expand_user(&self) -> io::Result<PathBuf> {
+        expand_tilde(self, crate::config_files::home)
+    }
+
+    fn expand_user_with(&self, home: impl AsRef<str>) -> io::Result<PathBuf> {
+        self.expand_user_with_fn(|| home)
+    }
+
+    fn expand_user_with_fn<F, H>(&self, home: F) -> io::Result<PathBuf>
+    where
+        H: AsRef<str>,
+        F: FnOnce() -> H,
+    {
+        expand_tilde(self, || Some(PathBuf::from(home().as_ref())))
+    }
+}
+
+/// Check, whether the [`PathExt::reflink_to`] failed, because reflinks can't be used for the
+/// pair of files at all (so copying is the way to go).
+fn is_reflink_unsupported(err: &io::Error) -> bool {
+    #[cfg(unix)]
+    if err.raw_os_error() == Some(libc::ENOTTY) {
+        return true;
+    }
+    matches!(
+        err.kind(),
+        io::ErrorKind::Unsupported | io::ErrorKind::CrossesDevices | io::ErrorKind::InvalidInput
+    )
+}
+
+/// Return unique identity of the file (pair of device and a file number), and its number of
+/// hard links.
+#[cfg(unix)]
+fn file_identity(path: &Path) -> io::Result<((u64, u64), u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = path.metadata()?;
+    Ok(((metadata.dev(), metadata.ino()), metadata.nlink()))
+}
+
+#[cfg(windows)]
+fn file_identity(path: &Path) -> io::Result<((u64, u64), u64)> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::{
+        Foundation::FALSE,
+        Storage::FileSystem::{
+            BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS, GetFileInformationByHandle,
+        },
+    };
+    // Directories can be opened only with the backup semantics, no access rights are needed.
+    let file = OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)?;
+    let mut info = std::mem::MaybeUninit::<BY_HANDLE_FILE_INFORMATION>::uninit();
+    // SAFETY: `file` is a valid handle, and `info` is valid for writes.
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle(), info.as_mut_ptr()) } == FALSE {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: A successful call initialized `info`.
+    let info = unsafe { info.assume_init() };
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    Ok((
+        (u64::from(info.dwVolumeSerialNumber), index),
+        u64::from(info.nNumberOfLinks),
+    ))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn file_identity(_path: &Path) -> io::Result<((u64, u64), u64)> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+fn remove_matching_impl(
+    dir: &Path,
+    relative: &str,
+    depth: usize,
+    glob: &crate::fs::glob::Glob,
+    opts: RemoveOptions,
+    matched: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let relative = format!("{relative}{}", entry.file_name().to_string_lossy());
+        let is_dir = entry.file_type()?.is_dir();
+        if glob.matches(&relative) {
+            if !opts.dry_run {
+                if is_dir { path.rmtree() } else { path.rm() }?;
+            }
+            matched.push(path);
+        } else if is_dir && opts.max_depth.is_none_or(|max_depth| depth < max_depth) {
+            remove_matching_impl(
+                &path,
+                &format!("{relative}/"),
+                depth + 1,
+                glob,
+                opts,
+                matched,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_times_impl(
+    path: &Path,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+    follow: FollowSymlinks,
+) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+    fn timespec(time: Option<SystemTime>) -> io::Result<libc::timespec> {
+        // SAFETY: `timespec` is a plain C struct, for which zeroes are valid.
+        let mut spec: libc::timespec = unsafe { std::mem::zeroed() };
+        let Some(time) = time else {
+            spec.tv_nsec = libc::UTIME_OMIT;
+            return Ok(spec);
+        };
+        // Seconds are floored, so nanoseconds stay non-negative.
+        let (secs, nanos) = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => (i128::from(since.as_secs()), since.subsec_nanos()),
+            Err(err) => match err.duration() {
+                before if before.subsec_nanos() == 0 => (-i128::from(before.as_secs()), 0),
+                before => (
+                    -i128::from(before.as_secs()) - 1,
+                    1_000_000_000 - before.subsec_nanos(),
+                ),
+            },
+        };
+        spec.tv_sec = libc::time_t::try_from(secs)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "time is out of range"))?;
+        spec.tv_nsec = nanos.into();
+        Ok(spec)
+    }
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let times = [timespec(accessed)?, timespec(modified)?];
+    let flags = match follow {
+        FollowSymlinks::No => libc::AT_SYMLINK_NOFOLLOW,
+        FollowSymlinks::Yes => 0,
+    };
+    // SAFETY: `path` is NUL terminated, and `times` has exactly two elements.
+    if unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), flags) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_times_impl(
+    path: &Path,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+    follow: FollowSymlinks,
+) -> io::Result<()> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT, FILE_WRITE_ATTRIBUTES,
+    };
+    // Directories can be opened only with the backup semantics.
+    let mut flags = FILE_FLAG_BACKUP_SEMANTICS;
+    if follow == FollowSymlinks::No {
+        flags |= FILE_FLAG_OPEN_REPARSE_POINT;
+    }
+    let file = OpenOptions::new()
+        .access_mode(FILE_WRITE_ATTRIBUTES)
+        .custom_flags(flags)
+        .open(path)?;
+    file.set_times(file_times(accessed, modified))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn set_times_impl(
+    path: &Path,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+    follow: FollowSymlinks,
+) -> io::Result<()> {
+    if follow == FollowSymlinks::No {
+        return Err(io::Error::from(io::ErrorKind::Unsupported));
+    }
+    File::open(path)?.set_times(file_times(accessed, modified))
+}
+
+#[cfg(not(unix))]
+fn file_times(accessed: Option<SystemTime>, modified: Option<SystemTime>) -> std::fs::FileTimes {
+    let mut times = std::fs::FileTimes::new();
+    if let Some(accessed) = accessed {
+        times = times.set_accessed(accessed);
+    }
+    if let Some(modified) = modified {
+        times = times.set_modified(modified);
     }
+    times
+}
 
-    fn hard_link_to(&self, to: impl AsRef<Path>) -> io::Result<()> {
-        hard_link(self, to)
+fn modify_impl<R>(
+    path: &Path,
+    options: ModifyOptions,
+    f: impl FnOnce(Vec<u8>) -> io::Result<(Vec<u8>, R)>,
+) -> io::Result<R> {
+    use std::hash::BuildHasher;
+    let _guard = if options.lock {
+        let lock = path.with_added_extension("lock");
+        Some(lock.lock(ShouldBlock::Yes)?)
+    } else {
+        None
+    };
+    let contents = path.read()?;
+    // Contents are moved into `f`, so they're compared by the length, and a (randomly keyed)
+    // hash.
+    let hasher = std::hash::RandomState::new();
+    let fingerprint = |contents: &[u8]| (contents.len(), hasher.hash_one(contents));
+    let original = fingerprint(&contents);
+    let (contents, result) = f(contents)?;
+    if fingerprint(&contents) != original {
+        if let Some(suffix) = &options.backup_suffix {
+            let mut backup = path.as_os_str().to_os_string();
+            backup.push(suffix);
+            path.copy_to(backup)?;
+        }
+        replace_atomically(path, &contents)?;
     }
+    Ok(result)
+}
 
-    fn read(&self) -> io::Result<Vec<u8>> {
-        read(self)
+/// Replace `path` with a file with the `contents` (and permissions, and the owner where allowed,
+/// of the current one), so readers (and the file after a crash) see either the old, or the new
+/// contents. Symlinks are followed, so the file they point to is replaced, not them.
+pub(crate) fn replace_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+    let path = &resolve_symlinks(path)?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = crate::fs::TempBuilder::new().parent(parent).tempfile()?;
+    file.write_all(contents)?;
+    match path.metadata() {
+        Ok(metadata) => copy_ownership(&file, &metadata)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
     }
+    file.sync_all()?;
+    file.persist(path)?;
+    // Make the rename itself durable (directories can't be opened like this on Windows).
+    #[cfg(unix)]
+    File::open(parent)?.sync_all()?;
+    Ok(())
+}
 
-    fn read_to_string(&self) -> io::Result<String> {
-        read_to_string(self)
+/// Follow symlinks at the `path` (also dangling ones), returning path of the final entry.
+fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+    /// Like the `MAXSYMLINKS` of Linux.
+    const MAX_LINKS: usize = 40;
+    let mut resolved = path.to_path_buf();
+    for _ in 0..MAX_LINKS {
+        match resolved.symlink_metadata() {
+            Ok(metadata) if metadata.is_symlink() => {
+                let target = std::fs::read_link(&resolved)?;
+                // An absolute `target` replaces the parent.
+                resolved = match resolved.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+            }
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => return Ok(resolved),
+        }
     }
+    Err(io::Error::other(format!(
+        "too many levels of symlinks at `{}`",
+        path.display()
+    )))
+}
 
-    fn rename_to(&self, to: impl AsRef<Path>) -> io::Result<()> {
-        rename(self, to)
+/// Give the `file` permissions, and the owner (where allowed) from the `metadata`.
+fn copy_ownership(file: &File, metadata: &std::fs::Metadata) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, fchown};
+        // Only the root can give files away, but members of the group can keep it.
+        if fchown(file, Some(metadata.uid()), Some(metadata.gid())).is_err() {
+            let _ = fchown(file, None, Some(metadata.gid()));
+        }
     }
+    // After the `fchown`, which may clear the setuid and setgid bits.
+    file.set_permissions(metadata.permissions())
+}
 
-    fn rm(&self) -> io::Result<()> {
-        remove_file(self)
+/// Read into `buf` until it's full, or the end of file.
+fn read_full(reader: &mut impl std::io::Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
     }
+    Ok(filled)
+}
 
-    fn rmdir(&self) -> io::Result<()> {
-        remove_dir(self)
+/// Create a symlink at `link` pointing to the `target` (relative to the `link`).
+///
+/// On Windows, the kind of symlink depends on what `target` currently is.
+#[cfg(unix)]
+pub(crate) fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+pub(crate) fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    let resolved = link
+        .parent()
+        .map_or(target.to_path_buf(), |p| p.join(target));
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
     }
+}
 
-    fn rmtree(&self) -> io::Result<()> {
-        remove_dir_all(self)
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+fn link_tree_impl(from: &Path, to: &Path) -> io::Result<()> {
+    create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let (from, to) = (entry.path(), to.join(entry.file_name()));
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            link_tree_impl(&from, &to)?;
+        } else if file_type.is_symlink() {
+            symlink(&std::fs::read_link(&from)?, &to)?;
+        } else {
+            match from.hard_link_to(&to) {
+                Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+                    from.copy_to(&to)?;
+                }
+                result => result?,
+            }
+        }
     }
+    // Last, so read-only directories don't prevent filling them.
+    to.set_permissions(from.metadata()?.permissions())
+}
 
-    fn set_permissions(&self, permissions: Permissions) -> io::Result<()> {
-        set_permissions(self, permissions)
+fn rmtree_force_impl(path: &Path) -> io::Result<()> {
+    let metadata = path.symlink_metadata()?;
+    if !metadata.is_dir() {
+        return force_remove_entry(path, &metadata);
     }
+    make_removable(path, &metadata)?;
+    for entry in std::fs::read_dir(path)? {
+        rmtree_force_impl(&entry?.path())?;
+    }
+    with_retries(|| path.rmdir())
+}
 
-    fn write(&self, contents: impl AsRef<[u8]>) -> io::Result<()> {
-        write(self, contents)
+/// Remove a non-directory entry.
+fn force_remove_entry(path: &Path, metadata: &std::fs::Metadata) -> io::Result<()> {
+    make_removable(path, metadata)?;
+    // On Windows, symlinks to directories have to be removed as directories.
+    #[cfg(windows)]
+    if metadata.file_type().is_symlink() && path.rmdir().is_ok() {
+        return Ok(());
     }
+    with_retries(|| path.rm())
+}
 
-    #[cfg(feature = "full-resolve")]
-    fn resolve(&self) -> io::Result<PathBuf> {
-        use soft_canonicalize::soft_canonicalize;
-        soft_canonicalize(self)
+#[cfg(unix)]
+fn make_removable(path: &Path, metadata: &std::fs::Metadata) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    // Removing entries requires writing and searching the directory.
+    const NEEDED: u32 = 0o700;
+    let mode = metadata.permissions().mode();
+    if metadata.is_dir() && mode & NEEDED != NEEDED {
+        path.set_permissions(Permissions::from_mode(mode | NEEDED))?;
     }
+    Ok(())
+}
 
-    #[cfg(feature = "expand-user")]
-    fn expand_user(&self) -> io::Result<PathBuf> {
-        use shellexpand::tilde;
-        let Some(as_str) = self.to_str() else {
-            return Err(io::Error::other("path is not an UTF-8 string"));
-        };
-        Ok(PathBuf::from(tilde(as_str).into_owned()))
+#[allow(clippy::permissions_set_readonly_false)] // That's exactly the intent here.
+#[cfg(not(unix))]
+fn make_removable(path: &Path, metadata: &std::fs::Metadata) -> io::Result<()> {
+    let mut permissions = metadata.permissions();
+    if permissions.readonly() {
+        permissions.set_readonly(false);
+        path.set_permissions(permissions)?;
     }
+    Ok(())
+}
 
-    #[cfg(feature = "expand-user")]
-    fn expand_user_with(&self, home: impl AsRef<str>) -> io::Result<PathBuf> {
-        self.expand_user_with_fn(|| home)
+/// Retry `f` on Windows, when it fails because of a (likely transient) sharing violation.
+#[cfg(windows)]
+fn with_retries(mut f: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    use windows_sys::Win32::Foundation::{
+        ERROR_ACCESS_DENIED, ERROR_DIR_NOT_EMPTY, ERROR_SHARING_VIOLATION,
+    };
+    const ATTEMPTS: u32 = 6;
+    let mut delay = std::time::Duration::from_millis(10);
+    for _ in 1..ATTEMPTS {
+        match f() {
+            Err(err)
+                if [
+                    ERROR_SHARING_VIOLATION,
+                    ERROR_ACCESS_DENIED,
+                    ERROR_DIR_NOT_EMPTY,
+                ]
+                .into_iter()
+                .any(|code| err.raw_os_error() == Some(code as i32)) =>
+            {
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
     }
+    f()
+}
 
-    #[cfg(feature = "expand-user")]
-    fn expand_user_with_fn<F, H>(&self, home: F) -> io::Result<PathBuf>
-    where
-        H: AsRef<str>,
-        F: FnOnce() -> H,
-    {
-        use shellexpand::tilde_with_context;
-        let Some(as_str) = self.to_str() else {
-            return Err(io::Error::other("path is not an UTF-8 string"));
-        };
-        Ok(PathBuf::from(
-            tilde_with_context(as_str, || Some(home())).into_owned(),
-        ))
+#[cfg(not(windows))]
+fn with_retries(mut f: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    f()
+}
+
+/// Expand the leading `~` (into `home()`) or `~user` component of `path`.
+///
+/// Unresolvable components (unknown home or user) are left untouched.
+fn expand_tilde(path: &Path, home: impl FnOnce() -> Option<PathBuf>) -> io::Result<PathBuf> {
+    use std::path::Component;
+    let mut components = path.components();
+    let Some(Component::Normal(first)) = components.next() else {
+        return Ok(path.to_path_buf());
+    };
+    let Some(user) = first.as_encoded_bytes().strip_prefix(b"~") else {
+        return Ok(path.to_path_buf());
+    };
+    let expanded = if user.is_empty() {
+        home()
+    } else {
+        user_home(user)?
+    };
+    let Some(mut expanded) = expanded else {
+        return Ok(path.to_path_buf());
+    };
+    let rest = components.as_path();
+    if !rest.as_os_str().is_empty() {
+        expanded.push(rest);
     }
+    Ok(expanded)
+}
+
+#[cfg(unix)]
+fn user_home(user: &[u8]) -> io::Result<Option<PathBuf>> {
+    use std::os::unix::ffi::OsStrExt;
+    let user = std::ffi::OsStr::from_bytes(user);
+    Ok(crate::os::passwd::user_by_name(user)?.map(|entry| entry.home))
+}
+
+// TODO: Implement (e.g. through the profile list in the registry on Windows).
+#[cfg(not(unix))]
+fn user_home(_user: &[u8]) -> io::Result<Option<PathBuf>> {
+    Ok(None)
+}
+
+#[cfg(unix)]
+fn group_id(group: &str) -> io::Result<u32> {
+    crate::os::passwd::group_by_name(group.as_ref())?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no such group `{group}`")))
 }
 
 #[cfg(test)]
@@ -440,6 +2578,15 @@ mod tests {
         assert_ok!(new_file.touch());
     }
 
+    #[test]
+    fn create_new_fails_on_existing() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("a").join("b");
+        assert_ok!(path.create_new());
+        let err = assert_err!(path.create_new());
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
     #[test]
     fn multiple_touch() {
         let tmp = tempdir().expect("needed for tests");
@@ -454,6 +2601,220 @@ mod tests {
         assert_eq!(content, "test");
     }
 
+    #[test]
+    fn next_available_counts_up() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("dir").join("report.tar.gz");
+        let names: Vec<_> = (0..3)
+            .map(|_| assert_ok!(path.next_available()).0)
+            .map(|path| path.file_name().unwrap().to_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(
+            names,
+            ["report.tar.gz", "report (1).tar.gz", "report (2).tar.gz"]
+        );
+    }
+
+    #[test]
+    fn next_available_keeps_tarball_extensions() {
+        let tmp = tempdir().expect("needed for tests");
+        for (name, next) in [
+            ("backup.TAR.zst", "backup (1).TAR.zst"),
+            ("notes.v2.txt", "notes.v2 (1).txt"),
+            ("archive.tar", "archive (1).tar"),
+            (".tar.gz", ".tar (1).gz"),
+        ] {
+            let path = tmp.path().join(name);
+            assert_ok!(path.touch());
+            let (path, _) = assert_ok!(path.next_available());
+            assert_eq!(path.file_name().unwrap(), next);
+        }
+    }
+
+    #[test]
+    fn next_available_custom_pattern() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join(".bashrc");
+        assert_ok!(path.touch());
+        let (next, _) = assert_ok!(path.next_available_with("{stem}-{n}{ext}.{bak}"));
+        assert_eq!(next.file_name().unwrap(), ".bashrc-1.{bak}");
+        let err = assert_err!(path.next_available_with("{stem}"));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn read_at_most_reports_truncation() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.write("hello"));
+        assert_eq!(assert_ok!(path.read_at_most(5)), (b"hello".to_vec(), false));
+        assert_eq!(
+            assert_ok!(path.read_at_most(100)),
+            (b"hello".to_vec(), false)
+        );
+        assert_eq!(assert_ok!(path.read_at_most(2)), (b"he".to_vec(), true));
+        assert_eq!(assert_ok!(path.read_at_most(0)), (Vec::new(), true));
+    }
+
+    #[test]
+    fn read_to_string_at_most_drops_split_character() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.write("aż"));
+        assert_eq!(
+            assert_ok!(path.read_to_string_at_most(2)),
+            ("a".to_owned(), true)
+        );
+        assert_eq!(
+            assert_ok!(path.read_to_string_at_most(3)),
+            ("aż".to_owned(), false)
+        );
+        assert_ok!(path.write(b"\xff\xfe"));
+        let err = assert_err!(path.read_to_string_at_most(10));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn write_with_mode_sets_exact_permissions() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("secret");
+        assert_ok!(path.write("old"));
+        assert_ok!(path.write_with_mode("secret", 0o600));
+        assert_eq!(assert_ok!(path.read_to_string()), "secret");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = assert_ok!(path.metadata()).permissions().mode();
+            assert_eq!(mode & 0o7777, 0o600);
+            let fresh = tmp.path().join("fresh");
+            assert_ok!(fresh.write_with_mode("x", 0o640));
+            let mode = assert_ok!(fresh.metadata()).permissions().mode();
+            assert_eq!(mode & 0o7777, 0o640);
+        }
+        #[cfg(not(unix))]
+        {
+            assert_ok!(path.write_with_mode("public", 0o444));
+            assert!(assert_ok!(path.metadata()).permissions().readonly());
+        }
+    }
+
+    #[test]
+    fn rmtree_force_handles_read_only_entries() {
+        let tmp = tempdir().expect("needed for tests");
+        let root = tmp.path().join("root");
+        let file = root.join("a").join("b").join("x");
+        assert_ok!(file.touch());
+        let mut permissions = assert_ok!(file.metadata()).permissions();
+        permissions.set_readonly(true);
+        assert_ok!(file.set_permissions(permissions));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_ok!(
+                root.join("a")
+                    .set_permissions(Permissions::from_mode(0o500))
+            );
+        }
+        assert_ok!(root.rmtree_force());
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn shred_removes_file() {
+        let tmp = tempdir().expect("needed for tests");
+        let file = tmp.path().join("secret");
+        assert_ok!(file.write(vec![b'x'; 100_000]));
+        assert_ok!(file.shred(2));
+        assert!(!file.exists());
+        let err = assert_err!(tmp.path().shred(1));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn empty_dirs() {
+        let tmp = tempdir().expect("needed for tests");
+        assert!(tmp.path().is_empty_dir());
+        let file = tmp.path().join("x");
+        assert_ok!(file.touch());
+        assert!(!tmp.path().is_empty_dir());
+        assert!(!file.is_empty_dir());
+        assert!(!tmp.path().join("missing").is_empty_dir());
+    }
+
+    #[test]
+    fn remove_matching_dry_run_and_removal() {
+        let tmp = tempdir().expect("needed for tests");
+        let root = tmp.path();
+        let spec = [
+            "a.tmp",
+            "keep.txt",
+            "sub/b.tmp",
+            "sub/deeper/c.tmp",
+            "cache/x",
+        ]
+        .into_iter()
+        .fold(crate::fs::TreeSpec::new(), |spec, path| spec.file(path, ""));
+        assert_ok!(spec.create_in(root));
+        let planned = assert_ok!(root.remove_matching("*.tmp", RemoveOptions::new().dry_run(true)));
+        assert_eq!(
+            planned,
+            [
+                root.join("a.tmp"),
+                root.join("sub/b.tmp"),
+                root.join("sub/deeper/c.tmp")
+            ]
+        );
+        assert!(root.join("a.tmp").exists());
+
+        let removed = assert_ok!(root.remove_matching("*.tmp", RemoveOptions::new().max_depth(2)));
+        assert_eq!(removed, [root.join("a.tmp"), root.join("sub/b.tmp")]);
+        assert!(root.join("sub/deeper/c.tmp").exists());
+
+        // Directories are removed as a whole.
+        let removed = assert_ok!(root.remove_matching("cache", RemoveOptions::new()));
+        assert_eq!(removed, [root.join("cache")]);
+        assert!(!root.join("cache").exists());
+        assert!(root.join("keep.txt").exists());
+        assert_err!(root.remove_matching("[", RemoveOptions::new()));
+    }
+
+    #[test]
+    fn remove_empty_parents_stops_at_root_and_non_empty() {
+        let tmp = tempdir().expect("needed for tests");
+        let root = tmp.path().join("root");
+        let file = root.join("a").join("b").join("c").join("x");
+        assert_ok!(file.touch());
+        assert_ok!(root.join("a").join("keep").touch());
+        assert_ok!(file.rm());
+        assert_eq!(assert_ok!(file.remove_empty_parents(&root)), 2);
+        assert!(!root.join("a").join("b").exists());
+        assert!(root.join("a").exists());
+
+        let file = root.join("y").join("x");
+        assert_ok!(file.touch());
+        assert_ok!(file.rm());
+        assert_eq!(assert_ok!(file.remove_empty_parents(&root)), 1);
+        assert_ok!(root.join("a").join("keep").rm());
+        assert_eq!(
+            assert_ok!(root.join("a").join("keep").remove_empty_parents(&root)),
+            1
+        );
+        assert!(root.is_empty_dir());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn mkfifo_creates_pipe_and_parents() {
+        let tmp = tempdir().expect("needed for tests");
+        let fifo = tmp.path().join("a").join("fifo");
+        assert_ok!(fifo.mkfifo(0o600));
+        assert!(fifo.is_fifo());
+        let err = assert_err!(fifo.mkfifo(0o600));
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(!tmp.path().is_fifo());
+        assert!(!tmp.path().join("missing").is_fifo());
+    }
+
     #[test]
     fn create_dirs() {
         {
@@ -508,6 +2869,444 @@ mod tests {
         assert!(new_file.exists());
     }
 
+    #[test]
+    fn expand_tilde_leading_component_only() {
+        let home = || Some(PathBuf::from("/home/user"));
+        let expand = |path: &str| assert_ok!(expand_tilde(Path::new(path), home));
+        assert_eq!(expand("~"), Path::new("/home/user"));
+        assert_eq!(expand("~/a/b"), Path::new("/home/user/a/b"));
+        assert_eq!(expand("a/~"), Path::new("a/~"));
+        assert_eq!(expand("/~/a"), Path::new("/~/a"));
+        assert_eq!(
+            expand("~rustvil-no-such-user/a"),
+            Path::new("~rustvil-no-such-user/a")
+        );
+        assert_eq!(
+            assert_ok!(expand_tilde(Path::new("~/a"), || None)),
+            Path::new("~/a")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn expand_user_with_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(OsStr::from_bytes(b"~/\xff/a"));
+        let expanded = assert_ok!(path.expand_user_with("/h"));
+        assert_eq!(expanded.as_os_str().as_bytes(), b"/h/\xff/a");
+        let expanded = assert_ok!(path.expand_user_with_fn(|| String::from("/x")));
+        assert_eq!(expanded.as_os_str().as_bytes(), b"/x/\xff/a");
+        let untouched = Path::new(OsStr::from_bytes(b"\xff/~"));
+        assert_eq!(
+            assert_ok!(untouched.expand_user_with_fn(|| -> String { unreachable!() })),
+            untouched
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn expand_tilde_user_and_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let root = assert_ok!(crate::os::passwd::user_by_name(OsStr::new("root"))).unwrap();
+        assert_eq!(
+            assert_ok!(Path::new("~root/a").expand_user()),
+            root.home.join("a")
+        );
+        let non_utf8 = Path::new(OsStr::from_bytes(b"~/\xff"));
+        let expanded = assert_ok!(expand_tilde(non_utf8, || Some(PathBuf::from("/h"))));
+        assert_eq!(expanded.as_os_str().as_bytes(), b"/h/\xff");
+    }
+
+    #[test]
+    fn relative_paths() {
+        let relative = |path: &str, base: &str| {
+            Path::new(path)
+                .relative_to(base)
+                .map(|path| path.to_str().unwrap().replace('\\', "/"))
+        };
+        assert_eq!(relative("/a/b/c", "/a").unwrap(), "b/c");
+        assert_eq!(relative("/a", "/a/b/c").unwrap(), "../..");
+        assert_eq!(relative("/a/b/c", "/a/d/e").unwrap(), "../../b/c");
+        assert_eq!(relative("/a/b", "/a/b").unwrap(), ".");
+        assert_eq!(relative("a/./b", "a/c/").unwrap(), "../b");
+        assert_eq!(relative("../x", "..").unwrap(), "x");
+        assert_eq!(
+            relative("x", "../y").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            relative("/a", "a").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            relative("a", "/a").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn join_secure_stays_inside() {
+        let base = Path::new("base");
+        assert_eq!(assert_ok!(base.join_secure("a/./b")), Path::new("base/a/b"));
+        assert_eq!(assert_ok!(base.join_secure("a/..")), Path::new("base"));
+        assert_eq!(assert_ok!(base.join_secure("")), Path::new("base"));
+        assert_err!(base.join_secure(".."));
+        assert_err!(base.join_secure("a/../../b"));
+        assert_err!(base.join_secure(if cfg!(windows) { r"C:\x" } else { "/x" }));
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn relative_paths_across_drives() {
+        assert_err!(Path::new(r"C:\a").relative_to(r"D:\a"));
+        assert_eq!(
+            assert_ok!(Path::new(r"C:\a\b").relative_to(r"c:\a")),
+            Path::new("b")
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn dotfiles_are_hidden() {
+        assert!(Path::new(".config").is_hidden());
+        assert!(Path::new("/home/user/.bashrc").is_hidden());
+        assert!(Path::new("a/.git").is_hidden_with(HiddenDotfiles::No));
+        assert!(!Path::new("/home/.user/file").is_hidden());
+        assert!(!Path::new("file.txt").is_hidden());
+        assert!(!Path::new(".").is_hidden());
+        assert!(!Path::new("..").is_hidden());
+    }
+
+    #[test]
+    fn copy_metadata_permissions_and_times() {
+        let tmp = tempdir().expect("needed for tests");
+        let (src, dst) = (tmp.path().join("src"), tmp.path().join("dst"));
+        assert_ok!(src.write("x"));
+        assert_ok!(dst.write("y"));
+        let mtime = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        assert_ok!(
+            assert_ok!(OpenOptions::new().write(true).open(&src))
+                .set_times(std::fs::FileTimes::new().set_modified(mtime))
+        );
+        let mut permissions = assert_ok!(src.metadata()).permissions();
+        permissions.set_readonly(true);
+        assert_ok!(src.set_permissions(permissions));
+
+        assert_ok!(dst.copy_metadata_from(&src, MetadataKinds::default()));
+        let metadata = assert_ok!(dst.metadata());
+        assert_eq!(assert_ok!(metadata.modified()), mtime);
+        assert!(metadata.permissions().readonly());
+        // Contents are untouched.
+        assert_eq!(assert_ok!(dst.read_to_string()), "y");
+    }
+
+    #[test]
+    fn set_times_leaves_omitted_unchanged() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.touch());
+        let accessed = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000);
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_millis(1_000_000_500);
+        assert_ok!(path.set_times(Some(accessed), Some(modified), FollowSymlinks::Yes));
+        let metadata = assert_ok!(path.metadata());
+        assert_eq!(assert_ok!(metadata.modified()), modified);
+        assert_eq!(assert_ok!(metadata.accessed()), accessed);
+
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(3_000_000);
+        assert_ok!(path.set_modified(modified));
+        let metadata = assert_ok!(path.metadata());
+        assert_eq!(assert_ok!(metadata.modified()), modified);
+        assert_eq!(assert_ok!(metadata.accessed()), accessed);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn set_times_without_following_symlinks() {
+        let tmp = tempdir().expect("needed for tests");
+        let (target, link) = (tmp.path().join("target"), tmp.path().join("link"));
+        assert_ok!(target.touch());
+        assert_ok!(std::os::unix::fs::symlink(&target, &link));
+        let before = assert_ok!(assert_ok!(target.metadata()).modified());
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        assert_ok!(link.set_times(None, Some(modified), FollowSymlinks::No));
+        assert_eq!(
+            assert_ok!(assert_ok!(link.symlink_metadata()).modified()),
+            modified
+        );
+        assert_eq!(assert_ok!(assert_ok!(target.metadata()).modified()), before);
+    }
+
+    #[test]
+    fn copy_metadata_kinds_are_selective() {
+        let tmp = tempdir().expect("needed for tests");
+        let (src, dst) = (tmp.path().join("src"), tmp.path().join("dst"));
+        assert_ok!(src.write("x"));
+        assert_ok!(dst.write("y"));
+        let mut permissions = assert_ok!(src.metadata()).permissions();
+        permissions.set_readonly(true);
+        assert_ok!(src.set_permissions(permissions));
+        assert_ok!(dst.copy_metadata_from(&src, MetadataKinds::TIMESTAMPS));
+        assert!(!assert_ok!(dst.metadata()).permissions().readonly());
+        #[cfg(unix)]
+        assert_ok!(dst.copy_metadata_from(&src, MetadataKinds::OWNERSHIP));
+        assert!(MetadataKinds::default().contains(MetadataKinds::PERMISSIONS));
+        assert!(!MetadataKinds::NONE.contains(MetadataKinds::TIMESTAMPS));
+    }
+
+    #[test]
+    fn link_tree_links_files() {
+        let tmp = tempdir().expect("needed for tests");
+        let (from, to) = (tmp.path().join("from"), tmp.path().join("to"));
+        let spec = crate::fs::TreeSpec::new()
+            .file("a.txt", "a")
+            .file("sub/b.txt", "b")
+            .dir("empty");
+        #[cfg(unix)]
+        let spec = spec.symlink("link", "sub/b.txt");
+        assert_ok!(spec.create_in(&from));
+        assert_ok!(from.link_tree(&to));
+        spec.assert_matches(&to);
+        assert!(assert_ok!(
+            from.join("sub/b.txt").same_file_as(to.join("sub/b.txt"))
+        ));
+        assert_eq!(assert_ok!(to.join("a.txt").hard_link_count()), 2);
+        let err = assert_err!(from.link_tree(&to));
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn first_difference_offsets() {
+        let tmp = tempdir().expect("needed for tests");
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        let mut contents = vec![7u8; 200_000];
+        assert_ok!(a.write(&contents));
+        assert_ok!(b.write(&contents));
+        assert_eq!(assert_ok!(a.first_difference(&b)), None);
+        contents[150_000] = 8;
+        assert_ok!(b.write(&contents));
+        assert_eq!(assert_ok!(a.first_difference(&b)), Some(150_000));
+        assert_ok!(b.write(&contents[..100]));
+        assert_eq!(assert_ok!(a.first_difference(&b)), Some(100));
+        assert_eq!(assert_ok!(b.first_difference(&a)), Some(100));
+        assert_err!(a.first_difference(tmp.path().join("missing")));
+    }
+
+    #[test]
+    fn copy_to_fast_copies_contents_and_permissions() {
+        let tmp = tempdir().expect("needed for tests");
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        assert_ok!(a.write_with_mode("contents", 0o640));
+        assert_ok!(b.write("longer, previous contents"));
+        assert_eq!(assert_ok!(a.copy_to_fast(&b)), 8);
+        assert_eq!(assert_ok!(b.read_to_string()), "contents");
+        assert_eq!(
+            assert_ok!(b.metadata()).permissions(),
+            assert_ok!(a.metadata()).permissions()
+        );
+
+        #[cfg_attr(not(unix), allow(unused_mut))]
+        let mut same = vec![a.clone(), tmp.path().join("link")];
+        assert_ok!(a.hard_link_to(&same[1]));
+        #[cfg(unix)]
+        {
+            let symlink = tmp.path().join("symlink");
+            assert_ok!(std::os::unix::fs::symlink(&a, &symlink));
+            same.push(symlink);
+        }
+        for same in same {
+            let err = assert_err!(a.copy_to_fast(&same));
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert_eq!(assert_ok!(a.read_to_string()), "contents");
+    }
+
+    #[test]
+    fn modify_replaces_contents_with_backup() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("config");
+        assert_ok!(path.write("a = 1\n"));
+        let len = assert_ok!(
+            path.modify(ModifyOptions::new().backup(".bak"), |contents| {
+                contents.push_str("b = 2\n");
+                contents.len()
+            })
+        );
+        assert_eq!(len, 12);
+        assert_eq!(assert_ok!(path.read_to_string()), "a = 1\nb = 2\n");
+        let backup = tmp.path().join("config.bak");
+        assert_eq!(assert_ok!(backup.read_to_string()), "a = 1\n");
+        assert!(tmp.path().join("config.lock").exists());
+
+        // Unchanged contents aren't written, nor backed up.
+        assert_ok!(backup.rm());
+        assert_ok!(path.modify_bytes(ModifyOptions::new().backup(".bak"), |_| ()));
+        assert!(!backup.exists());
+        // Changes keeping the length are still noticed.
+        assert_ok!(path.modify_bytes(ModifyOptions::new(), |contents| contents[0] = b'c'));
+        assert_eq!(assert_ok!(path.read_to_string()), "c = 1\nb = 2\n");
+
+        assert_ok!(path.write(b"\xff"));
+        let err = assert_err!(path.modify(ModifyOptions::new().lock(false), |_| ()));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_err!(
+            tmp.path()
+                .join("missing")
+                .modify(ModifyOptions::new(), |_| ())
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn modify_replaces_target_of_symlink() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempdir().expect("needed for tests");
+        let target = tmp.path().join("dotfiles/config");
+        assert_ok!(target.parent().unwrap().mkdir(MkdirOptions::WithoutParents));
+        assert_ok!(target.write_with_mode("a", 0o640));
+        let link = tmp.path().join("config");
+        assert_ok!(std::os::unix::fs::symlink("dotfiles/config", &link));
+        assert_ok!(link.modify(ModifyOptions::new().lock(false), |contents| {
+            contents.push('b')
+        }));
+        assert!(assert_ok!(link.symlink_metadata()).is_symlink());
+        assert_eq!(assert_ok!(target.read_to_string()), "ab");
+        let mode = assert_ok!(target.metadata()).permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[test]
+    fn same_file_and_link_count() {
+        let tmp = tempdir().expect("needed for tests");
+        let a = tmp.path().join("a");
+        let b = tmp.path().join("b");
+        let c = tmp.path().join("c");
+        assert_ok!(a.write("test"));
+        assert_ok!(c.write("test"));
+        assert_eq!(assert_ok!(a.hard_link_count()), 1);
+        assert_ok!(a.hard_link_to(&b));
+        assert_eq!(assert_ok!(a.hard_link_count()), 2);
+        assert!(assert_ok!(a.same_file_as(&b)));
+        assert!(assert_ok!(a.same_file_as(tmp.path().join(".").join("a"))));
+        assert!(!assert_ok!(a.same_file_as(&c)));
+        assert_err!(a.same_file_as(tmp.path().join("missing")));
+    }
+
+    #[test]
+    fn reflink_leaves_nothing_behind_or_clones() {
+        let tmp = tempdir().expect("needed for tests");
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+        assert_ok!(src.write("test"));
+        match src.reflink_to(&dst) {
+            Ok(()) => assert_eq!(assert_ok!(dst.read_to_string()), "test"),
+            Err(_) => assert!(!dst.exists()),
+        }
+    }
+
+    #[test]
+    fn reflink_refuses_existing_destination() {
+        let tmp = tempdir().expect("needed for tests");
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+        assert_ok!(src.write("test"));
+        assert_ok!(dst.write("old"));
+        assert_err!(src.reflink_to(&dst));
+        assert_eq!(assert_ok!(dst.read_to_string()), "old");
+    }
+
+    #[test]
+    fn reflink_or_copy_always_copies() {
+        let tmp = tempdir().expect("needed for tests");
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+        assert_ok!(src.write("test"));
+        assert_ok!(src.reflink_or_copy_to(&dst));
+        assert_eq!(assert_ok!(dst.read_to_string()), "test");
+    }
+
+    #[test]
+    fn reflink_or_copy_refuses_existing_destination() {
+        let tmp = tempdir().expect("needed for tests");
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+        assert_ok!(src.write("test"));
+        assert_ok!(dst.write("old"));
+        let err = assert_err!(src.reflink_or_copy_to(&dst));
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(assert_ok!(dst.read_to_string()), "old");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symbolic_modes() {
+        assert_eq!(apply_symbolic_mode("u+x", 0o644, false), Some(0o744));
+        assert_eq!(apply_symbolic_mode("u+x,go-w", 0o666, false), Some(0o744));
+        assert_eq!(apply_symbolic_mode("a=r", 0o777, false), Some(0o444));
+        assert_eq!(apply_symbolic_mode("=rw", 0o777, false), Some(0o666));
+        assert_eq!(apply_symbolic_mode("+x", 0o644, false), Some(0o755));
+        assert_eq!(apply_symbolic_mode("go=u", 0o740, false), Some(0o777));
+        assert_eq!(apply_symbolic_mode("g=u-w", 0o700, false), Some(0o750));
+        assert_eq!(apply_symbolic_mode("a+X", 0o644, false), Some(0o644));
+        assert_eq!(apply_symbolic_mode("a+X", 0o644, true), Some(0o755));
+        assert_eq!(apply_symbolic_mode("a+X", 0o744, false), Some(0o755));
+        assert_eq!(apply_symbolic_mode("u+s,g+s", 0o755, false), Some(0o6755));
+        assert_eq!(apply_symbolic_mode("+t", 0o777, true), Some(0o1777));
+        assert_eq!(apply_symbolic_mode("u+x+w", 0o400, false), Some(0o700));
+        assert_eq!(apply_symbolic_mode("750", 0o000, false), Some(0o750));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn malformed_symbolic_modes() {
+        assert_eq!(apply_symbolic_mode("", 0o644, false), None);
+        assert_eq!(apply_symbolic_mode("u", 0o644, false), None);
+        assert_eq!(apply_symbolic_mode("u+q", 0o644, false), None);
+        assert_eq!(apply_symbolic_mode("u+x,", 0o644, false), None);
+        assert_eq!(apply_symbolic_mode("99999", 0o644, false), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn chmod_applies_modes() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.touch());
+        let mode = || assert_ok!(path.metadata()).permissions().mode() & 0o7777;
+        assert_ok!(path.chmod(0o600));
+        assert_eq!(mode(), 0o600);
+        assert_ok!(path.chmod("u+x,g+r"));
+        assert_eq!(mode(), 0o740);
+        let err = assert_err!(path.chmod("bogus"));
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn chown_to_self_is_noop() {
+        use std::os::unix::fs::MetadataExt;
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.touch());
+        let metadata = assert_ok!(path.metadata());
+        assert_ok!(path.chown(Some(metadata.uid()), Some(metadata.gid())));
+        assert_ok!(path.lchown(None, None));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn chown_user_unknown_names() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.touch());
+        let err = assert_err!(path.chown_user("rustvil-no-such-user", "rustvil-no-such-group"));
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = assert_err!(path.chgrp("rustvil-no-such-group"));
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
     #[test]
     fn lock_blocking_should_work() {
         use tempfile::NamedTempFile;
@@ -0,0 +1,179 @@
+use std::{
+    io::{self, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::fs::{FileLockGuard, PathExt, ShouldBlock};
+
+/// Errors encountered when acquiring a [`PidFile`].
+#[derive(Debug, Error)]
+pub enum PidFileError {
+    /// This variant indicates, that another live process (with PID `AlreadyRunning.0`) owns the
+    /// pid file.
+    #[error("pid file is owned by a running process with PID {0}")]
+    AlreadyRunning(u32),
+
+    /// This variant wraps any I/O error encountered along the way.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// A RAII pid file: an exclusively locked file containing the PID of the current process.
+///
+/// The file is removed (and unlocked) on a drop.
+///
+/// ```rust,no_run
+/// # use rustvil::fs::PidFile;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let pid_file = PidFile::acquire("/run/user/1000/daemon.pid")?;
+/// // Daemon's main loop...
+/// drop(pid_file);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+    // `None` only during the drop.
+    guard: Option<FileLockGuard>,
+}
+
+impl PidFile {
+    /// Acquire the pid file at `path`, creating it (and its parent directories) if needed.
+    ///
+    /// Stale pid files, left behind by dead processes, are detected and taken over: a file is
+    /// considered stale, when its lock is not held. The PID it contains is only reported in the
+    /// error, as it may have been reused by an unrelated process since.
+    ///
+    /// # Returns
+    /// [`Ok(PidFile)`](PidFile) on a success, [`PidFileError::AlreadyRunning`] if the file is
+    /// owned by another live process, or [`PidFileError::Io`] on any other failure.
+    pub fn acquire(path: impl AsRef<Path>) -> Result<Self, PidFileError> {
+        let path = path.as_ref();
+        loop {
+            let mut guard = match path.lock(ShouldBlock::No) {
+                Ok(guard) => guard,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return Err(match read_pid(path) {
+                        Some(pid) => PidFileError::AlreadyRunning(pid),
+                        None => PidFileError::Io(err),
+                    });
+                }
+                Err(err) => return Err(err.into()),
+            };
+            // Previous owner could have removed the file, while we were waiting on the lock.
+            if !is_same_file(&guard, path)? {
+                continue;
+            }
+            guard.set_len(0)?;
+            guard.seek(SeekFrom::Start(0))?;
+            writeln!(guard, "{}", std::process::id())?;
+            guard.sync_all()?;
+            return Ok(Self {
+                path: path.to_path_buf(),
+                guard: Some(guard),
+            });
+        }
+    }
+
+    /// Return path to the underlying pid file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return PID written into the pid file.
+    pub fn pid(&self) -> u32 {
+        std::process::id()
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Remove while still holding the lock, so nobody can observe our PID without the lock.
+        let _ = self.path.rm();
+        drop(self.guard.take());
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    path.read_to_string().ok()?.trim().parse().ok()
+}
+
+#[cfg(unix)]
+fn is_same_file(guard: &FileLockGuard, path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let locked = guard.metadata()?;
+    match path.metadata() {
+        Ok(on_disk) => Ok(locked.dev() == on_disk.dev() && locked.ino() == on_disk.ino()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_guard: &FileLockGuard, _path: &Path) -> io::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+    use tempfile::tempdir;
+
+    #[test]
+    fn acquire_writes_pid_and_removes_on_drop() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("run").join("daemon.pid");
+        let pid_file = assert_ok!(PidFile::acquire(&path));
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+        assert_eq!(pid_file.path(), path);
+        drop(pid_file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn second_acquire_fails() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("daemon.pid");
+        let _pid_file = assert_ok!(PidFile::acquire(&path));
+        let result = PidFile::acquire(&path);
+        assert_err!(&result);
+        assert!(matches!(
+            result.unwrap_err(),
+            PidFileError::AlreadyRunning(pid) if pid == std::process::id()
+        ));
+    }
+
+    #[test]
+    fn stale_pid_file_is_taken_over() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("daemon.pid");
+        // PIDs are bounded by far smaller limits on every supported platform.
+        assert_ok!(path.write(format!("{}\n", i32::MAX)));
+        let _pid_file = assert_ok!(PidFile::acquire(&path));
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unlocked_pid_file_of_live_process_is_taken_over() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("daemon.pid");
+        // The PID was reused by `init`, which is always alive.
+        assert_ok!(path.write("1\n"));
+        let _pid_file = assert_ok!(PidFile::acquire(&path));
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+    }
+
+    #[test]
+    fn garbage_pid_file_is_taken_over() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("daemon.pid");
+        assert_ok!(path.write("not a pid"));
+        let _pid_file = assert_ok!(PidFile::acquire(&path));
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+    }
+}
@@ -0,0 +1,234 @@
+//! Byte-range locks, for the [`PathExt::lock_range`](crate::fs::PathExt::lock_range).
+
+use std::{fs::File, io, ops::Deref};
+
+use crate::fs::ShouldBlock;
+
+/// Kind of the lock taken by the [`PathExt::lock_range`](crate::fs::PathExt::lock_range).
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum LockType {
+    /// Many shared locks can overlap, but not with an exclusive one.
+    Shared,
+    /// Exclusive lock doesn't overlap with any other lock.
+    Exclusive,
+}
+
+/// A RAII guard of a locked byte range, which unlocks it on a drop.
+///
+/// It dereferences into the locked [`File`].
+///
+/// Guards are independent on Linux and Windows only. Elsewhere on `unix`, the classic `fcntl`
+/// locks are owned by the process, so within a process overlapping guards don't conflict, and
+/// dropping any guard (or closing any other descriptor of the same file) releases all locks of
+/// the process on the file. There, hold at most one guard per file in a process, and don't open
+/// the file elsewhere while it's held.
+#[derive(Debug)]
+pub struct RangeLockGuard {
+    file: File,
+    offset: u64,
+    len: u64,
+}
+
+impl RangeLockGuard {
+    /// Return offset and length of the locked range (length `0` means, up to the end of the
+    /// file, and beyond).
+    pub fn range(&self) -> (u64, u64) {
+        (self.offset, self.len)
+    }
+}
+
+impl Deref for RangeLockGuard {
+    type Target = File;
+
+    fn deref(&self) -> &Self::Target {
+        &self.file
+    }
+}
+
+impl Drop for RangeLockGuard {
+    fn drop(&mut self) {
+        let _ = unlock(&self.file, self.offset, self.len);
+    }
+}
+
+pub(crate) fn lock_range(
+    file: File,
+    offset: u64,
+    len: u64,
+    lock_type: LockType,
+    should_block: ShouldBlock,
+) -> io::Result<RangeLockGuard> {
+    lock(&file, offset, len, lock_type, should_block)?;
+    Ok(RangeLockGuard { file, offset, len })
+}
+
+/// Open file description locks are owned by the opened file (like the `flock`), not by the
+/// process, so they conflict also between threads, and survive closing other descriptors.
+#[cfg(target_os = "linux")]
+const SETLK: (libc::c_int, libc::c_int) = (libc::F_OFD_SETLK, libc::F_OFD_SETLKW);
+
+#[cfg(all(unix, not(target_os = "linux")))]
+const SETLK: (libc::c_int, libc::c_int) = (libc::F_SETLK, libc::F_SETLKW);
+
+#[cfg(unix)]
+fn fcntl_lock(
+    file: &File,
+    offset: u64,
+    len: u64,
+    kind: libc::c_int,
+    cmd: libc::c_int,
+) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    let out_of_range = |_| io::Error::new(io::ErrorKind::InvalidInput, "range is too big");
+    // SAFETY: `flock` is a plain C struct, for which zeroes are valid (and `l_pid` must be `0`
+    // for the OFD locks).
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = kind as _;
+    lock.l_whence = libc::SEEK_SET as _;
+    lock.l_start = libc::off_t::try_from(offset).map_err(out_of_range)?;
+    lock.l_len = libc::off_t::try_from(len).map_err(out_of_range)?;
+    loop {
+        // SAFETY: `file` is a valid descriptor, and `lock` is a valid `flock`.
+        if unsafe { libc::fcntl(file.as_raw_fd(), cmd, &lock) } == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            // Conflicting lock is reported with either of them, depending on the platform.
+            Some(libc::EAGAIN | libc::EACCES) if cmd == SETLK.0 => {
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+            _ => return Err(err),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn lock(
+    file: &File,
+    offset: u64,
+    len: u64,
+    lock_type: LockType,
+    should_block: ShouldBlock,
+) -> io::Result<()> {
+    let kind = match lock_type {
+        LockType::Shared => libc::F_RDLCK,
+        LockType::Exclusive => libc::F_WRLCK,
+    };
+    let cmd = match should_block {
+        ShouldBlock::No => SETLK.0,
+        ShouldBlock::Yes => SETLK.1,
+    };
+    fcntl_lock(file, offset, len, kind as _, cmd)
+}
+
+#[cfg(unix)]
+fn unlock(file: &File, offset: u64, len: u64) -> io::Result<()> {
+    fcntl_lock(file, offset, len, libc::F_UNLCK as _, SETLK.0)
+}
+
+/// Split `offset` and `len` into the `LockFileEx` arguments.
+#[cfg(windows)]
+fn overlapped(offset: u64, len: u64) -> (windows_sys::Win32::System::IO::OVERLAPPED, u32, u32) {
+    let mut overlapped = windows_sys::Win32::System::IO::OVERLAPPED::default();
+    overlapped.Anonymous.Anonymous.Offset = offset as u32;
+    overlapped.Anonymous.Anonymous.OffsetHigh = (offset >> 32) as u32;
+    // Windows has no notion of "up to the end", lock everything after the offset instead.
+    let len = if len == 0 { u64::MAX - offset } else { len };
+    (overlapped, len as u32, (len >> 32) as u32)
+}
+
+#[cfg(windows)]
+fn lock(
+    file: &File,
+    offset: u64,
+    len: u64,
+    lock_type: LockType,
+    should_block: ShouldBlock,
+) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::{
+        Foundation::{ERROR_LOCK_VIOLATION, FALSE},
+        Storage::FileSystem::{LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY, LockFileEx},
+    };
+    let mut flags = 0;
+    if lock_type == LockType::Exclusive {
+        flags |= LOCKFILE_EXCLUSIVE_LOCK;
+    }
+    if should_block == ShouldBlock::No {
+        flags |= LOCKFILE_FAIL_IMMEDIATELY;
+    }
+    let (mut overlapped, low, high) = overlapped(offset, len);
+    // SAFETY: `file` is a valid, synchronous handle, and `overlapped` is valid for the call.
+    if unsafe { LockFileEx(file.as_raw_handle(), flags, 0, low, high, &mut overlapped) } == FALSE {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) {
+            return Err(io::Error::from(io::ErrorKind::WouldBlock));
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn unlock(file: &File, offset: u64, len: u64) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::{Foundation::FALSE, Storage::FileSystem::UnlockFileEx};
+    let (mut overlapped, low, high) = overlapped(offset, len);
+    // SAFETY: `file` is a valid, synchronous handle, and `overlapped` is valid for the call.
+    if unsafe { UnlockFileEx(file.as_raw_handle(), 0, low, high, &mut overlapped) } == FALSE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock(
+    _file: &File,
+    _offset: u64,
+    _len: u64,
+    _lock_type: LockType,
+    _should_block: ShouldBlock,
+) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unlock(_file: &File, _offset: u64, _len: u64) -> io::Result<()> {
+    Ok(())
+}
+
+// Classic `fcntl` locks don't conflict within a single process.
+#[cfg(any(target_os = "linux", windows))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::PathExt;
+    use claim::{assert_err, assert_ok};
+    use tempfile::tempdir;
+
+    #[test]
+    fn overlapping_ranges_conflict() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("journal");
+        let guard = assert_ok!(path.lock_range(0, 100, LockType::Exclusive, ShouldBlock::No));
+        assert_eq!(guard.range(), (0, 100));
+        let err = assert_err!(path.lock_range(50, 10, LockType::Shared, ShouldBlock::No));
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        // Disjoint range is fine.
+        let _other = assert_ok!(path.lock_range(100, 10, LockType::Exclusive, ShouldBlock::No));
+        drop(guard);
+        let _shared = assert_ok!(path.lock_range(50, 10, LockType::Shared, ShouldBlock::No));
+    }
+
+    #[test]
+    fn shared_ranges_coexist() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("journal");
+        let _a = assert_ok!(path.lock_range(0, 0, LockType::Shared, ShouldBlock::No));
+        let _b = assert_ok!(path.lock_range(10, 10, LockType::Shared, ShouldBlock::Yes));
+        let err = assert_err!(path.lock_range(1000, 1, LockType::Exclusive, ShouldBlock::No));
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    }
+}
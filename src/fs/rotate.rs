@@ -0,0 +1,239 @@
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::fs::PathExt;
+
+/// Rotates a (log) file: the `file` is renamed to the `file.1`, the `file.1` to the `file.2`, and
+/// so on, keeping only the configured number of old files.
+///
+/// Rotation is triggered by a size, or an age (since the file was created, where the
+/// filesystem records it) of the file, see the [`Rotator::rotate_if_needed`]. Each step is a
+/// rename within the directory, and compressed files are written under temporary names first,
+/// so an interrupted rotation never leaves a partial file behind.
+///
+/// The file isn't recreated, the writer should reopen it after a rotation.
+///
+/// ```rust,no_run
+/// # use rustvil::fs::Rotator;
+/// # use std::time::Duration;
+/// # fn main() -> std::io::Result<()> {
+/// let rotator = Rotator::new("/var/log/daemon.log")
+///     .max_size(10 << 20)
+///     .max_age(Duration::from_secs(7 * 24 * 60 * 60))
+///     .keep(3);
+/// if rotator.rotate_if_needed()? {
+///     // Reopen the log.
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rotator {
+    path: PathBuf,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    keep: usize,
+    #[cfg(feature = "gzip")]
+    compress: bool,
+}
+
+impl Rotator {
+    /// Create a rotator of the file at `path`, keeping 5 old files, without any triggers.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_size: None,
+            max_age: None,
+            keep: 5,
+            #[cfg(feature = "gzip")]
+            compress: false,
+        }
+    }
+
+    /// Rotate the file, once it has at least `max_size` bytes.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Rotate the file, once it's at least `max_age` old.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Keep `keep` old files, removing older ones (`0` means the file is just removed).
+    pub fn keep(mut self, keep: usize) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Compress old files with gzip (into the `file.2.gz` and so on), except for the newest one
+    /// (the `file.1`), which the writer may still have open.
+    #[cfg(feature = "gzip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Return path of the rotated file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check, whether the file reached the maximum size or age. Missing, and empty files are
+    /// never rotated.
+    ///
+    /// # Returns
+    /// [`Ok(bool)`](bool) on a success, otherwise an error, as reported by the OS.
+    pub fn should_rotate(&self) -> io::Result<bool> {
+        let metadata = match self.path.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        if metadata.len() == 0 {
+            return Ok(false);
+        }
+        if self
+            .max_size
+            .is_some_and(|max_size| metadata.len() >= max_size)
+        {
+            return Ok(true);
+        }
+        Ok(self.max_age.is_some_and(|max_age| {
+            metadata
+                .created()
+                .ok()
+                .and_then(|created| created.elapsed().ok())
+                .is_some_and(|age| age >= max_age)
+        }))
+    }
+
+    /// Rotate the file, if the [`Rotator::should_rotate`].
+    ///
+    /// # Returns
+    /// [`Ok(bool)`](bool), whether the file was rotated, otherwise an error, as reported by the
+    /// OS.
+    pub fn rotate_if_needed(&self) -> io::Result<bool> {
+        if !self.should_rotate()? {
+            return Ok(false);
+        }
+        self.rotate()?;
+        Ok(true)
+    }
+
+    /// Rotate the file now, regardless of the triggers (nothing happens to a missing one, but old
+    /// files are still shifted).
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) on a success, otherwise an error, as reported by the OS.
+    pub fn rotate(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return ignore_missing(self.path.rm());
+        }
+        for compressed in [false, true] {
+            ignore_missing(self.old(self.keep, compressed).rm())?;
+        }
+        for n in (1..self.keep).rev() {
+            ignore_missing(self.old(n, true).rename_to(self.old(n + 1, true)))?;
+            let plain = self.old(n, false);
+            #[cfg(feature = "gzip")]
+            if self.compress && plain.is_file() {
+                gzip(&plain, &self.old(n + 1, true))?;
+                plain.rm()?;
+                continue;
+            }
+            ignore_missing(plain.rename_to(self.old(n + 1, false)))?;
+        }
+        ignore_missing(self.path.rename_to(self.old(1, false)))
+    }
+
+    /// Return path of the `n`th old file.
+    fn old(&self, n: usize, compressed: bool) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{n}"));
+        if compressed {
+            path.push(".gz");
+        }
+        PathBuf::from(path)
+    }
+}
+
+/// Compress the `from` into a new file at the `to`.
+#[cfg(feature = "gzip")]
+fn gzip(from: &Path, to: &Path) -> io::Result<()> {
+    use flate2::{Compression, write::GzEncoder};
+
+    let parent = to.parent().unwrap_or(Path::new("."));
+    let tmp = crate::fs::TempFile::new_in(parent)?;
+    let mut encoder = GzEncoder::new(&*tmp, Compression::default());
+    io::copy(&mut std::fs::File::open(from)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    tmp.persist(to).map(drop)
+}
+
+fn ignore_missing(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_ok, assert_ok_eq};
+    use tempfile::tempdir;
+
+    #[test]
+    fn files_are_rotated() {
+        let tmp = tempdir().expect("needed for tests");
+        let log = tmp.path().join("app.log");
+        let rotator = Rotator::new(&log).max_size(4).keep(2);
+        assert_ok_eq!(rotator.rotate_if_needed(), false);
+        for contents in ["first", "second", "third"] {
+            assert_ok!(log.write("abc"));
+            assert_ok_eq!(rotator.rotate_if_needed(), false);
+            assert_ok!(log.write(contents));
+            assert_ok_eq!(rotator.rotate_if_needed(), true);
+            assert!(!log.exists());
+        }
+        let old = |n| tmp.path().join(format!("app.log.{n}"));
+        assert_ok_eq!(old(1).read_to_string(), "third");
+        assert_ok_eq!(old(2).read_to_string(), "second");
+        assert!(!old(3).exists());
+
+        assert_ok!(log.write("x"));
+        assert_ok!(Rotator::new(&log).keep(0).rotate());
+        assert!(!log.exists());
+        assert_ok_eq!(old(1).read_to_string(), "third");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn old_files_are_compressed() {
+        use std::io::Read;
+        let tmp = tempdir().expect("needed for tests");
+        let log = tmp.path().join("app.log");
+        let rotator = Rotator::new(&log).keep(3).compress(true);
+        for contents in ["first", "second", "third"] {
+            assert_ok!(log.write(contents));
+            assert_ok!(rotator.rotate());
+        }
+        let old = |name: &str| tmp.path().join(name);
+        assert_ok_eq!(old("app.log.1").read_to_string(), "third");
+        for (name, expected) in [("app.log.2.gz", "second"), ("app.log.3.gz", "first")] {
+            let file = assert_ok!(std::fs::File::open(old(name)));
+            let mut contents = String::new();
+            assert_ok!(flate2::read::GzDecoder::new(file).read_to_string(&mut contents));
+            assert_eq!(contents, expected);
+            assert!(!old(name.trim_end_matches(".gz")).exists());
+        }
+    }
+}
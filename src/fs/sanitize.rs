@@ -0,0 +1,152 @@
+/// Target platform of the [`sanitize_filename`].
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    /// Only `/` and `NUL` are invalid, names are limited to 255 bytes.
+    Unix,
+    /// Also `<>:"\|?*`, reserved device names, and trailing dots or spaces are invalid, names
+    /// are limited to 255 UTF-16 units.
+    Windows,
+    /// Names valid on every platform (Windows rules, limited to 255 bytes).
+    Portable,
+}
+
+impl Platform {
+    /// Return the platform this code is compiled for.
+    pub const fn current() -> Self {
+        if cfg!(windows) {
+            Self::Windows
+        } else {
+            Self::Unix
+        }
+    }
+}
+
+/// Character, which replaces the invalid ones.
+const REPLACEMENT: char = '_';
+
+const MAX_LEN: usize = 255;
+
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turn `name` into a file name valid on the `platform`.
+///
+/// Invalid characters (including all control characters) are replaced with `_`, reserved
+/// Windows names (like `CON` or `nul.txt`) are prefixed with `_`, and trailing dots and spaces
+/// are stripped on Windows. Too long names are truncated, keeping the extension if possible.
+/// Names, which would end up empty (or be `.`/`..`), become `_`.
+///
+/// Result is a single path component, so it's safe to [`join`](std::path::Path::join).
+///
+/// ```rust
+/// # use rustvil::fs::{Platform, sanitize_filename};
+/// assert_eq!(sanitize_filename("a/b:c?.txt", Platform::Windows), "a_b_c_.txt");
+/// assert_eq!(sanitize_filename("con.txt", Platform::Windows), "_con.txt");
+/// assert_eq!(sanitize_filename("report. ", Platform::Portable), "report");
+/// assert_eq!(sanitize_filename("..", Platform::Unix), "_");
+/// ```
+pub fn sanitize_filename(name: &str, platform: Platform) -> String {
+    let windows_rules = matches!(platform, Platform::Windows | Platform::Portable);
+    let mut name: String = name
+        .chars()
+        .map(|c| {
+            let invalid = c == '/'
+                || c.is_control()
+                || (windows_rules && matches!(c, '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*'));
+            if invalid { REPLACEMENT } else { c }
+        })
+        .collect();
+    if windows_rules {
+        trim_trailing_dots_and_spaces(&mut name);
+        if is_reserved(&name) {
+            name.insert(0, REPLACEMENT);
+        }
+    }
+    let len = |s: &str| match platform {
+        Platform::Windows => s.encode_utf16().count(),
+        Platform::Unix | Platform::Portable => s.len(),
+    };
+    if len(&name) > MAX_LEN {
+        // Keep reasonably short extensions, they usually matter more than the end of the stem.
+        let ext = match name.rfind('.') {
+            Some(dot) if dot > 0 && len(&name[dot..]) <= MAX_LEN / 4 => name.split_off(dot),
+            _ => String::new(),
+        };
+        let budget = MAX_LEN - len(&ext);
+        let mut used = 0;
+        let end = name
+            .char_indices()
+            .find(|&(_, c)| {
+                used += len(c.encode_utf8(&mut [0; 4]));
+                used > budget
+            })
+            .map_or(name.len(), |(i, _)| i);
+        name.truncate(end);
+        if windows_rules {
+            trim_trailing_dots_and_spaces(&mut name);
+        }
+        name.push_str(&ext);
+    }
+    if name.is_empty() || name == "." || name == ".." {
+        return REPLACEMENT.to_string();
+    }
+    name
+}
+
+/// Check if `name` is a reserved device name on Windows (extension doesn't matter).
+pub(crate) fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default();
+    WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem.trim_end()))
+}
+
+fn trim_trailing_dots_and_spaces(name: &mut String) {
+    name.truncate(name.trim_end_matches(['.', ' ']).len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_characters_are_replaced() {
+        assert_eq!(sanitize_filename("a/b\0c", Platform::Unix), "a_b_c");
+        assert_eq!(sanitize_filename("a:b*c", Platform::Unix), "a:b*c");
+        assert_eq!(sanitize_filename("a:b*c\n", Platform::Windows), "a_b_c_");
+        assert_eq!(sanitize_filename(r#"<>"\|"#, Platform::Portable), "_____");
+    }
+
+    #[test]
+    fn windows_reserved_names_and_trailing_characters() {
+        assert_eq!(sanitize_filename("NUL", Platform::Windows), "_NUL");
+        assert_eq!(
+            sanitize_filename("com1.tar.gz", Platform::Windows),
+            "_com1.tar.gz"
+        );
+        assert_eq!(sanitize_filename("console", Platform::Windows), "console");
+        assert_eq!(sanitize_filename("NUL", Platform::Unix), "NUL");
+        assert_eq!(sanitize_filename("name . .", Platform::Windows), "name");
+        assert_eq!(sanitize_filename("...", Platform::Windows), "_");
+        assert_eq!(sanitize_filename("", Platform::Unix), "_");
+        assert_eq!(sanitize_filename(".", Platform::Unix), "_");
+        assert_eq!(sanitize_filename(".hidden", Platform::Unix), ".hidden");
+    }
+
+    #[test]
+    fn long_names_are_truncated() {
+        let long = format!("{}.json", "a".repeat(300));
+        let sanitized = sanitize_filename(&long, Platform::Unix);
+        assert_eq!(sanitized.len(), MAX_LEN);
+        assert!(sanitized.ends_with("a.json"));
+
+        // Multibyte characters are never split.
+        let sanitized = sanitize_filename(&"ż".repeat(200), Platform::Unix);
+        assert_eq!(sanitized, "ż".repeat(127));
+        // But UTF-16 has room for all of them.
+        let sanitized = sanitize_filename(&"ż".repeat(200), Platform::Windows);
+        assert_eq!(sanitized, "ż".repeat(200));
+    }
+}
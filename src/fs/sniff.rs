@@ -0,0 +1,183 @@
+/// How many bytes are read by the [`PathExt::sniff_type`](crate::fs::PathExt::sniff_type).
+pub(crate) const SNIFF_LEN: usize = 8 * 1024;
+
+/// File type, as detected by the [`PathExt::sniff_type`](crate::fs::PathExt::sniff_type).
+///
+/// Detection is conservative: anything not recognized with confidence is a
+/// [`Binary`](SniffedType::Binary).
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SniffedType {
+    /// File without any contents.
+    Empty,
+    /// gzip compressed data.
+    Gzip,
+    /// zip archive (including formats based on it, like jar or docx).
+    Zip,
+    /// POSIX (ustar) or GNU tar archive.
+    Tar,
+    /// ELF executable, shared object, or object file.
+    Elf,
+    /// Windows PE executable or DLL.
+    Pe,
+    /// PNG image.
+    Png,
+    /// JPEG image.
+    Jpeg,
+    /// UTF-8 (or ASCII) text.
+    Utf8Text,
+    /// UTF-16 text with a byte order mark.
+    Utf16Text,
+    /// Anything else.
+    Binary,
+}
+
+impl SniffedType {
+    /// Detect type of the file, starting with `prefix`.
+    ///
+    /// [`PathExt::sniff_type`](crate::fs::PathExt::sniff_type) passes the first 8 KiB, shorter
+    /// prefixes can miss some formats (e.g. tar needs at least 262 bytes).
+    pub fn from_bytes(prefix: &[u8]) -> Self {
+        const MAGICS: &[(&[u8], SniffedType)] = &[
+            (b"\x1f\x8b", SniffedType::Gzip),
+            (b"PK\x03\x04", SniffedType::Zip),
+            (b"PK\x05\x06", SniffedType::Zip),
+            (b"PK\x07\x08", SniffedType::Zip),
+            (b"\x7fELF", SniffedType::Elf),
+            (b"\x89PNG\r\n\x1a\n", SniffedType::Png),
+            (b"\xff\xd8\xff", SniffedType::Jpeg),
+            (b"\xff\xfe", SniffedType::Utf16Text),
+            (b"\xfe\xff", SniffedType::Utf16Text),
+        ];
+        if prefix.is_empty() {
+            return Self::Empty;
+        }
+        if let Some((_, kind)) = MAGICS.iter().find(|(magic, _)| prefix.starts_with(magic)) {
+            return *kind;
+        }
+        if prefix.get(257..262) == Some(b"ustar") {
+            return Self::Tar;
+        }
+        if is_pe(prefix) {
+            return Self::Pe;
+        }
+        if is_utf8_text(prefix) {
+            return Self::Utf8Text;
+        }
+        Self::Binary
+    }
+}
+
+/// `MZ` header, pointing (at offset `0x3c`) to the `PE\0\0` signature.
+fn is_pe(prefix: &[u8]) -> bool {
+    if !prefix.starts_with(b"MZ") {
+        return false;
+    }
+    let Some(offset) = prefix.get(0x3c..0x40) else {
+        return false;
+    };
+    let offset = u32::from_le_bytes(offset.try_into().expect("4 bytes")) as usize;
+    prefix.get(offset..offset.saturating_add(4)) == Some(b"PE\0\0")
+}
+
+fn is_utf8_text(prefix: &[u8]) -> bool {
+    let text = match std::str::from_utf8(prefix) {
+        Ok(text) => text,
+        // Prefix can cut a multibyte character in half.
+        Err(err) if err.error_len().is_none() && prefix.len() - err.valid_up_to() < 4 => {
+            std::str::from_utf8(&prefix[..err.valid_up_to()]).expect("valid prefix")
+        }
+        Err(_) => return false,
+    };
+    // Text files don't contain control characters (C0, DEL and C1), other than the whitespace
+    // and escape.
+    text.chars()
+        .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use tempfile::tempdir;
+
+    #[test]
+    fn magic_bytes() {
+        assert_eq!(SniffedType::from_bytes(b""), SniffedType::Empty);
+        assert_eq!(
+            SniffedType::from_bytes(b"\x1f\x8b\x08\x00"),
+            SniffedType::Gzip
+        );
+        assert_eq!(SniffedType::from_bytes(b"PK\x03\x04rest"), SniffedType::Zip);
+        assert_eq!(
+            SniffedType::from_bytes(b"\x7fELF\x02\x01"),
+            SniffedType::Elf
+        );
+        assert_eq!(
+            SniffedType::from_bytes(b"\x89PNG\r\n\x1a\n\0\0"),
+            SniffedType::Png
+        );
+        assert_eq!(
+            SniffedType::from_bytes(b"\xff\xd8\xff\xe0"),
+            SniffedType::Jpeg
+        );
+        assert_eq!(
+            SniffedType::from_bytes(b"\xff\xfeh\0i\0"),
+            SniffedType::Utf16Text
+        );
+    }
+
+    #[test]
+    fn tar_and_pe() {
+        let mut tar = vec![0u8; 512];
+        tar[257..263].copy_from_slice(b"ustar\0");
+        assert_eq!(SniffedType::from_bytes(&tar), SniffedType::Tar);
+
+        let mut pe = vec![0u8; 0x100];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        assert_eq!(SniffedType::from_bytes(&pe), SniffedType::Pe);
+        // Plain `MZ` is not enough.
+        assert_eq!(SniffedType::from_bytes(b"MZ\0\0"), SniffedType::Binary);
+    }
+
+    #[test]
+    fn text_detection() {
+        assert_eq!(
+            SniffedType::from_bytes(b"hello\n\tworld"),
+            SniffedType::Utf8Text
+        );
+        assert_eq!(
+            SniffedType::from_bytes("zażółć".as_bytes()),
+            SniffedType::Utf8Text
+        );
+        // Truncated multibyte character at the end.
+        assert_eq!(
+            SniffedType::from_bytes(&"aż".as_bytes()[..2]),
+            SniffedType::Utf8Text
+        );
+        assert_eq!(SniffedType::from_bytes(b"a\0b"), SniffedType::Binary);
+        // C1 control characters, `U+0085` and `U+009F`.
+        assert_eq!(
+            SniffedType::from_bytes("a\u{85}b".as_bytes()),
+            SniffedType::Binary
+        );
+        assert_eq!(
+            SniffedType::from_bytes("a\u{9f}".as_bytes()),
+            SniffedType::Binary
+        );
+        assert_eq!(SniffedType::from_bytes(b"\xc3\x28"), SniffedType::Binary);
+    }
+
+    #[test]
+    fn sniff_file() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.write(b"\x1f\x8b rest of the gzip stream"));
+        assert_eq!(assert_ok!(path.sniff_type()), SniffedType::Gzip);
+        assert_ok!(path.write(""));
+        assert_eq!(assert_ok!(path.sniff_type()), SniffedType::Empty);
+    }
+}
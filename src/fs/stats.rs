@@ -0,0 +1,199 @@
+//! Filesystem statistics, for the [`PathExt::fs_stats`](crate::fs::PathExt::fs_stats).
+
+use std::{io, path::Path};
+
+/// Statistics of a filesystem, as returned by the [`PathExt::fs_stats`](crate::fs::PathExt::fs_stats).
+///
+/// All sizes are in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FsStats {
+    /// Total size of the filesystem.
+    pub total: u64,
+    /// Free space, including space reserved for the privileged users.
+    pub free: u64,
+    /// Free space available to the current user (e.g. without the root's reserve, or quotas).
+    pub available: u64,
+    /// Allocation unit (fragment or cluster size).
+    pub block_size: u64,
+    /// Name of the filesystem type (e.g. `ext4`, `apfs`, `NTFS`), if known.
+    pub fs_type: Option<String>,
+}
+
+#[cfg(unix)]
+// Field types differ between platforms.
+#[allow(clippy::useless_conversion)]
+pub(crate) fn stats(path: &Path) -> io::Result<FsStats> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut buf = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL terminated, and `buf` is valid for writes.
+    if unsafe { libc::statvfs(path.as_ptr(), buf.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: A successful call initialized `buf`.
+    let buf = unsafe { buf.assume_init() };
+    // On some platforms `f_frsize` is 0, and `f_bsize` should be used instead.
+    let block_size = u64::from(if buf.f_frsize != 0 {
+        buf.f_frsize
+    } else {
+        buf.f_bsize
+    });
+    Ok(FsStats {
+        total: u64::from(buf.f_blocks).saturating_mul(block_size),
+        free: u64::from(buf.f_bfree).saturating_mul(block_size),
+        available: u64::from(buf.f_bavail).saturating_mul(block_size),
+        block_size,
+        fs_type: fs_type(&path),
+    })
+}
+
+/// Linux reports only a magic number, so only some popular filesystems are named.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn fs_type(path: &std::ffi::CStr) -> Option<String> {
+    let mut buf = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: `path` is NUL terminated, and `buf` is valid for writes.
+    if unsafe { libc::statfs(path.as_ptr(), buf.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: A successful call initialized `buf`.
+    let magic = unsafe { buf.assume_init() }.f_type as u32;
+    let name = match magic {
+        0x9123_683e => "btrfs",
+        0x0000_ef53 => "ext4",
+        0xf2f5_2010 => "f2fs",
+        0x6573_5546 => "fuse",
+        0x0000_6969 => "nfs",
+        0x5346_544e => "ntfs",
+        0x794c_7630 => "overlay",
+        0x9fa0 => "proc",
+        0x7371_7368 => "squashfs",
+        0x0102_1994 => "tmpfs",
+        0x0000_4d44 => "vfat",
+        0x5846_5342 => "xfs",
+        0x2fc1_2fc1 => "zfs",
+        _ => return None,
+    };
+    Some(name.to_owned())
+}
+
+#[cfg(target_vendor = "apple")]
+fn fs_type(path: &std::ffi::CStr) -> Option<String> {
+    let mut buf = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: `path` is NUL terminated, and `buf` is valid for writes.
+    if unsafe { libc::statfs(path.as_ptr(), buf.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: A successful call initialized `buf`.
+    let buf = unsafe { buf.assume_init() };
+    // SAFETY: `f_fstypename` is NUL terminated.
+    let name = unsafe { std::ffi::CStr::from_ptr(buf.f_fstypename.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "linux", target_os = "android", target_vendor = "apple"))
+))]
+fn fs_type(_path: &std::ffi::CStr) -> Option<String> {
+    None
+}
+
+#[cfg(windows)]
+pub(crate) fn stats(path: &Path) -> io::Result<FsStats> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::{
+        Foundation::{FALSE, MAX_PATH},
+        Storage::FileSystem::{
+            GetDiskFreeSpaceExW, GetDiskFreeSpaceW, GetVolumeInformationW, GetVolumePathNameW,
+        },
+    };
+    let path = crate::fs::long_path::to_extended(path);
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    // Volume path can't be longer than the path itself (plus a trailing separator).
+    let mut root = vec![0u16; wide.len().max(MAX_PATH as usize) + 1];
+    let root_len = u32::try_from(root.len()).map_err(|_| io::Error::other("path is too long"))?;
+    // SAFETY: `wide` is NUL terminated, `root` is valid for writes of `root_len` characters.
+    if unsafe { GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root_len) } == FALSE {
+        return Err(io::Error::last_os_error());
+    }
+    let (mut available, mut total, mut free) = (0u64, 0u64, 0u64);
+    // SAFETY: `root` is NUL terminated, all outputs are valid for writes.
+    if unsafe { GetDiskFreeSpaceExW(root.as_ptr(), &mut available, &mut total, &mut free) } == FALSE
+    {
+        return Err(io::Error::last_os_error());
+    }
+    let (mut sectors, mut sector_size, mut free_clusters, mut clusters) = (0u32, 0u32, 0u32, 0u32);
+    // SAFETY: `root` is NUL terminated, all outputs are valid for writes.
+    let block_size = if unsafe {
+        GetDiskFreeSpaceW(
+            root.as_ptr(),
+            &mut sectors,
+            &mut sector_size,
+            &mut free_clusters,
+            &mut clusters,
+        )
+    } == FALSE
+    {
+        0
+    } else {
+        u64::from(sectors) * u64::from(sector_size)
+    };
+    let mut name = [0u16; MAX_PATH as usize + 1];
+    // SAFETY: `root` is NUL terminated, `name` is valid for writes of its length, and other
+    // outputs are allowed to be NULL.
+    let fs_type = (unsafe {
+        GetVolumeInformationW(
+            root.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            name.as_mut_ptr(),
+            name.len() as u32,
+        )
+    } != FALSE)
+        .then(|| {
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            String::from_utf16_lossy(&name[..len])
+        });
+    Ok(FsStats {
+        total,
+        free,
+        available,
+        block_size,
+        fs_type,
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn stats(_path: &Path) -> io::Result<FsStats> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use tempfile::tempdir;
+
+    #[test]
+    fn stats_are_consistent() {
+        let tmp = tempdir().expect("needed for tests");
+        let stats = assert_ok!(tmp.path().fs_stats());
+        assert!(stats.block_size > 0);
+        assert!(stats.free <= stats.total);
+        assert!(stats.available <= stats.free);
+        assert!(assert_ok!(tmp.path().has_space_for(0)));
+        assert!(!assert_ok!(tmp.path().has_space_for(u64::MAX)));
+    }
+
+    #[test]
+    fn stats_of_missing_path_fail() {
+        let tmp = tempdir().expect("needed for tests");
+        assert!(tmp.path().join("missing").fs_stats().is_err());
+        // But the space check looks at the closest existing ancestor.
+        assert!(assert_ok!(tmp.path().join("a/b").has_space_for(1)));
+    }
+}
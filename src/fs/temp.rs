@@ -0,0 +1,573 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fs::{File, OpenOptions},
+    hash::{BuildHasher, Hasher, RandomState},
+    io,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::fs::PathExt;
+
+/// How many times creation is retried, when the generated name is already taken.
+const MAX_ATTEMPTS: usize = 1 << 16;
+
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Hasher seeded with randomly keyed [`RandomState`], a counter, PID, and the current time.
+fn seeded_hasher() -> impl Hasher {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u32(std::process::id());
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    hasher
+}
+
+/// Fill `buf` with random bytes.
+///
+/// This is __not__ cryptographically secure (it's a `splitmix64` stream), it only needs to be
+/// hard to predict.
+pub(crate) fn fill_random(buf: &mut [u8]) {
+    let mut state = seeded_hasher().finish();
+    for chunk in buf.chunks_mut(8) {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        chunk.copy_from_slice(&z.to_ne_bytes()[..chunk.len()]);
+    }
+}
+
+/// Generate a random alphanumeric string of length `len`.
+///
+/// This is __not__ cryptographically secure, it only needs to be hard to predict, to avoid
+/// collisions.
+pub(crate) fn random_suffix(len: usize) -> String {
+    let mut hasher = seeded_hasher();
+    let mut state = hasher.finish();
+    (0..len)
+        .map(|i| {
+            if i % 8 == 0 {
+                hasher.write_u64(state);
+                state = hasher.finish();
+            }
+            let c = ALPHABET[(state % ALPHABET.len() as u64) as usize];
+            state /= ALPHABET.len() as u64;
+            c as char
+        })
+        .collect()
+}
+
+/// Builder for the [`TempDir`] and the [`TempFile`].
+///
+/// Created entries are named `{prefix}{random}{suffix}`, and placed in the
+/// [`parent`](TempBuilder::parent) directory ([`std::env::temp_dir`] by default).
+///
+/// ```rust,no_run
+/// # use rustvil::fs::TempBuilder;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = TempBuilder::new().prefix("build-").tempdir()?;
+/// let file = TempBuilder::new()
+///     .parent(dir.path())
+///     .suffix(".json")
+///     .tempfile()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TempBuilder {
+    parent: Option<PathBuf>,
+    prefix: OsString,
+    suffix: OsString,
+    random_len: usize,
+}
+
+impl TempBuilder {
+    /// Create new [`TempBuilder`] with default settings: prefix `.tmp`, no suffix, and 6 random
+    /// characters.
+    pub fn new() -> Self {
+        Self {
+            parent: None,
+            prefix: OsString::from(".tmp"),
+            suffix: OsString::new(),
+            random_len: 6,
+        }
+    }
+
+    /// Set directory in which entries are created.
+    pub fn parent(&mut self, parent: impl AsRef<Path>) -> &mut Self {
+        self.parent = Some(parent.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set prefix of the created entries.
+    pub fn prefix(&mut self, prefix: impl AsRef<OsStr>) -> &mut Self {
+        self.prefix = prefix.as_ref().to_os_string();
+        self
+    }
+
+    /// Set suffix of the created entries.
+    pub fn suffix(&mut self, suffix: impl AsRef<OsStr>) -> &mut Self {
+        self.suffix = suffix.as_ref().to_os_string();
+        self
+    }
+
+    /// Set number of random characters in the created entries.
+    pub fn random_len(&mut self, random_len: usize) -> &mut Self {
+        self.random_len = random_len;
+        self
+    }
+
+    /// Create new [`TempDir`].
+    ///
+    /// On `unix` the directory is created with mode `0o700`.
+    pub fn tempdir(&self) -> io::Result<TempDir> {
+        self.create(|path| {
+            #[cfg_attr(not(unix), allow(unused_mut))]
+            let mut builder = std::fs::DirBuilder::new();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::DirBuilderExt;
+                builder.mode(0o700);
+            }
+            builder.create(path)?;
+            Ok(TempDir {
+                path: Some(path.to_path_buf()),
+            })
+        })
+    }
+
+    /// Create new [`TempFile`], opened for reading and writing.
+    ///
+    /// On `unix` the file is created with mode `0o600`.
+    pub fn tempfile(&self) -> io::Result<TempFile> {
+        self.create(|path| {
+            let mut opts = OpenOptions::new();
+            opts.read(true).write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                opts.mode(0o600);
+            }
+            let file = opts.open(path)?;
+            Ok(TempFile {
+                file: Some(file),
+                path: Some(path.to_path_buf()),
+            })
+        })
+    }
+
+    fn create<R>(&self, mut f: impl FnMut(&Path) -> io::Result<R>) -> io::Result<R> {
+        let parent = match &self.parent {
+            Some(parent) => parent.clone(),
+            None => std::env::temp_dir(),
+        };
+        let attempts = if self.random_len == 0 {
+            1
+        } else {
+            MAX_ATTEMPTS
+        };
+        for _ in 0..attempts {
+            let mut name = self.prefix.clone();
+            name.push(random_suffix(self.random_len));
+            name.push(&self.suffix);
+            match f(&parent.join(name)) {
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempts > 1 => continue,
+                result => return result,
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "too many temporary entries already exist",
+        ))
+    }
+}
+
+impl Default for TempBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A RAII temporary directory, which is recursively removed on a drop.
+#[derive(Debug)]
+pub struct TempDir {
+    // `None` only after `keep`.
+    path: Option<PathBuf>,
+}
+
+impl TempDir {
+    /// Create new [`TempDir`] inside of the [`std::env::temp_dir`].
+    pub fn new() -> io::Result<Self> {
+        TempBuilder::new().tempdir()
+    }
+
+    /// Create new [`TempDir`] inside of the `parent`.
+    pub fn new_in(parent: impl AsRef<Path>) -> io::Result<Self> {
+        TempBuilder::new().parent(parent).tempdir()
+    }
+
+    /// Return path to the directory.
+    pub fn path(&self) -> &Path {
+        self.path.as_deref().expect("path is present until `keep`")
+    }
+
+    /// Persist the directory: it won't be removed on a drop.
+    pub fn keep(mut self) -> PathBuf {
+        self.path.take().expect("path is present until `keep`")
+    }
+
+    /// Remove the directory, reporting any errors (unlike the drop).
+    pub fn close(mut self) -> io::Result<()> {
+        self.path
+            .take()
+            .expect("path is present until `keep`")
+            .rmtree()
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = path.rmtree();
+        }
+    }
+}
+
+/// A RAII temporary file, which is removed on a drop.
+///
+/// It dereferences into the opened [`File`].
+#[derive(Debug)]
+pub struct TempFile {
+    // Both are `None` only after `keep` or `persist`.
+    file: Option<File>,
+    path: Option<PathBuf>,
+}
+
+impl TempFile {
+    /// Create new [`TempFile`] inside of the [`std::env::temp_dir`].
+    pub fn new() -> io::Result<Self> {
+        TempBuilder::new().tempfile()
+    }
+
+    /// Create new [`TempFile`] inside of the `parent`.
+    pub fn new_in(parent: impl AsRef<Path>) -> io::Result<Self> {
+        TempBuilder::new().parent(parent).tempfile()
+    }
+
+    /// Return path to the file.
+    pub fn path(&self) -> &Path {
+        self.path.as_deref().expect("path is present until `keep`")
+    }
+
+    /// Persist the file: it won't be removed on a drop.
+    pub fn keep(mut self) -> (File, PathBuf) {
+        let file = self.file.take().expect("file is present until `keep`");
+        let path = self.path.take().expect("path is present until `keep`");
+        (file, path)
+    }
+
+    /// Persist the file by renaming it to `to`.
+    ///
+    /// # Returns
+    /// [`Ok(File)`](File) on a success, otherwise an error as reported by the
+    /// [`PathExt::rename_to`]. On failure the file is still removed on a drop.
+    pub fn persist(mut self, to: impl AsRef<Path>) -> io::Result<File> {
+        self.path().rename_to(to)?;
+        self.path = None;
+        Ok(self.file.take().expect("file is present until `keep`"))
+    }
+}
+
+impl Deref for TempFile {
+    type Target = File;
+
+    fn deref(&self) -> &Self::Target {
+        self.file.as_ref().expect("file is present until `keep`")
+    }
+}
+
+impl DerefMut for TempFile {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.file.as_mut().expect("file is present until `keep`")
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // Close before removing, Windows disallows removing opened files.
+        drop(self.file.take());
+        if let Some(path) = self.path.take() {
+            let _ = path.rm();
+        }
+    }
+}
+
+/// Create an unnamed temporary file inside of the `dir`, opened for reading and writing.
+///
+/// On Linux it uses the `O_TMPFILE`, so the file never appears in the directory, and
+/// it's reclaimed once closed, even if the process crashes. Elsewhere (or when the filesystem
+/// doesn't support `O_TMPFILE`) it falls back to a hidden [`TempFile`], removed on a drop.
+///
+/// File can be given a name later, with the [`AnonymousFile::materialize`].
+///
+/// ```rust,no_run
+/// # use rustvil::fs::anonymous_file;
+/// # use std::io::Write;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut file = anonymous_file("/srv/data")?;
+/// file.write_all(b"complete contents")?;
+/// file.sync_all()?;
+/// // Readers see either no file, or the complete one.
+/// file.materialize("/srv/data/report.txt")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn anonymous_file(dir: impl AsRef<Path>) -> io::Result<AnonymousFile> {
+    let dir = dir.as_ref();
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        let result = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .mode(0o600)
+            .custom_flags(libc::O_TMPFILE)
+            .open(dir);
+        match result {
+            Ok(file) => {
+                return Ok(AnonymousFile {
+                    repr: AnonymousRepr::Unnamed(file),
+                });
+            }
+            // Filesystem (or kernel, in the case of `EISDIR`) doesn't support `O_TMPFILE`.
+            Err(err)
+                if matches!(
+                    err.raw_os_error(),
+                    Some(libc::EOPNOTSUPP | libc::EISDIR | libc::EINVAL)
+                ) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(AnonymousFile {
+        repr: AnonymousRepr::Named(TempFile::new_in(dir)?),
+    })
+}
+
+/// An unnamed temporary file, created by the [`anonymous_file`].
+///
+/// It dereferences into the opened [`File`].
+#[derive(Debug)]
+pub struct AnonymousFile {
+    repr: AnonymousRepr,
+}
+
+#[derive(Debug)]
+enum AnonymousRepr {
+    /// Opened with `O_TMPFILE`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Unnamed(File),
+    /// Fallback, removed on a drop.
+    Named(TempFile),
+}
+
+impl AnonymousFile {
+    /// Atomically give the file a name `path` (with a hard link), which must not exist.
+    ///
+    /// Written data isn't synced before, call [`File::sync_all`] first, if needed. To replace an
+    /// existing file, materialize under a temporary name, and then [rename](PathExt::rename_to)
+    /// it.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) on a success, otherwise an error, as reported by the OS (e.g.
+    /// [`ErrorKind::AlreadyExists`](io::ErrorKind::AlreadyExists), or a failure, when `path` is
+    /// on another filesystem). The file stays usable either way.
+    pub fn materialize(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        match &self.repr {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            AnonymousRepr::Unnamed(file) => link_unnamed(file, path.as_ref()),
+            AnonymousRepr::Named(file) => file.path().hard_link_to(path),
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn link_unnamed(file: &File, path: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::fd::AsRawFd, os::unix::ffi::OsStrExt};
+    let target = CString::new(path.as_os_str().as_bytes())?;
+    let proc_path = CString::new(format!("/proc/self/fd/{}", file.as_raw_fd()))?;
+    // SAFETY: Both paths are NUL terminated.
+    let ret = unsafe {
+        libc::linkat(
+            libc::AT_FDCWD,
+            proc_path.as_ptr(),
+            libc::AT_FDCWD,
+            target.as_ptr(),
+            libc::AT_SYMLINK_FOLLOW,
+        )
+    };
+    if ret == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    if err.kind() != io::ErrorKind::NotFound || Path::new("/proc/self/fd").exists() {
+        return Err(err);
+    }
+    // Without `/proc` only the `AT_EMPTY_PATH` is left, which requires `CAP_DAC_READ_SEARCH`.
+    // SAFETY: Both paths are NUL terminated, and `file` is a valid descriptor.
+    let ret = unsafe {
+        libc::linkat(
+            file.as_raw_fd(),
+            c"".as_ptr(),
+            libc::AT_FDCWD,
+            target.as_ptr(),
+            libc::AT_EMPTY_PATH,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+impl Deref for AnonymousFile {
+    type Target = File;
+
+    fn deref(&self) -> &Self::Target {
+        match &self.repr {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            AnonymousRepr::Unnamed(file) => file,
+            AnonymousRepr::Named(file) => file,
+        }
+    }
+}
+
+impl DerefMut for AnonymousFile {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.repr {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            AnonymousRepr::Unnamed(file) => file,
+            AnonymousRepr::Named(file) => file,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn random_suffixes_differ() {
+        let a = random_suffix(12);
+        let b = random_suffix(12);
+        assert_eq!(a.len(), 12);
+        assert!(a.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn random_bytes_differ() {
+        let mut a = [0u8; 13];
+        let mut b = [0u8; 13];
+        fill_random(&mut a);
+        fill_random(&mut b);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn tempdir_is_removed_on_drop() {
+        let dir = assert_ok!(TempDir::new());
+        let path = dir.path().to_path_buf();
+        assert!(path.is_dir());
+        assert_ok!(path.join("a").join("b").touch());
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn tempdir_keep_persists() {
+        let dir = assert_ok!(TempDir::new());
+        let path = dir.keep();
+        assert!(path.is_dir());
+        assert_ok!(path.rmtree());
+    }
+
+    #[test]
+    fn builder_respects_settings() {
+        let parent = assert_ok!(TempDir::new());
+        let file = assert_ok!(
+            TempBuilder::new()
+                .parent(parent.path())
+                .prefix("pre-")
+                .suffix(".txt")
+                .random_len(3)
+                .tempfile()
+        );
+        let name = file.path().file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("pre-"));
+        assert!(name.ends_with(".txt"));
+        assert_eq!(name.len(), "pre-".len() + 3 + ".txt".len());
+        assert_eq!(file.path().parent(), Some(parent.path()));
+    }
+
+    #[test]
+    fn tempfile_is_usable_and_removed_on_drop() {
+        let mut file = assert_ok!(TempFile::new());
+        let path = file.path().to_path_buf();
+        assert_ok!(file.write_all(b"test"));
+        assert_ok!(file.seek(SeekFrom::Start(0)));
+        let mut content = String::new();
+        assert_ok!(file.read_to_string(&mut content));
+        assert_eq!(content, "test");
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn anonymous_file_is_invisible_until_materialized() {
+        let dir = assert_ok!(TempDir::new());
+        let mut file = assert_ok!(anonymous_file(dir.path()));
+        assert_ok!(file.write_all(b"test"));
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if matches!(file.repr, AnonymousRepr::Unnamed(_)) {
+            assert!(dir.path().is_empty_dir());
+        }
+        let target = dir.path().join("target");
+        assert_ok!(file.materialize(&target));
+        let err = file.materialize(&target).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        drop(file);
+        assert_eq!(assert_ok!(target.read_to_string()), "test");
+        assert_eq!(assert_ok!(std::fs::read_dir(dir.path())).count(), 1);
+    }
+
+    #[test]
+    fn tempfile_persist() {
+        let dir = assert_ok!(TempDir::new());
+        let mut file = assert_ok!(TempFile::new_in(dir.path()));
+        assert_ok!(file.write_all(b"test"));
+        let target = dir.path().join("target");
+        assert_ok!(file.persist(&target));
+        assert_eq!(assert_ok!(target.read_to_string()), "test");
+    }
+}
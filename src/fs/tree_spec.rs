@@ -0,0 +1,213 @@
+use std::{
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::fs::{MkdirOptions, PathExt, TempDir};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    Dir,
+    File(Vec<u8>),
+    Symlink(PathBuf),
+}
+
+/// Declarative description of a directory tree, mostly for test fixtures.
+///
+/// Paths are relative to the root, given on [creation](TreeSpec::create_in). Parent directories
+/// are implied, and entries are created in the order they were added.
+///
+/// ```rust,no_run
+/// # use rustvil::fs::TreeSpec;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let spec = TreeSpec::new()
+///     .dir("a")
+///     .file("a/b.txt", "contents")
+///     .symlink("c", "a");
+/// let tmp = spec.create_temp()?;
+/// // Exercise the code under test...
+/// spec.assert_matches(tmp.path());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TreeSpec {
+    entries: Vec<(PathBuf, Entry)>,
+}
+
+impl TreeSpec {
+    /// Create an empty [`TreeSpec`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory.
+    pub fn dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.entries.push((path.as_ref().to_path_buf(), Entry::Dir));
+        self
+    }
+
+    /// Add a regular file with the `contents`.
+    pub fn file(mut self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Self {
+        self.entries.push((
+            path.as_ref().to_path_buf(),
+            Entry::File(contents.as_ref().to_vec()),
+        ));
+        self
+    }
+
+    /// Add a symlink pointing to the `target` (taken verbatim, so relative to the link).
+    ///
+    /// On Windows, a directory symlink is created, when `target` is an existing directory at
+    /// the time of creation. Creating symlinks may require extra privileges there.
+    pub fn symlink(mut self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> Self {
+        self.entries.push((
+            path.as_ref().to_path_buf(),
+            Entry::Symlink(target.as_ref().to_path_buf()),
+        ));
+        self
+    }
+
+    /// Create the tree under the `root` (which is created if needed).
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) on a success, otherwise the first error encountered. Existing files are
+    /// overwritten, other existing entries cause an error.
+    pub fn create_in(&self, root: impl AsRef<Path>) -> io::Result<()> {
+        let root = root.as_ref();
+        root.mkdir(MkdirOptions::WithParents)?;
+        for (path, entry) in &self.entries {
+            let path = root.join(path);
+            if let Some(parent) = path.parent() {
+                parent.mkdir(MkdirOptions::WithParents)?;
+            }
+            match entry {
+                Entry::Dir => path.mkdir(MkdirOptions::WithParents)?,
+                Entry::File(contents) => path.write(contents)?,
+                Entry::Symlink(target) => crate::fs::path_ext::symlink(target, &path)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the tree in a new [`TempDir`].
+    pub fn create_temp(&self) -> io::Result<TempDir> {
+        let tmp = TempDir::new()?;
+        self.create_in(tmp.path())?;
+        Ok(tmp)
+    }
+
+    /// Compare the tree under the `root` against this spec.
+    ///
+    /// Only entries in the spec are checked, extra entries under the `root` are ignored.
+    ///
+    /// # Returns
+    /// Human-readable descriptions of all mismatches (empty, if the tree matches).
+    pub fn mismatches(&self, root: impl AsRef<Path>) -> Vec<String> {
+        let root = root.as_ref();
+        self.entries
+            .iter()
+            .filter_map(|(path, entry)| {
+                let problem = check(&root.join(path), entry)?;
+                Some(format!("{}: {problem}", path.display()))
+            })
+            .collect()
+    }
+
+    /// Panic with all the [mismatches](TreeSpec::mismatches), if the tree under the `root`
+    /// doesn't match this spec.
+    #[track_caller]
+    pub fn assert_matches(&self, root: impl AsRef<Path>) {
+        let mismatches = self.mismatches(root.as_ref());
+        if !mismatches.is_empty() {
+            let mut message = format!("tree at {} doesn't match:", root.as_ref().display());
+            for mismatch in mismatches {
+                let _ = write!(message, "\n  {mismatch}");
+            }
+            panic!("{message}");
+        }
+    }
+}
+
+/// Describe, how `path` doesn't match the `entry`.
+fn check(path: &Path, entry: &Entry) -> Option<String> {
+    let metadata = match path.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(err) => return Some(err.to_string()),
+    };
+    match entry {
+        Entry::Dir if !metadata.is_dir() => Some("expected a directory".to_owned()),
+        Entry::Dir => None,
+        Entry::File(_) if !metadata.is_file() => Some("expected a regular file".to_owned()),
+        Entry::File(expected) => match path.read() {
+            Ok(contents) if contents == *expected => None,
+            Ok(contents) => Some(format!(
+                "expected contents {:?}, found {:?}",
+                String::from_utf8_lossy(expected),
+                String::from_utf8_lossy(&contents)
+            )),
+            Err(err) => Some(err.to_string()),
+        },
+        Entry::Symlink(_) if !metadata.is_symlink() => Some("expected a symlink".to_owned()),
+        Entry::Symlink(expected) => match std::fs::read_link(path) {
+            Ok(target) if target == *expected => None,
+            Ok(target) => Some(format!(
+                "expected target {}, found {}",
+                expected.display(),
+                target.display()
+            )),
+            Err(err) => Some(err.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+
+    #[test]
+    fn created_tree_matches() {
+        let spec = TreeSpec::new()
+            .dir("a/empty")
+            .file("a/b.txt", "contents")
+            .file("c/d/e", b"\0\x01");
+        let tmp = assert_ok!(spec.create_temp());
+        spec.assert_matches(tmp.path());
+        assert_eq!(
+            assert_ok!(tmp.path().join("a/b.txt").read_to_string()),
+            "contents"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinks_are_created_and_checked() {
+        let spec = TreeSpec::new().dir("a").symlink("c", "a");
+        let tmp = assert_ok!(spec.create_temp());
+        spec.assert_matches(tmp.path());
+        assert!(tmp.path().join("c").is_dir());
+        let other = TreeSpec::new().symlink("c", "b");
+        assert_eq!(
+            other.mismatches(tmp.path()),
+            ["c: expected target b, found a"]
+        );
+    }
+
+    #[test]
+    fn mismatches_are_reported() {
+        let tmp = assert_ok!(TreeSpec::new().file("a", "x").dir("b").create_temp());
+        let spec = TreeSpec::new()
+            .file("a", "y")
+            .file("b", "")
+            .dir("a")
+            .file("missing", "");
+        let mismatches = spec.mismatches(tmp.path());
+        assert_eq!(mismatches.len(), 4);
+        assert_eq!(mismatches[0], r#"a: expected contents "y", found "x""#);
+        assert_eq!(mismatches[1], "b: expected a regular file");
+        assert_eq!(mismatches[2], "a: expected a directory");
+        assert!(mismatches[3].starts_with("missing: "));
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::{Mutex, MutexGuard};
+
+/// Serializes the [`UmaskGuard::set_locked`] users.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// A RAII guard of the process umask, which restores the previous one on a drop.
+///
+/// __Umask is process-global__: while the guard is alive, it applies to files created by
+/// __all__ threads, and guards dropped out of order restore unexpected masks. Prefer the
+/// [`UmaskGuard::set_locked`], which serializes the guards (but still affects threads creating
+/// files without one), or pass explicit modes (like the
+/// [`PathExt::write_with_mode`](crate::fs::PathExt::write_with_mode)), when possible.
+///
+/// ```rust,no_run
+/// # use rustvil::fs::{PathExt, UmaskGuard};
+/// # use std::path::Path;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let _umask = UmaskGuard::set_locked(0o077);
+/// // Readable by the owner only.
+/// Path::new("secrets/key.pem").write("...")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "umask is restored, when the guard is dropped"]
+pub struct UmaskGuard {
+    previous: libc::mode_t,
+    _lock: Option<MutexGuard<'static, ()>>,
+}
+
+impl UmaskGuard {
+    /// Set the process umask to the `mask` (only permission bits are used), until the guard is
+    /// dropped.
+    pub fn set(mask: u32) -> Self {
+        Self {
+            previous: umask(mask),
+            _lock: None,
+        }
+    }
+
+    /// Like the [`UmaskGuard::set`], but blocks while another guard created by this function is
+    /// alive, so they can't interleave.
+    pub fn set_locked(mask: u32) -> Self {
+        // Umask is restored even if the previous holder panicked.
+        let lock = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Self {
+            previous: umask(mask),
+            _lock: Some(lock),
+        }
+    }
+
+    /// Return the umask, which will be restored.
+    #[allow(clippy::useless_conversion)]
+    pub fn previous(&self) -> u32 {
+        self.previous.into()
+    }
+}
+
+impl Drop for UmaskGuard {
+    fn drop(&mut self) {
+        // SAFETY: FFI call without pointers, which can't fail.
+        unsafe { libc::umask(self.previous) };
+    }
+}
+
+fn umask(mask: u32) -> libc::mode_t {
+    // Truncation is fine, only the permission bits matter.
+    let mask = (mask & 0o777) as libc::mode_t;
+    // SAFETY: FFI call without pointers, which can't fail.
+    unsafe { libc::umask(mask) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn umask_is_applied_and_restored() {
+        // Umask is process-global, so it's changed only in a child process running just this
+        // test, not to affect files created by the other tests.
+        const CHILD: &str = "RUSTVIL_UMASK_TEST_CHILD";
+        if std::env::var_os(CHILD).is_none() {
+            let (_, module) = module_path!().split_once("::").expect("crate has modules");
+            let output = std::process::Command::new(assert_ok!(std::env::current_exe()))
+                .args([
+                    "--exact",
+                    &format!("{module}::umask_is_applied_and_restored"),
+                ])
+                .env(CHILD, "1")
+                .output();
+            let output = assert_ok!(output);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "{stdout}");
+            assert!(stdout.contains("1 passed"), "{stdout}");
+            return;
+        }
+
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("secret");
+        let original = {
+            let guard = UmaskGuard::set_locked(0o077);
+            assert_ok!(path.write("x"));
+            let mode = assert_ok!(path.metadata()).permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            guard.previous()
+        };
+        let guard = UmaskGuard::set_locked(0o022);
+        assert_eq!(guard.previous(), original);
+    }
+}
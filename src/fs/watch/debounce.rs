@@ -0,0 +1,208 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use super::{Event, EventKind, Watcher};
+
+/// Changes to a single path, seen during the current burst.
+#[derive(Debug)]
+struct Burst {
+    existed_before: bool,
+    exists_now: bool,
+    deadline: Instant,
+}
+
+impl Burst {
+    /// Kind of the single event, which replaces the whole burst (if any).
+    fn kind(&self) -> Option<EventKind> {
+        match (self.existed_before, self.exists_now) {
+            (true, true) => Some(EventKind::Modified),
+            (false, true) => Some(EventKind::Created),
+            (true, false) => Some(EventKind::Removed),
+            // Temporary file, which came and went.
+            (false, false) => None,
+        }
+    }
+}
+
+/// A [`Watcher`], which coalesces bursts of changes to a path into a single event.
+///
+/// Event for a path is emitted only after it stays quiet for the configured period, so e.g.
+/// an editor writing a temporary file and renaming it over the original is reported as a change
+/// of the original only. The coalesced kind is [`Created`](EventKind::Created), when the path
+/// didn't exist before the burst, [`Removed`](EventKind::Removed), when it doesn't exist after
+/// it, and [`Modified`](EventKind::Modified) otherwise. [`Rescan`](EventKind::Rescan) events are
+/// passed through immediately.
+///
+/// Raw events are read on a background thread, which exits after the next raw event once the
+/// [`DebouncedWatcher`] is dropped (or when the watched path is removed).
+///
+/// ```rust,no_run
+/// # use rustvil::fs::watch::DebouncedWatcher;
+/// # use std::time::Duration;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// for event in DebouncedWatcher::new("src", Duration::from_millis(200))? {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DebouncedWatcher {
+    events: Receiver<io::Result<Event>>,
+    quiet: Duration,
+    bursts: HashMap<PathBuf, Burst>,
+    closed: bool,
+}
+
+impl DebouncedWatcher {
+    /// Start watching `path`, which must exist, emitting events after `quiet` period without
+    /// further changes.
+    pub fn new(path: impl AsRef<Path>, quiet: Duration) -> io::Result<Self> {
+        Watcher::new(path)?.debounced(quiet)
+    }
+
+    pub(super) fn from_watcher(mut watcher: Watcher, quiet: Duration) -> io::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("rustvil-watch".to_owned())
+            .spawn(move || {
+                while let Some(event) = watcher.next_event().transpose() {
+                    let failed = event.is_err();
+                    if sender.send(event).is_err() || failed {
+                        break;
+                    }
+                }
+            })?;
+        Ok(Self {
+            events,
+            quiet,
+            bursts: HashMap::new(),
+            closed: false,
+        })
+    }
+
+    /// Block until the next coalesced change.
+    ///
+    /// # Returns
+    /// [`Ok(Some(Event))`](Event) on a change, [`Ok(None)`](None) if the watched path is gone
+    /// (and all the pending changes were emitted), otherwise an error, as reported by the OS.
+    pub fn next_event(&mut self) -> io::Result<Option<Event>> {
+        loop {
+            let now = Instant::now();
+            let earliest = self
+                .bursts
+                .iter()
+                .min_by_key(|(_, burst)| burst.deadline)
+                .map(|(path, burst)| (path.clone(), burst.deadline));
+            if let Some((path, deadline)) = &earliest
+                && (*deadline <= now || self.closed)
+            {
+                let burst = self.bursts.remove(path).expect("path was just found");
+                if let Some(kind) = burst.kind() {
+                    let path = path.clone();
+                    return Ok(Some(Event { kind, path }));
+                }
+                continue;
+            }
+            if self.closed {
+                return Ok(None);
+            }
+            let received = match earliest.map(|(_, deadline)| deadline) {
+                Some(deadline) => self.events.recv_timeout(deadline - now),
+                None => self
+                    .events
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(Ok(event)) if event.kind == EventKind::Rescan => return Ok(Some(event)),
+                Ok(Ok(event)) => self.record(event),
+                Ok(Err(err)) => return Err(err),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => self.closed = true,
+            }
+        }
+    }
+
+    fn record(&mut self, event: Event) {
+        let exists_now = event.kind != EventKind::Removed;
+        let deadline = Instant::now() + self.quiet;
+        self.bursts
+            .entry(event.path)
+            .and_modify(|burst| {
+                burst.exists_now = exists_now;
+                burst.deadline = deadline;
+            })
+            .or_insert(Burst {
+                existed_before: event.kind != EventKind::Created,
+                exists_now,
+                deadline,
+            });
+    }
+}
+
+impl Iterator for DebouncedWatcher {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use tempfile::tempdir;
+
+    const QUIET: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn burst_is_coalesced() {
+        let tmp = tempdir().expect("needed for tests");
+        let mut watcher = assert_ok!(DebouncedWatcher::new(tmp.path(), QUIET));
+        let file = tmp.path().join("x");
+        for contents in ["a", "b", "c"] {
+            assert_ok!(file.write(contents));
+        }
+        let event = assert_ok!(watcher.next_event()).unwrap();
+        assert_eq!(event.kind, EventKind::Created);
+        assert_eq!(event.path, file);
+
+        assert_ok!(file.write("d"));
+        let event = assert_ok!(watcher.next_event()).unwrap();
+        assert_eq!(event.kind, EventKind::Modified);
+        assert_eq!(event.path, file);
+    }
+
+    #[test]
+    fn temporary_files_are_dropped() {
+        let tmp = tempdir().expect("needed for tests");
+        let (config, temp) = (tmp.path().join("config"), tmp.path().join("config.tmp"));
+        assert_ok!(config.write("old"));
+        let mut watcher = assert_ok!(DebouncedWatcher::new(tmp.path(), QUIET));
+        assert_ok!(temp.write("new"));
+        assert_ok!(std::fs::rename(&temp, &config));
+        let scratch = tmp.path().join("scratch");
+        assert_ok!(scratch.write("x"));
+        assert_ok!(scratch.rm());
+
+        let event = assert_ok!(watcher.next_event()).unwrap();
+        assert_eq!(event.path, config);
+        // Neither `config.tmp`, nor `scratch` are ever reported.
+        assert_ok!(config.rm());
+        assert_ok!(std::fs::remove_dir(tmp.path()));
+        let events: Vec<_> = watcher.map(Result::unwrap).collect();
+        assert!(
+            events
+                .iter()
+                .all(|event| event.path == config || event.path == tmp.path())
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    collections::VecDeque,
+    ffi::{CString, OsStr},
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+};
+
+use super::{Event, EventKind};
+
+const MASK: u32 = libc::IN_CREATE
+    | libc::IN_MOVED_TO
+    | libc::IN_MODIFY
+    | libc::IN_ATTRIB
+    | libc::IN_DELETE
+    | libc::IN_MOVED_FROM
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVE_SELF;
+
+// `struct inotify_event` without the trailing name.
+const HEADER_LEN: usize = size_of::<libc::inotify_event>();
+
+// Linux's `NAME_MAX`, not exposed by the `libc`.
+const NAME_MAX: usize = 255;
+
+#[derive(Debug)]
+pub(super) struct Backend {
+    fd: OwnedFd,
+    root: PathBuf,
+    buf: Vec<u8>,
+}
+
+impl Backend {
+    pub(super) fn new(path: &Path) -> io::Result<Self> {
+        let cpath = CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: FFI call without pointers.
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a freshly created, valid descriptor, which we exclusively own.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        // SAFETY: `fd` is valid and `cpath` is NUL terminated.
+        if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), cpath.as_ptr(), MASK) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            fd,
+            root: path.to_path_buf(),
+            // Enough for a lot of events with the longest names.
+            buf: vec![0; 16 * (HEADER_LEN + NAME_MAX + 1)],
+        })
+    }
+
+    pub(super) fn read_events(&mut self, out: &mut VecDeque<Event>) -> io::Result<bool> {
+        let read = loop {
+            // SAFETY: `buf` is valid for writes of `buf.len()` bytes.
+            let read = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    self.buf.as_mut_ptr().cast(),
+                    self.buf.len(),
+                )
+            };
+            if read >= 0 {
+                break read as usize;
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        };
+
+        let mut open = true;
+        let mut offset = 0;
+        while offset + HEADER_LEN <= read {
+            let field = |at: usize| {
+                let start = offset + at;
+                u32::from_ne_bytes(self.buf[start..start + 4].try_into().expect("4 bytes"))
+            };
+            // Layout: `wd: c_int`, `mask: u32`, `cookie: u32`, `len: u32`, `name: [c_char]`.
+            let mask = field(4);
+            let len = field(12) as usize;
+            let name = &self.buf[offset + HEADER_LEN..offset + HEADER_LEN + len];
+            offset += HEADER_LEN + len;
+
+            // Name is padded with NULs.
+            let name = name.split(|&b| b == 0).next().unwrap_or_default();
+            let path = if name.is_empty() {
+                self.root.clone()
+            } else {
+                self.root.join(OsStr::from_bytes(name))
+            };
+            let kind = if mask & libc::IN_Q_OVERFLOW != 0 {
+                EventKind::Rescan
+            } else if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                EventKind::Created
+            } else if mask & (libc::IN_MODIFY | libc::IN_ATTRIB) != 0 {
+                EventKind::Modified
+            } else if mask & (libc::IN_DELETE | libc::IN_MOVED_FROM) != 0 {
+                EventKind::Removed
+            } else if mask & (libc::IN_DELETE_SELF | libc::IN_MOVE_SELF) != 0 {
+                open = false;
+                EventKind::Removed
+            } else {
+                // `IN_IGNORED`: watch was removed.
+                open &= mask & libc::IN_IGNORED == 0;
+                continue;
+            };
+            let event = Event { kind, path };
+            // Single write is often reported as several `IN_MODIFY`s.
+            if out.back() != Some(&event) {
+                out.push_back(event);
+            }
+        }
+        Ok(open)
+    }
+}
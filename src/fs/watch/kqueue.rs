@@ -0,0 +1,145 @@
+use std::{
+    collections::{BTreeSet, VecDeque},
+    ffi::{CString, OsString},
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use super::{Event, EventKind};
+
+#[cfg(target_vendor = "apple")]
+const OPEN_FLAGS: libc::c_int = libc::O_EVTONLY | libc::O_CLOEXEC;
+#[cfg(not(target_vendor = "apple"))]
+const OPEN_FLAGS: libc::c_int = libc::O_RDONLY | libc::O_CLOEXEC;
+
+const FFLAGS: u32 = libc::NOTE_WRITE
+    | libc::NOTE_EXTEND
+    | libc::NOTE_ATTRIB
+    | libc::NOTE_DELETE
+    | libc::NOTE_RENAME
+    | libc::NOTE_REVOKE;
+
+#[derive(Debug)]
+pub(super) struct Backend {
+    kq: OwnedFd,
+    // Kept open, as `kqueue` watches descriptors, not paths.
+    _file: OwnedFd,
+    root: PathBuf,
+    // Snapshot of the directory entries, `None` for files.
+    entries: Option<BTreeSet<OsString>>,
+}
+
+fn cvt(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+fn list(path: &Path) -> io::Result<BTreeSet<OsString>> {
+    std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect()
+}
+
+impl Backend {
+    pub(super) fn new(path: &Path) -> io::Result<Self> {
+        let cpath = CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: `cpath` is NUL terminated.
+        let file = cvt(unsafe { libc::open(cpath.as_ptr(), OPEN_FLAGS) })?;
+        // SAFETY: `file` is a freshly opened, valid descriptor, which we exclusively own.
+        let file = unsafe { OwnedFd::from_raw_fd(file) };
+        // SAFETY: FFI call without pointers.
+        let kq = cvt(unsafe { libc::kqueue() })?;
+        // SAFETY: `kq` is a freshly created, valid descriptor, which we exclusively own.
+        let kq = unsafe { OwnedFd::from_raw_fd(kq) };
+
+        // SAFETY: `kevent` is a plain C struct, for which all zeroes is a valid value.
+        let mut change: libc::kevent = unsafe { std::mem::zeroed() };
+        change.ident = file.as_raw_fd() as _;
+        change.filter = libc::EVFILT_VNODE as _;
+        change.flags = (libc::EV_ADD | libc::EV_CLEAR) as _;
+        change.fflags = FFLAGS as _;
+        // SAFETY: `change` points to a single valid `kevent`, no events are requested.
+        cvt(unsafe { libc::kevent(kq.as_raw_fd(), &change, 1, ptr::null_mut(), 0, ptr::null()) })?;
+
+        let entries = if path.is_dir() {
+            Some(list(path)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            kq,
+            _file: file,
+            root: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub(super) fn read_events(&mut self, out: &mut VecDeque<Event>) -> io::Result<bool> {
+        // SAFETY: `kevent` is a plain C struct, for which all zeroes is a valid value.
+        let mut event: libc::kevent = unsafe { std::mem::zeroed() };
+        loop {
+            // SAFETY: `event` is valid for a write of a single `kevent`, no changes are passed.
+            let ret = unsafe {
+                libc::kevent(
+                    self.kq.as_raw_fd(),
+                    ptr::null(),
+                    0,
+                    &mut event,
+                    1,
+                    ptr::null(),
+                )
+            };
+            match cvt(ret) {
+                Ok(1) => break,
+                Ok(_) => continue,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let fflags = event.fflags as u32;
+        if fflags & (libc::NOTE_DELETE | libc::NOTE_RENAME | libc::NOTE_REVOKE) != 0 {
+            out.push_back(Event {
+                kind: EventKind::Removed,
+                path: self.root.clone(),
+            });
+            return Ok(false);
+        }
+        match &mut self.entries {
+            Some(entries) if fflags & libc::NOTE_WRITE != 0 => {
+                let current = list(&self.root)?;
+                let created = current
+                    .difference(entries)
+                    .map(|name| (EventKind::Created, name));
+                let removed = entries
+                    .difference(&current)
+                    .map(|name| (EventKind::Removed, name));
+                let before = out.len();
+                out.extend(created.chain(removed).map(|(kind, name)| Event {
+                    kind,
+                    path: self.root.join(name),
+                }));
+                if out.len() == before {
+                    out.push_back(Event {
+                        kind: EventKind::Modified,
+                        path: self.root.clone(),
+                    });
+                }
+                *entries = current;
+            }
+            _ => out.push_back(Event {
+                kind: EventKind::Modified,
+                path: self.root.clone(),
+            }),
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,192 @@
+//! Blocking file and directory change watching.
+//!
+//! Backed by the `inotify` on Linux, the `kqueue` on macOS/BSD, and the `ReadDirectoryChangesW`
+//! on Windows. Bursts of raw events can be coalesced with the [`DebouncedWatcher`].
+
+use std::{io, path::Path, path::PathBuf, time::Duration};
+
+mod debounce;
+
+pub use debounce::DebouncedWatcher;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[path = "inotify.rs"]
+mod imp;
+
+#[cfg(any(
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly",
+))]
+#[path = "kqueue.rs"]
+mod imp;
+
+#[cfg(windows)]
+#[path = "windows.rs"]
+mod imp;
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly",
+    windows,
+)))]
+mod imp {
+    use super::Event;
+    use std::{collections::VecDeque, io, path::Path};
+
+    #[derive(Debug)]
+    pub(super) struct Backend;
+
+    impl Backend {
+        pub(super) fn new(_path: &Path) -> io::Result<Self> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "watching is not supported on this platform",
+            ))
+        }
+
+        pub(super) fn read_events(&mut self, _out: &mut VecDeque<Event>) -> io::Result<bool> {
+            Ok(false)
+        }
+    }
+}
+
+/// Kind of the change reported by the [`Watcher`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum EventKind {
+    /// Entry was created (or moved into the watched directory).
+    Created,
+    /// Contents or metadata of the entry were modified.
+    Modified,
+    /// Entry was removed (or moved out of the watched directory).
+    Removed,
+    /// Some events were lost (e.g. because of a queue overflow), state should be rescanned.
+    Rescan,
+}
+
+/// Single change reported by the [`Watcher`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Event {
+    /// What happened.
+    pub kind: EventKind,
+    /// Path of the changed entry. For [`EventKind::Rescan`] it's the watched path.
+    pub path: PathBuf,
+}
+
+/// A blocking iterator of changes to the watched file, or entries of the watched directory.
+///
+/// Watching is __not__ recursive. The iterator ends, when the watched path is removed.
+///
+/// On macOS/BSD, a modification of a file inside of the watched directory is not reported
+/// (only creation and removal are), as `kqueue` doesn't provide such information.
+///
+/// Usually created by the [`PathExt::watch`](crate::fs::PathExt::watch).
+#[derive(Debug)]
+pub struct Watcher {
+    backend: imp::Backend,
+    pending: std::collections::VecDeque<Event>,
+    closed: bool,
+}
+
+impl Watcher {
+    /// Start watching `path`, which must exist.
+    pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            backend: imp::Backend::new(path.as_ref())?,
+            pending: Default::default(),
+            closed: false,
+        })
+    }
+
+    /// Block until the next change.
+    ///
+    /// # Returns
+    /// [`Ok(Some(Event))`](Event) on a change, [`Ok(None)`](None) if the watched path is gone,
+    /// otherwise an error, as reported by the OS.
+    pub fn next_event(&mut self) -> io::Result<Option<Event>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            if self.closed {
+                return Ok(None);
+            }
+            self.closed = !self.backend.read_events(&mut self.pending)?;
+        }
+    }
+
+    /// Turn into a [`DebouncedWatcher`], emitting events after `quiet` period without further
+    /// changes to a path.
+    pub fn debounced(self, quiet: Duration) -> io::Result<DebouncedWatcher> {
+        DebouncedWatcher::from_watcher(self, quiet)
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use tempfile::tempdir;
+
+    #[test]
+    fn directory_changes_are_reported() {
+        let tmp = tempdir().expect("needed for tests");
+        let mut watcher = assert_ok!(tmp.path().watch());
+        let file = tmp.path().join("x");
+        assert_ok!(file.write("a"));
+        assert_ok!(file.rm());
+
+        let created = assert_ok!(watcher.next_event()).unwrap();
+        assert_eq!(created.kind, EventKind::Created);
+        assert_eq!(created.path, file);
+        let removed = watcher
+            .find_map(|event| {
+                let event = event.ok()?;
+                (event.kind == EventKind::Removed).then_some(event)
+            })
+            .unwrap();
+        assert_eq!(removed.path, file);
+    }
+
+    #[test]
+    fn file_changes_are_reported() {
+        let tmp = tempdir().expect("needed for tests");
+        let file = tmp.path().join("x");
+        assert_ok!(file.touch());
+        let mut watcher = assert_ok!(file.watch());
+        assert_ok!(file.write("a"));
+        let event = assert_ok!(watcher.next_event()).unwrap();
+        assert_eq!(event.kind, EventKind::Modified);
+        assert_eq!(event.path, file);
+    }
+
+    #[test]
+    fn iterator_ends_on_removal() {
+        let tmp = tempdir().expect("needed for tests");
+        let file = tmp.path().join("x");
+        assert_ok!(file.touch());
+        let watcher = assert_ok!(file.watch());
+        assert_ok!(file.rm());
+        let events: Vec<_> = watcher.map(Result::unwrap).collect();
+        assert_eq!(
+            events.last().map(|event| event.kind),
+            Some(EventKind::Removed)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    io,
+    os::windows::{
+        ffi::{OsStrExt, OsStringExt},
+        io::{AsRawHandle, FromRawHandle, OwnedHandle},
+    },
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use windows_sys::Win32::{
+    Foundation::{ERROR_ACCESS_DENIED, FALSE, INVALID_HANDLE_VALUE},
+    Storage::FileSystem::{
+        CreateFileW, FILE_ACTION_ADDED, FILE_ACTION_MODIFIED, FILE_ACTION_REMOVED,
+        FILE_ACTION_RENAMED_NEW_NAME, FILE_ACTION_RENAMED_OLD_NAME, FILE_FLAG_BACKUP_SEMANTICS,
+        FILE_LIST_DIRECTORY, FILE_NOTIFY_CHANGE_ATTRIBUTES, FILE_NOTIFY_CHANGE_CREATION,
+        FILE_NOTIFY_CHANGE_DIR_NAME, FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE,
+        FILE_NOTIFY_CHANGE_SIZE, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+        OPEN_EXISTING, ReadDirectoryChangesW,
+    },
+};
+
+use super::{Event, EventKind};
+
+const FILTER: u32 = FILE_NOTIFY_CHANGE_FILE_NAME
+    | FILE_NOTIFY_CHANGE_DIR_NAME
+    | FILE_NOTIFY_CHANGE_ATTRIBUTES
+    | FILE_NOTIFY_CHANGE_SIZE
+    | FILE_NOTIFY_CHANGE_LAST_WRITE
+    | FILE_NOTIFY_CHANGE_CREATION;
+
+// `FILE_NOTIFY_INFORMATION` without the trailing name.
+const HEADER_LEN: usize = 12;
+
+#[derive(Debug)]
+pub(super) struct Backend {
+    dir: OwnedHandle,
+    root: PathBuf,
+    // Name of the watched file, when watching a file: its parent directory is watched instead.
+    file_name: Option<OsString>,
+    // `u32`s, because `ReadDirectoryChangesW` requires a `DWORD` aligned buffer.
+    buf: Vec<u32>,
+}
+
+impl Backend {
+    pub(super) fn new(path: &Path) -> io::Result<Self> {
+        let (dir, file_name) = if path.metadata()?.is_dir() {
+            (path, None)
+        } else {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+            let dir = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            (dir, Some(file_name.to_os_string()))
+        };
+        let dir = crate::fs::long_path::to_extended(dir);
+        let wide: Vec<u16> = dir.as_os_str().encode_wide().chain([0]).collect();
+        // SAFETY: `wide` is NUL terminated, other pointers are allowed to be NULL.
+        let handle = unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                FILE_LIST_DIRECTORY,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                ptr::null(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS,
+                ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `handle` is a freshly opened, valid handle, which we exclusively own.
+        let dir_handle = unsafe { OwnedHandle::from_raw_handle(handle) };
+        Ok(Self {
+            dir: dir_handle,
+            root: path.to_path_buf(),
+            file_name,
+            buf: vec![0; 16 * 1024],
+        })
+    }
+
+    pub(super) fn read_events(&mut self, out: &mut VecDeque<Event>) -> io::Result<bool> {
+        let mut read = 0u32;
+        // SAFETY: `buf` is valid for writes of its size in bytes, call is synchronous.
+        let ok = unsafe {
+            ReadDirectoryChangesW(
+                self.dir.as_raw_handle(),
+                self.buf.as_mut_ptr().cast(),
+                (self.buf.len() * size_of::<u32>()) as u32,
+                FALSE,
+                FILTER,
+                &mut read,
+                ptr::null_mut(),
+                None,
+            )
+        };
+        if ok == FALSE {
+            let err = io::Error::last_os_error();
+            // Watched directory is gone.
+            if err.kind() == io::ErrorKind::NotFound
+                || err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32)
+            {
+                out.push_back(Event {
+                    kind: EventKind::Removed,
+                    path: self.root.clone(),
+                });
+                return Ok(false);
+            }
+            return Err(err);
+        }
+        if read == 0 {
+            // Buffer overflowed.
+            out.push_back(Event {
+                kind: EventKind::Rescan,
+                path: self.root.clone(),
+            });
+            return Ok(true);
+        }
+
+        let bytes: Vec<u8> = self
+            .buf
+            .iter()
+            .flat_map(|word| word.to_ne_bytes())
+            .collect();
+        let bytes = &bytes[..read as usize];
+        let mut open = true;
+        let mut offset = 0;
+        while offset + HEADER_LEN <= bytes.len() {
+            let field = |at: usize| {
+                let start = offset + at;
+                u32::from_ne_bytes(bytes[start..start + 4].try_into().expect("4 bytes"))
+            };
+            // Layout: `NextEntryOffset: u32`, `Action: u32`, `FileNameLength: u32`, `FileName: [u16]`.
+            let next = field(0) as usize;
+            let action = field(4);
+            let len = field(8) as usize;
+            let name: Vec<u16> = bytes[offset + HEADER_LEN..offset + HEADER_LEN + len]
+                .chunks_exact(2)
+                .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+                .collect();
+            let name = OsString::from_wide(&name);
+
+            let kind = match action {
+                FILE_ACTION_ADDED | FILE_ACTION_RENAMED_NEW_NAME => Some(EventKind::Created),
+                FILE_ACTION_MODIFIED => Some(EventKind::Modified),
+                FILE_ACTION_REMOVED | FILE_ACTION_RENAMED_OLD_NAME => Some(EventKind::Removed),
+                _ => None,
+            };
+            let event = match (&self.file_name, kind) {
+                (Some(file_name), Some(kind)) if *file_name == name => {
+                    open &= kind != EventKind::Removed;
+                    Some(Event {
+                        kind,
+                        path: self.root.clone(),
+                    })
+                }
+                (None, Some(kind)) => Some(Event {
+                    kind,
+                    path: self.root.join(name),
+                }),
+                _ => None,
+            };
+            if let Some(event) = event
+                && out.back() != Some(&event)
+            {
+                out.push_back(event);
+            }
+
+            if next == 0 {
+                break;
+            }
+            offset += next;
+        }
+        Ok(open)
+    }
+}
@@ -0,0 +1,143 @@
+//! Extended attributes support, for the [`PathExt`](crate::fs::PathExt).
+
+use std::{
+    ffi::{CString, OsStr, OsString},
+    io,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::Path,
+};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const ENOATTR: libc::c_int = libc::ENODATA;
+#[cfg(target_vendor = "apple")]
+const ENOATTR: libc::c_int = libc::ENOATTR;
+
+fn cstr(s: &OsStr) -> io::Result<CString> {
+    Ok(CString::new(s.as_bytes())?)
+}
+
+/// Call `f` with a growing buffer, until it fits.
+///
+/// `f(buf, len)` has to follow the `*xattr` convention: return the needed size, when `len` is 0.
+fn with_buffer(mut f: impl FnMut(*mut libc::c_void, usize) -> isize) -> io::Result<Vec<u8>> {
+    loop {
+        let size = f(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut buf = vec![0u8; size as usize];
+        let read = f(buf.as_mut_ptr().cast(), buf.len());
+        if read >= 0 {
+            buf.truncate(read as usize);
+            return Ok(buf);
+        }
+        let err = io::Error::last_os_error();
+        // Attribute grew between the calls.
+        if err.raw_os_error() != Some(libc::ERANGE) {
+            return Err(err);
+        }
+    }
+}
+
+pub(crate) fn get(path: &Path, name: &OsStr) -> io::Result<Option<Vec<u8>>> {
+    let path = cstr(path.as_os_str())?;
+    let name = cstr(name)?;
+    let result = with_buffer(|buf, len| {
+        // SAFETY: `path` and `name` are NUL terminated, `buf` is valid for writes of `len` bytes.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        unsafe {
+            libc::getxattr(path.as_ptr(), name.as_ptr(), buf, len)
+        }
+        // SAFETY: `path` and `name` are NUL terminated, `buf` is valid for writes of `len` bytes.
+        #[cfg(target_vendor = "apple")]
+        unsafe {
+            libc::getxattr(path.as_ptr(), name.as_ptr(), buf, len, 0, 0)
+        }
+    });
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.raw_os_error() == Some(ENOATTR) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+pub(crate) fn set(path: &Path, name: &OsStr, value: &[u8]) -> io::Result<()> {
+    let path = cstr(path.as_os_str())?;
+    let name = cstr(name)?;
+    let value_ptr = value.as_ptr().cast();
+    // SAFETY: `path` and `name` are NUL terminated, `value` is valid for reads of its length.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let ret = unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value_ptr, value.len(), 0) };
+    // SAFETY: `path` and `name` are NUL terminated, `value` is valid for reads of its length.
+    #[cfg(target_vendor = "apple")]
+    let ret = unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value_ptr, value.len(), 0, 0) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub(crate) fn list(path: &Path) -> io::Result<Vec<OsString>> {
+    let path = cstr(path.as_os_str())?;
+    let names = with_buffer(|buf, len| {
+        // SAFETY: `path` is NUL terminated, `buf` is valid for writes of `len` bytes.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        unsafe {
+            libc::listxattr(path.as_ptr(), buf.cast(), len)
+        }
+        // SAFETY: `path` is NUL terminated, `buf` is valid for writes of `len` bytes.
+        #[cfg(target_vendor = "apple")]
+        unsafe {
+            libc::listxattr(path.as_ptr(), buf.cast(), len, 0)
+        }
+    })?;
+    // Names are NUL terminated, and concatenated.
+    Ok(names
+        .split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| OsString::from_vec(name.to_vec()))
+        .collect())
+}
+
+pub(crate) fn remove(path: &Path, name: &OsStr) -> io::Result<()> {
+    let path = cstr(path.as_os_str())?;
+    let name = cstr(name)?;
+    // SAFETY: `path` and `name` are NUL terminated.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let ret = unsafe { libc::removexattr(path.as_ptr(), name.as_ptr()) };
+    // SAFETY: `path` and `name` are NUL terminated.
+    #[cfg(target_vendor = "apple")]
+    let ret = unsafe { libc::removexattr(path.as_ptr(), name.as_ptr(), 0) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::PathExt;
+    use claim::assert_ok;
+    use std::ffi::OsString;
+    use tempfile::tempdir;
+
+    #[test]
+    fn xattr_roundtrip() {
+        let tmp = tempdir().expect("needed for tests");
+        let path = tmp.path().join("x");
+        assert_ok!(path.touch());
+        match path.xattr_set("user.rustvil.origin", b"https://example.com") {
+            Ok(()) => {}
+            // Not every filesystem supports user attributes.
+            Err(err) if err.kind() == std::io::ErrorKind::Unsupported => return,
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+        assert_eq!(
+            assert_ok!(path.xattr_get("user.rustvil.origin")).as_deref(),
+            Some(&b"https://example.com"[..])
+        );
+        assert!(assert_ok!(path.xattr_list()).contains(&OsString::from("user.rustvil.origin")));
+        assert_ok!(path.xattr_remove("user.rustvil.origin"));
+        assert_eq!(assert_ok!(path.xattr_get("user.rustvil.origin")), None);
+    }
+}
@@ -1,6 +1,10 @@
 use std::convert::Infallible;
-use std::io;
-use std::process::Command;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc;
+
+use thiserror::Error;
 
 mod sealed {
     use std::process::Command;
@@ -8,7 +12,61 @@ mod sealed {
     impl Sealed for Command {}
 }
 
-/// Adds portable [`exec_replace`](CommandExt::exec_replace) to the [`Command`].
+/// Errors encountered when running a command with the [`CommandExt::output_checked`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum CommandError {
+    /// This variant indicates, that the command couldn't be run (e.g. the program doesn't exist).
+    #[error("cannot run `{}`: {source}", command_line(.program, .args))]
+    Io {
+        /// Program of the command.
+        program: OsString,
+        /// Arguments of the command.
+        args: Vec<OsString>,
+        /// Underlying error.
+        #[source]
+        source: io::Error,
+    },
+
+    /// This variant indicates, that the command exited unsuccessfully.
+    #[error("`{}` failed with {status}{}", command_line(.program, .args), details(.stderr))]
+    Failed {
+        /// Program of the command.
+        program: OsString,
+        /// Arguments of the command.
+        args: Vec<OsString>,
+        /// Exit status of the command.
+        status: ExitStatus,
+        /// Captured standard error of the command (converted lossily).
+        stderr: String,
+    },
+
+    /// This variant indicates, that output of the command isn't an UTF-8 string (only without
+    /// the [`CommandExt::output_checked_lossy`]).
+    #[error("output of `{}` is not an UTF-8 string", command_line(.program, .args))]
+    NonUTF8 {
+        /// Program of the command.
+        program: OsString,
+        /// Arguments of the command.
+        args: Vec<OsString>,
+    },
+}
+
+/// Output of a command, which has exited successfully, see the [`CommandExt::output_checked`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckedOutput {
+    /// Exit status of the command (always a successful one).
+    pub status: ExitStatus,
+    /// Captured standard output.
+    pub stdout: String,
+    /// Captured standard error.
+    pub stderr: String,
+}
+
+/// Adds portable [`exec_replace`](CommandExt::exec_replace), checked
+/// [`output_checked`](CommandExt::output_checked), and live
+/// [`stream_lines`](CommandExt::stream_lines) to the [`Command`].
 pub trait CommandExt: sealed::Sealed {
     // TODO: Replace `Infallible` with `!` when latter is stabilized.
     /// Replace current process with command from `Self` and execute it.
@@ -17,6 +75,63 @@ pub trait CommandExt: sealed::Sealed {
     /// [`Err`](io::Error) variant means, that spawning new command failed.
     /// Otherwise this function shall never return.
     fn exec_replace(&mut self) -> io::Result<Infallible>;
+
+    /// Run the command, capturing its output (like the [`Command::output`]), and check, that it
+    /// has exited successfully.
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::os::CommandExt;
+    /// # use std::process::Command;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let output = Command::new("git").args(["rev-parse", "HEAD"]).output_checked()?;
+    /// println!("commit {}", output.stdout.trim());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Returns
+    /// [`Ok(CheckedOutput)`](CheckedOutput) on a success, otherwise a [`CommandError`]: with the
+    /// command line, exit status and captured standard error, if the command has failed.
+    fn output_checked(&mut self) -> Result<CheckedOutput, CommandError>;
+
+    /// Same as the [`CommandExt::output_checked`], but invalid UTF-8 in the output is replaced
+    /// with `U+FFFD` (like in the [`String::from_utf8_lossy`]), instead of being an error.
+    fn output_checked_lossy(&mut self) -> Result<CheckedOutput, CommandError>;
+
+    /// Run the command, calling the `on_stdout` and `on_stderr` with lines of its output (without
+    /// the line endings, and converted lossily), as soon as they appear, e.g. for showing a live
+    /// build log, while also capturing it.
+    ///
+    /// Both pipes are read concurrently, so the command never blocks on a full one. Callbacks
+    /// are called on the current thread, in the order the lines were read.
+    ///
+    /// ```rust,no_run
+    /// # use rustvil::os::CommandExt;
+    /// # use std::process::Command;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut warnings = Vec::new();
+    /// let status = Command::new("cargo").arg("build").stream_lines(
+    ///     |line| println!("{line}"),
+    ///     |line| {
+    ///         eprintln!("{line}");
+    ///         if line.starts_with("warning") {
+    ///             warnings.push(line.to_owned());
+    ///         }
+    ///     },
+    /// )?;
+    /// println!("{status}, {} warnings", warnings.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Returns
+    /// [`Ok(ExitStatus)`](ExitStatus) of the command, once it has exited and its output was
+    /// read, otherwise an error, as reported by the OS.
+    fn stream_lines(
+        &mut self,
+        on_stdout: impl FnMut(&str),
+        on_stderr: impl FnMut(&str),
+    ) -> io::Result<ExitStatus>;
 }
 
 impl CommandExt for Command {
@@ -47,4 +162,216 @@ impl CommandExt for Command {
     fn exec_replace(&mut self) -> io::Result<Infallible> {
         Err(io::Error::other("implement `exec_replace`"))
     }
+
+    fn output_checked(&mut self) -> Result<CheckedOutput, CommandError> {
+        checked(self, false)
+    }
+
+    fn output_checked_lossy(&mut self) -> Result<CheckedOutput, CommandError> {
+        checked(self, true)
+    }
+
+    fn stream_lines(
+        &mut self,
+        mut on_stdout: impl FnMut(&str),
+        mut on_stderr: impl FnMut(&str),
+    ) -> io::Result<ExitStatus> {
+        let mut child = self.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+        let (sender, receiver) = mpsc::channel();
+        let mut failure = None;
+        std::thread::scope(|scope| {
+            let stderr_sender = sender.clone();
+            scope.spawn(move || forward_lines(stdout, Stream::Stdout, sender));
+            scope.spawn(move || forward_lines(stderr, Stream::Stderr, stderr_sender));
+            // Ends, when both pipes are closed.
+            for (stream, line) in receiver {
+                match (stream, line) {
+                    (Stream::Stdout, Ok(line)) => on_stdout(&line),
+                    (Stream::Stderr, Ok(line)) => on_stderr(&line),
+                    (_, Err(err)) => {
+                        failure.get_or_insert(err);
+                    }
+                }
+            }
+        });
+        // Waited for anyway, so it isn't left as a zombie.
+        let status = child.wait()?;
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(status),
+        }
+    }
+}
+
+/// Output stream of a child process.
+#[derive(Debug, Clone, Copy)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Send lines of the `pipe` (if any) with the `stream`, until it's closed, or fails.
+fn forward_lines(
+    pipe: Option<impl Read>,
+    stream: Stream,
+    sender: mpsc::Sender<(Stream, io::Result<String>)>,
+) {
+    let Some(pipe) = pipe else {
+        return;
+    };
+    let mut pipe = BufReader::new(pipe);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match pipe.read_until(b'\n', &mut line) {
+            Ok(0) => return,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                let text = text.strip_suffix('\n').unwrap_or(&text);
+                let text = text.strip_suffix('\r').unwrap_or(text);
+                if sender.send((stream, Ok(text.to_owned()))).is_err() {
+                    return;
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                let _ = sender.send((stream, Err(err)));
+                return;
+            }
+        }
+    }
+}
+
+fn checked(command: &mut Command, lossy: bool) -> Result<CheckedOutput, CommandError> {
+    let program = command.get_program().to_os_string();
+    let args: Vec<OsString> = command.get_args().map(OsStr::to_os_string).collect();
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = match command.output() {
+        Ok(output) => output,
+        Err(source) => {
+            return Err(CommandError::Io {
+                program,
+                args,
+                source,
+            });
+        }
+    };
+    if !status.success() {
+        return Err(CommandError::Failed {
+            program,
+            args,
+            status,
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        });
+    }
+    let decode = |bytes: Vec<u8>| {
+        if lossy {
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        } else {
+            String::from_utf8(bytes).ok()
+        }
+    };
+    match (decode(stdout), decode(stderr)) {
+        (Some(stdout), Some(stderr)) => Ok(CheckedOutput {
+            status,
+            stdout,
+            stderr,
+        }),
+        _ => Err(CommandError::NonUTF8 { program, args }),
+    }
+}
+
+/// Format the command for messages, quoting arguments with whitespace or quotes.
+fn command_line(program: &OsStr, args: &[OsString]) -> String {
+    let mut line = program.to_string_lossy().into_owned();
+    for arg in args {
+        let arg = arg.to_string_lossy();
+        line.push(' ');
+        if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+            line.push_str(&format!("{arg:?}"));
+        } else {
+            line.push_str(&arg);
+        }
+    }
+    line
+}
+
+/// Format the captured `stderr` for the end of a message, if there is any.
+fn details(stderr: &str) -> String {
+    let stderr = stderr.trim_end();
+    if stderr.is_empty() {
+        String::new()
+    } else {
+        format!(": {stderr}")
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    fn output_is_checked() {
+        let output = assert_ok!(
+            Command::new("sh")
+                .args(["-c", "echo out; echo err >&2"])
+                .output_checked()
+        );
+        assert_eq!(
+            (output.stdout.as_str(), output.stderr.as_str()),
+            ("out\n", "err\n")
+        );
+
+        let err = assert_err!(
+            Command::new("sh")
+                .args(["-c", "echo oops >&2; exit 3"])
+                .output_checked()
+        );
+        assert!(matches!(&err, CommandError::Failed { status, .. } if status.code() == Some(3)));
+        assert_eq!(
+            err.to_string(),
+            "`sh -c \"echo oops >&2; exit 3\"` failed with exit status: 3: oops"
+        );
+
+        let mut invalid = Command::new("printf");
+        invalid.arg("\\377");
+        assert!(matches!(
+            assert_err!(invalid.output_checked()),
+            CommandError::NonUTF8 { .. }
+        ));
+        assert_eq!(
+            assert_ok!(invalid.output_checked_lossy()).stdout,
+            "\u{fffd}"
+        );
+
+        let err = assert_err!(Command::new("/nonexistent/program").output_checked());
+        assert!(matches!(err, CommandError::Io { .. }), "{err}");
+    }
+
+    #[test]
+    fn lines_are_streamed() {
+        let (mut stdout, mut stderr) = (Vec::new(), 0);
+        let status = assert_ok!(
+            Command::new("sh")
+                .args([
+                    "-c",
+                    "echo a; yes err | head -n 100000 >&2; printf 'b\\r\\nc'; exit 2"
+                ])
+                .stream_lines(
+                    |line| stdout.push(line.to_owned()),
+                    |line| {
+                        assert_eq!(line, "err");
+                        stderr += 1;
+                    }
+                )
+        );
+        assert_eq!(status.code(), Some(2));
+        assert_eq!(stdout, ["a", "b", "c"]);
+        assert_eq!(stderr, 100000);
+    }
 }
@@ -0,0 +1,168 @@
+use std::io::IsTerminal;
+
+use crate::os::env::Env;
+
+/// Colors supported by a terminal, see the [`color_support`]. Levels are ordered, so
+/// `support >= ColorSupport::Ansi256` can be checked.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    /// Colors shouldn't be used.
+    None,
+    /// The basic 16 ANSI colors.
+    Ansi16,
+    /// The 256 colors palette.
+    Ansi256,
+    /// 24-bit RGB colors.
+    TrueColor,
+}
+
+/// Detect colors supported by the terminal, from the variables of the `env`, assuming the
+/// output goes to a terminal (see the [`color_support_for`] otherwise).
+///
+/// In the order of precedence:
+/// - `CLICOLOR_FORCE` (non-empty, and not `0`), or `FORCE_COLOR` (`1`, `2`, `3` or `true`, or
+///   empty for the level `1`, and `0` or `false` for no colors) force the colors,
+/// - `NO_COLOR` (non-empty) disables them,
+/// - `CLICOLOR=0`, or `TERM=dumb` disable them,
+/// - `COLORTERM=truecolor` (or `24bit`), and the `TERM` (like `xterm-256color`) choose the
+///   level, with no colors without the `TERM` (except on Windows, whose consoles support them).
+///
+/// # Examples
+/// ```rust
+/// # use rustvil::os::env::{self, ColorSupport, Env};
+/// let env = Env::from_iter([("TERM", "xterm-256color")]);
+/// assert_eq!(env::color_support(&env), ColorSupport::Ansi256);
+/// ```
+pub fn color_support(env: &Env) -> ColorSupport {
+    detect(env, true)
+}
+
+/// Same as the [`color_support`], but returns [`ColorSupport::None`] if the `stream` (like the
+/// [`std::io::stdout`]) isn't a terminal, unless colors are forced.
+///
+/// # Examples
+/// ```rust,no_run
+/// # use rustvil::os::env::{self, ColorSupport, Env};
+/// if env::color_support_for(&Env::new(), &std::io::stdout()) > ColorSupport::None {
+///     println!("\x1b[32mok\x1b[0m");
+/// }
+/// ```
+pub fn color_support_for(env: &Env, stream: &impl IsTerminal) -> ColorSupport {
+    detect(env, stream.is_terminal())
+}
+
+fn detect(env: &Env, is_terminal: bool) -> ColorSupport {
+    let get = |key| env.get_lossy(key);
+    let forced = match get("FORCE_COLOR").as_deref().map(str::trim) {
+        Some("0" | "false") => return ColorSupport::None,
+        Some("" | "1" | "true") => Some(ColorSupport::Ansi16),
+        Some("2") => Some(ColorSupport::Ansi256),
+        Some("3") => Some(ColorSupport::TrueColor),
+        // Some tools set other values, forcing nothing.
+        Some(_) | None => None,
+    }
+    .or_else(|| {
+        get("CLICOLOR_FORCE")
+            .filter(|value| !value.is_empty() && value != "0")
+            .map(|_| ColorSupport::Ansi16)
+    });
+    if let Some(forced) = forced {
+        return forced.max(level(env));
+    }
+    if env.has_non_empty("NO_COLOR")
+        || !is_terminal
+        || get("CLICOLOR").as_deref() == Some("0")
+        || get("TERM").as_deref() == Some("dumb")
+    {
+        return ColorSupport::None;
+    }
+    level(env)
+}
+
+/// Level of the terminal, according to the `COLORTERM` and the `TERM`.
+fn level(env: &Env) -> ColorSupport {
+    let colorterm = env
+        .get_lossy("COLORTERM")
+        .map(|value| value.to_ascii_lowercase());
+    let term = env
+        .get_non_empty("TERM")
+        .map(|value| value.to_string_lossy().to_ascii_lowercase());
+    let is_truecolor = |value: &str| {
+        ["truecolor", "24bit", "direct"]
+            .iter()
+            .any(|suffix| value.ends_with(suffix))
+    };
+    if colorterm.as_deref().is_some_and(is_truecolor) || term.as_deref().is_some_and(is_truecolor) {
+        return ColorSupport::TrueColor;
+    }
+    match term.as_deref() {
+        Some("dumb") => ColorSupport::None,
+        Some(term) if term.contains("256") => ColorSupport::Ansi256,
+        Some(_) => ColorSupport::Ansi16,
+        None if cfg!(windows) && env.has("WT_SESSION") => ColorSupport::TrueColor,
+        None if cfg!(windows) => ColorSupport::Ansi16,
+        None => ColorSupport::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_support_is_detected() {
+        type Case<'a> = (&'a [(&'a str, &'a str)], bool, ColorSupport);
+        let cases: &[Case<'_>] = &[
+            (&[("TERM", "xterm")], true, ColorSupport::Ansi16),
+            (&[("TERM", "xterm-256color")], true, ColorSupport::Ansi256),
+            (&[("TERM", "xterm-direct")], true, ColorSupport::TrueColor),
+            (
+                &[("TERM", "xterm"), ("COLORTERM", "truecolor")],
+                true,
+                ColorSupport::TrueColor,
+            ),
+            (&[("TERM", "xterm")], false, ColorSupport::None),
+            (&[("TERM", "dumb")], true, ColorSupport::None),
+            (
+                &[("TERM", "xterm"), ("NO_COLOR", "1")],
+                true,
+                ColorSupport::None,
+            ),
+            (
+                &[("TERM", "xterm"), ("NO_COLOR", "")],
+                true,
+                ColorSupport::Ansi16,
+            ),
+            (
+                &[("TERM", "xterm"), ("CLICOLOR", "0")],
+                true,
+                ColorSupport::None,
+            ),
+            (
+                &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")],
+                false,
+                ColorSupport::Ansi16,
+            ),
+            (&[("CLICOLOR_FORCE", "0")], false, ColorSupport::None),
+            (&[("FORCE_COLOR", "3")], false, ColorSupport::TrueColor),
+            (
+                &[("FORCE_COLOR", ""), ("TERM", "xterm-256color")],
+                false,
+                ColorSupport::Ansi256,
+            ),
+            (
+                &[("FORCE_COLOR", "0"), ("CLICOLOR_FORCE", "1")],
+                true,
+                ColorSupport::None,
+            ),
+        ];
+        for (vars, is_terminal, expected) in cases {
+            assert_eq!(
+                detect(&Env::from(*vars), *is_terminal),
+                *expected,
+                "{vars:?}"
+            );
+        }
+        assert!(ColorSupport::TrueColor > ColorSupport::Ansi256);
+    }
+}
@@ -0,0 +1,383 @@
+use std::ffi::OsString;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor,
+    value::SeqDeserializer,
+};
+
+use crate::os::env::{Env, EnvParseError, parse_bool};
+
+impl de::Error for EnvParseError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::Missing(OsString::from(field.to_uppercase()))
+    }
+}
+
+impl Env {
+    /// Deserialize the variables into a `T` (usually a struct), like a config: each field is
+    /// read from the variable named like it, in uppercase (so `timeout_secs` from the
+    /// `$TIMEOUT_SECS`).
+    ///
+    /// Values are parsed according to the field types: booleans like in the [`Env::get_bool`],
+    /// sequences are separated with `,`, and enums are read from the variant names. Missing (and
+    /// empty) variables make [`Option`]s [`None`], and fields with `#[serde(default)]` get their
+    /// defaults. A map (or a `#[serde(flatten)]` one) gets all the variables, except ones with
+    /// non-UTF-8 keys or values (which can't be told apart from the unrelated ones).
+    ///
+    /// # Returns
+    /// [`Ok(T)`](Ok) on a success, otherwise an [`EnvParseError`]: of the first invalid, or
+    /// missing (required) variable, or [`EnvParseError::Custom`] for other errors.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::Env;
+    /// #[derive(serde::Deserialize)]
+    /// struct Config {
+    ///     port: u16,
+    ///     #[serde(default)]
+    ///     debug: bool,
+    ///     database_url: Option<String>,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let env = Env::from_iter([("PORT", "8080")]);
+    /// let config: Config = env.deserialize_into()?;
+    /// assert_eq!(config.port, 8080);
+    /// assert!(!config.debug && config.database_url.is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deserialize_into<T: DeserializeOwned>(&self) -> Result<T, EnvParseError> {
+        self.deserialize_with_prefix("")
+    }
+
+    /// Same as the [`Env::deserialize_into`], but the variables are prefixed with the `prefix`
+    /// (like `APP_`, so `timeout_secs` is read from the `$APP_TIMEOUT_SECS`), and a map gets only
+    /// the variables with the `prefix` (without it).
+    pub fn deserialize_with_prefix<T: DeserializeOwned>(
+        &self,
+        prefix: &str,
+    ) -> Result<T, EnvParseError> {
+        T::deserialize(EnvDeserializer { env: self, prefix }).map_err(|err| match err {
+            EnvParseError::Missing(key) => {
+                let mut prefixed = OsString::from(prefix);
+                prefixed.push(key);
+                EnvParseError::Missing(prefixed)
+            }
+            err => err,
+        })
+    }
+}
+
+struct EnvDeserializer<'a> {
+    env: &'a Env,
+    prefix: &'a str,
+}
+
+impl<'de> de::Deserializer<'de> for EnvDeserializer<'_> {
+    type Error = EnvParseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut entries = Vec::new();
+        for (key, value) in self.env.iter() {
+            let Some(name) = key.to_str().and_then(|key| key.strip_prefix(self.prefix)) else {
+                continue;
+            };
+            // Any environment can contain such variables, even if unrelated to the app.
+            let Some(value) = value.to_str() else {
+                continue;
+            };
+            entries.push((
+                name.to_owned(),
+                Value {
+                    key: key.to_string_lossy().into_owned(),
+                    value: value.to_owned(),
+                },
+            ));
+        }
+        visitor.visit_map(Entries {
+            entries: entries.into_iter(),
+            next: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let mut entries = Vec::new();
+        for field in fields {
+            let key = format!("{}{}", self.prefix, field.to_uppercase());
+            let Some(value) = self.env.get_os(&key) else {
+                continue;
+            };
+            let value = value
+                .to_str()
+                .ok_or_else(|| EnvParseError::NonUTF8(OsString::from(&key)))?;
+            entries.push((
+                (*field).to_owned(),
+                Value {
+                    key,
+                    value: value.to_owned(),
+                },
+            ));
+        }
+        visitor.visit_map(Entries {
+            entries: entries.into_iter(),
+            next: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct enum identifier
+        ignored_any
+    }
+}
+
+/// Yields the found variables to the visitor.
+struct Entries {
+    entries: std::vec::IntoIter<(String, Value)>,
+    next: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for Entries {
+    type Error = EnvParseError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some((name, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.next = Some(value);
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self.next.take().expect("value is requested after its key");
+        seed.deserialize(value)
+    }
+}
+
+/// Value of the variable `key`, parsed according to the requested type.
+struct Value {
+    key: String,
+    value: String,
+}
+
+impl Value {
+    fn invalid(&self, message: impl ToString) -> EnvParseError {
+        EnvParseError::Invalid {
+            key: OsString::from(&self.key),
+            value: self.value.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Attach the variable to an error of the visitor.
+    fn context(&self, err: EnvParseError) -> EnvParseError {
+        match err {
+            EnvParseError::Custom(message) => self.invalid(message),
+            err => err,
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, EnvParseError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let parsed = self.value.trim().parse().map_err(|err| self.invalid(err))?;
+                visitor.$visit(parsed).map_err(|err| self.context(err))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = EnvParseError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor
+            .visit_str(&self.value)
+            .map_err(|err| self.context(err))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let parsed = parse_bool(&self.value).ok_or_else(|| {
+            self.invalid("expected one of `1/0`, `true/false`, `yes/no` or `on/off`")
+        })?;
+        visitor.visit_bool(parsed)
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.value.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let items: Vec<Value> = self
+            .value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| Value {
+                key: self.key.clone(),
+                value: item.to_owned(),
+            })
+            .collect();
+        let mut items = SeqDeserializer::new(items.into_iter());
+        let value = visitor.visit_seq(&mut items)?;
+        items.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let variant: de::value::StrDeserializer<'_, EnvParseError> =
+            self.value.as_str().into_deserializer();
+        visitor.visit_enum(variant).map_err(|err| self.context(err))
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        port: u16,
+        #[serde(default)]
+        verbose: bool,
+        hosts: Vec<String>,
+        level: Option<Level>,
+        timeout_secs: Option<u64>,
+    }
+
+    #[test]
+    fn struct_is_deserialized() {
+        let vars = Env::from_iter([
+            ("APP_PORT", "8080"),
+            ("APP_VERBOSE", "yes"),
+            ("APP_HOSTS", "a, b,"),
+            ("APP_LEVEL", "debug"),
+            ("APP_TIMEOUT_SECS", ""),
+            ("PORT", "x"),
+        ]);
+        let config: Config = assert_ok!(vars.deserialize_with_prefix("APP_"));
+        assert_eq!(
+            config,
+            Config {
+                port: 8080,
+                verbose: true,
+                hosts: vec!["a".to_owned(), "b".to_owned()],
+                level: Some(Level::Debug),
+                timeout_secs: None,
+            }
+        );
+        let map: HashMap<String, String> = assert_ok!(vars.deserialize_with_prefix("APP_"));
+        assert_eq!(map["LEVEL"], "debug");
+        assert_eq!(map.len(), 5);
+
+        let err = assert_err!(vars.deserialize_into::<Config>());
+        assert!(
+            matches!(&err, EnvParseError::Invalid { key, value, .. } if key == "PORT" && value == "x"),
+            "{err}"
+        );
+        let err = assert_err!(vars.deserialize_with_prefix::<Config>("OTHER_"));
+        assert_eq!(err, EnvParseError::Missing(OsString::from("OTHER_PORT")));
+        let err = assert_err!(Env::from_iter([("PORT", "1")]).deserialize_into::<Config>());
+        assert_eq!(err, EnvParseError::Missing(OsString::from("HOSTS")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unrelated_non_utf8_variables_are_skipped() {
+        use std::os::unix::ffi::OsStringExt;
+
+        #[derive(Debug, Deserialize)]
+        struct Flattened {
+            #[serde(flatten)]
+            rest: HashMap<String, String>,
+        }
+
+        let mut vars = Env::from_iter([("PORT", "8080"), ("HOME", "/home/user")]);
+        vars.extend([(
+            OsString::from("LEGACY"),
+            OsString::from_vec(b"caf\xe9".to_vec()),
+        )]);
+        let map: HashMap<String, String> = assert_ok!(vars.deserialize_into());
+        assert_eq!(map.len(), 2);
+        let flattened: Flattened = assert_ok!(vars.deserialize_into());
+        assert_eq!(flattened.rest["HOME"], "/home/user");
+        assert!(!flattened.rest.contains_key("LEGACY"));
+    }
+}
@@ -0,0 +1,121 @@
+use std::ffi::OsString;
+
+use crate::os::env::Env;
+
+/// Differences between two [`Env`]s, see the [`Env::diff`] and the [`Env::reload`].
+///
+/// Keys are compared exactly, and sorted.
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct EnvDiff {
+    /// Keys of the added variables.
+    pub added: Vec<OsString>,
+    /// Keys of the removed variables.
+    pub removed: Vec<OsString>,
+    /// Keys of the variables with changed values.
+    pub changed: Vec<OsString>,
+}
+
+impl EnvDiff {
+    /// Check, whether nothing has changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Env {
+    /// Compare this [`Env`] (as the old one) with the `other` (as the new one).
+    pub fn diff(&self, other: &Env) -> EnvDiff {
+        let mut diff = EnvDiff::default();
+        for (key, value) in &self.env {
+            match other.env.get(key) {
+                None => diff.removed.push(key.clone()),
+                Some(other) if other != value => diff.changed.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.added = other
+            .env
+            .keys()
+            .filter(|key| !self.env.contains_key(*key))
+            .cloned()
+            .collect();
+        diff.added.sort_unstable();
+        diff.removed.sort_unstable();
+        diff.changed.sort_unstable();
+        diff
+    }
+
+    /// Return a hash of all the variables, which is cheap to compare, e.g. for detecting changes
+    /// of the environment in long-running processes.
+    ///
+    /// It doesn't depend on the order of the variables, and is stable across runs, and
+    /// platforms (but not [case sensitivities](Env::with_case), or encodings of the keys).
+    pub fn fingerprint(&self) -> u64 {
+        let mut variables: Vec<_> = self.iter().collect();
+        variables.sort_unstable();
+        let mut hash = Fnv::new();
+        for (key, value) in variables {
+            // Lengths keep `A=BC` and `AB=C` apart.
+            for part in [key, value] {
+                let bytes = part.as_encoded_bytes();
+                hash.write(&(bytes.len() as u64).to_le_bytes());
+                hash.write(bytes);
+            }
+        }
+        hash.0
+    }
+}
+
+/// The FNV-1a hash, which doesn't change between Rust versions (unlike the
+/// [`DefaultHasher`](std::hash::DefaultHasher)).
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_are_detected() {
+        let old = Env::from_iter([("A", "1"), ("B", "2"), ("C", "3")]);
+        let new = Env::from_iter([("A", "1"), ("B", "x"), ("D", "4"), ("E", "5")]);
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["D", "E"]);
+        assert_eq!(diff.removed, ["C"]);
+        assert_eq!(diff.changed, ["B"]);
+        assert!(!diff.is_empty());
+        assert!(old.diff(&old.clone()).is_empty());
+
+        assert_ne!(old.fingerprint(), new.fingerprint());
+        let reordered = Env::from_iter([("C", "3"), ("A", "1"), ("B", "2")]);
+        assert_eq!(old.fingerprint(), reordered.fingerprint());
+        assert_ne!(
+            Env::from_iter([("A", "BC")]).fingerprint(),
+            Env::from_iter([("AB", "C")]).fingerprint()
+        );
+        assert_eq!(Env::empty().fingerprint(), 0xcbf2_9ce4_8422_2325);
+
+        let mut env = old.clone();
+        let reloaded = std::collections::HashMap::from([
+            (OsString::from("A"), OsString::from("1")),
+            (OsString::from("B"), OsString::from("x")),
+        ]);
+        let diff = env.reload_from(reloaded);
+        assert_eq!(diff.removed, ["C"]);
+        assert_eq!(diff.changed, ["B"]);
+        assert_eq!(env.get("B"), Ok("x"));
+    }
+}
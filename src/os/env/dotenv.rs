@@ -0,0 +1,124 @@
+use crate::config_files::dotenv::{self, ParseError, Precedence};
+use crate::os::env::Env;
+
+impl Env {
+    /// Format the variables in the `.env` format (see the [`dotenv`]), one `KEY=value` line per
+    /// variable, sorted by the keys.
+    ///
+    /// Values are quoted and escaped, if needed, so the [`Env::from_dotenv_str`] reads them back
+    /// unchanged (including any `$`). Variables, which can't be represented (with non-UTF-8
+    /// keys or values, or keys, which aren't valid names, like `ProgramFiles(x86)`), are skipped.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::Env;
+    /// let env = Env::from_iter([("NAME", "app"), ("GREETING", "Hello, $USER!")]);
+    /// assert_eq!(env.to_dotenv_string(), "GREETING='Hello, $USER!'\nNAME=app\n");
+    /// ```
+    pub fn to_dotenv_string(&self) -> String {
+        let mut variables: Vec<(&str, &str)> = self
+            .iter()
+            .filter_map(|(key, value)| Some((key.to_str()?, value.to_str()?)))
+            .filter(|(key, _)| is_name(key))
+            .collect();
+        variables.sort_unstable();
+        let mut formatted = String::new();
+        for (key, value) in variables {
+            formatted.push_str(key);
+            formatted.push('=');
+            push_quoted(&mut formatted, value);
+            formatted.push('\n');
+        }
+        formatted
+    }
+
+    /// Parse the `contents` in the `.env` format (see the [`dotenv`]), like the ones made by the
+    /// [`Env::to_dotenv_string`].
+    ///
+    /// Only the variables defined earlier in the `contents` are interpolated, and repeated ones
+    /// take the last value.
+    ///
+    /// # Returns
+    /// [`Ok(Env)`](Env) with the variables on a success, otherwise a [`ParseError`].
+    pub fn from_dotenv_str(contents: &str) -> Result<Env, ParseError> {
+        let mut env = Env::empty();
+        for (key, value) in dotenv::parse(contents, &Env::empty(), Precedence::File)? {
+            env.set(key, value);
+        }
+        Ok(env)
+    }
+}
+
+/// Check, whether the `key` is read back by the parser.
+fn is_name(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn push_quoted(formatted: &mut String, value: &str) {
+    let bare = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-./:,@+=%^~".contains(c));
+    if bare {
+        formatted.push_str(value);
+    } else if !value.contains(|c: char| c == '\'' || c.is_control()) {
+        formatted.push('\'');
+        formatted.push_str(value);
+        formatted.push('\'');
+    } else {
+        formatted.push('"');
+        for c in value.chars() {
+            match c {
+                '\n' => formatted.push_str("\\n"),
+                '\r' => formatted.push_str("\\r"),
+                '\t' => formatted.push_str("\\t"),
+                '"' | '\\' | '$' => {
+                    formatted.push('\\');
+                    formatted.push(c);
+                }
+                c => formatted.push(c),
+            }
+        }
+        formatted.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+    use std::ffi::OsStr;
+
+    #[test]
+    fn env_is_round_tripped() {
+        let values = [
+            ("EMPTY", ""),
+            ("PLAIN", "/usr/bin:/bin"),
+            ("SPACES", "  a b  "),
+            ("DOLLAR", "$HOME ${X:-y}"),
+            ("QUOTES", "it's \"quoted\" \\ $HOME"),
+            ("LINES", "a\nb\r\n\tc"),
+            ("HASH", "# not a comment"),
+        ];
+        let mut env = Env::from_iter(values);
+        env.set("ProgramFiles(x86)", "skipped");
+        let formatted = env.to_dotenv_string();
+        assert!(
+            formatted.starts_with("DOLLAR='$HOME ${X:-y}'\nEMPTY=\n"),
+            "{formatted}"
+        );
+        assert!(!formatted.contains("skipped"));
+
+        let parsed = assert_ok!(Env::from_dotenv_str(&formatted));
+        assert_eq!(parsed.len(), values.len());
+        for (key, value) in values {
+            assert_eq!(parsed.get_os(key), Some(OsStr::new(value)), "{key}");
+        }
+
+        let err = assert_err!(Env::from_dotenv_str("A=1\nB"));
+        assert_eq!(err.line, 2);
+    }
+}
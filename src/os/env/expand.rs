@@ -0,0 +1,348 @@
+use thiserror::Error;
+
+use crate::os::env::{Env, EnvStrError};
+
+/// Errors encountered when expanding variables, see the [`Env::expand`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum ExpandError {
+    /// Variable is undefined, and has no default.
+    #[error("variable `${0}` is undefined")]
+    Undefined(String),
+
+    /// Variable isn't an UTF-8 string.
+    #[error("variable `${0}` is not an UTF-8 string")]
+    NonUtf8(String),
+
+    /// A `${` (at the byte offset `Unterminated.0`) has no matching `}`.
+    #[error("unterminated `${{` at offset {0}")]
+    Unterminated(usize),
+}
+
+/// What the [`Env::expand`] does with undefined variables (without a default).
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Undefined {
+    /// Fail with the [`ExpandError::Undefined`].
+    #[default]
+    Error,
+    /// Replace them with an empty string, like shells do.
+    Empty,
+    /// Keep the reference, like `$VAR`, as it is.
+    Keep,
+}
+
+impl Env {
+    /// Substitute variables in the `input`, like `"%APPDATA%\app"` or `"${HOME}/app"`.
+    ///
+    /// - `$VAR` and `${VAR}` are replaced with the value of `VAR`,
+    /// - `${VAR:-default}` uses the `default` (which gets expanded too), if `VAR` is unset or
+    ///   empty,
+    /// - `%VAR%` (the Windows style) is replaced with the value of `VAR`, whose name may contain
+    ///   parentheses too, like `%ProgramFiles(x86)%`,
+    /// - `$$` and `%%` are a literal `$` and `%`, and so are ones not followed by a name (like in
+    ///   `50% off`).
+    ///
+    /// Undefined variables are treated according to the `undefined`.
+    ///
+    /// # Returns
+    /// [`Ok(String)`](String) with the expanded `input` on a success, otherwise an
+    /// [`ExpandError`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::{Env, Undefined};
+    /// let env = Env::from_iter([("USER", "ala")]);
+    /// assert_eq!(env.expand("hi $USER, %USER%!", Undefined::Error).unwrap(), "hi ala, ala!");
+    /// assert_eq!(env.expand("[$TERM]", Undefined::Empty).unwrap(), "[]");
+    /// assert_eq!(env.expand("[${TERM}]", Undefined::Keep).unwrap(), "[${TERM}]");
+    /// ```
+    pub fn expand(&self, input: &str, undefined: Undefined) -> Result<String, ExpandError> {
+        Expander {
+            env: self,
+            undefined,
+            percent: true,
+        }
+        .expand_at(input, 0)
+    }
+}
+
+impl Env {
+    /// Substitute `%VAR%` references in the `value`, the way the Windows
+    /// `ExpandEnvironmentStrings` does (e.g. for values of the `REG_EXPAND_SZ` type, copied from
+    /// the registry).
+    ///
+    /// Names are looked up case-insensitively, and may contain any characters but `%`. References
+    /// to undefined variables are kept as they are, and so are lone `%`s (there is no escaping).
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::Env;
+    /// let env = Env::from_iter([("SystemRoot", "C:\\Windows")]);
+    /// assert_eq!(
+    ///     env.expand_windows("%SYSTEMROOT%\\System32;%Missing%;100%"),
+    ///     "C:\\Windows\\System32;%Missing%;100%"
+    /// );
+    /// ```
+    pub fn expand_windows(&self, value: &str) -> String {
+        expand_percent(value, |name| {
+            self.get_os(name)
+                .or_else(|| self.get_normalised(name.as_ref()))
+                .map(|found| found.to_string_lossy().into_owned())
+        })
+    }
+
+    /// Same as the [`Env::expand_windows`], but variables missing in this [`Env`] are read from
+    /// the persistent environment in the registry (of the user, and then the system one), like
+    /// ones set by installers, but not yet seen by the running process.
+    ///
+    /// References in the values read from the registry are expanded too (once, from this
+    /// [`Env`]).
+    #[cfg(all(windows, feature = "registry"))]
+    #[cfg_attr(docsrs, doc(cfg(all(windows, feature = "registry"))))]
+    pub fn expand_windows_with_registry(&self, value: &str) -> String {
+        expand_percent(value, |name| {
+            self.get_os(name)
+                .or_else(|| self.get_normalised(name.as_ref()))
+                .map(|found| found.to_string_lossy().into_owned())
+                .or_else(|| super::registry::lookup(name).map(|found| self.expand_windows(&found)))
+        })
+    }
+}
+
+/// Substitute the `%NAME%` references in the `value`, whose names the `lookup` finds.
+fn expand_percent(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            // A lone `%`.
+            expanded.push_str(&rest[start..]);
+            return expanded;
+        };
+        let name = &after[..end];
+        match lookup(name).filter(|_| !name.is_empty()) {
+            Some(found) => {
+                expanded.push_str(&found);
+                rest = &after[end + 1..];
+            }
+            None => {
+                // The closing `%` may start another reference.
+                expanded.push('%');
+                expanded.push_str(name);
+                rest = &after[end..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Expand the `value` without the `%VAR%` syntax, failing on undefined variables (see the
+/// [`config_files::expand`](crate::config_files::expand)).
+pub(crate) fn expand_posix(value: &str, env: &Env) -> Result<String, ExpandError> {
+    Expander {
+        env,
+        undefined: Undefined::Error,
+        percent: false,
+    }
+    .expand_at(value, 0)
+}
+
+struct Expander<'a> {
+    env: &'a Env,
+    undefined: Undefined,
+    /// Whether the `%VAR%` is supported.
+    percent: bool,
+}
+
+impl Expander<'_> {
+    /// Expand the `value`, which starts at the `offset` of the original one (used only for
+    /// errors).
+    fn expand_at(&self, value: &str, offset: usize) -> Result<String, ExpandError> {
+        let mut expanded = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(at) = rest.find(|c| c == '$' || (self.percent && c == '%')) {
+            expanded.push_str(&rest[..at]);
+            let after = &rest[at + 1..];
+            let start = offset + (value.len() - rest.len()) + at;
+            if rest[at..].starts_with('%') {
+                rest = self.percent_at(after, &mut expanded)?;
+            } else if let Some(after) = after.strip_prefix('$') {
+                expanded.push('$');
+                rest = after;
+            } else if let Some(braced) = after.strip_prefix('{') {
+                let end = matching_brace(braced).ok_or(ExpandError::Unterminated(start))?;
+                let inner = &braced[..end];
+                let name_len = name_len(inner);
+                let name = &inner[..name_len];
+                match inner[name_len..].strip_prefix(":-") {
+                    Some(default) => match self.lookup(name)?.filter(|found| !found.is_empty()) {
+                        Some(found) => expanded.push_str(found),
+                        // Default starts after the `${`, the name, and the `:-`.
+                        None => expanded.push_str(&self.expand_at(default, start + 4 + name_len)?),
+                    },
+                    None if name_len == inner.len() && !name.is_empty() => {
+                        self.substitute(name, &rest[at..at + end + 3], &mut expanded)?;
+                    }
+                    // Something like `${}` or `${A B}`, which isn't a variable.
+                    None => {
+                        expanded.push_str("${");
+                        expanded.push_str(inner);
+                        expanded.push('}');
+                    }
+                }
+                rest = &braced[end + 1..];
+            } else {
+                let name_len = name_len(after);
+                if name_len == 0 {
+                    expanded.push('$');
+                } else {
+                    let name = &after[..name_len];
+                    self.substitute(name, &rest[at..at + 1 + name_len], &mut expanded)?;
+                }
+                rest = &after[name_len..];
+            }
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    /// Expand the `%VAR%`, whose first `%` is before the `after`, returning the rest.
+    fn percent_at<'v>(
+        &self,
+        after: &'v str,
+        expanded: &mut String,
+    ) -> Result<&'v str, ExpandError> {
+        if let Some(after) = after.strip_prefix('%') {
+            expanded.push('%');
+            return Ok(after);
+        }
+        let name = after.split_once('%').map(|(name, _)| name).filter(|name| {
+            name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '(' | ')'))
+        });
+        match name {
+            Some(name) => {
+                self.substitute(name, &format!("%{name}%"), expanded)?;
+                Ok(&after[name.len() + 1..])
+            }
+            None => {
+                expanded.push('%');
+                Ok(after)
+            }
+        }
+    }
+
+    /// Push the value of the variable `name`, referenced by the `literal`.
+    fn substitute(
+        &self,
+        name: &str,
+        literal: &str,
+        expanded: &mut String,
+    ) -> Result<(), ExpandError> {
+        match (self.lookup(name)?, self.undefined) {
+            (Some(found), _) => expanded.push_str(found),
+            (None, Undefined::Error) => return Err(ExpandError::Undefined(name.to_owned())),
+            (None, Undefined::Empty) => {}
+            (None, Undefined::Keep) => expanded.push_str(literal),
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<Option<&str>, ExpandError> {
+        match self.env.get(name) {
+            Ok(found) => Ok(Some(found)),
+            Err(EnvStrError::Missing(_)) => Ok(None),
+            Err(EnvStrError::NonUTF8(_)) => Err(ExpandError::NonUtf8(name.to_owned())),
+        }
+    }
+}
+
+/// Length of the variable name at the start of the `value`.
+fn name_len(value: &str) -> usize {
+    value
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(value.len())
+}
+
+/// Offset of the `}` closing the one before the `value`, skipping nested `${...}`.
+fn matching_brace(value: &str) -> Option<usize> {
+    let mut depth = 0_usize;
+    let bytes = value.as_bytes();
+    for (index, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'{' if index > 0 && bytes[index - 1] == b'$' => depth += 1,
+            b'}' if depth == 0 => return Some(index),
+            b'}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+    use std::ffi::OsString;
+
+    #[test]
+    fn windows_style_and_undefined_modes() {
+        let env = Env::from([
+            (OsString::from("HOME"), OsString::from("/home/user")),
+            (
+                OsString::from("ProgramFiles(x86)"),
+                OsString::from("C:\\Program Files (x86)"),
+            ),
+        ]);
+        let cases = [
+            ("%HOME%/app", "/home/user/app"),
+            ("%ProgramFiles(x86)%\\App", "C:\\Program Files (x86)\\App"),
+            ("50% off, 100%%, a%20b%20c", "50% off, 100%, a%20b%20c"),
+            ("%HOME", "%HOME"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                assert_ok!(env.expand(input, Undefined::Error)),
+                expected,
+                "{input}"
+            );
+        }
+        let input = "$A ${B} %C% ${D:-d}";
+        assert_eq!(
+            env.expand(input, Undefined::Error),
+            Err(ExpandError::Undefined("A".to_owned()))
+        );
+        assert_eq!(assert_ok!(env.expand(input, Undefined::Empty)), "   d");
+        assert_eq!(
+            assert_ok!(env.expand(input, Undefined::Keep)),
+            "$A ${B} %C% d"
+        );
+        assert_eq!(assert_ok!(expand_posix("%HOME%", &env)), "%HOME%");
+    }
+
+    #[test]
+    fn windows_strings_are_expanded() {
+        let env = Env::from_iter([
+            ("SystemRoot", "C:\\Windows"),
+            ("Program Files", "C:\\Program Files"),
+            ("B", "b"),
+        ]);
+        let cases = [
+            ("%systemroot%\\System32", "C:\\Windows\\System32"),
+            ("%Program Files%", "C:\\Program Files"),
+            ("%A%B%", "%Ab"),
+            ("%%B%%", "%b%"),
+            ("50% off", "50% off"),
+            ("%$HOME%${B}", "%$HOME%${B}"),
+            ("%", "%"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(env.expand_windows(value), expected, "{value}");
+        }
+    }
+}
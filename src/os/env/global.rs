@@ -0,0 +1,94 @@
+use std::sync::{Mutex, OnceLock, PoisonError, RwLock};
+
+use crate::os::env::{Env, EnvDiff};
+
+type Listener = Box<dyn Fn(&Env) + Send + Sync>;
+
+static GLOBAL: OnceLock<RwLock<Env>> = OnceLock::new();
+static LISTENERS: Mutex<Vec<Listener>> = Mutex::new(Vec::new());
+
+/// Return the process-wide [`Env`], initialized from the process environment on the first use.
+///
+/// Gives code deep in a call stack a consistent snapshot, without passing an [`Env`] everywhere.
+/// It's not updated by the [`std::env::set_var`], see the [`reload_global`].
+///
+/// ```rust,no_run
+/// # use rustvil::os::env;
+/// let home = env::global().read().unwrap().get_path("HOME");
+/// ```
+pub fn global() -> &'static RwLock<Env> {
+    GLOBAL.get_or_init(|| RwLock::new(Env::new()))
+}
+
+/// Reload the [`global`] [`Env`] from the process environment (dropping changes made through
+/// the lock), and call the listeners registered by the [`on_global_reload`], unless nothing has
+/// changed.
+///
+/// # Returns
+/// [`EnvDiff`] with the changes (see the [`Env::reload`]).
+pub fn reload_global() -> EnvDiff {
+    let (diff, reloaded) = {
+        let mut env = global().write().unwrap_or_else(PoisonError::into_inner);
+        let diff = env.reload();
+        if diff.is_empty() {
+            return diff;
+        }
+        (diff, env.clone())
+    };
+    // With the lock released, so listeners can use it.
+    let listeners = LISTENERS.lock().unwrap_or_else(PoisonError::into_inner);
+    for listener in listeners.iter() {
+        listener(&reloaded);
+    }
+    diff
+}
+
+/// Call the `listener` with the reloaded [`Env`] after each [`reload_global`], which changed it
+/// (e.g. to invalidate caches derived from it).
+///
+/// Listeners can't be removed, and mustn't call the [`on_global_reload`], or
+/// [`reload_global`] themselves.
+pub fn on_global_reload(listener: impl Fn(&Env) + Send + Sync + 'static) {
+    LISTENERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(listener));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    #[test]
+    fn global_is_reloaded() {
+        let key = "RUSTVIL_GLOBAL_ENV_TEST";
+        global().write().unwrap().set(key, "1");
+        assert!(global().read().unwrap().has(key));
+
+        let reloads = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&reloads);
+        // Listeners can't be removed, but they are called on the reloading thread, so reloads of
+        // other tests skip this one.
+        let this_test = std::thread::current().id();
+        on_global_reload(move |env| {
+            if std::thread::current().id() != this_test {
+                return;
+            }
+            assert!(!env.has(key));
+            // The lock isn't held.
+            assert!(!global().read().unwrap().has(key));
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let diff = reload_global();
+        assert_eq!(diff.removed, [key]);
+        assert_eq!(reloads.load(Ordering::SeqCst), 1);
+        assert!(!global().read().unwrap().has(key));
+        // Listeners aren't called, if nothing has changed.
+        assert!(reload_global().is_empty());
+        assert_eq!(reloads.load(Ordering::SeqCst), 1);
+    }
+}
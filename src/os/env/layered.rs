@@ -0,0 +1,149 @@
+use std::ffi::OsStr;
+
+use crate::os::env::{Env, EnvStrError};
+
+/// Stack of [`Env`]s (like the process environment, a `.env` file, and defaults), resolving
+/// variables through them in the priority order: the first layer having a variable supplies it.
+///
+/// ```rust,no_run
+/// # use rustvil::config_files::dotenv;
+/// # use rustvil::os::env::{Env, Layered};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let defaults = Env::from_iter([("PORT", "8080")]);
+/// let env = Layered::new()
+///     .layer("environment", Env::new())
+///     .layer(".env", dotenv::load(".env")?)
+///     .layer("defaults", defaults);
+/// let port = env.lookup("PORT").ok_or("no port")?;
+/// println!("using port {:?} from the {}", port.value, port.layer);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Layered {
+    layers: Vec<(String, Env)>,
+}
+
+/// Variable found by the [`Layered::lookup`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolved<'a> {
+    /// Value of the variable.
+    pub value: &'a OsStr,
+    /// Name of the layer, which supplied the value.
+    pub layer: &'a str,
+}
+
+impl Layered {
+    /// Create a stack without any layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the `env` named `name` as the next layer, with a lower priority than the previous
+    /// ones (so it's a fallback for them).
+    pub fn layer(mut self, name: impl Into<String>, env: Env) -> Self {
+        self.layers.push((name.into(), env));
+        self
+    }
+
+    /// Iterate over names of the layers, and the layers, from the highest priority.
+    pub fn layers(&self) -> impl Iterator<Item = (&str, &Env)> {
+        self.layers.iter().map(|(name, env)| (name.as_str(), env))
+    }
+
+    /// Find the variable pointed by `key` in the first layer having it.
+    ///
+    /// # Returns
+    /// [`Some(Resolved)`](Resolved) with the value, and the layer it was found in, or [`None`],
+    /// if no layer has it.
+    pub fn lookup(&self, key: impl AsRef<OsStr>) -> Option<Resolved<'_>> {
+        let key = key.as_ref();
+        self.layers.iter().find_map(|(name, env)| {
+            env.get_os(key).map(|value| Resolved {
+                value,
+                layer: name.as_str(),
+            })
+        })
+    }
+
+    /// Same as the [`Env::get_os`], but searches all the layers, see the [`Layered::lookup`].
+    pub fn get_os(&self, key: impl AsRef<OsStr>) -> Option<&OsStr> {
+        self.lookup(key).map(|resolved| resolved.value)
+    }
+
+    /// Same as the [`Env::get`], but searches all the layers, see the [`Layered::lookup`].
+    ///
+    /// The value from the first layer having the `key` is used, even if it isn't UTF-8.
+    pub fn get(&self, key: impl AsRef<OsStr>) -> Result<&str, EnvStrError> {
+        let key = key.as_ref();
+        self.get_os(key)
+            .ok_or_else(|| EnvStrError::Missing(key.to_os_string()))?
+            .to_str()
+            .ok_or_else(|| EnvStrError::NonUTF8(key.to_os_string()))
+    }
+
+    /// Check, whether any layer has the `key`.
+    pub fn has(&self, key: impl AsRef<OsStr>) -> bool {
+        self.get_os(key).is_some()
+    }
+
+    /// Flatten the layers into a single [`Env`] (e.g. for the [`Env::apply_to`]), with the
+    /// resolved values of all the variables.
+    ///
+    /// The result compares keys like the first layer (see the [`Env::with_case`]).
+    pub fn to_env(&self) -> Env {
+        let case = self
+            .layers
+            .first()
+            .map(|(_, env)| env.case())
+            .unwrap_or_default();
+        let mut flattened = Env::empty().with_case(case);
+        for (_, env) in self.layers.iter().rev() {
+            flattened.extend_from(env);
+        }
+        flattened
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_none, assert_some};
+    use std::ffi::OsString;
+
+    #[test]
+    fn variables_are_resolved_in_order() {
+        let layered = Layered::new()
+            .layer("process", Env::from_iter([("HOST", "example.com")]))
+            .layer(
+                ".env",
+                Env::from_iter([("HOST", "localhost"), ("PORT", "5432")]),
+            )
+            .layer(
+                "defaults",
+                Env::from_iter([("PORT", "80"), ("USER", "app")]),
+            );
+        let host = assert_some!(layered.lookup("HOST"));
+        assert_eq!(
+            (host.value, host.layer),
+            (OsStr::new("example.com"), "process")
+        );
+        assert_eq!(assert_some!(layered.lookup("PORT")).layer, ".env");
+        assert_eq!(layered.get("USER"), Ok("app"));
+        assert_none!(layered.lookup("MISSING"));
+        assert_eq!(
+            layered.get("MISSING"),
+            Err(EnvStrError::Missing(OsString::from("MISSING")))
+        );
+        assert_eq!(
+            layered.layers().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["process", ".env", "defaults"]
+        );
+
+        let flattened = layered.to_env();
+        assert_eq!(flattened.len(), 3);
+        assert_eq!(flattened.get("HOST"), Ok("example.com"));
+        assert_eq!(flattened.get("PORT"), Ok("5432"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::ffi::{OsStr, OsString};
+
+use thiserror::Error;
+
+use crate::os::env::Env;
+
+/// Separator of list variables (like the `$PATH`, or `$PKG_CONFIG_PATH`): `;` on Windows, and
+/// `:` elsewhere.
+pub const LIST_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// Errors encountered when joining a list, see the [`join_list`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum JoinListError {
+    /// This variant indicates, that item `Separator.0` contains the [`LIST_SEPARATOR`].
+    #[error("list item {0:?} contains the separator `{LIST_SEPARATOR}`")]
+    Separator(OsString),
+}
+
+/// Split the `value` of a list variable with the [`LIST_SEPARATOR`], skipping empty items.
+///
+/// Unlike the [`std::env::split_paths`], items don't have to be paths, and no quoting is
+/// supported.
+///
+/// # Examples
+/// ```rust
+/// # use rustvil::os::env::{self, LIST_SEPARATOR};
+/// let value = format!("a{LIST_SEPARATOR}{LIST_SEPARATOR}b");
+/// assert_eq!(env::split_list(value), ["a", "b"]);
+/// ```
+pub fn split_list(value: impl AsRef<OsStr>) -> Vec<OsString> {
+    value
+        .as_ref()
+        .as_encoded_bytes()
+        .split(|&byte| byte == LIST_SEPARATOR as u8)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            // SAFETY: Split at an ASCII character, from the valid encoded bytes.
+            unsafe { OsStr::from_encoded_bytes_unchecked(item) }.to_os_string()
+        })
+        .collect()
+}
+
+/// Join the `items` into a value of a list variable, with the [`LIST_SEPARATOR`].
+///
+/// # Returns
+/// [`Ok(OsString)`](OsString) with the value, otherwise [`JoinListError::Separator`], if an item
+/// contains the separator.
+pub fn join_list<I>(items: I) -> Result<OsString, JoinListError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<OsStr>,
+{
+    let mut joined = OsString::new();
+    for (index, item) in items.into_iter().enumerate() {
+        let item = item.as_ref();
+        if item.as_encoded_bytes().contains(&(LIST_SEPARATOR as u8)) {
+            return Err(JoinListError::Separator(item.to_os_string()));
+        }
+        if index > 0 {
+            joined.push(LIST_SEPARATOR.to_string());
+        }
+        joined.push(item);
+    }
+    Ok(joined)
+}
+
+impl Env {
+    /// Split the list variable pointed by `key` (like the `$PKG_CONFIG_PATH`, or the
+    /// `$PSModulePath`), see the [`split_list`].
+    ///
+    /// # Returns
+    /// The items in order, or an empty [`Vec`], if the variable is missing.
+    pub fn get_list(&self, key: impl AsRef<OsStr>) -> Vec<OsString> {
+        self.get_os(key).map(split_list).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_are_split_and_joined() {
+        let value = ["a", "", "b c", "d\"e"].join(&LIST_SEPARATOR.to_string());
+        let items = split_list(&value);
+        assert_eq!(items, ["a", "b c", "d\"e"]);
+        assert_eq!(
+            join_list(&items),
+            Ok(OsString::from(
+                ["a", "b c", "d\"e"].join(&LIST_SEPARATOR.to_string())
+            ))
+        );
+        assert_eq!(join_list(Vec::<OsString>::new()), Ok(OsString::new()));
+        let invalid = format!("x{LIST_SEPARATOR}y");
+        assert_eq!(
+            join_list(["a", &invalid]),
+            Err(JoinListError::Separator(OsString::from(&invalid)))
+        );
+        assert!(split_list("").is_empty());
+
+        let env = Env::from_iter([("LIST", value.as_str())]);
+        assert_eq!(env.get_list("LIST"), items);
+        assert!(env.get_list("MISSING").is_empty());
+    }
+}
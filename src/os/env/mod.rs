@@ -0,0 +1,1081 @@
+//! Cross-platform environment variables snapshot.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! # use rustvil::os::env::Env;
+//! # use std::ffi::OsStr;
+//! let env = Env::new();
+//!
+//! // Get some variable
+//! let foo = env.get_os("FOO").unwrap_or(OsStr::new("empty"));
+//!
+//! // String getters also exist.
+//! let bar = env.get("BAR").unwrap_or("empty");
+//! ```
+//!
+//! You can also _reload_ it:
+//! ```rust,no_run
+//! # use rustvil::os::env::Env;
+//! let mut env = Env::new();
+//!
+//! let x = env.get("X"); // Maybe `Err`.
+//! unsafe { std::env::set_var("X", "Y"); }
+//! let diff = env.reload(); // Tells what has changed.
+//! let x = env.get("X"); // Now it should be `Some("Y")`.
+//! ```
+//!
+//! Names of the current [user](username) and [host](hostname) are read from the variables,
+//! falling back to the OS.
+//!
+//! Colors supported by the terminal are detected with the [`color_support`].
+//!
+//! Proxy settings, like the `https_proxy`, are read with the [`proxy`].
+//!
+//! Variables, which child processes shouldn't inherit, can be [removed](Env::sanitized), and
+//! secrets can be [redacted](Env::redacted) before logging.
+//!
+//! Variables required by an app can be declared, and validated at once, with the
+//! [`Requirements`].
+//!
+//! `$PATH`-like variables can be [split](split_list), edited with the [`PathList`], and searched
+//! for executables with the [`which`]. Variables in strings (like `%APPDATA%\app`) are
+//! substituted with the [`Env::expand`], or the [`Env::expand_windows`] (exactly like Windows
+//! does, and with the __registry__ feature, falling back to the persistent environment).
+//!
+//! A process-wide [`Env`] is available with the [`global`].
+//!
+//! Multiple sources of variables (like the process environment, and defaults) can be stacked
+//! with the [`Layered`].
+//!
+//! An [`Env`] can be [saved](Env::to_dotenv_string) in the `.env` format, and
+//! [loaded](Env::from_dotenv_str) back.
+//!
+//! With the __serde__ feature, an [`Env`] can be [deserialized](Env::deserialize_into) into a
+//! config struct.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+mod color;
+#[cfg(feature = "serde")]
+mod de;
+mod diff;
+mod dotenv;
+mod expand;
+mod global;
+mod layered;
+mod list;
+mod path_list;
+mod proxy;
+mod redact;
+#[cfg(all(windows, feature = "registry"))]
+mod registry;
+mod requirements;
+mod sanitize;
+mod which;
+mod whoami;
+pub use color::{ColorSupport, color_support, color_support_for};
+pub use diff::EnvDiff;
+pub(crate) use expand::expand_posix;
+pub use expand::{ExpandError, Undefined};
+pub use global::{global, on_global_reload, reload_global};
+pub use layered::{Layered, Resolved};
+pub use list::{JoinListError, LIST_SEPARATOR, join_list, split_list};
+pub use path_list::PathList;
+pub use proxy::{ProxySettings, ProxyUrl, proxy};
+pub use redact::SECRET_PATTERNS;
+pub use requirements::{Requirements, RequirementsError, VarProblem};
+pub use sanitize::{SanitizeMode, SanitizePolicy};
+pub use which::{which, which_all};
+pub use whoami::{hostname, username};
+
+/// Safe wrapper around [`std::env::vars_os`], which is safe to access on Windows: some of its
+/// environmental variables are case-insensitive.
+///
+/// Keys are case-insensitive on Windows only, which can be changed with the [`Env::with_case`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Env {
+    env: HashMap<OsString, OsString>,
+
+    // Map from normalised keys (uppercase) to original.
+    normalised_keys: HashMap<String, String>,
+
+    case: CaseSensitivity,
+}
+
+/// How keys of an [`Env`] are compared, see the [`Env::with_case`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    /// Keys must match exactly, like on Unix.
+    Sensitive,
+    /// Keys with different case (like `Path` and `PATH`) are the same, like on Windows.
+    Insensitive,
+}
+
+impl CaseSensitivity {
+    /// Return the case sensitivity of the current platform.
+    pub const fn native() -> Self {
+        if cfg!(windows) {
+            Self::Insensitive
+        } else {
+            Self::Sensitive
+        }
+    }
+}
+
+impl Default for CaseSensitivity {
+    fn default() -> Self {
+        Self::native()
+    }
+}
+
+/// Errors encountered when getting environmental variable.
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum EnvStrError {
+    /// This variant indicates, that variable `Missing.0` is missing.
+    #[error("there is no environmental variable `${0:?}`")]
+    Missing(OsString),
+
+    /// This variant indicates, that variable `NonUTF8.0` is not an UTF-8 string.
+    #[error("environmental variable `${0:?}` is not an UTF-8 string")]
+    NonUTF8(OsString),
+}
+
+/// Errors encountered when parsing environmental variable, see the [`Env::get_parsed`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum EnvParseError {
+    /// This variant indicates, that variable `Missing.0` is missing.
+    #[error("there is no environmental variable `${0:?}`")]
+    Missing(OsString),
+
+    /// This variant indicates, that variable `NonUTF8.0` is not an UTF-8 string.
+    #[error("environmental variable `${0:?}` is not an UTF-8 string")]
+    NonUTF8(OsString),
+
+    /// This variant indicates, that the `value` of variable `key` couldn't be parsed.
+    #[error("environmental variable `${key:?}` has invalid value `{value}`: {message}")]
+    Invalid {
+        /// Key of the variable.
+        key: OsString,
+        /// Offending value.
+        value: String,
+        /// Description of the parse error.
+        message: String,
+    },
+
+    /// Other error of the [`Env::deserialize_into`].
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    #[error("{0}")]
+    Custom(String),
+}
+
+/// How the [`Env::apply_to`] treats the environment inherited by the command.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ApplyMode {
+    /// Variables are added to the inherited ones (replacing the same keys).
+    Merge,
+    /// Inherited variables are cleared first, so the command gets exactly the [`Env`].
+    Replace,
+}
+
+/// Errors encountered when setting environmental variable, see the [`Env::try_set`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+pub enum EnvSetError {
+    /// This variant indicates, that `InvalidName.0` isn't a valid variable name, see the
+    /// [`is_valid_var_name`].
+    #[error("`{0:?}` is not a valid environmental variable name")]
+    InvalidName(OsString),
+}
+
+impl From<EnvStrError> for EnvParseError {
+    fn from(err: EnvStrError) -> Self {
+        match err {
+            EnvStrError::Missing(key) => Self::Missing(key),
+            EnvStrError::NonUTF8(key) => Self::NonUTF8(key),
+        }
+    }
+}
+
+impl Env {
+    /// Create new default [`Env`].
+    pub fn new() -> Self {
+        Self::new_from(std::env::vars_os().collect())
+    }
+
+    /// Create new [`Env`] without any variables, e.g. for building one from scratch.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::Env;
+    /// let mut env = Env::empty();
+    /// env.extend([("LANG", "C"), ("TZ", "UTC")]);
+    /// assert_eq!(env.len(), 2);
+    /// ```
+    pub fn empty() -> Self {
+        Self::new_from(HashMap::new())
+    }
+
+    /// Create new [`Env`] using `env` as existing environmental variables.
+    pub fn new_from(env: HashMap<OsString, OsString>) -> Self {
+        let normalised_keys = Env::normalize_keys(&env);
+        Self {
+            env,
+            normalised_keys,
+            case: CaseSensitivity::native(),
+        }
+    }
+
+    /// Compare the keys according to the `case`, instead of the platform default.
+    ///
+    /// Useful for exercising the Windows semantics elsewhere (e.g. in tests), or for emulating
+    /// other platforms. If there are keys differing only in case, lookups of other spellings find
+    /// one of them.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::{CaseSensitivity, Env};
+    /// let mut env = Env::from_iter([("Path", "C:\\Windows")])
+    ///     .with_case(CaseSensitivity::Insensitive);
+    /// assert_eq!(env.get("PATH"), Ok("C:\\Windows"));
+    /// env.set("PATH", "C:\\Tools");
+    /// assert_eq!(env.len(), 1);
+    /// ```
+    pub fn with_case(mut self, case: CaseSensitivity) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Return how the keys are compared, see the [`Env::with_case`].
+    pub fn case(&self) -> CaseSensitivity {
+        self.case
+    }
+
+    fn is_insensitive(&self) -> bool {
+        self.case == CaseSensitivity::Insensitive
+    }
+
+    fn normalize_keys(keys: &HashMap<OsString, OsString>) -> HashMap<String, String> {
+        keys.keys()
+            .filter_map(|k| k.to_str())
+            .map(|k| (k.to_uppercase(), k.to_owned()))
+            .collect()
+    }
+
+    /// Reload environmental variables from `env`.
+    ///
+    /// # Returns
+    /// [`EnvDiff`] with the changes (see the [`Env::diff`]).
+    pub fn reload_from(&mut self, env: HashMap<OsString, OsString>) -> EnvDiff {
+        let reloaded = Self::new_from(env).with_case(self.case);
+        let diff = self.diff(&reloaded);
+        *self = reloaded;
+        diff
+    }
+
+    /// Reload environmental variables from [`std::env::vars_os`].
+    ///
+    /// # Returns
+    /// [`EnvDiff`] with the changes (see the [`Env::diff`]), so e.g. daemons reloading on a
+    /// `SIGHUP` can skip the work, if nothing has changed.
+    pub fn reload(&mut self) -> EnvDiff {
+        self.reload_from(std::env::vars_os().collect())
+    }
+
+    /// Get environmental variable pointed by `key`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key for environmental variable. Must implement [`AsRef<OsStr>`].
+    ///
+    /// # Returns
+    /// [`Option<&OsStr>`]. [`None`] variant indicates missing key, [`Some`]: existing key.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::Env;
+    /// let env = Env::new();
+    /// println!("$FOO = {:?}", env.get_os("FOO"));
+    /// ```
+    pub fn get_os(&self, key: impl AsRef<OsStr>) -> Option<&OsStr> {
+        let key = key.as_ref();
+        match self.env.get(key) {
+            Some(x) => Some(x),
+            None => {
+                if self.is_insensitive() {
+                    self.get_normalised(key)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Same as the [`Env::get_os`], but treats empty values (like after `export FOO=`) as
+    /// missing, as they usually mean "unset" in practice.
+    pub fn get_non_empty(&self, key: impl AsRef<OsStr>) -> Option<&OsStr> {
+        self.get_os(key).filter(|value| !value.is_empty())
+    }
+
+    fn get_normalised(&self, key: &OsStr) -> Option<&OsStr> {
+        let k = key.to_str()?.to_uppercase();
+        let env_key: &OsStr = self.normalised_keys.get(&k)?.as_ref();
+        self.env.get(env_key).map(OsString::as_ref)
+    }
+
+    /// Check, whether this [`Env`] has key `key`.
+    pub fn has(&self, key: impl AsRef<OsStr>) -> bool {
+        self.get_os(key).is_some()
+    }
+
+    /// Check, whether this [`Env`] has key `key` with a non-empty value, see the
+    /// [`Env::get_non_empty`].
+    pub fn has_non_empty(&self, key: impl AsRef<OsStr>) -> bool {
+        self.get_non_empty(key).is_some()
+    }
+
+    /// Get environmental variable pointed by `key` and convert it to UTF-8.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - key for environmental variable. Must implement [`AsRef<Str>`].
+    ///
+    /// # Returns
+    /// [`Result<&str, EnvStrError>`]. [`Ok`] variant indicates existing UTF-8 variable, [`Err`]
+    /// indicates some kind of error. See [`EnvStrError`] for
+    /// details.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::Env;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let env = Env::new();
+    /// let _path = env.get("PATH")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get(&self, key: impl AsRef<OsStr>) -> Result<&str, EnvStrError> {
+        let key = key.as_ref();
+        self.get_os(key)
+            .ok_or_else(|| EnvStrError::Missing(key.to_os_string()))?
+            .to_str()
+            .ok_or_else(|| EnvStrError::NonUTF8(key.to_os_string()))
+    }
+
+    /// Get environmental variable pointed by `key`, replacing invalid UTF-8 with `�` (see the
+    /// [`OsStr::to_string_lossy`]), e.g. for displaying, or logging it.
+    ///
+    /// Prefer the [`Env::get`], when the exact value matters.
+    ///
+    /// # Returns
+    /// [`Some(Cow<str>)`](Cow) with the value, borrowed if it's valid UTF-8, or [`None`], if the
+    /// variable is missing.
+    pub fn get_lossy(&self, key: impl AsRef<OsStr>) -> Option<Cow<'_, str>> {
+        self.get_os(key).map(OsStr::to_string_lossy)
+    }
+
+    /// Get environmental variable pointed by `key` and parse it with [`FromStr`] (surrounding
+    /// whitespace is kept).
+    ///
+    /// # Returns
+    /// [`Result<T, EnvParseError>`]. [`Ok`] variant holds the parsed value, [`Err`] tells whether
+    /// the variable is missing, non-UTF-8, or invalid (with the offending value).
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::Env;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let env = Env::new();
+    /// let port: u16 = env.get_parsed("PORT")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_parsed<T>(&self, key: impl AsRef<OsStr>) -> Result<T, EnvParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let key = key.as_ref();
+        let value = self.get(key)?;
+        value.parse().map_err(|err: T::Err| EnvParseError::Invalid {
+            key: key.to_os_string(),
+            value: value.to_owned(),
+            message: err.to_string(),
+        })
+    }
+
+    /// Same as the [`Env::get_parsed`], but returns the `default`, if the variable is missing.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::Env;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let env = Env::new();
+    /// let timeout: u64 = env.get_parsed_or("TIMEOUT_SECS", 30)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_parsed_or<T>(&self, key: impl AsRef<OsStr>, default: T) -> Result<T, EnvParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.get_parsed(key) {
+            Err(EnvParseError::Missing(_)) => Ok(default),
+            parsed => parsed,
+        }
+    }
+
+    /// Get environmental variable pointed by `key` as a boolean: `1`, `true`, `yes` and `on` are
+    /// `true`, `0`, `false`, `no` and `off` are `false` (case-insensitively, ignoring surrounding
+    /// whitespace).
+    ///
+    /// # Returns
+    /// Same as the [`Env::get_parsed`], with [`EnvParseError::Invalid`] for other values.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::{Env, EnvParseError};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let env = Env::new();
+    /// let verbose = match env.get_bool("APP_VERBOSE") {
+    ///     Err(EnvParseError::Missing(_)) => false,
+    ///     verbose => verbose?,
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_bool(&self, key: impl AsRef<OsStr>) -> Result<bool, EnvParseError> {
+        let key = key.as_ref();
+        let value = self.get(key)?;
+        parse_bool(value).ok_or_else(|| EnvParseError::Invalid {
+            key: key.to_os_string(),
+            value: value.to_owned(),
+            message: "expected one of `1/0`, `true/false`, `yes/no` or `on/off`".to_owned(),
+        })
+    }
+
+    /// Check, whether the variable pointed by `key` is set to a non-empty value (any, so even
+    /// `0` counts), like the `NO_COLOR`.
+    pub fn get_flag(&self, key: impl AsRef<OsStr>) -> bool {
+        self.has_non_empty(key)
+    }
+
+    /// Get the variable pointed by `key` as a path, without requiring it to be UTF-8.
+    ///
+    /// # Returns
+    /// [`Some(PathBuf)`](PathBuf) with the value (possibly empty), or [`None`], if it's missing.
+    pub fn get_path(&self, key: impl AsRef<OsStr>) -> Option<PathBuf> {
+        self.get_os(key).map(PathBuf::from)
+    }
+
+    /// Split the list variable pointed by `key` (like the `$PATH`, or `$XDG_DATA_DIRS`) into
+    /// paths, with the platform separator (`:`, or `;` on Windows), skipping empty entries.
+    ///
+    /// # Returns
+    /// The paths in order, or an empty [`Vec`], if the variable is missing. See the [`PathList`]
+    /// for editing them.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::Env;
+    /// for dir in Env::new().get_paths("PATH") {
+    ///     println!("{}", dir.display());
+    /// }
+    /// ```
+    pub fn get_paths(&self, key: impl AsRef<OsStr>) -> Vec<PathBuf> {
+        self.get_os(key)
+            .map(|value| {
+                std::env::split_paths(value)
+                    .filter(|path| !path.as_os_str().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Set the variable `key` to the `value`, replacing the existing one (case-insensitively, if
+    /// [`CaseSensitivity::Insensitive`], and the new spelling of the `key` is kept then).
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::Env;
+    /// let mut env = Env::new();
+    /// env.set("RUST_LOG", "debug");
+    /// env.remove("http_proxy");
+    /// env.retain(|key, _| !key.to_string_lossy().starts_with("npm_"));
+    /// ```
+    pub fn set(&mut self, key: impl Into<OsString>, value: impl Into<OsString>) {
+        let (key, value) = (key.into(), value.into());
+        if let Some(key) = key.to_str() {
+            let previous = self
+                .normalised_keys
+                .insert(key.to_uppercase(), key.to_owned());
+            if let Some(previous) =
+                previous.filter(|previous| self.is_insensitive() && previous != key)
+            {
+                self.env.remove(OsStr::new(&previous));
+            }
+        }
+        self.env.insert(key, value);
+    }
+
+    /// Same as the [`Env::set`], but rejects invalid names (see the [`is_valid_var_name`]), e.g.
+    /// ones defined by users, before they are passed to a [`Command`].
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok) on a success, otherwise [`EnvSetError::InvalidName`] (and the [`Env`] is
+    /// left untouched).
+    pub fn try_set(
+        &mut self,
+        key: impl Into<OsString>,
+        value: impl Into<OsString>,
+    ) -> Result<(), EnvSetError> {
+        let key = key.into();
+        if !is_valid_var_name(&key) {
+            return Err(EnvSetError::InvalidName(key));
+        }
+        self.set(key, value);
+        Ok(())
+    }
+
+    /// Remove the variable `key` (case-insensitively, if [`CaseSensitivity::Insensitive`]).
+    ///
+    /// # Returns
+    /// Value of the removed variable, if any.
+    pub fn remove(&mut self, key: impl AsRef<OsStr>) -> Option<OsString> {
+        let key = key.as_ref();
+        let key = match self.env.get_key_value(key) {
+            Some((existing, _)) => existing.clone(),
+            None if self.is_insensitive() => {
+                let normalised = key.to_str()?.to_uppercase();
+                OsString::from(self.normalised_keys.get(&normalised)?)
+            }
+            None => return None,
+        };
+        let value = self.env.remove(&key)?;
+        if let Some(key) = key.to_str() {
+            let normalised = key.to_uppercase();
+            if self.normalised_keys.get(&normalised).map(String::as_str) == Some(key) {
+                self.normalised_keys.remove(&normalised);
+                // Other spelling of the same key (there can be one only if case-sensitive).
+                let other = self
+                    .env
+                    .keys()
+                    .filter_map(|other| other.to_str())
+                    .find(|other| other.to_uppercase() == normalised);
+                if let Some(other) = other {
+                    self.normalised_keys.insert(normalised, other.to_owned());
+                }
+            }
+        }
+        Some(value)
+    }
+
+    /// Iterate over the variables (keys, and values), in an arbitrary order.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::Env;
+    /// let env = Env::new();
+    /// let mut keys: Vec<_> = env.keys().collect();
+    /// keys.sort();
+    /// for key in keys {
+    ///     println!("{} = {:?}", key.display(), env.get_os(key).unwrap_or_default());
+    /// }
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.env.iter())
+    }
+
+    /// Iterate over the keys, in an arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &OsStr> {
+        self.env.keys().map(OsString::as_os_str)
+    }
+
+    /// Iterate over the values, in an arbitrary order.
+    pub fn values(&self) -> impl Iterator<Item = &OsStr> {
+        self.env.values().map(OsString::as_os_str)
+    }
+
+    /// Return number of the variables.
+    pub fn len(&self) -> usize {
+        self.env.len()
+    }
+
+    /// Check, whether there are no variables.
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty()
+    }
+
+    /// Pass the variables to the `command`, according to the `mode`.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::{ApplyMode, Env};
+    /// # use std::process::Command;
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut env = Env::new();
+    /// env.retain(|key, _| key == "PATH" || key == "HOME");
+    /// env.set("LC_ALL", "C");
+    /// env.apply_to(&mut Command::new("make"), ApplyMode::Replace).status()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_to<'a>(&self, command: &'a mut Command, mode: ApplyMode) -> &'a mut Command {
+        if mode == ApplyMode::Replace {
+            command.env_clear();
+        }
+        command.envs(&self.env)
+    }
+
+    /// Keep only the variables, for which the `predicate` returns `true`.
+    pub fn retain(&mut self, mut predicate: impl FnMut(&OsStr, &OsStr) -> bool) {
+        self.env.retain(|key, value| predicate(key, value));
+        self.normalised_keys = Env::normalize_keys(&self.env);
+    }
+
+    /// Set all the variables of the `overlay`, replacing the existing ones (like the
+    /// [`Env::set`] does, so keys are compared according to the [case](Env::with_case) of this
+    /// [`Env`]).
+    pub fn extend_from(&mut self, overlay: &Env) {
+        for (key, value) in overlay {
+            self.set(key, value);
+        }
+    }
+
+    /// Return a copy of this [`Env`] with the variables of the `overlay` on top of it (so they
+    /// win), see the [`Env::extend_from`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::Env;
+    /// let base = Env::from_iter([("LANG", "C")]);
+    /// let overrides = Env::from_iter([("LANG", "pl_PL.UTF-8")]);
+    /// assert_eq!(base.merged_with(&overrides).get("LANG"), Ok("pl_PL.UTF-8"));
+    /// ```
+    pub fn merged_with(&self, overlay: &Env) -> Env {
+        let mut merged = self.clone();
+        merged.extend_from(overlay);
+        merged
+    }
+
+    fn from_pairs(t: impl Iterator<Item = (OsString, OsString)>) -> Self {
+        let mut env = HashMap::new();
+        let mut normalised_keys = HashMap::new();
+        for (key, value) in t {
+            if let Some(key) = key.to_str() {
+                normalised_keys.insert(key.to_uppercase(), key.to_owned());
+            }
+            env.insert(key, value);
+        }
+        Self {
+            env,
+            normalised_keys,
+            case: CaseSensitivity::native(),
+        }
+    }
+}
+
+/// Check, whether the `name` is a valid name of an environmental variable on the current
+/// platform.
+///
+/// On Windows, names can't be empty, and can't contain `=` or NUL, so names like
+/// `ProgramFiles(x86)` are valid. Elsewhere, the POSIX rules are followed: names consist of ASCII
+/// letters, digits and `_`, and don't start with a digit (so they're valid on Windows too).
+///
+/// # Examples
+/// ```rust
+/// # use rustvil::os::env;
+/// assert!(env::is_valid_var_name("RUST_LOG"));
+/// assert!(!env::is_valid_var_name("A=B"));
+/// assert!(!env::is_valid_var_name(""));
+/// ```
+pub fn is_valid_var_name(name: impl AsRef<OsStr>) -> bool {
+    let name = name.as_ref();
+    if cfg!(windows) {
+        is_valid_windows_name(name)
+    } else {
+        is_valid_posix_name(name)
+    }
+}
+
+fn is_valid_posix_name(name: &OsStr) -> bool {
+    name.to_str().is_some_and(|name| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+fn is_valid_windows_name(name: &OsStr) -> bool {
+    let name = name.as_encoded_bytes();
+    !name.is_empty() && !name.contains(&b'=') && !name.contains(&0)
+}
+
+/// Parse a boolean, see the [`Env::get_bool`].
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<(OsString, OsString)> for Env {
+    fn from_iter<T: IntoIterator<Item = (OsString, OsString)>>(iter: T) -> Self {
+        Self::from_pairs(iter.into_iter())
+    }
+}
+
+impl<'a> FromIterator<(&'a str, &'a str)> for Env {
+    fn from_iter<T: IntoIterator<Item = (&'a str, &'a str)>>(iter: T) -> Self {
+        Self::from_pairs(
+            iter.into_iter()
+                .map(|(key, value)| (OsString::from(key), OsString::from(value))),
+        )
+    }
+}
+
+impl<K: Into<OsString>, V: Into<OsString>> Extend<(K, V)> for Env {
+    /// Set the variables, like the [`Env::set`].
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.set(key, value);
+        }
+    }
+}
+
+impl IntoIterator for Env {
+    type Item = (OsString, OsString);
+    type IntoIter = std::collections::hash_map::IntoIter<OsString, OsString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.env.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Env {
+    type Item = (&'a OsStr, &'a OsStr);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the variables of an [`Env`], see the [`Env::iter`].
+#[derive(Debug, Clone)]
+pub struct Iter<'a>(std::collections::hash_map::Iter<'a, OsString, OsString>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a OsStr, &'a OsStr);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .next()
+            .map(|(key, value)| (key.as_os_str(), value.as_os_str()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<const N: usize> From<[(OsString, OsString); N]> for Env {
+    fn from(value: [(OsString, OsString); N]) -> Self {
+        Self::from_pairs(value.into_iter())
+    }
+}
+
+impl From<&[(&str, &str)]> for Env {
+    fn from(value: &[(&str, &str)]) -> Self {
+        value.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn make_dummy_env() -> Env {
+        Env::from([(OsString::from("ala"), OsString::from("bar"))])
+    }
+
+    #[test]
+    fn basic_test() {
+        let env = make_dummy_env();
+        assert!(env.has("ala"));
+        assert_eq!(env.get_os("ala"), Some(OsStr::new("bar")));
+        assert_eq!(env.get("ala"), Ok("bar"));
+        if cfg!(windows) {
+            assert!(env.has("aLA"));
+            assert_eq!(env.get_os("aLA"), Some(OsStr::new("bar")));
+            assert_eq!(env.get("aLA"), Ok("bar"));
+        } else {
+            assert!(!env.has("aLA"));
+            assert_eq!(env.get_os("aLA"), None);
+            assert_eq!(
+                env.get("aLA"),
+                Err(EnvStrError::Missing(OsString::from("aLA")))
+            );
+        }
+    }
+
+    #[test]
+    fn variables_are_modified() {
+        let mut env = make_dummy_env();
+        env.set("PATH", "/bin");
+        env.set("npm_config", "x");
+        assert_eq!(env.get("PATH"), Ok("/bin"));
+        if cfg!(windows) {
+            env.set("Path", "/usr/bin");
+            assert_eq!(env.get("PATH"), Ok("/usr/bin"));
+            assert_eq!(env.remove("path"), Some(OsString::from("/usr/bin")));
+        } else {
+            assert_eq!(env.remove("PATH"), Some(OsString::from("/bin")));
+        }
+        assert!(!env.has("PATH"));
+        assert_eq!(env.remove("PATH"), None);
+
+        env.retain(|key, _| !key.to_string_lossy().starts_with("npm_"));
+        assert!(!env.has("npm_config"));
+        assert_eq!(env.get("ala"), Ok("bar"));
+    }
+
+    #[test]
+    fn case_sensitivity_is_configurable() {
+        let mut env = make_dummy_env().with_case(CaseSensitivity::Insensitive);
+        assert_eq!(env.case(), CaseSensitivity::Insensitive);
+        assert_eq!(env.get("ALA"), Ok("bar"));
+        env.set("Path", "/bin");
+        env.set("PATH", "/usr/bin");
+        assert_eq!(env.len(), 2);
+        assert_eq!(env.get("path"), Ok("/usr/bin"));
+        assert_eq!(env.remove("pAtH"), Some(OsString::from("/usr/bin")));
+        assert!(!env.has("Path"));
+
+        let mut env = make_dummy_env().with_case(CaseSensitivity::Sensitive);
+        assert!(!env.has("ALA"));
+        env.set("ALA", "baz");
+        assert_eq!(env.len(), 2);
+        assert_eq!(env.remove("Ala"), None);
+        assert_eq!(env.get("ala"), Ok("bar"));
+    }
+
+    #[test]
+    fn envs_are_merged() {
+        let base = make_dummy_env().with_case(CaseSensitivity::Insensitive);
+        let overlay = Env::from([
+            (OsString::from("ALA"), OsString::from("baz")),
+            (OsString::from("PATH"), OsString::from("/bin")),
+        ]);
+        let merged = base.merged_with(&overlay);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged.get("ala"), Ok("baz"));
+        assert_eq!(merged.keys().filter(|key| *key == "ALA").count(), 1);
+        assert_eq!(base.get("ala"), Ok("bar"));
+
+        let mut base = make_dummy_env().with_case(CaseSensitivity::Sensitive);
+        base.extend_from(&overlay);
+        assert_eq!(base.len(), 3);
+        assert_eq!(base.get("ala"), Ok("bar"));
+        assert_eq!(base.get("PATH"), Ok("/bin"));
+    }
+
+    #[test]
+    fn names_are_validated() {
+        for name in ["PATH", "_x1", "rust_log"] {
+            assert!(is_valid_posix_name(OsStr::new(name)), "{name}");
+            assert!(is_valid_windows_name(OsStr::new(name)), "{name}");
+        }
+        for name in ["", "1A", "A=B", "A\0", "ProgramFiles(x86)", "A B", "zażółć"] {
+            assert!(!is_valid_posix_name(OsStr::new(name)), "{name}");
+        }
+        for name in ["ProgramFiles(x86)", "A B", "zażółć"] {
+            assert!(is_valid_windows_name(OsStr::new(name)), "{name}");
+        }
+        for name in ["", "A=B", "A\0"] {
+            assert!(!is_valid_windows_name(OsStr::new(name)), "{name}");
+        }
+
+        let mut env = make_dummy_env();
+        assert_eq!(env.try_set("RUST_LOG", "debug"), Ok(()));
+        assert_eq!(env.get("RUST_LOG"), Ok("debug"));
+        assert_eq!(
+            env.try_set("A=B", "x"),
+            Err(EnvSetError::InvalidName(OsString::from("A=B")))
+        );
+        assert_eq!(env.len(), 2);
+    }
+
+    #[test]
+    fn envs_are_built_from_strs() {
+        let mut env = Env::empty();
+        assert_eq!(env, Env::from([]));
+        env.extend([("A", "1"), ("B", "2")]);
+        env.extend([(OsString::from("A"), String::from("3"))]);
+        assert_eq!(env.get("A"), Ok("3"));
+        assert_eq!(env.len(), 2);
+
+        let vars: &[(&str, &str)] = &[("A", "1"), ("B", "2")];
+        assert_eq!(Env::from(vars).get("B"), Ok("2"));
+        assert_eq!(Env::from_iter([("A", "1")]).get("A"), Ok("1"));
+        let collected: Env = vars.iter().copied().collect();
+        assert_eq!(collected, Env::from(vars));
+    }
+
+    #[test]
+    fn variables_are_iterated() {
+        let mut env = make_dummy_env();
+        env.set("PATH", "/bin");
+        assert_eq!(env.len(), 2);
+        assert!(!env.is_empty());
+        let mut keys: Vec<_> = env.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["PATH", "ala"]);
+        assert!(env.values().any(|value| value == "/bin"));
+        assert_eq!((&env).into_iter().count(), env.iter().count());
+        let mut owned: Vec<_> = env.into_iter().collect();
+        owned.sort();
+        assert_eq!(owned[0], (OsString::from("PATH"), OsString::from("/bin")));
+        assert!(Env::empty().is_empty());
+    }
+
+    #[test]
+    fn env_is_applied_to_command() {
+        let mut env = make_dummy_env();
+        env.set("PATH", "/bin");
+        let mut command = Command::new("true");
+        command.env("OTHER", "x");
+        env.apply_to(&mut command, ApplyMode::Merge);
+        let mut vars: Vec<_> = command.get_envs().collect();
+        vars.sort();
+        assert_eq!(
+            vars,
+            [
+                (OsStr::new("OTHER"), Some(OsStr::new("x"))),
+                (OsStr::new("PATH"), Some(OsStr::new("/bin"))),
+                (OsStr::new("ala"), Some(OsStr::new("bar"))),
+            ]
+        );
+        env.apply_to(&mut command, ApplyMode::Replace);
+        assert_eq!(command.get_envs().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn values_are_read_lossily() {
+        use std::os::unix::ffi::OsStringExt;
+        let env = Env::from([
+            (
+                OsString::from("BAD"),
+                OsString::from_vec(b"a\xffb".to_vec()),
+            ),
+            (OsString::from("GOOD"), OsString::from("ab")),
+        ]);
+        assert_eq!(env.get_lossy("BAD").as_deref(), Some("a\u{FFFD}b"));
+        assert!(matches!(env.get_lossy("GOOD"), Some(Cow::Borrowed("ab"))));
+        assert_eq!(env.get_lossy("MISSING"), None);
+        assert_eq!(
+            env.get("BAD"),
+            Err(EnvStrError::NonUTF8(OsString::from("BAD")))
+        );
+    }
+
+    #[test]
+    fn empty_values_are_missing() {
+        let env = Env::from([
+            (OsString::from("EMPTY"), OsString::new()),
+            (OsString::from("SET"), OsString::from("x")),
+        ]);
+        assert!(env.has("EMPTY"));
+        assert!(!env.has_non_empty("EMPTY"));
+        assert_eq!(env.get_non_empty("EMPTY"), None);
+        assert_eq!(env.get_non_empty("MISSING"), None);
+        assert_eq!(env.get_non_empty("SET"), Some(OsStr::new("x")));
+        assert!(env.has_non_empty("SET"));
+    }
+
+    #[test]
+    fn paths_are_read() {
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let env = Env::from([
+            (OsString::from("HOME"), OsString::from("/home/user")),
+            (
+                OsString::from("DIRS"),
+                OsString::from(["/a", "", "b/c"].join(separator)),
+            ),
+        ]);
+        assert_eq!(env.get_path("HOME"), Some(PathBuf::from("/home/user")));
+        assert_eq!(env.get_path("MISSING"), None);
+        assert_eq!(
+            env.get_paths("DIRS"),
+            [PathBuf::from("/a"), PathBuf::from("b/c")]
+        );
+        assert!(env.get_paths("MISSING").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_may_be_non_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+        let value = OsString::from_vec(b"/tmp/\xff".to_vec());
+        let env = Env::from([(OsString::from("DIR"), value.clone())]);
+        assert_eq!(env.get_path("DIR"), Some(PathBuf::from(&value)));
+        assert_eq!(env.get_paths("DIR"), [PathBuf::from(value)]);
+    }
+
+    #[test]
+    fn booleans_are_parsed() {
+        let env = Env::from([
+            (OsString::from("A"), OsString::from("Yes")),
+            (OsString::from("B"), OsString::from(" off ")),
+            (OsString::from("C"), OsString::from("maybe")),
+            (OsString::from("D"), OsString::from("")),
+        ]);
+        assert_eq!(env.get_bool("A"), Ok(true));
+        assert_eq!(env.get_bool("B"), Ok(false));
+        assert!(matches!(
+            env.get_bool("C"),
+            Err(EnvParseError::Invalid { .. })
+        ));
+        assert!(matches!(env.get_bool("E"), Err(EnvParseError::Missing(_))));
+        assert!(env.get_flag("B"));
+        assert!(!env.get_flag("D"));
+        assert!(!env.get_flag("E"));
+    }
+
+    #[test]
+    fn values_are_parsed() {
+        let env = Env::from([
+            (OsString::from("PORT"), OsString::from("8080")),
+            (OsString::from("TIMEOUT"), OsString::from("soon")),
+        ]);
+        assert_eq!(env.get_parsed::<u16>("PORT"), Ok(8080));
+        assert_eq!(env.get_parsed_or::<u16>("MISSING", 80), Ok(80));
+        let err = env.get_parsed_or::<u64>("TIMEOUT", 30).unwrap_err();
+        assert!(
+            matches!(&err, EnvParseError::Invalid { value, .. } if value == "soon"),
+            "{err}"
+        );
+        assert_eq!(
+            env.get_parsed::<u16>("MISSING"),
+            Err(EnvParseError::Missing(OsString::from("MISSING")))
+        );
+    }
+}
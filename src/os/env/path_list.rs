@@ -0,0 +1,197 @@
+use std::{
+    env::JoinPathsError,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+use crate::os::env::Env;
+
+/// List of paths from a `$PATH`-like variable (separated with `:`, or `;` on Windows), for
+/// editing it safely, e.g. in installers and wrappers.
+///
+/// Order of the paths is preserved, and empty entries are dropped (they'd mean the current
+/// directory). Paths are compared component-wise (so trailing separators don't matter), and
+/// case-insensitively on Windows.
+///
+/// ```rust,no_run
+/// # use rustvil::os::env::{Env, PathList};
+/// # use std::process::Command;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut path = PathList::from_env(&Env::new(), "PATH");
+/// path.prepend("/opt/app/bin");
+/// path.remove("/usr/local/bin");
+/// Command::new("app").env("PATH", path.to_os_string()?).status()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct PathList {
+    paths: Vec<PathBuf>,
+}
+
+impl PathList {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the `value` of a `$PATH`-like variable.
+    pub fn parse(value: impl AsRef<OsStr>) -> Self {
+        std::env::split_paths(value.as_ref()).collect()
+    }
+
+    /// Parse the variable `key` of the `env` (empty, if it's missing).
+    pub fn from_env(env: &Env, key: impl AsRef<OsStr>) -> Self {
+        env.get_os(key).map(Self::parse).unwrap_or_default()
+    }
+
+    /// Put the `path` first, removing its other occurrences.
+    ///
+    /// Empty `path` is ignored (it'd mean the current directory).
+    pub fn prepend(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        if path.as_os_str().is_empty() {
+            return;
+        }
+        self.remove(&path);
+        self.paths.insert(0, path);
+    }
+
+    /// Put the `path` last, removing its other occurrences.
+    ///
+    /// Empty `path` is ignored (it'd mean the current directory).
+    pub fn append(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        if path.as_os_str().is_empty() {
+            return;
+        }
+        self.remove(&path);
+        self.paths.push(path);
+    }
+
+    /// Remove all occurrences of the `path`.
+    ///
+    /// # Returns
+    /// `true`, if the `path` was in the list.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> bool {
+        let len = self.paths.len();
+        self.paths.retain(|existing| !same(existing, path.as_ref()));
+        self.paths.len() != len
+    }
+
+    /// Remove duplicates, keeping the first occurrences (which win in lookups).
+    pub fn dedupe(&mut self) {
+        let mut seen: Vec<PathBuf> = Vec::with_capacity(self.paths.len());
+        self.paths.retain(|path| {
+            if seen.iter().any(|seen| same(seen, path)) {
+                false
+            } else {
+                seen.push(path.clone());
+                true
+            }
+        });
+    }
+
+    /// Check, whether the list contains the `path`.
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        self.paths
+            .iter()
+            .any(|existing| same(existing, path.as_ref()))
+    }
+
+    /// Iterate over the paths, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Path> {
+        self.paths.iter().map(PathBuf::as_path)
+    }
+
+    /// Return number of the paths.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Check, whether there are no paths.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Join the paths back into a value of the variable, e.g. for the
+    /// [`Command::env`](std::process::Command::env).
+    ///
+    /// # Returns
+    /// [`Ok(OsString)`](OsString) with the value, or [`JoinPathsError`], if some path contains the
+    /// separator (or `"` on Windows).
+    pub fn to_os_string(&self) -> Result<OsString, JoinPathsError> {
+        std::env::join_paths(&self.paths)
+    }
+}
+
+impl<P: Into<PathBuf>> FromIterator<P> for PathList {
+    fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
+        Self {
+            paths: iter
+                .into_iter()
+                .map(Into::into)
+                .filter(|path: &PathBuf| !path.as_os_str().is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl IntoIterator for PathList {
+    type Item = PathBuf;
+    type IntoIter = std::vec::IntoIter<PathBuf>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.paths.into_iter()
+    }
+}
+
+fn same(a: &Path, b: &Path) -> bool {
+    if cfg!(windows) {
+        a.components().count() == b.components().count()
+            && a.components()
+                .zip(b.components())
+                .all(|(a, b)| a.as_os_str().eq_ignore_ascii_case(b.as_os_str()))
+    } else {
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_edited_in_order() {
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let value = ["/usr/bin", "", "/bin", "/usr/bin/", "/sbin"].join(separator);
+        let mut path = PathList::parse(&value);
+        assert_eq!(path.len(), 4);
+        assert!(path.contains("/bin"));
+
+        path.dedupe();
+        path.prepend("/opt/app/bin");
+        path.append("/usr/bin");
+        assert!(path.remove("/sbin"));
+        assert!(!path.remove("/sbin"));
+        assert_eq!(
+            path.iter().collect::<Vec<_>>(),
+            [
+                Path::new("/opt/app/bin"),
+                Path::new("/bin"),
+                Path::new("/usr/bin")
+            ]
+        );
+        assert_eq!(
+            path.to_os_string().ok(),
+            Some(OsString::from(
+                ["/opt/app/bin", "/bin", "/usr/bin"].join(separator)
+            ))
+        );
+        path.prepend("");
+        path.append("");
+        assert_eq!(path.len(), 3);
+        path.append(format!("/a{separator}b"));
+        assert!(path.to_os_string().is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::ffi::OsString;
+
+use crate::os::env::{CaseSensitivity, Env, sanitize::matches};
+
+/// Patterns of keys holding secrets usually, for the [`Env::redacted`].
+pub const SECRET_PATTERNS: &[&str] = &[
+    "*TOKEN*",
+    "*SECRET*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*API_KEY*",
+    "*APIKEY*",
+    "*PRIVATE_KEY*",
+    "*CREDENTIAL*",
+    "AWS_*KEY*",
+];
+
+/// Replacement of the redacted values.
+const REDACTED: &str = "***";
+
+impl Env {
+    /// Return a copy of this [`Env`], with values of keys matching any of the `patterns`
+    /// (case-insensitively, and `*` matches any run of characters) replaced with `***`, e.g. for
+    /// including it in logs, or bug reports.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rustvil::os::env::{Env, SECRET_PATTERNS};
+    /// let env = Env::from_iter([("GITHUB_TOKEN", "ghp_123"), ("LANG", "C")]);
+    /// let redacted = env.redacted(SECRET_PATTERNS);
+    /// assert_eq!(redacted.get("GITHUB_TOKEN"), Ok("***"));
+    /// assert_eq!(redacted.get("LANG"), Ok("C"));
+    /// ```
+    pub fn redacted(&self, patterns: &[&str]) -> Env {
+        let mut redacted = self.clone();
+        let secrets: Vec<OsString> = self
+            .keys()
+            .filter(|key| {
+                let key = key.to_string_lossy();
+                patterns
+                    .iter()
+                    .any(|pattern| matches(pattern, &key, CaseSensitivity::Insensitive))
+            })
+            .map(OsString::from)
+            .collect();
+        for key in secrets {
+            redacted.set(key, REDACTED);
+        }
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_redacted() {
+        let env = Env::from_iter([
+            ("GITHUB_TOKEN", "1"),
+            ("db_password", "2"),
+            ("AWS_SECRET_ACCESS_KEY", "3"),
+            ("AWS_ACCESS_KEY_ID", "4"),
+            ("AWS_REGION", "eu-central-1"),
+            ("TOKENIZER", "5"),
+            ("PATH", "/bin"),
+        ]);
+        let redacted = env.redacted(SECRET_PATTERNS);
+        for key in [
+            "GITHUB_TOKEN",
+            "db_password",
+            "AWS_SECRET_ACCESS_KEY",
+            "AWS_ACCESS_KEY_ID",
+            "TOKENIZER",
+        ] {
+            assert_eq!(redacted.get(key), Ok(REDACTED), "{key}");
+        }
+        assert_eq!(redacted.get("AWS_REGION"), Ok("eu-central-1"));
+        assert_eq!(redacted.get("PATH"), Ok("/bin"));
+        assert_eq!(redacted.len(), env.len());
+        assert_eq!(env.get("GITHUB_TOKEN"), Ok("1"));
+
+        let redacted = env.redacted(&["PATH"]);
+        assert_eq!(redacted.get("PATH"), Ok(REDACTED));
+        assert_eq!(redacted.get("GITHUB_TOKEN"), Ok("1"));
+    }
+
+    #[test]
+    fn wildcards_are_matched() {
+        let cases = [
+            ("*TOKEN*", "TOKEN", true),
+            ("*TOKEN*", "MY_TOKEN_X", true),
+            ("AWS_*KEY*", "AWS_KEY", true),
+            ("AWS_*KEY*", "XAWS_KEY", false),
+            ("A*B*C", "AxxBxxBxxC", true),
+            ("A*B*C", "AxxBxxC_", false),
+            ("LD_*", "LD_", true),
+            ("PATH", "PATHS", false),
+        ];
+        for (pattern, key, expected) in cases {
+            assert_eq!(
+                matches(pattern, key, CaseSensitivity::Sensitive),
+                expected,
+                "{pattern} {key}"
+            );
+        }
+        assert!(matches("*token*", "A_TOKEN", CaseSensitivity::Insensitive));
+        assert!(!matches("*token*", "A_TOKEN", CaseSensitivity::Sensitive));
+    }
+}
@@ -0,0 +1,63 @@
+//! Persistent environment of Windows, stored in the registry.
+
+use windows_sys::Win32::{
+    Foundation::ERROR_SUCCESS,
+    System::Registry::{
+        HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, RRF_NOEXPAND, RRF_RT_REG_EXPAND_SZ,
+        RRF_RT_REG_SZ, RegGetValueW,
+    },
+};
+
+const USER: &str = "Environment";
+const SYSTEM: &str = r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+
+/// Read the variable `name` of the user environment, or the system one (unexpanded).
+pub(super) fn lookup(name: &str) -> Option<String> {
+    [(HKEY_CURRENT_USER, USER), (HKEY_LOCAL_MACHINE, SYSTEM)]
+        .into_iter()
+        .find_map(|(root, key)| read(root, key, name))
+}
+
+fn read(root: HKEY, key: &str, name: &str) -> Option<String> {
+    let wide = |value: &str| -> Vec<u16> { value.encode_utf16().chain([0]).collect() };
+    let (key, name) = (wide(key), wide(name));
+    let flags = RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ | RRF_NOEXPAND;
+    let mut len = 0;
+    // SAFETY: Strings are NUL terminated, and querying the size only, with a null buffer, is
+    // allowed.
+    let status = unsafe {
+        RegGetValueW(
+            root,
+            key.as_ptr(),
+            name.as_ptr(),
+            flags,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut len,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let mut buf = vec![0u16; (len as usize).div_ceil(2)];
+    // SAFETY: Strings are NUL terminated, and `buf` is valid for writes of `len` bytes.
+    let status = unsafe {
+        RegGetValueW(
+            root,
+            key.as_ptr(),
+            name.as_ptr(),
+            flags,
+            std::ptr::null_mut(),
+            buf.as_mut_ptr().cast(),
+            &mut len,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    buf.truncate(len as usize / 2);
+    while buf.last() == Some(&0) {
+        buf.pop();
+    }
+    Some(String::from_utf16_lossy(&buf))
+}
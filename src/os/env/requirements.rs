@@ -0,0 +1,249 @@
+use std::{ffi::OsString, fmt, fmt::Display, str::FromStr};
+
+use thiserror::Error;
+
+use crate::os::env::{Env, EnvParseError};
+
+/// Declaration of the variables an app uses, validated at once by the [`Requirements::check`],
+/// instead of failing on the first one later.
+///
+/// ```rust,no_run
+/// # use rustvil::os::env::{Env, Requirements};
+/// let requirements = Requirements::new()
+///     .required::<String>("DATABASE_URL", "URL of the database")
+///     .required::<u16>("PORT", "port to listen on")
+///     .optional::<bool>("DEBUG", "whether to log more");
+/// if let Err(err) = requirements.check(&Env::new()) {
+///     eprintln!("{err}");
+///     std::process::exit(1);
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Requirements {
+    variables: Vec<Variable>,
+}
+
+/// A variable declared in the [`Requirements`].
+#[derive(Debug, Clone)]
+struct Variable {
+    key: String,
+    description: String,
+    required: bool,
+    expected: &'static str,
+    check: fn(&Env, &str) -> Result<(), EnvParseError>,
+}
+
+/// A problem with a variable, found by the [`Requirements::check`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarProblem {
+    /// Key of the variable.
+    pub key: String,
+    /// Description of the variable.
+    pub description: String,
+    /// Name of the type, which the variable should parse as (without module paths, e.g. `u16`
+    /// or `Vec<String>`).
+    pub expected: String,
+    /// What's wrong with it: [`EnvParseError::Missing`] (for required ones, also if empty),
+    /// [`EnvParseError::NonUTF8`], or [`EnvParseError::Invalid`].
+    pub error: EnvParseError,
+}
+
+/// Error of the [`Requirements::check`], with all the problems found.
+///
+/// It's displayed as a list of the problems, one per line, suitable for printing at startup.
+#[non_exhaustive]
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub struct RequirementsError {
+    /// Problems, in the order of the declarations.
+    pub problems: Vec<VarProblem>,
+}
+
+impl Requirements {
+    /// Create requirements without any variables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare the variable `key`, which must be set (to a non-empty value), and parse as a `T`
+    /// (use [`String`] for any UTF-8 value).
+    pub fn required<T>(self, key: impl Into<String>, description: impl Into<String>) -> Self
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.variable(
+            key.into(),
+            description.into(),
+            true,
+            std::any::type_name::<T>(),
+            check_parsed::<T>,
+        )
+    }
+
+    /// Declare the variable `key`, which must parse as a `T`, if it's set (to a non-empty
+    /// value).
+    pub fn optional<T>(self, key: impl Into<String>, description: impl Into<String>) -> Self
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.variable(
+            key.into(),
+            description.into(),
+            false,
+            std::any::type_name::<T>(),
+            check_parsed::<T>,
+        )
+    }
+
+    fn variable(
+        mut self,
+        key: String,
+        description: String,
+        required: bool,
+        expected: &'static str,
+        check: fn(&Env, &str) -> Result<(), EnvParseError>,
+    ) -> Self {
+        self.variables.push(Variable {
+            key,
+            description,
+            required,
+            expected,
+            check,
+        });
+        self
+    }
+
+    /// Check all the declared variables in the `env`.
+    ///
+    /// # Returns
+    /// [`Ok(())`](Ok), if all the variables are fine, otherwise a [`RequirementsError`] with
+    /// all the problems.
+    pub fn check(&self, env: &Env) -> Result<(), RequirementsError> {
+        let problems: Vec<VarProblem> = self
+            .variables
+            .iter()
+            .filter_map(|variable| {
+                let error = if env.has_non_empty(&variable.key) {
+                    (variable.check)(env, &variable.key).err()?
+                } else if variable.required {
+                    EnvParseError::Missing(OsString::from(&variable.key))
+                } else {
+                    return None;
+                };
+                Some(VarProblem {
+                    key: variable.key.clone(),
+                    description: variable.description.clone(),
+                    expected: short_type_name(variable.expected),
+                    error,
+                })
+            })
+            .collect();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(RequirementsError { problems })
+        }
+    }
+}
+
+fn check_parsed<T>(env: &Env, key: &str) -> Result<(), EnvParseError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    env.get_parsed::<T>(key).map(drop)
+}
+
+/// Drop module paths from the `name` of a type, e.g. `alloc::string::String` becomes `String`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        if let Some(tail) = rest.strip_prefix("::") {
+            let start = short
+                .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .map_or(0, |i| i + 1);
+            short.truncate(start);
+            rest = tail;
+        } else {
+            short.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    short
+}
+
+impl Display for RequirementsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid environment:")?;
+        for problem in &self.problems {
+            write!(
+                f,
+                "\n- {} ({}, expected `{}`)",
+                problem.error, problem.description, problem.expected
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    fn type_names_are_shortened() {
+        assert_eq!(short_type_name(std::any::type_name::<u8>()), "u8");
+        assert_eq!(
+            short_type_name(std::any::type_name::<Vec<std::path::PathBuf>>()),
+            "Vec<PathBuf>"
+        );
+        assert_eq!(
+            short_type_name(std::any::type_name::<Option<(String, i32)>>()),
+            "Option<(String, i32)>"
+        );
+    }
+
+    #[test]
+    fn all_problems_are_reported() {
+        let requirements = Requirements::new()
+            .required::<String>("URL", "URL of the database")
+            .required::<u16>("PORT", "port to listen on")
+            .required::<String>("EMPTY", "must be non-empty")
+            .optional::<bool>("DEBUG", "whether to log more")
+            .optional::<u8>("LEVEL", "verbosity")
+            .optional::<u8>("MISSING", "not needed");
+        let env = Env::from_iter([
+            ("URL", "postgres://localhost"),
+            ("PORT", "http"),
+            ("EMPTY", ""),
+            ("DEBUG", "true"),
+            ("LEVEL", "1000"),
+        ]);
+        let err = assert_err!(requirements.check(&env));
+        let problems: Vec<_> = err
+            .problems
+            .iter()
+            .map(|problem| (problem.key.as_str(), problem.expected.as_str()))
+            .collect();
+        assert_eq!(
+            problems,
+            [("PORT", "u16"), ("EMPTY", "String"), ("LEVEL", "u8")]
+        );
+        let message = err.to_string();
+        assert_eq!(message.lines().count(), 4);
+        assert!(
+            message.contains("(port to listen on, expected `u16`)"),
+            "{message}"
+        );
+
+        assert_ok!(
+            Requirements::new()
+                .optional::<u8>("MISSING", "")
+                .check(&env)
+        );
+    }
+}
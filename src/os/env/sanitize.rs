@@ -0,0 +1,258 @@
+use std::ffi::OsStr;
+
+use crate::os::env::{CaseSensitivity, Env};
+
+/// Variables changing how programs load code (of the dynamic linker, or some interpreters), or
+/// how shells start, which a child process shouldn't usually inherit. `*` matches any run of
+/// characters.
+const DANGEROUS: &[&str] = &[
+    "LD_*",
+    "DYLD_*",
+    "PYTHONPATH",
+    "PYTHONHOME",
+    "PYTHONSTARTUP",
+    "PERL5LIB",
+    "PERL5OPT",
+    "PERLLIB",
+    "RUBYLIB",
+    "RUBYOPT",
+    "NODE_OPTIONS",
+    "NODE_PATH",
+    "JAVA_TOOL_OPTIONS",
+    "_JAVA_OPTIONS",
+    "BASH_ENV",
+    "ENV",
+    "IFS",
+    "CDPATH",
+    "PS4",
+    "SHELLOPTS",
+];
+
+/// Variables always kept by the [`Env::sanitized`].
+const ESSENTIAL: &[&str] = &["PATH", "HOME", "TERM"];
+
+/// Variables kept by the [`SanitizeMode::Allowlist`], besides the [`ESSENTIAL`] ones.
+const MINIMAL: &[&str] = &[
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LANGUAGE",
+    "LC_*",
+    "TZ",
+    "TMPDIR",
+    // Windows ones, without which many programs don't work.
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "TEMP",
+    "TMP",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+];
+
+/// Which variables the [`Env::sanitized`] keeps.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum SanitizeMode {
+    /// All, except the dangerous ones (like `LD_PRELOAD`, `DYLD_*`, or `PYTHONPATH`).
+    #[default]
+    Denylist,
+    /// Only a minimal set, like `PATH`, `HOME`, `TERM`, `USER`, `LANG`, `LC_*`, and `TZ` (and
+    /// the essential Windows ones, like `SystemRoot`).
+    Allowlist,
+}
+
+/// Options for controlling the [`Env::sanitized`].
+///
+/// Default removes the dangerous variables only (see the [`SanitizeMode::Denylist`]).
+#[derive(Debug, Default, Hash, Clone, PartialEq, Eq)]
+pub struct SanitizePolicy {
+    mode: SanitizeMode,
+    git: bool,
+    keep: Vec<String>,
+    remove: Vec<String>,
+}
+
+impl SanitizePolicy {
+    /// Create default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Choose which variables are kept.
+    pub fn mode(mut self, mode: SanitizeMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Remove the `GIT_*` variables too (like the `GIT_DIR`, set in git hooks), so spawned
+    /// `git` commands don't act on another repository.
+    pub fn remove_git(mut self, remove_git: bool) -> Self {
+        self.git = remove_git;
+        self
+    }
+
+    /// Keep the variable `key` (`*` matches any run of characters), even if the mode would
+    /// remove it.
+    pub fn keep(mut self, key: impl Into<String>) -> Self {
+        self.keep.push(key.into());
+        self
+    }
+
+    /// Remove the variable `key` (`*` matches any run of characters) too.
+    pub fn remove(mut self, key: impl Into<String>) -> Self {
+        self.remove.push(key.into());
+        self
+    }
+
+    fn allows(&self, key: &OsStr, case: CaseSensitivity) -> bool {
+        let Some(key) = key.to_str() else {
+            // May not match any name on the lists.
+            return self.mode == SanitizeMode::Denylist;
+        };
+        let any = |patterns: &[&str]| patterns.iter().any(|pattern| matches(pattern, key, case));
+        let keep: Vec<&str> = self.keep.iter().map(String::as_str).collect();
+        let remove: Vec<&str> = self.remove.iter().map(String::as_str).collect();
+        if any(ESSENTIAL) || any(&keep) {
+            return true;
+        }
+        if any(&remove) || (self.git && any(&["GIT_*"])) {
+            return false;
+        }
+        match self.mode {
+            SanitizeMode::Denylist => !any(DANGEROUS),
+            SanitizeMode::Allowlist => any(MINIMAL),
+        }
+    }
+}
+
+/// Check, whether the `key` matches the `pattern` (where `*` matches any run of characters),
+/// according to the `case`.
+pub(super) fn matches(pattern: &str, key: &str, case: CaseSensitivity) -> bool {
+    let eq = |a: u8, b: u8| match case {
+        CaseSensitivity::Sensitive => a == b,
+        CaseSensitivity::Insensitive => a.eq_ignore_ascii_case(&b),
+    };
+    let (pattern, key) = (pattern.as_bytes(), key.as_bytes());
+    let (mut p, mut k) = (0, 0);
+    // Position after the last `*`, and the position in the `key` it has matched up to.
+    let mut backtrack = None;
+    while k < key.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            backtrack = Some((p, k));
+        } else if pattern.get(p).is_some_and(|&c| eq(c, key[k])) {
+            p += 1;
+            k += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the `*` match one more character.
+            (p, k) = (star, matched + 1);
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+impl Env {
+    /// Return a copy of this [`Env`] without the variables, which the `policy` doesn't allow,
+    /// e.g. for spawning less trusted, or setuid programs.
+    ///
+    /// `PATH`, `HOME` and `TERM` are always kept. Keys are compared according to the
+    /// [case](Env::with_case) of this [`Env`].
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use rustvil::os::env::{ApplyMode, Env, SanitizeMode, SanitizePolicy};
+    /// # use std::process::Command;
+    /// # fn main() -> std::io::Result<()> {
+    /// let policy = SanitizePolicy::new()
+    ///     .mode(SanitizeMode::Allowlist)
+    ///     .keep("SSH_AUTH_SOCK");
+    /// let env = Env::new().sanitized(&policy);
+    /// env.apply_to(&mut Command::new("helper"), ApplyMode::Replace).status()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sanitized(&self, policy: &SanitizePolicy) -> Env {
+        let mut sanitized = self.clone();
+        let case = self.case();
+        sanitized.retain(|key, _| policy.allows(key, case));
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> Env {
+        [
+            "PATH",
+            "HOME",
+            "TERM",
+            "LANG",
+            "LC_ALL",
+            "EDITOR",
+            "LD_PRELOAD",
+            "DYLD_LIBRARY_PATH",
+            "PYTHONPATH",
+            "GIT_DIR",
+            "SECRET",
+        ]
+        .map(|key| (key, "x"))
+        .into_iter()
+        .collect::<Env>()
+        .with_case(CaseSensitivity::Sensitive)
+    }
+
+    fn keys(env: &Env) -> Vec<&OsStr> {
+        let mut keys: Vec<_> = env.keys().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn dangerous_variables_are_removed() {
+        let vars = env();
+        let policy = SanitizePolicy::new()
+            .remove_git(true)
+            .remove("SECRET")
+            .remove("PATH");
+        assert_eq!(
+            keys(&vars.sanitized(&policy)),
+            ["EDITOR", "HOME", "LANG", "LC_ALL", "PATH", "TERM"]
+        );
+        assert_eq!(vars.sanitized(&SanitizePolicy::new()).len(), 8);
+
+        let policy = SanitizePolicy::new()
+            .mode(SanitizeMode::Allowlist)
+            .keep("EDITOR")
+            .remove("LANG");
+        assert_eq!(
+            keys(&vars.sanitized(&policy)),
+            ["EDITOR", "HOME", "LC_ALL", "PATH", "TERM"]
+        );
+    }
+
+    #[test]
+    fn case_is_respected() {
+        let vars = Env::from_iter([("Path", "x"), ("ld_preload", "x")]);
+        let sensitive = vars.clone().with_case(CaseSensitivity::Sensitive);
+        let policy = SanitizePolicy::new().mode(SanitizeMode::Allowlist);
+        assert!(sensitive.sanitized(&policy).is_empty());
+        let insensitive = vars.with_case(CaseSensitivity::Insensitive);
+        assert_eq!(keys(&insensitive.sanitized(&policy)), ["Path"]);
+        assert!(insensitive.sanitized(&SanitizePolicy::new()).has("Path"));
+        assert!(
+            !insensitive
+                .sanitized(&SanitizePolicy::new())
+                .has("ld_preload")
+        );
+    }
+}
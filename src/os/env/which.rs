@@ -0,0 +1,126 @@
+use std::{
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+use crate::fs::PathExt;
+use crate::os::env::{Env, PathList};
+
+/// Find the executable `name` in the `$PATH` of the `env`, like the `which` command.
+///
+/// A `name` with a directory (like `./run.sh`, or `/usr/bin/env`) isn't searched for, it's only
+/// checked. On Windows, extensions from the `$PATHEXT` (`.COM;.EXE;.BAT;.CMD` by default) are
+/// tried, unless the `name` already has one of them.
+///
+/// # Returns
+///
+/// [`Some(PathBuf)`](PathBuf) with the first [executable](PathExt::is_executable) match, or
+/// [`None`], if there is none.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rustvil::os::env::{self, Env};
+/// if let Some(git) = env::which(&Env::new(), "git") {
+///     println!("using {}", git.display());
+/// }
+/// ```
+pub fn which(env: &Env, name: impl AsRef<OsStr>) -> Option<PathBuf> {
+    candidates(env, name.as_ref()).find(|candidate| candidate.is_executable())
+}
+
+/// Same as the [`which`], but returns all the matches, in the order of the `$PATH` (without
+/// duplicates).
+pub fn which_all(env: &Env, name: impl AsRef<OsStr>) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    for candidate in candidates(env, name.as_ref()) {
+        if candidate.is_executable() && !found.contains(&candidate) {
+            found.push(candidate);
+        }
+    }
+    found
+}
+
+/// Return all the paths, which the `name` may refer to.
+fn candidates<'a>(env: &Env, name: &'a OsStr) -> impl Iterator<Item = PathBuf> + 'a {
+    let path = Path::new(name);
+    let dirs: Vec<PathBuf> = if path.components().count() > 1 || path.is_absolute() {
+        vec![PathBuf::new()]
+    } else {
+        PathList::from_env(env, "PATH").into_iter().collect()
+    };
+    let extensions = extensions(env, path);
+    dirs.into_iter().flat_map(move |dir| {
+        let path = dir.join(name);
+        extensions
+            .iter()
+            .map(move |extension| {
+                let mut candidate = path.clone().into_os_string();
+                candidate.push(extension);
+                PathBuf::from(candidate)
+            })
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Return suffixes to try for the `name`: only the empty one, except on Windows.
+fn extensions(env: &Env, name: &Path) -> Vec<OsString> {
+    if !cfg!(windows) {
+        return vec![OsString::new()];
+    }
+    let pathext = env
+        .get_non_empty("PATHEXT")
+        .unwrap_or(OsStr::new(".COM;.EXE;.BAT;.CMD"))
+        .to_string_lossy()
+        .into_owned();
+    let extensions: Vec<OsString> = pathext
+        .split(';')
+        .filter(|extension| !extension.is_empty())
+        .map(OsString::from)
+        .collect();
+    let has_extension = name.extension().is_some_and(|current| {
+        extensions
+            .iter()
+            .any(|extension| extension.eq_ignore_ascii_case(format!(".{}", current.display())))
+    });
+    if has_extension {
+        vec![OsString::new()]
+    } else {
+        extensions
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::fs::TreeSpec;
+    use claim::{assert_ok, assert_some};
+
+    #[test]
+    fn executables_are_found_in_path() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = assert_ok!(
+            TreeSpec::new()
+                .file("a/tool", "")
+                .file("b/tool", "")
+                .file("b/data", "")
+                .create_temp()
+        );
+        for tool in ["a/tool", "b/tool"] {
+            let path = tmp.path().join(tool);
+            assert_ok!(path.set_permissions(std::fs::Permissions::from_mode(0o755)));
+        }
+        let path = PathList::from_iter([tmp.path().join("a"), tmp.path().join("b")]);
+        let env = Env::from([(OsString::from("PATH"), assert_ok!(path.to_os_string()))]);
+
+        assert_eq!(assert_some!(which(&env, "tool")), tmp.path().join("a/tool"));
+        assert_eq!(
+            which_all(&env, "tool"),
+            [tmp.path().join("a/tool"), tmp.path().join("b/tool")]
+        );
+        assert_eq!(which(&env, "data"), None);
+        let direct = tmp.path().join("b/tool");
+        assert_eq!(which(&env, &direct), Some(direct));
+        assert_eq!(which(&env, "b/tool"), None);
+    }
+}
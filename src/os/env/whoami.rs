@@ -0,0 +1,100 @@
+use std::ffi::OsString;
+
+use crate::os::env::Env;
+
+/// Return the name of the current user: from the `USER`, `USERNAME`, or `LOGNAME` variable of
+/// the `env` (the first non-empty one), or as reported by the OS (the user database entry of
+/// the effective UID on `unix`, the `GetUserNameW` on Windows).
+///
+/// # Returns
+///
+/// [`Some(OsString)`](OsString) with the name, or [`None`], if it can't be determined.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rustvil::os::env::{self, Env};
+/// let env = Env::new();
+/// let user = env::username(&env).unwrap_or_default();
+/// let host = env::hostname(&env).unwrap_or_default();
+/// println!("{}@{}", user.display(), host.display());
+/// ```
+pub fn username(env: &Env) -> Option<OsString> {
+    ["USER", "USERNAME", "LOGNAME"]
+        .into_iter()
+        .find_map(|key| env.get_non_empty(key))
+        .map(OsString::from)
+        .or_else(os_username)
+}
+
+/// Return the name of this machine: from the `COMPUTERNAME`, or `HOSTNAME` variable of the `env`
+/// (the first non-empty one), or the [`os::hostname`](crate::os::hostname).
+///
+/// # Returns
+///
+/// [`Some(OsString)`](OsString) with the name, or [`None`], if it can't be determined.
+pub fn hostname(env: &Env) -> Option<OsString> {
+    ["COMPUTERNAME", "HOSTNAME"]
+        .into_iter()
+        .find_map(|key| env.get_non_empty(key))
+        .map(OsString::from)
+        .or_else(|| crate::os::hostname().ok())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(unix)]
+fn os_username() -> Option<OsString> {
+    // SAFETY: Always succeeds.
+    let uid = unsafe { libc::geteuid() };
+    crate::os::passwd::user_by_uid(uid)
+        .ok()
+        .flatten()
+        .map(|entry| entry.name)
+}
+
+#[cfg(windows)]
+fn os_username() -> Option<OsString> {
+    use std::os::windows::ffi::OsStringExt;
+    use windows_sys::Win32::System::WindowsProgramming::GetUserNameW;
+    // `UNLEN` is 256, plus the NUL.
+    let mut buf = vec![0u16; 257];
+    let mut len = buf.len() as u32;
+    // SAFETY: `buf` is valid for writes of `len` characters.
+    if unsafe { GetUserNameW(buf.as_mut_ptr(), &mut len) } == 0 {
+        return None;
+    }
+    // `len` includes the NUL.
+    buf.truncate((len as usize).saturating_sub(1));
+    Some(OsString::from_wide(&buf))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn os_username() -> Option<OsString> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables_take_precedence() {
+        let env = Env::from([
+            (OsString::from("USER"), OsString::new()),
+            (OsString::from("USERNAME"), OsString::from("ala")),
+            (OsString::from("HOSTNAME"), OsString::from("box")),
+        ]);
+        assert_eq!(username(&env), Some(OsString::from("ala")));
+        assert_eq!(hostname(&env), Some(OsString::from("box")));
+    }
+
+    #[test]
+    fn os_is_used_as_fallback() {
+        let env = Env::empty();
+        // Sandboxes can run as a user without a passwd entry.
+        let username = username(&env);
+        assert_eq!(username, os_username());
+        assert!(username.is_none_or(|name| !name.is_empty()));
+        assert_eq!(hostname(&env), crate::os::hostname().ok());
+    }
+}
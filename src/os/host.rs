@@ -0,0 +1,67 @@
+use std::{ffi::OsString, io};
+
+/// Return the name of this machine, as reported by the OS (the `gethostname` on `unix`, the DNS
+/// host name on Windows).
+///
+/// It may be fully qualified, like `laptop.example.com`, depending on the system.
+///
+/// # Returns
+///
+/// [`Ok(OsString)`](OsString) with the name, otherwise an error, as reported by the OS.
+pub fn hostname() -> io::Result<OsString> {
+    imp()
+}
+
+#[cfg(unix)]
+fn imp() -> io::Result<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+    // `HOST_NAME_MAX` is 255 at most, on all the supported systems.
+    let mut buf = vec![0u8; 256];
+    // SAFETY: `buf` is valid for writes of its length.
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // It may be not NUL terminated, when truncated.
+    let len = buf.iter().position(|&byte| byte == 0).unwrap_or(buf.len());
+    buf.truncate(len);
+    Ok(OsString::from_vec(buf))
+}
+
+#[cfg(windows)]
+fn imp() -> io::Result<OsString> {
+    use std::os::windows::ffi::OsStringExt;
+    use windows_sys::Win32::System::SystemInformation::{
+        ComputerNameDnsHostname, GetComputerNameExW,
+    };
+    let mut len = 0;
+    // SAFETY: Querying the length only, with a null buffer, is allowed.
+    unsafe { GetComputerNameExW(ComputerNameDnsHostname, std::ptr::null_mut(), &mut len) };
+    let mut buf = vec![0u16; len as usize];
+    // SAFETY: `buf` is valid for writes of `len` wide characters.
+    if unsafe { GetComputerNameExW(ComputerNameDnsHostname, buf.as_mut_ptr(), &mut len) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // On a success, `len` excludes the terminating NUL.
+    buf.truncate(len as usize);
+    Ok(OsString::from_wide(&buf))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn imp() -> io::Result<OsString> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "hostname isn't supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+
+    #[test]
+    fn hostname_is_not_empty() {
+        assert!(!assert_ok!(hostname()).is_empty());
+    }
+}
@@ -2,4 +2,9 @@
 
 mod command_ext;
 pub mod env;
+mod host;
+#[cfg(unix)]
+pub(crate) mod passwd;
+pub mod pipeline;
 pub use command_ext::*;
+pub use host::hostname;
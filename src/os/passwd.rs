@@ -0,0 +1,142 @@
+//! Thin wrappers around the user and group databases (`getpwnam_r` and friends).
+
+use std::{
+    ffi::{CStr, CString, OsStr, OsString},
+    io,
+    mem::MaybeUninit,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::PathBuf,
+};
+
+/// Entry of the user database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Passwd {
+    pub name: OsString,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: PathBuf,
+}
+
+/// Call `f` with a growing buffer, until it stops failing with `ERANGE`.
+///
+/// `f` follows the `get*_r` convention: returns an errno, alongside the entry (if found).
+fn with_buffer<T>(
+    mut f: impl FnMut(&mut [libc::c_char]) -> (libc::c_int, Option<T>),
+) -> io::Result<Option<T>> {
+    let mut buf = vec![0; 1024];
+    loop {
+        match f(&mut buf) {
+            (0, entry) => return Ok(entry),
+            (libc::ERANGE, _) if buf.len() < (1 << 20) => buf.resize(buf.len() * 2, 0),
+            (errno, _) => return Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+}
+
+fn to_passwd(pwd: &libc::passwd) -> Passwd {
+    // SAFETY: On a success, `pw_name` and `pw_dir` point to NUL terminated strings.
+    let (name, home) = unsafe { (CStr::from_ptr(pwd.pw_name), CStr::from_ptr(pwd.pw_dir)) };
+    Passwd {
+        name: OsString::from_vec(name.to_bytes().to_vec()),
+        uid: pwd.pw_uid,
+        gid: pwd.pw_gid,
+        home: PathBuf::from(OsString::from_vec(home.to_bytes().to_vec())),
+    }
+}
+
+/// Look up a user by its name.
+pub(crate) fn user_by_name(name: &OsStr) -> io::Result<Option<Passwd>> {
+    let name = CString::new(name.as_bytes())?;
+    with_buffer(|buf| {
+        let mut pwd = MaybeUninit::<libc::passwd>::uninit();
+        let mut result = std::ptr::null_mut();
+        // SAFETY: `name` is NUL terminated, all other pointers are valid for writes.
+        let errno = unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                pwd.as_mut_ptr(),
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        // SAFETY: Non-NULL `result` points to the initialized `pwd`.
+        (
+            errno,
+            (!result.is_null()).then(|| to_passwd(unsafe { pwd.assume_init_ref() })),
+        )
+    })
+}
+
+/// Look up a user by its UID.
+pub(crate) fn user_by_uid(uid: u32) -> io::Result<Option<Passwd>> {
+    with_buffer(|buf| {
+        let mut pwd = MaybeUninit::<libc::passwd>::uninit();
+        let mut result = std::ptr::null_mut();
+        // SAFETY: All pointers are valid for writes.
+        let errno = unsafe {
+            libc::getpwuid_r(
+                uid,
+                pwd.as_mut_ptr(),
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        // SAFETY: Non-NULL `result` points to the initialized `pwd`.
+        (
+            errno,
+            (!result.is_null()).then(|| to_passwd(unsafe { pwd.assume_init_ref() })),
+        )
+    })
+}
+
+/// Look up GID of a group by its name.
+pub(crate) fn group_by_name(name: &OsStr) -> io::Result<Option<u32>> {
+    let name = CString::new(name.as_bytes())?;
+    with_buffer(|buf| {
+        let mut grp = MaybeUninit::<libc::group>::uninit();
+        let mut result = std::ptr::null_mut();
+        // SAFETY: `name` is NUL terminated, all other pointers are valid for writes.
+        let errno = unsafe {
+            libc::getgrnam_r(
+                name.as_ptr(),
+                grp.as_mut_ptr(),
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        // SAFETY: Non-NULL `result` points to the initialized `grp`.
+        (
+            errno,
+            (!result.is_null()).then(|| unsafe { grp.assume_init_ref() }.gr_gid),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claim::assert_ok;
+
+    #[test]
+    fn root_exists() {
+        let root = assert_ok!(user_by_name(OsStr::new("root"))).unwrap();
+        assert_eq!(root.uid, 0);
+        assert_eq!(root.name, "root");
+        assert_eq!(assert_ok!(user_by_uid(0)), Some(root));
+    }
+
+    #[test]
+    fn missing_entries() {
+        assert_eq!(
+            assert_ok!(user_by_name(OsStr::new("rustvil-no-such-user"))),
+            None
+        );
+        assert_eq!(
+            assert_ok!(group_by_name(OsStr::new("rustvil-no-such-group"))),
+            None
+        );
+    }
+}
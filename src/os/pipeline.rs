@@ -0,0 +1,245 @@
+//! Pipelines of commands, like `a | b | c` in a shell, without a shell.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! # use rustvil::os::pipeline::Pipeline;
+//! # use std::process::Command;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut log = Command::new("git");
+//! log.args(["log", "--format=%an"]);
+//! let mut count = Command::new("uniq");
+//! count.arg("-c");
+//! let output = Pipeline::new(log)
+//!     .pipe(Command::new("sort"))
+//!     .pipe(count)
+//!     .pipefail(true)
+//!     .output()?;
+//! println!("{}", String::from_utf8_lossy(&output.stdout));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Read};
+use std::process::{Child, ChildStdout, Command, ExitStatus, Stdio};
+
+/// Commands, with the standard output of each connected to the standard input of the next one.
+///
+/// Standard input of the first command, standard error of all of them, and standard output of
+/// the last one (for the [`Pipeline::status`]) are left as configured in the [`Command`]s.
+#[derive(Debug)]
+pub struct Pipeline {
+    commands: Vec<Command>,
+    pipefail: bool,
+}
+
+/// Exit statuses of the commands of a [`Pipeline`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineStatus {
+    /// Exit statuses, in order of the commands.
+    pub statuses: Vec<ExitStatus>,
+    pipefail: bool,
+}
+
+/// Exit statuses, and captured output of a [`Pipeline`], see the [`Pipeline::output`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineOutput {
+    /// Exit statuses of the commands.
+    pub status: PipelineStatus,
+    /// Captured standard output of the last command.
+    pub stdout: Vec<u8>,
+}
+
+impl Pipeline {
+    /// Create a pipeline starting with the `first` command.
+    pub fn new(first: Command) -> Self {
+        Self {
+            commands: vec![first],
+            pipefail: false,
+        }
+    }
+
+    /// Pipe output of the current last command into the `next` one.
+    pub fn pipe(mut self, next: Command) -> Self {
+        self.commands.push(next);
+        self
+    }
+
+    /// Like the `set -o pipefail` of a shell: whether a failure of any command (not only of the
+    /// last one) fails the pipeline. `false` by default.
+    pub fn pipefail(mut self, pipefail: bool) -> Self {
+        self.pipefail = pipefail;
+        self
+    }
+
+    /// Run the commands (all at once, like a shell would), and wait for all of them to exit.
+    ///
+    /// # Returns
+    /// [`Ok(PipelineStatus)`](PipelineStatus) with the exit statuses, otherwise an error, as
+    /// reported by the OS (then the already started commands are killed).
+    pub fn status(self) -> io::Result<PipelineStatus> {
+        let pipefail = self.pipefail;
+        let children = self.spawn(false)?;
+        Ok(PipelineStatus {
+            statuses: wait_all(children)?,
+            pipefail,
+        })
+    }
+
+    /// Same as the [`Pipeline::status`], but standard output of the last command is captured.
+    pub fn output(self) -> io::Result<PipelineOutput> {
+        let pipefail = self.pipefail;
+        let mut children = self.spawn(true)?;
+        let mut stdout = Vec::new();
+        let read = match children.last_mut().and_then(|last| last.stdout.take()) {
+            Some(mut pipe) => pipe.read_to_end(&mut stdout).map(drop),
+            None => Ok(()),
+        };
+        if let Err(err) = read {
+            kill_all(children);
+            return Err(err);
+        }
+        Ok(PipelineOutput {
+            status: PipelineStatus {
+                statuses: wait_all(children)?,
+                pipefail,
+            },
+            stdout,
+        })
+    }
+
+    /// Start all the commands, piping the last one too, if `capture`.
+    fn spawn(self, capture: bool) -> io::Result<Vec<Child>> {
+        let last = self.commands.len() - 1;
+        let mut children: Vec<Child> = Vec::with_capacity(self.commands.len());
+        let mut input: Option<ChildStdout> = None;
+        // Each command is dropped right after spawning, so it doesn't keep its end of the pipe
+        // open (which would prevent the previous command from getting a `SIGPIPE`).
+        for (i, mut command) in self.commands.into_iter().enumerate() {
+            if let Some(input) = input.take() {
+                command.stdin(Stdio::from(input));
+            }
+            if i < last || capture {
+                command.stdout(Stdio::piped());
+            }
+            match command.spawn() {
+                Ok(mut child) => {
+                    if i < last {
+                        input = child.stdout.take();
+                    }
+                    children.push(child);
+                }
+                Err(err) => {
+                    kill_all(children);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(children)
+    }
+}
+
+impl PipelineStatus {
+    /// Return the status of the pipeline: of the last command, or with the
+    /// [`Pipeline::pipefail`], of the last failed one (if there is any).
+    pub fn exit_status(&self) -> ExitStatus {
+        let last = *self
+            .statuses
+            .last()
+            .expect("pipeline has at least one command");
+        if !self.pipefail {
+            return last;
+        }
+        self.statuses
+            .iter()
+            .rev()
+            .find(|status| !status.success())
+            .copied()
+            .unwrap_or(last)
+    }
+
+    /// Check, whether the pipeline has succeeded, see the [`PipelineStatus::exit_status`].
+    pub fn success(&self) -> bool {
+        self.exit_status().success()
+    }
+}
+
+/// Wait for all the `children`, even if some wait fails.
+///
+/// # Returns
+/// [`Ok(Vec<ExitStatus>)`](ExitStatus) in order of the `children`, otherwise the first error.
+fn wait_all(children: Vec<Child>) -> io::Result<Vec<ExitStatus>> {
+    let mut statuses = Vec::with_capacity(children.len());
+    let mut failure = None;
+    for mut child in children {
+        match child.wait() {
+            Ok(status) => statuses.push(status),
+            Err(err) => {
+                failure.get_or_insert(err);
+            }
+        }
+    }
+    match failure {
+        Some(err) => Err(err),
+        None => Ok(statuses),
+    }
+}
+
+/// Kill the `children` (best effort), and wait for them, so they aren't left as zombies.
+fn kill_all(children: Vec<Child>) {
+    for mut child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use claim::{assert_err, assert_ok};
+
+    fn command(program: &str, args: &[&str]) -> Command {
+        let mut command = Command::new(program);
+        command.args(args);
+        command
+    }
+
+    #[test]
+    fn commands_are_piped() {
+        let output = assert_ok!(
+            Pipeline::new(command("printf", &["b\na\nb\n"]))
+                .pipe(command("sort", &["-u"]))
+                .pipe(command("tr", &["a-z", "A-Z"]))
+                .output()
+        );
+        assert_eq!(output.stdout, b"A\nB\n");
+        assert_eq!(output.status.statuses.len(), 3);
+        assert!(output.status.success());
+
+        // Ends only if `yes` gets the `SIGPIPE`, once `head` exits.
+        let output = assert_ok!(
+            Pipeline::new(command("yes", &[]))
+                .pipe(command("head", &["-n", "2"]))
+                .output()
+        );
+        assert_eq!(output.stdout, b"y\ny\n");
+    }
+
+    #[test]
+    fn pipefail_is_respected() {
+        let failing = || Pipeline::new(command("sh", &["-c", "exit 3"])).pipe(command("cat", &[]));
+        let status = assert_ok!(failing().status());
+        assert!(status.success());
+        let status = assert_ok!(failing().pipefail(true).status());
+        assert_eq!(status.exit_status().code(), Some(3));
+
+        let err = assert_err!(
+            Pipeline::new(command("yes", &[]))
+                .pipe(command("/nonexistent/program", &[]))
+                .status()
+        );
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}